@@ -7,7 +7,12 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use embacle::cache::{CacheConfig, CacheProvider};
+use embacle::cache_backend::CacheBackend;
 use embacle::config::CliRunnerType;
+use embacle::model_catalog::{apply_overrides, ModelInfo, ModelOverride};
+use embacle::rate_limit::{RateLimitConfig, RateLimitProvider};
+use embacle::single_flight::SingleFlightProvider;
 use embacle::types::{LlmProvider, RunnerError};
 use tokio::sync::{Mutex, RwLock};
 
@@ -24,6 +29,10 @@ pub struct ServerState {
     active_provider: CliRunnerType,
     active_model: Option<String>,
     multiplex_providers: Vec<CliRunnerType>,
+    model_overrides: HashMap<CliRunnerType, Vec<ModelOverride>>,
+    rate_limits: HashMap<CliRunnerType, RateLimitConfig>,
+    response_cache: Option<(CacheConfig, Arc<dyn CacheBackend>)>,
+    single_flight: bool,
     runners: Mutex<HashMap<CliRunnerType, Arc<dyn LlmProvider>>>,
 }
 
@@ -34,6 +43,10 @@ impl ServerState {
             active_provider: default_provider,
             active_model: None,
             multiplex_providers: Vec::new(),
+            model_overrides: HashMap::new(),
+            rate_limits: HashMap::new(),
+            response_cache: None,
+            single_flight: false,
             runners: Mutex::new(HashMap::new()),
         }
     }
@@ -69,10 +82,47 @@ impl ServerState {
         self.multiplex_providers = providers;
     }
 
+    /// Set per-provider model catalog overrides (e.g. from `embacle.toml`)
+    pub fn set_model_overrides(&mut self, overrides: HashMap<CliRunnerType, Vec<ModelOverride>>) {
+        self.model_overrides = overrides;
+    }
+
+    /// Set per-provider concurrency and rate limits (e.g. from `embacle.toml`)
+    pub fn set_rate_limits(&mut self, limits: HashMap<CliRunnerType, RateLimitConfig>) {
+        self.rate_limits = limits;
+    }
+
+    /// Cache responses of runners created from now on in `backend`
+    pub fn set_response_cache(&mut self, config: CacheConfig, backend: Arc<dyn CacheBackend>) {
+        self.response_cache = Some((config, backend));
+    }
+
+    /// Coalesce concurrent identical requests on runners created from now on
+    pub fn set_single_flight(&mut self, enabled: bool) {
+        self.single_flight = enabled;
+    }
+
+    /// Model catalog for `provider`'s runner with any configured overrides applied
+    pub fn model_catalog(
+        &self,
+        provider: CliRunnerType,
+        runner: &dyn LlmProvider,
+    ) -> Vec<ModelInfo> {
+        let mut catalog = runner.model_catalog();
+        if let Some(overrides) = self.model_overrides.get(&provider) {
+            apply_overrides(&mut catalog, overrides, runner.capabilities());
+        }
+        catalog
+    }
+
     /// Get or lazily create a runner for the given provider type
     ///
     /// Created runners are cached for future calls. The runner cache uses
-    /// interior mutability so callers only need `&self`.
+    /// interior mutability so callers only need `&self`. Runners are wrapped
+    /// in a [`RateLimitProvider`] when the provider has limits configured, a
+    /// [`CacheProvider`] in front of that when a response cache is configured
+    /// (so cache hits are not rate limited), and a [`SingleFlightProvider`]
+    /// outermost when coalescing is enabled.
     pub async fn get_runner(
         &self,
         provider: CliRunnerType,
@@ -86,7 +136,18 @@ impl ServerState {
         }
 
         // Slow path: create runner without holding the lock
-        let runner = factory::create_runner(provider).await?;
+        let mut runner = factory::create_runner(provider).await?;
+        if let Some(limits) = self.rate_limits.get(&provider) {
+            runner = Box::new(RateLimitProvider::new(runner, limits));
+        }
+        if let Some((ref config, ref backend)) = self.response_cache {
+            runner = Box::new(
+                CacheProvider::new(runner, config.clone()).with_backend(Arc::clone(backend)),
+            );
+        }
+        if self.single_flight {
+            runner = Box::new(SingleFlightProvider::new(runner));
+        }
         let runner: Arc<dyn LlmProvider> = Arc::from(runner);
 
         let runner = self
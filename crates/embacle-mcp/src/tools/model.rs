@@ -11,7 +11,7 @@ use crate::protocol::{CallToolResult, ToolDefinition};
 use crate::state::SharedState;
 use crate::tools::McpTool;
 
-/// Returns the current model, default model, and available models for the active provider
+/// Returns the current model, default model, and model catalog for the active provider
 pub struct GetModel;
 
 #[async_trait]
@@ -19,8 +19,10 @@ impl McpTool for GetModel {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "get_model".to_owned(),
-            description: "Get the current model and list available models for the active provider"
-                .to_owned(),
+            description:
+                "Get the current model and list available models for the active provider, \
+                          with context window, capability and pricing metadata"
+                    .to_owned(),
             input_schema: json!({
                 "type": "object",
                 "properties": {}
@@ -33,13 +35,11 @@ impl McpTool for GetModel {
         let provider = state_guard.active_provider();
         let current_model = state_guard.active_model().map(ToOwned::to_owned);
         let runner_result = state_guard.get_runner(provider).await;
+        // A refresh may run the CLI or call an HTTP API; don't block state writers meanwhile
         drop(state_guard);
 
-        let (default_model, available_models) = match runner_result {
-            Ok(runner) => (
-                runner.default_model().to_owned(),
-                runner.available_models().to_vec(),
-            ),
+        let runner = match runner_result {
+            Ok(runner) => runner,
             Err(e) => {
                 return CallToolResult::text(
                     json!({
@@ -51,13 +51,20 @@ impl McpTool for GetModel {
                 );
             }
         };
+        let available_models = runner
+            .refresh_models()
+            .await
+            .unwrap_or_else(|_| runner.available_models().to_vec());
+        let default_model = runner.default_model().to_owned();
+        let models = state.read().await.model_catalog(provider, runner.as_ref());
 
         CallToolResult::text(
             json!({
                 "provider": provider.to_string(),
                 "current_model": current_model,
                 "default_model": default_model,
-                "available_models": available_models
+                "available_models": available_models,
+                "models": models
             })
             .to_string(),
         )
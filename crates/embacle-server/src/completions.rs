@@ -4,22 +4,27 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
 
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use axum::extract::State;
-use axum::http::StatusCode;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use embacle::types::{ChatMessage, ChatRequest, ErrorKind, LlmCapabilities, RunnerError};
-use embacle::FunctionDeclaration;
+use embacle::types::{
+    ChatChoice, ChatMessage, ChatRequest, ChatResponse, ErrorKind, LlmCapabilities, LlmProvider,
+    RequestMetadata, RunnerError,
+};
+use embacle::{CancellationToken, ChoicesConfig, ChoicesProvider, FunctionDeclaration};
 use tracing::{debug, error, warn};
 
 use crate::openai_types::{
     ChatCompletionMessage, ChatCompletionRequest, ChatCompletionResponse, Choice, ContentPart,
-    ErrorResponse, MessageContent, ModelField, MultiplexProviderResult, MultiplexResponse,
-    ResponseFormatRequest, ResponseMessage, StopField, ToolCall, ToolCallFunction, ToolChoice,
-    Usage,
+    ErrorResponse, FileDetail, MessageContent, ModelField, MultiplexProviderResult,
+    MultiplexResponse, ResponseFormatRequest, ResponseMessage, StopField, StreamOptions, ToolCall,
+    ToolCallFunction, ToolChoice,
 };
 use crate::provider_resolver::resolve_model;
 use crate::runner::multiplex::{MultiplexEngine, MultiplexParams};
@@ -29,14 +34,44 @@ use crate::streaming;
 /// OpenAI-specified upper bound for temperature
 const MAX_TEMPERATURE: f32 = 2.0;
 
+/// Non-standard "Client Closed Request" status for cancelled completions
+const CLIENT_CLOSED_REQUEST: u16 = 499;
+
+/// Request header carrying the conversation identifier for session resume
+const CONVERSATION_ID_HEADER: &str = "x-conversation-id";
+
+/// Request header carrying the caller's request identifier, echoed on the response
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Request header carrying the request timeout in seconds
+const REQUEST_TIMEOUT_HEADER: &str = "x-request-timeout";
+
+/// Upper bound for the request timeout, in seconds
+const MAX_REQUEST_TIMEOUT_SECS: f64 = 3600.0;
+
+/// Upper bound for the `n` (number of choices) parameter
+const MAX_CHOICES: u32 = 16;
+
+/// Default number of fanned-out choice requests running at once
+const DEFAULT_CHOICE_CONCURRENCY: usize = 4;
+
 /// Handle POST /v1/chat/completions
 ///
 /// Dispatches to single-provider or multiplex mode based on the model field.
 /// Supports both streaming (SSE) and non-streaming (JSON) responses.
+/// The conversation identifier for CLI session resume is taken from the
+/// `conversation_id` field, falling back to the `X-Conversation-Id` header.
+/// `X-Request-Id` and the `user` field become the request's metadata, and the
+/// request id is echoed back on the response. The `timeout` field, falling
+/// back to the `X-Request-Timeout` header, sets a deadline in seconds from
+/// arrival that overrides the runner's configured timeout.
 pub async fn handle(
     State(state): State<SharedState>,
-    Json(request): Json<ChatCompletionRequest>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatCompletionRequest>,
 ) -> Response {
+    request.conversation_id = resolve_conversation_id(&headers, request.conversation_id.take());
+
     if let Some(temp) = request.temperature {
         if !(0.0..=MAX_TEMPERATURE).contains(&temp) {
             return error_response(
@@ -63,19 +98,53 @@ pub async fn handle(
             );
         }
     }
+    if let Some(n) = request.n {
+        if !(1..=MAX_CHOICES).contains(&n) {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                &format!("n must be between 1 and {MAX_CHOICES}"),
+            );
+        }
+        if n > 1 && request.stream {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "n greater than 1 is not supported with stream=true",
+            );
+        }
+    }
+
+    if let Err(message) = validate_file_parts(&request.messages) {
+        return error_response(StatusCode::BAD_REQUEST, &message);
+    }
+
+    let deadline = match resolve_timeout(&headers, request.timeout) {
+        Ok(timeout) => timeout.and_then(|t| Instant::now().checked_add(t)),
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
 
-    match request.model {
+    let metadata = request_metadata(&headers, &request);
+    let mut response = match request.model {
         ModelField::Multiple(ref models) if models.len() > 1 => {
-            handle_multiplex(&state, &request, models).await
+            handle_multiplex(&state, &request, models, &metadata, deadline).await
         }
         ModelField::Multiple(ref models) if models.len() == 1 => {
-            handle_single(&state, &request, &models[0]).await
+            handle_single(&state, &request, &models[0], &metadata, deadline).await
         }
         ModelField::Multiple(_) => {
             error_response(StatusCode::BAD_REQUEST, "Model array must not be empty")
         }
-        ModelField::Single(ref model) => handle_single(&state, &request, model).await,
+        ModelField::Single(ref model) => {
+            handle_single(&state, &request, model, &metadata, deadline).await
+        }
+    };
+    if let Some(value) = metadata
+        .request_id
+        .as_deref()
+        .and_then(|id| HeaderValue::from_str(id).ok())
+    {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
     }
+    response
 }
 
 /// Handle a single-provider request (standard case)
@@ -83,6 +152,8 @@ async fn handle_single(
     state: &SharedState,
     request: &ChatCompletionRequest,
     model_str: &str,
+    metadata: &RequestMetadata,
+    deadline: Option<Instant>,
 ) -> Response {
     let has_tools = request
         .tools
@@ -99,16 +170,20 @@ async fn handle_single(
         "Dispatching completion"
     );
 
-    let runner = match state_guard.get_runner(resolved.runner_type).await {
+    let mut runner = match state_guard.get_runner(resolved.runner_type).await {
         Ok(r) => r,
         Err(e) => return runner_error_to_response(&e),
     };
     drop(state_guard);
 
+    // Providers without native `n` support get one request per choice
+    if request.n.is_some_and(|n| n > 1) && !runner.capabilities().supports_multiple_choices() {
+        let config = ChoicesConfig::default().with_max_concurrency(choice_concurrency());
+        runner = Arc::new(ChoicesProvider::from_shared(runner, config));
+    }
+
     let strict = request.strict_capabilities.unwrap_or_else(|| {
-        std::env::var("EMBACLE_STRICT_CAPS")
-            .map(|v| v == "true" || v == "1")
-            .unwrap_or(false)
+        std::env::var("EMBACLE_STRICT_CAPS").is_ok_and(|v| v == "true" || v == "1")
     });
 
     let mut messages = convert_messages(&request.messages);
@@ -140,6 +215,13 @@ async fn handle_single(
         .as_ref()
         .map(|tools| tools.iter().map(server_tool_to_core).collect());
     chat_request.tool_choice = request.tool_choice.as_ref().map(server_choice_to_core);
+    chat_request
+        .session_key
+        .clone_from(&request.conversation_id);
+    chat_request.n = request.n;
+    chat_request.reasoning_effort = request.reasoning_effort;
+    chat_request.metadata = metadata.clone();
+    chat_request.deadline = deadline;
 
     let warnings = match embacle::validate_capabilities(
         runner.name(),
@@ -159,10 +241,12 @@ async fn handle_single(
     let supports_streaming = runner.capabilities().contains(LlmCapabilities::STREAMING);
 
     dispatch_completion(
-        runner.as_ref(),
+        runner,
         resolved.runner_type,
         chat_request,
-        request.stream,
+        request
+            .stream
+            .then(|| request.stream_options.unwrap_or_default()),
         has_tools,
         supports_streaming,
         warnings_for_response,
@@ -177,16 +261,24 @@ async fn handle_single(
 /// 2. Streaming without provider support: downgrade to `complete()`, emit as SSE
 /// 3. Pure streaming: use `complete_stream()`
 /// 4. Non-streaming: use `complete()`, return JSON
+///
+/// `stream` is `Some` with the request's stream options when SSE output was
+/// requested.
+///
+/// Non-streaming calls go through [`complete_until_disconnect`] so a client
+/// disconnect cancels the runner. Streaming responses need no extra wiring:
+/// axum drops the SSE body on disconnect, which drops the `ChatStream` and
+/// kills the underlying subprocess.
 async fn dispatch_completion(
-    runner: &dyn embacle::types::LlmProvider,
+    runner: Arc<dyn LlmProvider>,
     runner_type: embacle::config::CliRunnerType,
     mut chat_request: ChatRequest,
-    stream: bool,
+    stream: Option<StreamOptions>,
     has_tools: bool,
     supports_streaming: bool,
     warnings: Option<Vec<String>>,
 ) -> Response {
-    if stream && (has_tools || !supports_streaming) {
+    if let Some(options) = stream.filter(|_| has_tools || !supports_streaming) {
         // Downgrade to non-streaming complete(), emit result as SSE
         if has_tools {
             debug!("Downgrading stream+tools to non-streaming complete");
@@ -196,56 +288,48 @@ async fn dispatch_completion(
                 "Provider does not support streaming; downgrading to non-streaming complete"
             );
         }
-        match runner.complete(&chat_request).await {
+        match complete_until_disconnect(runner, chat_request).await {
             Ok(response) => {
                 let model_name = format!("{runner_type}:{}", response.model);
-                let (message, finish_reason) = build_response_message(
+                let (mut message, finish_reason) = build_response_message(
                     has_tools,
                     response.content,
                     response.finish_reason,
                     response.tool_calls.as_ref(),
                 );
+                message.reasoning_content = response.reasoning;
                 let reason = finish_reason.as_deref().unwrap_or("stop");
-                streaming::sse_single_response(message, reason, &model_name)
+                streaming::sse_single_response(
+                    message,
+                    reason,
+                    &model_name,
+                    response.usage,
+                    options,
+                )
             }
             Err(e) => runner_error_to_response(&e),
         }
-    } else if stream {
+    } else if let Some(options) = stream {
         chat_request.stream = true;
         match runner.complete_stream(&chat_request).await {
             Ok(s) => {
                 let model_name = format!("{runner_type}:{}", runner.default_model());
-                streaming::sse_response(s, &model_name)
+                streaming::sse_response(s, &model_name, options)
             }
             Err(e) => runner_error_to_response(&e),
         }
     } else {
-        match runner.complete(&chat_request).await {
+        match complete_until_disconnect(runner, chat_request).await {
             Ok(response) => {
                 let model_name = format!("{runner_type}:{}", response.model);
-                let usage = response.usage.map(|u| Usage {
-                    prompt: u.prompt_tokens,
-                    completion: u.completion_tokens,
-                    total: u.total_tokens,
-                });
-
-                let (message, finish_reason) = build_response_message(
-                    has_tools,
-                    response.content,
-                    response.finish_reason,
-                    response.tool_calls.as_ref(),
-                );
+                let usage = response.usage.as_ref().map(streaming::usage_from_core);
 
                 let resp = ChatCompletionResponse {
                     id: generate_id(),
                     object: "chat.completion",
                     created: unix_timestamp(),
                     model: model_name,
-                    choices: vec![Choice {
-                        index: 0,
-                        message,
-                        finish_reason,
-                    }],
+                    choices: response_choices(has_tools, response),
                     usage,
                     warnings,
                 };
@@ -257,11 +341,128 @@ async fn dispatch_completion(
     }
 }
 
+/// Convert a completion into `OpenAI` choices, one per candidate when `n > 1`
+///
+/// The response's reasoning text belongs to the first choice.
+fn response_choices(has_tools: bool, response: ChatResponse) -> Vec<Choice> {
+    let mut reasoning = response.reasoning;
+    let candidates = response.choices.unwrap_or_else(|| {
+        vec![ChatChoice {
+            index: 0,
+            content: response.content,
+            finish_reason: response.finish_reason,
+            tool_calls: response.tool_calls,
+        }]
+    });
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let (mut message, finish_reason) = build_response_message(
+                has_tools,
+                candidate.content,
+                candidate.finish_reason,
+                candidate.tool_calls.as_ref(),
+            );
+            if candidate.index == 0 {
+                message.reasoning_content = reasoning.take();
+            }
+            Choice {
+                index: candidate.index,
+                message,
+                finish_reason,
+            }
+        })
+        .collect()
+}
+
+/// Concurrency cap for fanned-out choices from `EMBACLE_CHOICE_CONCURRENCY`
+fn choice_concurrency() -> usize {
+    std::env::var("EMBACLE_CHOICE_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_CHOICE_CONCURRENCY)
+}
+
+/// Run `complete_with_cancel()` on a detached task tied to the request lifetime
+///
+/// Axum drops the handler future when the HTTP client disconnects. The drop
+/// guard then cancels the token, letting the runner kill its subprocess (and
+/// send ACP `session/cancel`) instead of running until its own timeout.
+async fn complete_until_disconnect(
+    runner: Arc<dyn LlmProvider>,
+    request: ChatRequest,
+) -> Result<ChatResponse, RunnerError> {
+    let cancel = CancellationToken::new();
+    let _disconnect_guard = cancel.clone().drop_guard();
+    let task = tokio::spawn(async move { runner.complete_with_cancel(&request, &cancel).await });
+    task.await.unwrap_or_else(|e| {
+        Err(RunnerError::internal(format!(
+            "Completion task failed: {e}"
+        )))
+    })
+}
+
+/// Pick the conversation identifier from the body field or the request header
+///
+/// Blank values are ignored so clients can send an empty header without
+/// accidentally sharing one session across all requests.
+fn resolve_conversation_id(headers: &HeaderMap, field: Option<String>) -> Option<String> {
+    field.filter(|id| !id.trim().is_empty()).or_else(|| {
+        headers
+            .get(CONVERSATION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_owned)
+    })
+}
+
+/// Build request metadata from the `X-Request-Id` header and the `user` field
+fn request_metadata(headers: &HeaderMap, request: &ChatCompletionRequest) -> RequestMetadata {
+    RequestMetadata {
+        request_id: headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_owned),
+        user: request.user.clone().filter(|user| !user.trim().is_empty()),
+        tags: BTreeMap::new(),
+    }
+}
+
+/// Resolve the request timeout from the `timeout` field or the `X-Request-Timeout` header
+///
+/// The field wins when both are set. Values must be positive and at most
+/// [`MAX_REQUEST_TIMEOUT_SECS`].
+fn resolve_timeout(headers: &HeaderMap, field: Option<f64>) -> Result<Option<Duration>, String> {
+    let secs = match field {
+        Some(secs) => secs,
+        None => match headers.get(REQUEST_TIMEOUT_HEADER) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .ok_or_else(|| "X-Request-Timeout must be a number of seconds".to_owned())?,
+            None => return Ok(None),
+        },
+    };
+    if secs.is_nan() || secs <= 0.0 || secs > MAX_REQUEST_TIMEOUT_SECS {
+        return Err(format!(
+            "timeout must be greater than 0 and at most {MAX_REQUEST_TIMEOUT_SECS} seconds"
+        ));
+    }
+    Ok(Some(Duration::from_secs_f64(secs)))
+}
+
 /// Handle a multiplex request (multiple providers)
 async fn handle_multiplex(
     state: &SharedState,
     request: &ChatCompletionRequest,
     models: &[String],
+    metadata: &RequestMetadata,
+    deadline: Option<Instant>,
 ) -> Response {
     if request.stream {
         return error_response(
@@ -271,9 +472,7 @@ async fn handle_multiplex(
     }
 
     let strict = request.strict_capabilities.unwrap_or_else(|| {
-        std::env::var("EMBACLE_STRICT_CAPS")
-            .map(|v| v == "true" || v == "1")
-            .unwrap_or(false)
+        std::env::var("EMBACLE_STRICT_CAPS").is_ok_and(|v| v == "true" || v == "1")
     });
 
     let state_guard = state.read().await;
@@ -323,6 +522,8 @@ async fn handle_multiplex(
         top_p: request.top_p,
         stop: request.stop.as_ref().map(StopField::to_bounded_vec),
         response_format: request.response_format.as_ref().map(server_format_to_core),
+        metadata: metadata.clone(),
+        deadline,
     };
     match engine.execute(&messages, &providers, &params).await {
         Ok(result) => {
@@ -387,6 +588,7 @@ fn build_response_message(
                     role: "assistant",
                     content: text_content,
                     tool_calls: Some(tool_calls),
+                    reasoning_content: None,
                 },
                 Some("tool_calls".to_owned()),
             );
@@ -402,6 +604,7 @@ fn build_response_message(
                     role: "assistant",
                     content: Some(content),
                     tool_calls: None,
+                    reasoning_content: None,
                 },
                 finish_reason.or_else(|| Some("stop".to_owned())),
             )
@@ -431,6 +634,7 @@ fn build_response_message(
                     role: "assistant",
                     content: text_content,
                     tool_calls: Some(tool_calls),
+                    reasoning_content: None,
                 },
                 Some("tool_calls".to_owned()),
             )
@@ -441,6 +645,7 @@ fn build_response_message(
                 role: "assistant",
                 content: Some(content),
                 tool_calls: None,
+                reasoning_content: None,
             },
             finish_reason.or_else(|| Some("stop".to_owned())),
         )
@@ -471,7 +676,7 @@ fn extract_images(content: Option<&MessageContent>) -> Option<Vec<embacle::Image
         .iter()
         .filter_map(|p| match p {
             ContentPart::ImageUrl { image_url } => parse_data_uri(&image_url.url),
-            ContentPart::Text { .. } => None,
+            ContentPart::Text { .. } | ContentPart::File { .. } => None,
         })
         .collect();
 
@@ -482,13 +687,79 @@ fn extract_images(content: Option<&MessageContent>) -> Option<Vec<embacle::Image
     }
 }
 
+/// Decode a `file` content part into an attachment
+///
+/// Only inline `file_data` data URIs are supported; `file_id` references are
+/// rejected because the server does not store uploads.
+fn file_attachment(file: &FileDetail) -> Result<embacle::AttachmentPart, String> {
+    let Some(ref file_data) = file.file_data else {
+        return Err(if file.file_id.is_some() {
+            "file_id references are not supported; send the file as file_data".to_owned()
+        } else {
+            "file content part must carry file_data".to_owned()
+        });
+    };
+    let (mime_type, data) = file_data
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .ok_or_else(|| "file_data must be a base64 data URI".to_owned())?;
+    let attachment = embacle::AttachmentPart::from_base64(data, mime_type)
+        .and_then(|a| a.read_bytes().map(|_| a))
+        .map_err(|e| format!("invalid file_data: {}", e.message))?;
+    Ok(match file.filename {
+        Some(ref name) => attachment.with_filename(name),
+        None => attachment,
+    })
+}
+
+/// Reject `file` content parts that cannot be turned into attachments
+///
+/// Runs before dispatch so a request is never answered without its files.
+fn validate_file_parts(messages: &[ChatCompletionMessage]) -> Result<(), String> {
+    for message in messages {
+        let Some(MessageContent::Parts(ref parts)) = message.content else {
+            continue;
+        };
+        for part in parts {
+            if let ContentPart::File { file } = part {
+                file_attachment(file)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Extract file attachments from a `MessageContent::Parts` variant
+///
+/// Parts are checked by [`validate_file_parts`] first; any that fail to
+/// decode here are skipped.
+fn extract_attachments(content: Option<&MessageContent>) -> Option<Vec<embacle::AttachmentPart>> {
+    let Some(MessageContent::Parts(parts)) = content else {
+        return None;
+    };
+
+    let attachments: Vec<embacle::AttachmentPart> = parts
+        .iter()
+        .filter_map(|p| match p {
+            ContentPart::File { file } => file_attachment(file).ok(),
+            ContentPart::Text { .. } | ContentPart::ImageUrl { .. } => None,
+        })
+        .collect();
+
+    if attachments.is_empty() {
+        None
+    } else {
+        Some(attachments)
+    }
+}
+
 /// Convert `OpenAI` message format to embacle `ChatMessage`
 ///
 /// Handles all `OpenAI` roles including "tool" messages and assistant messages
 /// with `tool_calls`. Tool messages are collected and formatted as `<tool_result>`
 /// blocks. Assistant messages with `tool_calls` are reconstructed as `<tool_call>` blocks.
-/// User messages with multipart content (text + images) are converted to `ChatMessage`
-/// with attached `ImagePart` entries.
+/// User messages with multipart content (text + images + files) are converted to
+/// `ChatMessage` with attached `ImagePart` and `AttachmentPart` entries.
 fn convert_messages(messages: &[ChatCompletionMessage]) -> Vec<ChatMessage> {
     let mut result = Vec::with_capacity(messages.len());
     let mut i = 0;
@@ -502,12 +773,12 @@ fn convert_messages(messages: &[ChatCompletionMessage]) -> Vec<ChatMessage> {
             }
             "user" => {
                 let text = content_as_text(m.content.as_ref());
-                let images = extract_images(m.content.as_ref());
-                if let Some(imgs) = images {
-                    result.push(ChatMessage::user_with_images(text, imgs));
-                } else {
-                    result.push(ChatMessage::user(text));
-                }
+                let mut message = match extract_images(m.content.as_ref()) {
+                    Some(imgs) => ChatMessage::user_with_images(text, imgs),
+                    None => ChatMessage::user(text),
+                };
+                message.attachments = extract_attachments(m.content.as_ref());
+                result.push(message);
                 i += 1;
             }
             "assistant" => {
@@ -653,12 +924,29 @@ fn runner_error_to_response(err: &RunnerError) -> Response {
         ErrorKind::ExternalService => (StatusCode::BAD_GATEWAY, "external_service_error"),
         ErrorKind::Config => (StatusCode::BAD_REQUEST, "invalid_request_error"),
         ErrorKind::Guardrail => (StatusCode::BAD_REQUEST, "guardrail_error"),
+        ErrorKind::Cancelled => (
+            StatusCode::from_u16(CLIENT_CLOSED_REQUEST)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            "request_cancelled",
+        ),
+        ErrorKind::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "rate_limit_exceeded"),
+        ErrorKind::QuotaExhausted => (StatusCode::TOO_MANY_REQUESTS, "insufficient_quota"),
+        ErrorKind::ContextLengthExceeded => (StatusCode::BAD_REQUEST, "context_length_exceeded"),
+        ErrorKind::ContentFiltered => (StatusCode::BAD_REQUEST, "content_filter"),
         ErrorKind::Internal => (StatusCode::INTERNAL_SERVER_ERROR, "server_error"),
     };
 
     error!(kind = ?err.kind, message = %err.message, "Runner error");
     let body = ErrorResponse::new(error_type, &err.message);
-    (status, Json(body)).into_response()
+    let mut response = (status, Json(body)).into_response();
+    if let Some(retry_after) = err.retry_after {
+        // Retry-After is whole seconds; round up so clients never retry early
+        let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+    }
+    response
 }
 
 /// Build an error response with a given status and message
@@ -684,17 +972,17 @@ pub fn generate_id() -> String {
 pub fn unix_timestamp() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0)
+        .map_or(0, |d| d.as_secs())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::openai_types::{
-        ContentPart, FunctionObject, ImageUrlDetail, ToolCall, ToolCallFunction, ToolDefinition,
+        ContentPart, FileDetail, FunctionObject, ImageUrlDetail, ToolCall, ToolCallFunction,
+        ToolDefinition,
     };
-    use embacle::types::MessageRole;
+    use embacle::types::{MessageRole, RequestMetadata};
 
     /// Helper to create a `ChatCompletionMessage` with plain text content
     fn text_msg(role: &str, content: Option<&str>) -> ChatCompletionMessage {
@@ -820,6 +1108,92 @@ mod tests {
         assert_eq!(images[0].data, "aGVsbG8=");
     }
 
+    #[test]
+    fn convert_multipart_user_message_extracts_file_parts() {
+        let file_part = |file_data: Option<&str>, file_id: Option<&str>| ContentPart::File {
+            file: FileDetail {
+                file_data: file_data.map(ToOwned::to_owned),
+                filename: Some("report.pdf".to_owned()),
+                file_id: file_id.map(ToOwned::to_owned),
+            },
+        };
+        let openai_msgs = vec![ChatCompletionMessage {
+            role: "user".to_owned(),
+            content: Some(MessageContent::Parts(vec![
+                ContentPart::Text {
+                    text: "Summarize".to_owned(),
+                },
+                file_part(Some("data:application/pdf;base64,JVBERi0="), None),
+                file_part(None, Some("file-abc123")),
+            ])),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+        }];
+
+        let messages = convert_messages(&openai_msgs);
+        assert_eq!(messages[0].content, "Summarize");
+        assert!(messages[0].images.is_none());
+        let attachments = messages[0]
+            .attachments
+            .as_ref()
+            .expect("attachments present");
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].mime_type, "application/pdf");
+        assert_eq!(attachments[0].filename.as_deref(), Some("report.pdf"));
+    }
+
+    #[test]
+    fn validate_file_parts_rejects_unusable_files() {
+        let message = |file_data: Option<&str>, file_id: Option<&str>| ChatCompletionMessage {
+            role: "user".to_owned(),
+            content: Some(MessageContent::Parts(vec![ContentPart::File {
+                file: FileDetail {
+                    file_data: file_data.map(ToOwned::to_owned),
+                    filename: None,
+                    file_id: file_id.map(ToOwned::to_owned),
+                },
+            }])),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+        };
+
+        assert!(validate_file_parts(&[message(Some("data:text/plain;base64,aGk="), None)]).is_ok());
+        let err = validate_file_parts(&[message(None, Some("file-abc123"))]).unwrap_err();
+        assert!(err.contains("file_id"), "{err}");
+        let err = validate_file_parts(&[message(Some("not a data uri"), None)]).unwrap_err();
+        assert!(err.contains("data URI"), "{err}");
+        let err =
+            validate_file_parts(&[message(Some("data:text/plain;base64,!!"), None)]).unwrap_err();
+        assert!(err.contains("invalid file_data"), "{err}");
+    }
+
+    #[test]
+    fn response_choices_returns_every_candidate() {
+        let candidate = |index: u32, content: &str| ChatChoice {
+            index,
+            content: content.to_owned(),
+            finish_reason: Some("stop".to_owned()),
+            tool_calls: None,
+        };
+        let response = ChatResponse {
+            content: "first".to_owned(),
+            model: "m".to_owned(),
+            usage: None,
+            finish_reason: Some("stop".to_owned()),
+            warnings: None,
+            tool_calls: None,
+            reasoning: None,
+            choices: Some(vec![candidate(0, "first"), candidate(1, "second")]),
+            metadata: RequestMetadata::default(),
+        };
+        let choices = response_choices(false, response);
+        assert_eq!(choices.len(), 2);
+        assert_eq!(choices[1].index, 1);
+        assert_eq!(choices[1].message.content.as_deref(), Some("second"));
+    }
+
     #[test]
     fn parse_data_uri_valid() {
         let img = parse_data_uri("data:image/jpeg;base64,AAAA").expect("should parse");
@@ -928,6 +1302,160 @@ mod tests {
         assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
     }
 
+    #[test]
+    fn conversation_id_prefers_field_over_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONVERSATION_ID_HEADER, "from-header".parse().unwrap());
+
+        assert_eq!(
+            resolve_conversation_id(&headers, Some("from-field".to_owned())).as_deref(),
+            Some("from-field")
+        );
+        assert_eq!(
+            resolve_conversation_id(&headers, None).as_deref(),
+            Some("from-header")
+        );
+        assert_eq!(
+            resolve_conversation_id(&headers, Some("  ".to_owned())).as_deref(),
+            Some("from-header")
+        );
+        assert!(resolve_conversation_id(&HeaderMap::new(), None).is_none());
+    }
+
+    #[test]
+    fn request_metadata_reads_request_id_header_and_user_field() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, " req-42 ".parse().unwrap());
+        let request: ChatCompletionRequest = serde_json::from_str(
+            r#"{"model":"copilot","messages":[{"role":"user","content":"hi"}],"user":"alice"}"#,
+        )
+        .unwrap();
+
+        let metadata = request_metadata(&headers, &request);
+        assert_eq!(metadata.request_id.as_deref(), Some("req-42"));
+        assert_eq!(metadata.user.as_deref(), Some("alice"));
+        assert!(request_metadata(&HeaderMap::new(), &request)
+            .request_id
+            .is_none());
+    }
+
+    #[test]
+    fn timeout_field_wins_over_header_and_is_validated() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_TIMEOUT_HEADER, "30".parse().unwrap());
+        assert_eq!(
+            resolve_timeout(&headers, None),
+            Ok(Some(Duration::from_secs(30)))
+        );
+        assert_eq!(
+            resolve_timeout(&headers, Some(1.5)),
+            Ok(Some(Duration::from_millis(1500)))
+        );
+        assert_eq!(resolve_timeout(&HeaderMap::new(), None), Ok(None));
+        assert!(resolve_timeout(&HeaderMap::new(), Some(0.0)).is_err());
+        assert!(resolve_timeout(&HeaderMap::new(), Some(MAX_REQUEST_TIMEOUT_SECS + 1.0)).is_err());
+
+        headers.insert(REQUEST_TIMEOUT_HEADER, "soon".parse().unwrap());
+        assert!(resolve_timeout(&headers, None).is_err());
+    }
+
+    #[test]
+    fn error_maps_cancelled_to_499() {
+        let response = runner_error_to_response(&RunnerError::cancelled("client went away"));
+        assert_eq!(response.status().as_u16(), CLIENT_CLOSED_REQUEST);
+    }
+
+    #[test]
+    fn error_maps_rate_limit_to_429_with_retry_after() {
+        let err = RunnerError::rate_limited("slow down")
+            .with_retry_after(Some(std::time::Duration::from_millis(2500)));
+        let response = runner_error_to_response(&err);
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+    }
+
+    #[test]
+    fn error_maps_context_and_quota_kinds() {
+        let context =
+            runner_error_to_response(&RunnerError::context_length_exceeded("prompt too long"));
+        assert_eq!(context.status(), StatusCode::BAD_REQUEST);
+        assert!(context.headers().get(header::RETRY_AFTER).is_none());
+
+        let quota = runner_error_to_response(&RunnerError::quota_exhausted("no credits"));
+        assert_eq!(quota.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let filtered = runner_error_to_response(&RunnerError::content_filtered("blocked"));
+        assert_eq!(filtered.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Provider whose completion blocks until cancelled, recording the cancellation
+    struct BlockingProvider {
+        started: Arc<tokio::sync::Notify>,
+        cancelled: Arc<tokio::sync::Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for BlockingProvider {
+        fn name(&self) -> &'static str {
+            "blocking"
+        }
+        fn display_name(&self) -> &str {
+            "Blocking"
+        }
+        fn capabilities(&self) -> LlmCapabilities {
+            LlmCapabilities::empty()
+        }
+        fn default_model(&self) -> &'static str {
+            "blocking-model"
+        }
+        fn available_models(&self) -> &[String] {
+            &[]
+        }
+        async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+            self.complete_with_cancel(request, &CancellationToken::new())
+                .await
+        }
+        async fn complete_with_cancel(
+            &self,
+            _request: &ChatRequest,
+            cancel: &CancellationToken,
+        ) -> Result<ChatResponse, RunnerError> {
+            self.started.notify_one();
+            cancel.cancelled().await;
+            self.cancelled.notify_one();
+            Err(RunnerError::cancelled("blocking: cancelled"))
+        }
+        async fn complete_stream(
+            &self,
+            _request: &ChatRequest,
+        ) -> Result<embacle::types::ChatStream, RunnerError> {
+            Err(RunnerError::internal("not supported"))
+        }
+        async fn health_check(&self) -> Result<bool, RunnerError> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn dropped_handler_cancels_runner() {
+        let started = Arc::new(tokio::sync::Notify::new());
+        let cancelled = Arc::new(tokio::sync::Notify::new());
+        let runner: Arc<dyn LlmProvider> = Arc::new(BlockingProvider {
+            started: Arc::clone(&started),
+            cancelled: Arc::clone(&cancelled),
+        });
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        // Simulate axum dropping the handler future on client disconnect
+        let handler = tokio::spawn(complete_until_disconnect(runner, request));
+        started.notified().await;
+        handler.abort();
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), cancelled.notified())
+            .await
+            .expect("runner should observe cancellation");
+    }
+
     #[test]
     fn inject_tool_catalog_as_user_message_prepends_to_last_user() {
         let mut messages = vec![
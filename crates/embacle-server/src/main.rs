@@ -5,9 +5,14 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use clap::Parser;
-use embacle::types::RunnerError;
+use embacle::cache::CacheConfig;
+use embacle::cache_backend::DiskCacheBackend;
+use embacle::config::CliRunnerType;
+use embacle::types::{LlmProvider, RunnerError};
+use embacle::{AcpAgentServer, EmbacleConfig};
 use embacle_mcp::transport::McpTransport;
 use embacle_mcp::ServerState;
 use tokio::sync::RwLock;
@@ -19,7 +24,8 @@ use embacle_server::runner::{self, parse_runner_type};
 #[derive(Parser)]
 #[command(name = "embacle-server", version, about)]
 struct Cli {
-    /// Transport mode: "http" for REST API + MCP, "stdio" for MCP-only stdin/stdout
+    /// Transport mode: "http" for REST API + MCP, "stdio" for MCP-only stdin/stdout,
+    /// "acp" to act as an Agent Client Protocol agent on stdin/stdout
     #[arg(long, default_value = "http")]
     transport: String,
 
@@ -42,8 +48,8 @@ struct Cli {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
-    // stdio transport needs stderr-only logging to keep stdout clean for JSON-RPC
-    let is_stdio = cli.transport == "stdio";
+    // stdio and acp transports need stderr-only logging to keep stdout clean for JSON-RPC
+    let is_stdio = matches!(cli.transport.as_str(), "stdio" | "acp");
     if is_stdio {
         tracing_subscriber::fmt()
             .with_env_filter(
@@ -76,29 +82,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     // Try loading config file (best-effort — works without one)
-    let effective_provider = match embacle::load_config() {
-        Ok(Some(ref cfg)) => {
-            // Only use config file default if CLI --provider was not explicitly set
-            // clap sets the default to "copilot", so if the user didn't specify --provider
-            // we check if there's a config file override
-            if cli.provider == "copilot" {
-                cfg.defaults
-                    .model
-                    .as_deref()
-                    .and_then(parse_runner_type)
-                    .unwrap_or(effective_provider)
-            } else {
-                effective_provider
-            }
-        }
-        Ok(None) => effective_provider,
-        Err(e) => {
-            tracing::warn!(error = %e, "Failed to load config file, using CLI defaults");
-            effective_provider
-        }
+    let config = embacle::load_config().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "Failed to load config file, using CLI defaults");
+        None
+    });
+
+    // Only use config file default if CLI --provider was not explicitly set
+    // clap sets the default to "copilot", so if the user didn't specify --provider
+    // we check if there's a config file override
+    let effective_provider = match config {
+        Some(ref cfg) if cli.provider == "copilot" => cfg
+            .defaults
+            .model
+            .as_deref()
+            .and_then(parse_runner_type)
+            .unwrap_or(effective_provider),
+        _ => effective_provider,
     };
 
-    let state = Arc::new(RwLock::new(ServerState::new(effective_provider)));
+    let server_state = build_server_state(effective_provider, config.as_ref())?;
+    let state = Arc::new(RwLock::new(server_state));
 
     tracing::info!(
         transport = %cli.transport,
@@ -116,6 +119,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .serve(server)
                 .await?;
         }
+        "acp" => {
+            // A configured fallback chain takes precedence over the single default provider
+            let fallback = match config {
+                Some(ref cfg) => embacle::build_fallback_from_config(cfg).await?,
+                None => None,
+            };
+            let provider: Arc<dyn LlmProvider> = match fallback {
+                Some(chain) => Arc::new(chain),
+                None => state.read().await.get_runner(effective_provider).await?,
+            };
+            // No tool handlers are available here, so prompts are served without
+            // the AgentExecutor tool loop; embed AcpAgentServer::with_tools for that
+            Arc::new(AcpAgentServer::new(provider))
+                .serve_stdio()
+                .await?;
+        }
         "http" => {
             let app = router::build(state);
             let addr = format!("{}:{}", cli.host, cli.port);
@@ -131,7 +150,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         other => {
             return Err(RunnerError::config(format!(
-                "Unknown transport: {other}. Valid: http, stdio"
+                "Unknown transport: {other}. Valid: http, stdio, acp"
             ))
             .into());
         }
@@ -139,3 +158,53 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Server state with config-file overrides and rate limits, plus env-enabled caching
+fn build_server_state(
+    provider: CliRunnerType,
+    config: Option<&EmbacleConfig>,
+) -> Result<ServerState, RunnerError> {
+    let mut server_state = ServerState::new(provider);
+    if let Some(cfg) = config {
+        server_state.set_model_overrides(embacle::model_overrides(cfg)?);
+        server_state.set_rate_limits(embacle::rate_limits(cfg)?);
+    }
+    if let Some((cache_config, backend)) = disk_cache_from_env()? {
+        tracing::info!(dir = %backend.dir().display(), "Response cache enabled");
+        server_state.set_response_cache(cache_config, Arc::new(backend));
+    }
+    if std::env::var("EMBACLE_SINGLE_FLIGHT").is_ok_and(|v| v == "true" || v == "1") {
+        server_state.set_single_flight(true);
+    }
+    Ok(server_state)
+}
+
+/// On-disk response cache from `EMBACLE_CACHE_DIR`, if set
+///
+/// `EMBACLE_CACHE_TTL_SECS`, `EMBACLE_CACHE_MAX_ENTRIES` and
+/// `EMBACLE_CACHE_MAX_BYTES` override the defaults.
+fn disk_cache_from_env() -> Result<Option<(CacheConfig, DiskCacheBackend)>, RunnerError> {
+    let Some(dir) = std::env::var_os("EMBACLE_CACHE_DIR").filter(|d| !d.is_empty()) else {
+        return Ok(None);
+    };
+    let env_u64 = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+
+    let mut config = CacheConfig::default();
+    if let Some(secs) = env_u64("EMBACLE_CACHE_TTL_SECS") {
+        config.ttl = Duration::from_secs(secs);
+    }
+    if let Some(max_entries) = env_u64("EMBACLE_CACHE_MAX_ENTRIES") {
+        config.max_entries = usize::try_from(max_entries).unwrap_or(usize::MAX);
+    }
+    config.max_bytes = env_u64("EMBACLE_CACHE_MAX_BYTES");
+
+    let mut backend = DiskCacheBackend::open(dir, config.max_entries)?;
+    if let Some(max_bytes) = config.max_bytes {
+        backend = backend.with_max_bytes(max_bytes);
+    }
+    Ok(Some((config, backend)))
+}
@@ -9,6 +9,7 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
 use embacle::discovery::resolve_binary;
+use embacle::model_catalog::{capability_names, ModelInfo};
 use tracing::debug;
 
 use crate::openai_types::{ModelObject, ModelsResponse};
@@ -18,11 +19,12 @@ use crate::state::SharedState;
 /// Handle GET /v1/models
 ///
 /// Probes each known provider to check if its CLI binary is installed.
-/// For installed providers, lists their available models in `OpenAI` format
-/// with provider prefix (e.g., "copilot:gpt-4o").
+/// For installed providers, refreshes the runtime-discovered model list
+/// (cached per runner for its TTL) and lists the model catalog in `OpenAI` format
+/// with provider prefix (e.g., "copilot:gpt-4o") plus context window,
+/// capability and pricing metadata.
 pub async fn handle(State(state): State<SharedState>) -> impl IntoResponse {
     let mut data = Vec::new();
-    let state_guard = state.read().await;
 
     for &provider in ALL_PROVIDERS {
         let binary_name = provider.binary_name();
@@ -34,26 +36,34 @@ pub async fn handle(State(state): State<SharedState>) -> impl IntoResponse {
             continue;
         }
 
-        match state_guard.get_runner(provider).await {
+        // The state lock is not held across the refresh, which may run the CLI
+        let runner_result = state.read().await.get_runner(provider).await;
+        match runner_result {
             Ok(runner) => {
                 let provider_name = runner.name();
-                let models = runner.available_models();
+                if let Err(e) = runner.refresh_models().await {
+                    debug!(provider = %provider, error = %e, "Model refresh failed, using cached list");
+                }
+                let catalog = state.read().await.model_catalog(provider, runner.as_ref());
 
-                if models.is_empty() {
+                if catalog.is_empty() {
                     // Provider has no model list — expose just the provider name
                     data.push(ModelObject {
                         id: provider_name.to_owned(),
                         object: "model",
                         owned_by: provider_name.to_owned(),
+                        aliases: Vec::new(),
+                        context_window: None,
+                        max_output_tokens: None,
+                        capabilities: capability_names(runner.capabilities()),
+                        pricing: None,
                     });
                 } else {
-                    for model in models {
-                        data.push(ModelObject {
-                            id: format!("{provider_name}:{model}"),
-                            object: "model",
-                            owned_by: provider_name.to_owned(),
-                        });
-                    }
+                    data.extend(
+                        catalog
+                            .into_iter()
+                            .map(|info| model_object(provider_name, info)),
+                    );
                 }
             }
             Err(e) => {
@@ -69,3 +79,17 @@ pub async fn handle(State(state): State<SharedState>) -> impl IntoResponse {
 
     (StatusCode::OK, Json(resp))
 }
+
+/// Convert a catalog entry into a provider-prefixed `OpenAI` model object
+fn model_object(provider_name: &str, info: ModelInfo) -> ModelObject {
+    ModelObject {
+        id: format!("{provider_name}:{}", info.id),
+        object: "model",
+        owned_by: provider_name.to_owned(),
+        aliases: info.aliases,
+        context_window: info.context_window,
+        max_output_tokens: info.max_output_tokens,
+        capabilities: capability_names(info.capabilities),
+        pricing: info.pricing,
+    }
+}
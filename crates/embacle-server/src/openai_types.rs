@@ -4,6 +4,8 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
 
+use embacle::types::ReasoningEffort;
+use embacle::TokenPricing;
 use serde::{Deserialize, Serialize};
 
 // ============================================================================
@@ -24,6 +26,9 @@ pub struct ChatCompletionRequest {
     /// Whether to stream the response
     #[serde(default)]
     pub stream: bool,
+    /// Options for streamed responses (only honored when `stream` is true)
+    #[serde(default)]
+    pub stream_options: Option<StreamOptions>,
     /// Temperature for response randomness (0.0 - 2.0)
     #[serde(default)]
     pub temperature: Option<f32>,
@@ -48,6 +53,29 @@ pub struct ChatCompletionRequest {
     /// Stop sequence(s) that halt generation — single string or array
     #[serde(default)]
     pub stop: Option<StopField>,
+    /// Conversation identifier for CLI session resume (overrides `X-Conversation-Id`)
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    /// Number of candidate choices to generate
+    #[serde(default)]
+    pub n: Option<u32>,
+    /// End-user identifier, recorded in the request metadata
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Reasoning effort: "minimal", "low", "medium" or "high"
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Request timeout in seconds (overrides `X-Request-Timeout`)
+    #[serde(default)]
+    pub timeout: Option<f64>,
+}
+
+/// Options for streamed responses
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct StreamOptions {
+    /// Send a final chunk with empty `choices` carrying the token usage
+    #[serde(default)]
+    pub include_usage: bool,
 }
 
 /// Stop field that accepts either a single string or an array of strings
@@ -158,7 +186,7 @@ impl MessageContent {
                 .iter()
                 .filter_map(|p| match p {
                     ContentPart::Text { text } => Some(text.as_str()),
-                    ContentPart::ImageUrl { .. } => None,
+                    ContentPart::ImageUrl { .. } | ContentPart::File { .. } => None,
                 })
                 .collect::<Vec<_>>()
                 .join(""),
@@ -182,6 +210,26 @@ pub enum ContentPart {
         /// Image URL details
         image_url: ImageUrlDetail,
     },
+    /// File content part (PDFs, CSVs, logs, source files, ...)
+    #[serde(rename = "file")]
+    File {
+        /// File details
+        file: FileDetail,
+    },
+}
+
+/// File details within a content part
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileDetail {
+    /// File contents as a data URI like `data:application/pdf;base64,...`
+    #[serde(default)]
+    pub file_data: Option<String>,
+    /// Original file name
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// Uploaded file id (not supported; uploads are not stored)
+    #[serde(default)]
+    pub file_id: Option<String>,
 }
 
 /// Image URL details within a content part
@@ -315,7 +363,7 @@ pub struct ChatCompletionResponse {
 /// A single choice in a chat completion response
 #[derive(Debug, Serialize)]
 pub struct Choice {
-    /// Choice index (always 0)
+    /// Choice index (0 unless several choices were requested with `n`)
     pub index: u32,
     /// Generated message
     pub message: ResponseMessage,
@@ -334,6 +382,9 @@ pub struct ResponseMessage {
     /// Tool calls requested by the assistant
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Reasoning the model produced before answering
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
 }
 
 /// Token usage statistics
@@ -348,6 +399,26 @@ pub struct Usage {
     /// Total tokens
     #[serde(rename = "total_tokens")]
     pub total: u32,
+    /// Breakdown of prompt tokens, present when the provider reports cache hits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    /// Breakdown of completion tokens, present when the provider reports reasoning tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
+}
+
+/// Prompt token breakdown
+#[derive(Debug, Serialize)]
+pub struct PromptTokensDetails {
+    /// Prompt tokens served from the provider's prompt cache
+    pub cached_tokens: u32,
+}
+
+/// Completion token breakdown
+#[derive(Debug, Serialize)]
+pub struct CompletionTokensDetails {
+    /// Completion tokens spent on reasoning
+    pub reasoning_tokens: u32,
 }
 
 // ============================================================================
@@ -365,8 +436,11 @@ pub struct ChatCompletionChunk {
     pub created: u64,
     /// Model used for generation
     pub model: String,
-    /// Streaming choices
+    /// Streaming choices (empty on the usage-only chunk)
     pub choices: Vec<ChunkChoice>,
+    /// Token usage (only on the usage-only chunk requested via `stream_options`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 /// A single choice in a streaming chunk
@@ -389,9 +463,51 @@ pub struct Delta {
     /// Content token (empty string on role-only or final chunk)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
-    /// Tool calls (reserved for future streaming tool call support)
+    /// Reasoning ("thinking") token, kept separate from `content`
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_calls: Option<Vec<ToolCall>>,
+    pub reasoning_content: Option<String>,
+    /// Tool call fragments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallChunk>>,
+}
+
+/// Tool call fragment in a streaming delta
+#[derive(Debug, Serialize)]
+pub struct ToolCallChunk {
+    /// Position index of the tool call this fragment belongs to
+    pub index: usize,
+    /// Tool call identifier (first fragment only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Tool type, always "function" (first fragment only)
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tool_type: Option<&'static str>,
+    /// Function name and arguments fragment
+    pub function: ToolCallChunkFunction,
+}
+
+/// Function details within a streamed tool call fragment
+#[derive(Debug, Serialize)]
+pub struct ToolCallChunkFunction {
+    /// Function name (first fragment only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Next piece of the JSON-encoded arguments
+    pub arguments: String,
+}
+
+impl From<ToolCall> for ToolCallChunk {
+    fn from(call: ToolCall) -> Self {
+        Self {
+            index: call.index,
+            id: Some(call.id),
+            tool_type: Some("function"),
+            function: ToolCallChunkFunction {
+                name: Some(call.function.name),
+                arguments: call.function.arguments,
+            },
+        }
+    }
 }
 
 // ============================================================================
@@ -453,6 +569,21 @@ pub struct ModelObject {
     pub object: &'static str,
     /// Owner/provider name
     pub owned_by: String,
+    /// Alternative model names from the provider's catalog
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Maximum prompt plus completion tokens, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    /// Maximum completion tokens per response, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    /// Supported features as lowercase names (e.g. "vision")
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<String>,
+    /// Token pricing in USD per 1000 tokens, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<TokenPricing>,
 }
 
 // ============================================================================
@@ -680,6 +811,7 @@ mod tests {
                     role: "assistant",
                     content: Some("Hello!".to_owned()),
                     tool_calls: None,
+                    reasoning_content: None,
                 },
                 finish_reason: Some("stop".to_owned()),
             }],
@@ -713,6 +845,7 @@ mod tests {
                             arguments: r#"{"city":"Paris"}"#.to_owned(),
                         },
                     }]),
+                    reasoning_content: None,
                 },
                 finish_reason: Some("tool_calls".to_owned()),
             }],
@@ -746,15 +879,26 @@ mod tests {
                 delta: Delta {
                     role: None,
                     content: Some("token".to_owned()),
+                    reasoning_content: None,
                     tool_calls: None,
                 },
                 finish_reason: None,
             }],
+            usage: None,
         };
         let json = serde_json::to_string(&chunk).expect("serialize");
         assert!(json.contains("chat.completion.chunk"));
         assert!(json.contains("token"));
         assert!(!json.contains("tool_calls"));
+        assert!(!json.contains("reasoning_content"));
+        assert!(!json.contains("usage"));
+    }
+
+    #[test]
+    fn deserialize_stream_options() {
+        let json = r#"{"model":"copilot","messages":[],"stream":true,"stream_options":{"include_usage":true}}"#;
+        let req: ChatCompletionRequest = serde_json::from_str(json).expect("deserialize");
+        assert!(req.stream_options.expect("stream_options").include_usage);
     }
 
     #[test]
@@ -888,9 +1032,17 @@ mod tests {
                 id: "copilot:gpt-4o".to_owned(),
                 object: "model",
                 owned_by: "copilot".to_owned(),
+                aliases: vec![],
+                context_window: Some(128_000),
+                max_output_tokens: None,
+                capabilities: vec!["streaming".to_owned()],
+                pricing: None,
             }],
         };
         let json = serde_json::to_string(&resp).expect("serialize");
         assert!(json.contains("copilot:gpt-4o"));
+        assert!(json.contains("\"context_window\":128000"));
+        assert!(!json.contains("aliases"));
+        assert!(!json.contains("pricing"));
     }
 }
@@ -8,7 +8,7 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use embacle::config::CliRunnerType;
-use embacle::types::{ChatMessage, ChatRequest, ResponseFormat, RunnerError};
+use embacle::types::{ChatMessage, ChatRequest, RequestMetadata, ResponseFormat, RunnerError};
 
 use crate::state::SharedState;
 
@@ -25,6 +25,10 @@ pub struct MultiplexParams {
     pub stop: Option<Vec<String>>,
     /// Response format control
     pub response_format: Option<ResponseFormat>,
+    /// Caller context attached to every fanned-out request
+    pub metadata: RequestMetadata,
+    /// Deadline shared by every fanned-out request
+    pub deadline: Option<Instant>,
 }
 
 /// Aggregated result from dispatching a prompt to multiple providers
@@ -135,6 +139,8 @@ async fn dispatch_single(
     request.top_p = params.top_p;
     request.stop.clone_from(&params.stop);
     request.response_format.clone_from(&params.response_format);
+    request.metadata.clone_from(&params.metadata);
+    request.deadline = params.deadline;
     match runner.complete(&request).await {
         Ok(response) => ProviderResponse {
             provider: provider.to_string(),
@@ -8,20 +8,25 @@ use std::convert::Infallible;
 
 use axum::response::sse::{Event, Sse};
 use axum::response::{IntoResponse, Response};
-use embacle::types::ChatStream;
+use embacle::types::{ChatStream, TokenUsage, ToolCallDelta};
 use futures::StreamExt;
 
 use crate::completions::{generate_id, unix_timestamp};
-use crate::openai_types::{ChatCompletionChunk, ChunkChoice, Delta, ResponseMessage};
+use crate::openai_types::{
+    ChatCompletionChunk, ChunkChoice, CompletionTokensDetails, Delta, PromptTokensDetails,
+    ResponseMessage, StreamOptions, ToolCallChunk, ToolCallChunkFunction, Usage,
+};
 
 /// Convert a `ChatStream` into an SSE response in `OpenAI` streaming format
 ///
 /// Emits:
 /// 1. An initial chunk with role="assistant" and empty content
-/// 2. Content delta chunks as they arrive from the provider
+/// 2. Content, reasoning and tool-call delta chunks as they arrive from the provider
 /// 3. A final chunk with `finish_reason`
-/// 4. `data: [DONE]` terminator
-pub fn sse_response(stream: ChatStream, model: &str) -> Response {
+/// 4. With `include_usage`, a chunk with empty `choices` and the token usage
+///    (`usage: null` when the provider did not report it)
+/// 5. `data: [DONE]` terminator
+pub fn sse_response(stream: ChatStream, model: &str, options: StreamOptions) -> Response {
     let completion_id = generate_id();
     let created = unix_timestamp();
     let model = model.to_owned();
@@ -29,9 +34,16 @@ pub fn sse_response(stream: ChatStream, model: &str) -> Response {
     let sse_stream = {
         let mut sent_role = false;
 
-        stream.map(move |chunk_result| {
-            match chunk_result {
+        stream.flat_map(move |chunk_result| {
+            let events = match chunk_result {
                 Ok(chunk) => {
+                    let send_usage = chunk.is_final && options.include_usage;
+                    let usage = chunk.usage.as_ref().map(usage_from_core);
+                    let reasoning_content = chunk.reasoning_delta.filter(|r| !r.is_empty());
+                    let tool_calls = chunk
+                        .tool_calls
+                        .map(|calls| calls.into_iter().map(tool_call_chunk).collect());
+
                     let (role, content, finish_reason) = if !sent_role {
                         sent_role = true;
                         if chunk.delta.is_empty() && !chunk.is_final {
@@ -77,14 +89,19 @@ pub fn sse_response(stream: ChatStream, model: &str) -> Response {
                             delta: Delta {
                                 role,
                                 content,
-                                tool_calls: None,
+                                reasoning_content,
+                                tool_calls,
                             },
                             finish_reason,
                         }],
+                        usage: None,
                     };
 
-                    let json = serde_json::to_string(&data).unwrap_or_default();
-                    Ok::<_, Infallible>(Event::default().data(json))
+                    let mut events = vec![serde_json::to_string(&data).unwrap_or_default()];
+                    if send_usage {
+                        events.push(usage_chunk_json(&completion_id, created, &model, usage));
+                    }
+                    events
                 }
                 Err(e) => {
                     let error_json = serde_json::json!({
@@ -93,9 +110,14 @@ pub fn sse_response(stream: ChatStream, model: &str) -> Response {
                             "type": "stream_error"
                         }
                     });
-                    Ok(Event::default().data(error_json.to_string()))
+                    vec![error_json.to_string()]
                 }
-            }
+            };
+            futures::stream::iter(
+                events
+                    .into_iter()
+                    .map(|json| Ok::<_, Infallible>(Event::default().data(json))),
+            )
         })
     };
 
@@ -116,8 +138,15 @@ pub fn sse_response(stream: ChatStream, model: &str) -> Response {
 /// non-streaming `complete()` (e.g. for tool-calling downgrade). Produces:
 /// 1. Role announcement chunk with content and/or `tool_calls`
 /// 2. Final chunk with `finish_reason`
-/// 3. `[DONE]` sentinel
-pub fn sse_single_response(message: ResponseMessage, finish_reason: &str, model: &str) -> Response {
+/// 3. With `include_usage`, a usage-only chunk
+/// 4. `[DONE]` sentinel
+pub fn sse_single_response(
+    message: ResponseMessage,
+    finish_reason: &str,
+    model: &str,
+    usage: Option<TokenUsage>,
+    options: StreamOptions,
+) -> Response {
     let completion_id = generate_id();
     let created = unix_timestamp();
 
@@ -131,14 +160,18 @@ pub fn sse_single_response(message: ResponseMessage, finish_reason: &str, model:
             delta: Delta {
                 role: Some("assistant"),
                 content: message.content,
-                tool_calls: message.tool_calls,
+                reasoning_content: message.reasoning_content,
+                tool_calls: message
+                    .tool_calls
+                    .map(|calls| calls.into_iter().map(ToolCallChunk::from).collect()),
             },
             finish_reason: None,
         }],
+        usage: None,
     };
 
     let final_chunk = ChatCompletionChunk {
-        id: completion_id,
+        id: completion_id.clone(),
         object: "chat.completion.chunk",
         created,
         model: model.to_owned(),
@@ -147,16 +180,26 @@ pub fn sse_single_response(message: ResponseMessage, finish_reason: &str, model:
             delta: Delta {
                 role: None,
                 content: None,
+                reasoning_content: None,
                 tool_calls: None,
             },
             finish_reason: Some(finish_reason.to_owned()),
         }],
+        usage: None,
     };
 
-    let events = vec![
+    let mut events = vec![
         serde_json::to_string(&content_chunk).unwrap_or_default(),
         serde_json::to_string(&final_chunk).unwrap_or_default(),
     ];
+    if options.include_usage {
+        events.push(usage_chunk_json(
+            &completion_id,
+            created,
+            model,
+            usage.map(|u| usage_from_core(&u)),
+        ));
+    }
 
     let event_stream = futures::stream::iter(
         events
@@ -172,3 +215,172 @@ pub fn sse_single_response(message: ResponseMessage, finish_reason: &str, model:
         .keep_alive(axum::response::sse::KeepAlive::default())
         .into_response()
 }
+
+/// Serialize the usage-only chunk sent when `stream_options.include_usage` is set
+///
+/// The chunk is always sent before `[DONE]`; unknown usage is `"usage": null`.
+fn usage_chunk_json(
+    completion_id: &str,
+    created: u64,
+    model: &str,
+    usage: Option<Usage>,
+) -> String {
+    let chunk = ChatCompletionChunk {
+        id: completion_id.to_owned(),
+        object: "chat.completion.chunk",
+        created,
+        model: model.to_owned(),
+        choices: Vec::new(),
+        usage: None,
+    };
+    let mut value = serde_json::to_value(&chunk).unwrap_or_default();
+    value["usage"] = serde_json::to_value(usage).unwrap_or_default();
+    value.to_string()
+}
+
+/// Convert core token usage into the `OpenAI` wire shape
+pub(crate) fn usage_from_core(usage: &TokenUsage) -> Usage {
+    Usage {
+        prompt: usage.prompt_tokens,
+        completion: usage.completion_tokens,
+        total: usage.total_tokens,
+        prompt_tokens_details: usage
+            .cached_prompt_tokens
+            .map(|cached_tokens| PromptTokensDetails { cached_tokens }),
+        completion_tokens_details: usage
+            .reasoning_tokens
+            .map(|reasoning_tokens| CompletionTokensDetails { reasoning_tokens }),
+    }
+}
+
+fn tool_call_chunk(delta: ToolCallDelta) -> ToolCallChunk {
+    ToolCallChunk {
+        index: delta.index,
+        tool_type: delta.id.is_some().then_some("function"),
+        id: delta.id,
+        function: ToolCallChunkFunction {
+            name: delta.function_name,
+            arguments: delta.arguments_delta,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embacle::types::StreamChunk;
+    use http_body_util::BodyExt;
+
+    /// Collect the `data:` payloads of an SSE response
+    async fn sse_payloads(response: Response) -> Vec<String> {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec())
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(str::to_owned)
+            .collect()
+    }
+
+    fn rich_stream() -> ChatStream {
+        let chunks = vec![
+            Ok(StreamChunk {
+                reasoning_delta: Some("thinking".to_owned()),
+                ..StreamChunk::default()
+            }),
+            Ok(StreamChunk {
+                tool_calls: Some(vec![ToolCallDelta {
+                    index: 0,
+                    id: Some("call_1".to_owned()),
+                    function_name: Some("lookup".to_owned()),
+                    arguments_delta: "{}".to_owned(),
+                }]),
+                ..StreamChunk::default()
+            }),
+            Ok(StreamChunk {
+                is_final: true,
+                finish_reason: Some("tool_calls".to_owned()),
+                usage: Some(TokenUsage {
+                    prompt_tokens: 5,
+                    completion_tokens: 2,
+                    total_tokens: 7,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: Some(3),
+                    cache_creation_tokens: None,
+                    reported_cost: None,
+                }),
+                ..StreamChunk::default()
+            }),
+        ];
+        Box::pin(futures::stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn stream_emits_reasoning_tool_calls_and_usage() {
+        let options = StreamOptions {
+            include_usage: true,
+        };
+        let payloads = sse_payloads(sse_response(rich_stream(), "m", options)).await;
+        assert_eq!(payloads.last().map(String::as_str), Some("[DONE]"));
+
+        let events: Vec<serde_json::Value> = payloads[..payloads.len() - 1]
+            .iter()
+            .map(|p| serde_json::from_str(p).unwrap())
+            .collect();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(
+            events[0]["choices"][0]["delta"]["reasoning_content"],
+            "thinking"
+        );
+        let call = &events[1]["choices"][0]["delta"]["tool_calls"][0];
+        assert_eq!(call["id"], "call_1");
+        assert_eq!(call["type"], "function");
+        assert_eq!(call["function"]["name"], "lookup");
+        assert_eq!(events[2]["choices"][0]["finish_reason"], "tool_calls");
+        assert!(events[2].get("usage").is_none());
+        assert_eq!(events[3]["choices"], serde_json::json!([]));
+        assert_eq!(events[3]["usage"]["total_tokens"], 7);
+        assert_eq!(
+            events[3]["usage"]["prompt_tokens_details"]["cached_tokens"],
+            3
+        );
+        assert!(events[3]["usage"]
+            .get("completion_tokens_details")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn usage_chunk_is_null_when_provider_reports_none() {
+        let chunks = vec![Ok(StreamChunk {
+            delta: "hi".to_owned(),
+            is_final: true,
+            finish_reason: Some("stop".to_owned()),
+            ..StreamChunk::default()
+        })];
+        let options = StreamOptions {
+            include_usage: true,
+        };
+        let payloads = sse_payloads(sse_response(
+            Box::pin(futures::stream::iter(chunks)),
+            "m",
+            options,
+        ))
+        .await;
+
+        let usage_chunk: serde_json::Value =
+            serde_json::from_str(&payloads[payloads.len() - 2]).unwrap();
+        assert_eq!(usage_chunk["choices"], serde_json::json!([]));
+        assert!(usage_chunk["usage"].is_null());
+        assert!(usage_chunk.as_object().unwrap().contains_key("usage"));
+        assert_eq!(payloads.last().map(String::as_str), Some("[DONE]"));
+    }
+
+    #[tokio::test]
+    async fn usage_chunk_requires_include_usage() {
+        let payloads =
+            sse_payloads(sse_response(rich_stream(), "m", StreamOptions::default())).await;
+        assert_eq!(payloads.len(), 4);
+        assert!(payloads.iter().all(|p| !p.contains("\"usage\"")));
+    }
+}
@@ -13,7 +13,7 @@ use std::time::Duration;
 
 use embacle::compat::detect_capabilities;
 use embacle::config::CliRunnerType;
-use embacle::types::{ChatMessage, ChatRequest, LlmProvider};
+use embacle::types::{ChatMessage, ChatRequest, LlmProvider, RequestMetadata};
 use embacle::{auth::check_readiness, discovery::resolve_binary, ClaudeCodeRunner, RunnerConfig};
 use tokio_stream::StreamExt;
 
@@ -102,6 +102,12 @@ async fn main() {
         top_p: None,
         stop: None,
         response_format: None,
+        session_key: None,
+        n: None,
+        reasoning_effort: None,
+        thinking_budget: None,
+        metadata: RequestMetadata::default(),
+        deadline: None,
     };
 
     match runner.complete(&request).await {
@@ -135,6 +141,12 @@ async fn main() {
         top_p: None,
         stop: None,
         response_format: None,
+        session_key: None,
+        n: None,
+        reasoning_effort: None,
+        thinking_budget: None,
+        metadata: RequestMetadata::default(),
+        deadline: None,
     };
 
     match runner.complete_stream(&stream_request).await {
@@ -0,0 +1,994 @@
+// ABOUTME: Pool of long-lived ACP agent subprocesses that multiplex many sessions per process
+// ABOUTME: Routes NDJSON JSON-RPC responses and session notifications, and replaces crashed children
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # ACP Connection Pool
+//!
+//! Spawning an ACP agent (such as `copilot --acp`) and completing the `initialize`
+//! handshake takes seconds. [`AcpPool`] keeps up to `size` agent processes
+//! warm and hands out [`AcpConnection`]s that multiplex any number of
+//! `session/new` sessions over a single stdio pipe.
+//!
+//! Each connection runs a background reader task that routes:
+//!
+//! - JSON-RPC responses to the caller awaiting that request id
+//! - `session/*` notifications and agent-to-client requests (such as
+//!   `session/request_permission`) to the [`AcpTurn`] subscribed to the
+//!   message's `sessionId`
+//!
+//! A connection whose process exits is marked dead and dropped from the pool
+//! on the next [`AcpPool::acquire`], which spawns a replacement. Connections
+//! are also retired after `max_sessions_per_connection` sessions so agents
+//! that never free finished sessions do not grow without bound.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::process::{
+    spawn_in_process_group, terminate_process_group_in_background, TERMINATE_GRACE_PERIOD,
+};
+use crate::sandbox::{apply_sandbox, SandboxPolicy};
+use crate::types::RunnerError;
+
+/// Default number of warm agent processes per pool
+pub const DEFAULT_ACP_POOL_SIZE: usize = 1;
+
+/// Default number of sessions created on one process before it is retired
+pub const DEFAULT_ACP_MAX_SESSIONS_PER_CONNECTION: usize = 64;
+
+/// Default per-message idle timeout (90 seconds)
+const DEFAULT_ACP_MESSAGE_TIMEOUT_SECS: u64 = 90;
+
+/// Maximum bytes of agent stderr retained for diagnostics
+const STDERR_TAIL_BYTES: usize = 4096;
+
+/// Per-message idle timeout while a request or prompt turn is in flight.
+///
+/// If the agent process is alive but sends nothing for this long the call
+/// fails, catching hung processes that never exit.
+/// Override with `EMBACLE_ACP_MESSAGE_TIMEOUT_SECS`.
+pub(crate) fn acp_message_timeout() -> Duration {
+    let secs = std::env::var("EMBACLE_ACP_MESSAGE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_ACP_MESSAGE_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// How to launch an ACP agent subprocess
+#[derive(Debug, Clone)]
+pub struct AcpLaunchSpec {
+    /// Agent binary
+    pub program: PathBuf,
+    /// Arguments passed to the agent (e.g. `["--acp"]`)
+    pub args: Vec<String>,
+    /// Extra environment variables for the agent process
+    pub env: Vec<(String, String)>,
+    /// Environment allow-list and working directory (`None` inherits the host environment)
+    pub sandbox: Option<SandboxPolicy>,
+    /// Name used in logs and error messages (e.g. `"copilot-acp"`)
+    pub label: String,
+}
+
+/// Sizing and recycling limits for an [`AcpPool`]
+#[derive(Debug, Clone, Copy)]
+pub struct AcpPoolConfig {
+    /// Maximum number of agent processes kept alive
+    pub size: usize,
+    /// Sessions created on one process before it is retired and replaced
+    pub max_sessions_per_connection: usize,
+}
+
+impl Default for AcpPoolConfig {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_ACP_POOL_SIZE,
+            max_sessions_per_connection: DEFAULT_ACP_MAX_SESSIONS_PER_CONNECTION,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Connection
+// ---------------------------------------------------------------------------
+
+/// Pending JSON-RPC requests keyed by id, resolved with the raw response message
+type PendingMap = HashMap<i64, oneshot::Sender<Value>>;
+
+/// State shared between a connection and its reader task
+struct ConnectionShared {
+    label: String,
+    writer: tokio::sync::Mutex<BufWriter<ChildStdin>>,
+    pending: Mutex<PendingMap>,
+    sessions: Mutex<HashMap<String, mpsc::UnboundedSender<Value>>>,
+    alive: AtomicBool,
+    stderr_tail: Mutex<String>,
+}
+
+impl ConnectionShared {
+    /// Write a single NDJSON message
+    async fn write_message(&self, msg: &Value) -> Result<(), RunnerError> {
+        if !self.alive.load(Ordering::Acquire) {
+            return Err(RunnerError::internal(format!(
+                "{}: ACP connection closed",
+                self.label
+            )));
+        }
+        let mut line = serde_json::to_string(msg)
+            .map_err(|e| RunnerError::internal(format!("JSON serialization failed: {e}")))?;
+        line.push('\n');
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| RunnerError::internal(format!("Write failed: {e}")))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| RunnerError::internal(format!("Flush failed: {e}")))
+    }
+
+    /// Route one incoming message to its waiter or session subscriber
+    async fn dispatch(&self, msg: Value) {
+        if let Some(method) = msg.get("method").and_then(Value::as_str) {
+            let session_id = msg.pointer("/params/sessionId").and_then(Value::as_str);
+            let subscriber = session_id.and_then(|sid| {
+                self.sessions
+                    .lock()
+                    .expect("ACP session map poisoned")
+                    .get(sid)
+                    .cloned()
+            });
+            let request_id = msg.get("id").cloned();
+            let method = method.to_owned();
+            let delivered = subscriber.is_some_and(|tx| tx.send(msg).is_ok());
+            if delivered {
+                return;
+            }
+            // Nobody is listening for this session: refuse agent requests so
+            // the agent does not block waiting for an answer
+            if let Some(id) = request_id {
+                debug!(method = %method, "ACP: rejecting request for unattended session");
+                let reply = json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": "No active turn for this session" },
+                });
+                let _ = self.write_message(&reply).await;
+            }
+            return;
+        }
+
+        if let Some(id) = msg.get("id").and_then(Value::as_i64) {
+            let waiter = self
+                .pending
+                .lock()
+                .expect("ACP pending map poisoned")
+                .remove(&id);
+            if let Some(tx) = waiter {
+                let _ = tx.send(msg);
+            }
+        }
+    }
+
+    /// Mark the connection dead and wake every waiter
+    fn close(&self, reason: &str) {
+        if self.alive.swap(false, Ordering::AcqRel) {
+            info!(label = %self.label, reason, "ACP connection closed");
+        }
+        // Dropping the senders wakes waiters with a "connection closed" error
+        self.pending
+            .lock()
+            .expect("ACP pending map poisoned")
+            .clear();
+        self.sessions
+            .lock()
+            .expect("ACP session map poisoned")
+            .clear();
+    }
+}
+
+/// One running ACP agent process with multiplexed sessions
+pub struct AcpConnection {
+    id: u64,
+    shared: Arc<ConnectionShared>,
+    next_request_id: AtomicI64,
+    active_turns: AtomicUsize,
+    sessions_created: AtomicUsize,
+    child: Mutex<Option<Child>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for AcpConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcpConnection")
+            .field("id", &self.id)
+            .field("label", &self.shared.label)
+            .field("alive", &self.is_alive())
+            .field("active_turns", &self.active_turns())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AcpConnection {
+    /// Spawn the agent and complete the ACP `initialize` handshake
+    async fn spawn(id: u64, launch: &AcpLaunchSpec) -> Result<Self, RunnerError> {
+        let mut cmd = tokio::process::Command::new(&launch.program);
+        cmd.args(&launch.args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        if let Some(policy) = &launch.sandbox {
+            apply_sandbox(&mut cmd, policy);
+        }
+        for (key, value) in &launch.env {
+            cmd.env(key, value);
+        }
+
+        info!(
+            label = %launch.label,
+            program = %launch.program.display(),
+            "Spawning ACP agent subprocess"
+        );
+        let mut child = spawn_in_process_group(&mut cmd, None).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                RunnerError::binary_not_found(launch.program.display().to_string())
+            } else {
+                RunnerError::internal(format!("{}: failed to spawn agent: {e}", launch.label))
+            }
+        })?;
+        info!(
+            pid = child.id().unwrap_or(0),
+            "ACP agent subprocess started"
+        );
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| RunnerError::internal("Failed to capture ACP agent stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| RunnerError::internal("Failed to capture ACP agent stdout"))?;
+        let stderr = child.stderr.take();
+
+        let shared = Arc::new(ConnectionShared {
+            label: launch.label.clone(),
+            writer: tokio::sync::Mutex::new(BufWriter::new(stdin)),
+            pending: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+            alive: AtomicBool::new(true),
+            stderr_tail: Mutex::new(String::new()),
+        });
+
+        let mut tasks = vec![tokio::spawn(read_loop(stdout, Arc::clone(&shared)))];
+        if let Some(stderr) = stderr {
+            tasks.push(tokio::spawn(drain_stderr(stderr, Arc::clone(&shared))));
+        }
+
+        let conn = Self {
+            id,
+            shared,
+            next_request_id: AtomicI64::new(1),
+            active_turns: AtomicUsize::new(0),
+            sessions_created: AtomicUsize::new(0),
+            child: Mutex::new(Some(child)),
+            tasks,
+        };
+
+        info!("ACP: sending initialize handshake");
+        let init = conn
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": 1,
+                    "clientInfo": {
+                        "name": "embacle",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                    "capabilities": {},
+                }),
+            )
+            .await;
+        match init {
+            Ok(resp) => {
+                info!("ACP: initialize handshake complete");
+                debug!(response = %resp, "ACP initialize response");
+                Ok(conn)
+            }
+            Err(e) => {
+                warn!(error = %e, stderr = %conn.stderr_tail(), "ACP initialize failed");
+                conn.kill();
+                Err(e)
+            }
+        }
+    }
+
+    /// Pool-assigned identifier (stable for the life of the process)
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Whether the agent process is still running and readable
+    pub fn is_alive(&self) -> bool {
+        self.shared.alive.load(Ordering::Acquire)
+    }
+
+    /// Number of prompt turns currently in flight on this connection
+    pub fn active_turns(&self) -> usize {
+        self.active_turns.load(Ordering::Acquire)
+    }
+
+    /// Last few KiB of the agent's stderr, for diagnostics
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn stderr_tail(&self) -> String {
+        let tail = self
+            .shared
+            .stderr_tail
+            .lock()
+            .expect("ACP stderr buffer poisoned");
+        if tail.is_empty() {
+            "(empty)".to_owned()
+        } else {
+            tail.clone()
+        }
+    }
+
+    /// Kill the agent's process group and fail all in-flight calls
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn kill(&self) {
+        self.shared.close("killed");
+        let child = self.child.lock().expect("ACP child handle poisoned").take();
+        if let Some(child) = child {
+            terminate_process_group_in_background(child, TERMINATE_GRACE_PERIOD);
+        }
+    }
+
+    /// Send a JSON-RPC request; the receiver resolves with the raw response message
+    async fn send_request(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<(i64, oneshot::Receiver<Value>), RunnerError> {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.shared
+            .pending
+            .lock()
+            .expect("ACP pending map poisoned")
+            .insert(id, tx);
+
+        let msg = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        if let Err(e) = self.shared.write_message(&msg).await {
+            self.shared
+                .pending
+                .lock()
+                .expect("ACP pending map poisoned")
+                .remove(&id);
+            return Err(e);
+        }
+        Ok((id, rx))
+    }
+
+    /// Send a JSON-RPC request and wait for its `result`
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, RunnerError> {
+        let (_, rx) = self.send_request(method, params).await?;
+        let msg = tokio::time::timeout(acp_message_timeout(), rx)
+            .await
+            .map_err(|_| {
+                RunnerError::timeout(format!(
+                    "{}: no reply to {method} after {}s — agent process may be hung",
+                    self.shared.label,
+                    acp_message_timeout().as_secs()
+                ))
+            })?
+            .map_err(|_| self.closed_error())?;
+        if let Some(error) = msg.get("error") {
+            return Err(RunnerError::external_service(
+                &self.shared.label,
+                format!("RPC error: {error}"),
+            ));
+        }
+        Ok(msg.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Send a JSON-RPC notification (no response expected)
+    pub async fn notify(&self, method: &str, params: Value) -> Result<(), RunnerError> {
+        self.shared
+            .write_message(&json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params,
+            }))
+            .await
+    }
+
+    /// Answer an agent-to-client request (such as `session/request_permission`)
+    pub async fn respond(&self, id: &Value, result: Value) -> Result<(), RunnerError> {
+        self.shared
+            .write_message(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": result,
+            }))
+            .await
+    }
+
+    /// Create a session with `session/new` and return its id
+    pub async fn new_session(&self, params: Value) -> Result<String, RunnerError> {
+        let result = self.request("session/new", params).await?;
+        let session_id = result
+            .get("sessionId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                RunnerError::external_service(&self.shared.label, "Missing sessionId in response")
+            })?
+            .to_owned();
+        self.sessions_created.fetch_add(1, Ordering::AcqRel);
+        Ok(session_id)
+    }
+
+    /// Send `session/prompt` and return a handle yielding the turn's messages
+    ///
+    /// Only one turn may be in flight per session; callers reusing a session
+    /// across requests must serialize turns themselves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub async fn prompt(
+        self: &Arc<Self>,
+        session_id: &str,
+        prompt: Value,
+    ) -> Result<AcpTurn, RunnerError> {
+        let (tx, updates) = mpsc::unbounded_channel();
+        self.shared
+            .sessions
+            .lock()
+            .expect("ACP session map poisoned")
+            .insert(session_id.to_owned(), tx);
+        self.active_turns.fetch_add(1, Ordering::AcqRel);
+
+        // Constructed before sending so Drop unsubscribes on every path
+        let mut turn = AcpTurn {
+            conn: Arc::clone(self),
+            session_id: session_id.to_owned(),
+            prompt_id: 0,
+            updates,
+            response: None,
+        };
+        let (prompt_id, response) = self
+            .send_request(
+                "session/prompt",
+                json!({
+                    "sessionId": session_id,
+                    "prompt": prompt,
+                }),
+            )
+            .await?;
+        turn.prompt_id = prompt_id;
+        turn.response = Some(response);
+        Ok(turn)
+    }
+
+    fn closed_error(&self) -> RunnerError {
+        RunnerError::internal(format!(
+            "{}: ACP connection closed unexpectedly",
+            self.shared.label
+        ))
+    }
+}
+
+impl Drop for AcpConnection {
+    fn drop(&mut self) {
+        self.shared.close("dropped");
+        if let Some(child) = self.child.get_mut().ok().and_then(Option::take) {
+            terminate_process_group_in_background(child, TERMINATE_GRACE_PERIOD);
+        }
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Read NDJSON messages from the agent until EOF
+async fn read_loop(stdout: ChildStdout, shared: Arc<ConnectionShared>) {
+    let mut lines = BufReader::new(stdout).lines();
+    let reason = loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Value>(trimmed) {
+                    Ok(msg) => shared.dispatch(msg).await,
+                    Err(e) => warn!(error = %e, "ACP: skipping unparseable message"),
+                }
+            }
+            Ok(None) => break "agent closed stdout",
+            Err(e) => {
+                warn!(error = %e, "ACP: read failed");
+                break "read error";
+            }
+        }
+    };
+    shared.close(reason);
+}
+
+/// Keep the agent's stderr pipe drained, retaining the tail for diagnostics
+async fn drain_stderr(stderr: tokio::process::ChildStderr, shared: Arc<ConnectionShared>) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        debug!(label = %shared.label, line = %line, "ACP agent stderr");
+        let mut tail = shared
+            .stderr_tail
+            .lock()
+            .expect("ACP stderr buffer poisoned");
+        tail.push_str(&line);
+        tail.push('\n');
+        if tail.len() > STDERR_TAIL_BYTES {
+            let mut cut = tail.len() - STDERR_TAIL_BYTES;
+            while !tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            tail.drain(..cut);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Prompt turn
+// ---------------------------------------------------------------------------
+
+/// Event produced while a prompt turn is running
+#[derive(Debug)]
+pub enum AcpTurnEvent {
+    /// A notification or agent request addressed to this turn's session
+    Message(Value),
+    /// The raw JSON-RPC response to `session/prompt`; the turn is over
+    Finished(Value),
+}
+
+/// An in-flight `session/prompt` on a pooled connection
+///
+/// Dropping the turn unsubscribes from the session; it does not cancel the
+/// prompt on the agent side (send `session/cancel` for that).
+pub struct AcpTurn {
+    conn: Arc<AcpConnection>,
+    session_id: String,
+    prompt_id: i64,
+    updates: mpsc::UnboundedReceiver<Value>,
+    response: Option<oneshot::Receiver<Value>>,
+}
+
+impl AcpTurn {
+    /// Connection the turn runs on
+    pub fn connection(&self) -> &Arc<AcpConnection> {
+        &self.conn
+    }
+
+    /// Session the turn runs in
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Wait for the next session message or the prompt response
+    ///
+    /// Session messages queued before the response are always returned
+    /// first. Fails if the agent stays silent for [`acp_message_timeout`]
+    /// or the connection closes.
+    pub async fn next_event(&mut self) -> Result<AcpTurnEvent, RunnerError> {
+        let Some(response) = self.response.as_mut() else {
+            return Err(RunnerError::internal("ACP turn already finished"));
+        };
+        let event = tokio::time::timeout(acp_message_timeout(), async {
+            tokio::select! {
+                biased;
+                msg = self.updates.recv() => msg.map(AcpTurnEvent::Message),
+                msg = response => msg.ok().map(AcpTurnEvent::Finished),
+            }
+        })
+        .await
+        .map_err(|_| {
+            RunnerError::timeout(format!(
+                "ACP message read timed out after {}s — {} process may be hung",
+                acp_message_timeout().as_secs(),
+                self.conn.shared.label
+            ))
+        })?;
+
+        match event {
+            Some(AcpTurnEvent::Finished(msg)) => {
+                self.response = None;
+                Ok(AcpTurnEvent::Finished(msg))
+            }
+            Some(event) => Ok(event),
+            None => Err(self.conn.closed_error()),
+        }
+    }
+
+    /// Whether a raw message is the response to this turn's prompt
+    pub fn is_prompt_response(&self, msg: &Value) -> bool {
+        msg.get("id").and_then(Value::as_i64) == Some(self.prompt_id)
+    }
+}
+
+impl Drop for AcpTurn {
+    fn drop(&mut self) {
+        self.conn
+            .shared
+            .sessions
+            .lock()
+            .expect("ACP session map poisoned")
+            .remove(&self.session_id);
+        self.conn
+            .shared
+            .pending
+            .lock()
+            .expect("ACP pending map poisoned")
+            .remove(&self.prompt_id);
+        self.conn.active_turns.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Pool
+// ---------------------------------------------------------------------------
+
+/// Pool of warm ACP agent processes
+pub struct AcpPool {
+    launch: AcpLaunchSpec,
+    config: AcpPoolConfig,
+    connections: tokio::sync::Mutex<Vec<Arc<AcpConnection>>>,
+    /// Slots reserved by spawns in progress, counted against `size`
+    spawning: AtomicUsize,
+    /// Woken whenever a reserved slot is filled or released
+    spawn_finished: Notify,
+    next_connection_id: AtomicU64,
+}
+
+/// A pool slot reserved for a connection being spawned; released on drop
+struct SpawnSlot<'a>(&'a AcpPool);
+
+impl Drop for SpawnSlot<'_> {
+    fn drop(&mut self) {
+        self.0.spawning.fetch_sub(1, Ordering::AcqRel);
+        self.0.spawn_finished.notify_waiters();
+    }
+}
+
+impl std::fmt::Debug for AcpPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcpPool")
+            .field("launch", &self.launch)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AcpPool {
+    /// Create an empty pool; processes are spawned on first use or by [`warm_up`](Self::warm_up)
+    pub fn new(launch: AcpLaunchSpec, config: AcpPoolConfig) -> Self {
+        Self {
+            launch,
+            config: AcpPoolConfig {
+                size: config.size.max(1),
+                max_sessions_per_connection: config.max_sessions_per_connection.max(1),
+            },
+            connections: tokio::sync::Mutex::new(Vec::new()),
+            spawning: AtomicUsize::new(0),
+            spawn_finished: Notify::new(),
+            next_connection_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Pool sizing in effect
+    pub const fn config(&self) -> AcpPoolConfig {
+        self.config
+    }
+
+    /// Get a connection for a new session
+    ///
+    /// Prefers an idle live connection, spawns a new one while below `size`,
+    /// and otherwise shares the least busy connection. Dead and retired
+    /// connections are discarded first, so a crashed agent is replaced here.
+    /// The pool lock is not held while a process starts, so other callers
+    /// can keep using live connections meanwhile.
+    pub async fn acquire(&self) -> Result<Arc<AcpConnection>, RunnerError> {
+        let slot = loop {
+            let mut connections = self.connections.lock().await;
+            self.prune(&mut connections);
+
+            let occupied = connections.len() + self.spawning.load(Ordering::Acquire);
+            let least_busy = connections.iter().min_by_key(|c| c.active_turns()).cloned();
+            if let Some(conn) = least_busy {
+                if conn.active_turns() == 0 || occupied >= self.config.size {
+                    return Ok(conn);
+                }
+            }
+            if occupied < self.config.size {
+                break self.reserve_slot();
+            }
+
+            // Every slot is taken by a spawn in progress: wait for one to settle
+            let finished = self.spawn_finished.notified();
+            drop(connections);
+            finished.await;
+        };
+
+        let conn = Arc::new(self.spawn_connection().await?);
+        let mut connections = self.connections.lock().await;
+        connections.push(Arc::clone(&conn));
+        drop(slot);
+        Ok(conn)
+    }
+
+    /// Spawn processes until the pool holds `size` live connections
+    pub async fn warm_up(&self) -> Result<(), RunnerError> {
+        loop {
+            let slot = {
+                let mut connections = self.connections.lock().await;
+                self.prune(&mut connections);
+                if connections.len() + self.spawning.load(Ordering::Acquire) >= self.config.size {
+                    return Ok(());
+                }
+                self.reserve_slot()
+            };
+            let conn = Arc::new(self.spawn_connection().await?);
+            let mut connections = self.connections.lock().await;
+            connections.push(conn);
+            drop(slot);
+        }
+    }
+
+    /// Number of live connections currently held
+    pub async fn live_connections(&self) -> usize {
+        let mut connections = self.connections.lock().await;
+        self.prune(&mut connections);
+        connections.len()
+    }
+
+    /// Drop every pooled connection (in-flight turns keep theirs until done)
+    pub async fn shutdown(&self) {
+        self.connections.lock().await.clear();
+    }
+
+    fn prune(&self, connections: &mut Vec<Arc<AcpConnection>>) {
+        let max_sessions = self.config.max_sessions_per_connection;
+        connections.retain(|conn| {
+            if !conn.is_alive() {
+                warn!(
+                    connection = conn.id(),
+                    stderr = %conn.stderr_tail(),
+                    "ACP agent exited; replacing"
+                );
+                return false;
+            }
+            if conn.sessions_created.load(Ordering::Acquire) >= max_sessions {
+                info!(
+                    connection = conn.id(),
+                    "ACP agent reached session limit; retiring"
+                );
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Count a spawn against `size`; call with the connections lock held
+    fn reserve_slot(&self) -> SpawnSlot<'_> {
+        self.spawning.fetch_add(1, Ordering::AcqRel);
+        SpawnSlot(self)
+    }
+
+    async fn spawn_connection(&self) -> Result<AcpConnection, RunnerError> {
+        let id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        AcpConnection::spawn(id, &self.launch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_agent() -> AcpLaunchSpec {
+        AcpLaunchSpec {
+            program: PathBuf::from("sh"),
+            args: vec![concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/fake_acp_agent.sh"
+            )
+            .to_owned()],
+            env: Vec::new(),
+            sandbox: None,
+            label: "fake-acp".to_owned(),
+        }
+    }
+
+    fn text_prompt(text: &str) -> Value {
+        json!([{ "type": "text", "text": text }])
+    }
+
+    /// Run one turn and return the concatenated message texts
+    async fn run_turn(conn: &Arc<AcpConnection>, session_id: &str, text: &str) -> String {
+        let mut turn = conn.prompt(session_id, text_prompt(text)).await.unwrap();
+        let mut out = String::new();
+        loop {
+            match turn.next_event().await.unwrap() {
+                AcpTurnEvent::Message(msg) => {
+                    if let Some(t) = msg
+                        .pointer("/params/update/content/text")
+                        .and_then(Value::as_str)
+                    {
+                        out.push_str(t);
+                    }
+                }
+                AcpTurnEvent::Finished(msg) => {
+                    assert!(turn.is_prompt_response(&msg));
+                    assert_eq!(msg.pointer("/result/stopReason").unwrap(), "end_turn");
+                    return out;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_is_reused_across_sessions() {
+        let pool = AcpPool::new(fake_agent(), AcpPoolConfig::default());
+
+        let first = pool.acquire().await.unwrap();
+        let s1 = first.new_session(json!({ "cwd": "/" })).await.unwrap();
+        let second = pool.acquire().await.unwrap();
+        let s2 = second.new_session(json!({ "cwd": "/" })).await.unwrap();
+
+        assert_eq!(first.id(), second.id());
+        assert_ne!(s1, s2);
+        assert!(run_turn(&first, &s1, "hi").await.ends_with("turn=1"));
+        assert!(run_turn(&first, &s1, "again").await.ends_with("turn=2"));
+        assert!(run_turn(&second, &s2, "hi").await.ends_with("turn=1"));
+        assert_eq!(first.active_turns(), 0);
+    }
+
+    #[tokio::test]
+    async fn concurrent_turns_multiplex_on_one_process() {
+        let pool = AcpPool::new(fake_agent(), AcpPoolConfig::default());
+        let conn = pool.acquire().await.unwrap();
+        let s1 = conn.new_session(json!({})).await.unwrap();
+        let s2 = conn.new_session(json!({})).await.unwrap();
+
+        let (a, b) = tokio::join!(run_turn(&conn, &s1, "a"), run_turn(&conn, &s2, "b"));
+        assert!(a.contains(&format!("session={s1}")));
+        assert!(b.contains(&format!("session={s2}")));
+    }
+
+    #[tokio::test]
+    async fn busy_pool_spawns_up_to_size() {
+        let pool = AcpPool::new(
+            fake_agent(),
+            AcpPoolConfig {
+                size: 2,
+                max_sessions_per_connection: 10,
+            },
+        );
+        let first = pool.acquire().await.unwrap();
+        let session = first.new_session(json!({})).await.unwrap();
+        let _busy = first.prompt(&session, text_prompt("HANG")).await.unwrap();
+
+        let second = pool.acquire().await.unwrap();
+        assert_ne!(first.id(), second.id());
+        assert_eq!(pool.live_connections().await, 2);
+
+        // At capacity the least busy connection is shared
+        let third = pool.acquire().await.unwrap();
+        assert_eq!(third.id(), second.id());
+    }
+
+    #[tokio::test]
+    async fn slow_spawn_does_not_hold_the_pool_lock() {
+        let mut launch = fake_agent();
+        launch.args = vec![
+            "-c".to_owned(),
+            format!("sleep 2; exec sh {}", launch.args[0]),
+        ];
+        let pool = Arc::new(AcpPool::new(launch, AcpPoolConfig::default()));
+        let spawning = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move { pool.acquire().await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let live = tokio::time::timeout(Duration::from_millis(500), pool.live_connections())
+            .await
+            .expect("pool lock is free while the agent starts");
+        assert_eq!(live, 0);
+
+        // An abandoned spawn gives its reserved slot back
+        spawning.abort();
+        let _ = spawning.await;
+        assert_eq!(pool.spawning.load(Ordering::Acquire), 0);
+    }
+
+    #[tokio::test]
+    async fn crashed_agent_is_replaced() {
+        let pool = AcpPool::new(fake_agent(), AcpPoolConfig::default());
+        let conn = pool.acquire().await.unwrap();
+        let session = conn.new_session(json!({})).await.unwrap();
+
+        let mut turn = conn.prompt(&session, text_prompt("CRASH")).await.unwrap();
+        assert!(turn.next_event().await.is_err());
+        drop(turn);
+        assert!(!conn.is_alive());
+
+        let replacement = pool.acquire().await.unwrap();
+        assert_ne!(replacement.id(), conn.id());
+        let session = replacement.new_session(json!({})).await.unwrap();
+        assert!(run_turn(&replacement, &session, "hi")
+            .await
+            .contains("turn=1"));
+    }
+
+    #[tokio::test]
+    async fn connection_retires_after_session_limit() {
+        let pool = AcpPool::new(
+            fake_agent(),
+            AcpPoolConfig {
+                size: 1,
+                max_sessions_per_connection: 2,
+            },
+        );
+        let conn = pool.acquire().await.unwrap();
+        conn.new_session(json!({})).await.unwrap();
+        conn.new_session(json!({})).await.unwrap();
+
+        let next = pool.acquire().await.unwrap();
+        assert_ne!(next.id(), conn.id());
+        // The retired process stays usable for holders of the old handle
+        assert!(conn.is_alive());
+    }
+
+    #[tokio::test]
+    async fn cancel_finishes_hung_turn_without_killing_connection() {
+        let pool = AcpPool::new(fake_agent(), AcpPoolConfig::default());
+        let conn = pool.acquire().await.unwrap();
+        let session = conn.new_session(json!({})).await.unwrap();
+
+        let mut turn = conn.prompt(&session, text_prompt("HANG")).await.unwrap();
+        conn.notify("session/cancel", json!({ "sessionId": session }))
+            .await
+            .unwrap();
+        match turn.next_event().await.unwrap() {
+            AcpTurnEvent::Finished(msg) => {
+                assert_eq!(msg.pointer("/result/stopReason").unwrap(), "cancelled");
+            }
+            AcpTurnEvent::Message(msg) => panic!("unexpected message: {msg}"),
+        }
+        drop(turn);
+
+        assert!(conn.is_alive());
+        assert!(run_turn(&conn, &session, "hi").await.contains("turn=1"));
+    }
+}
@@ -0,0 +1,1350 @@
+// ABOUTME: Generic ACP (Agent Client Protocol) runner for any agent speaking NDJSON JSON-RPC over stdio
+// ABOUTME: Drives sessions, prompt turns, permission requests and cancellation on pooled agent processes
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # ACP Runner
+//!
+//! [`AcpRunner`] talks to any agent implementing the
+//! [Agent Client Protocol](https://agentclientprotocol.com) over stdio, such as
+//! `gemini --experimental-acp`, `claude-code-acp` or a custom agent. The agent
+//! command line comes from [`RunnerConfig`]: `binary_path` is the program and
+//! `extra_args` are passed to it verbatim.
+//!
+//! Agent processes are kept warm in an [`AcpPool`] and multiplex many ACP
+//! sessions. Requests carrying a [`session_key`](ChatRequest::session_key)
+//! reuse the same ACP session across turns of that conversation.
+//!
+//! The agent runs with the [`RunnerConfig`] sandbox (allowed environment keys
+//! and working directory) unless [`AcpRunner::with_inherited_env`] is used.
+//! [`CopilotHeadlessRunner`](crate::CopilotHeadlessRunner) is a preset over
+//! this runner for `copilot --acp`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use agent_client_protocol_schema as schema;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, OnceCell, OwnedMutexGuard};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::acp_pool::{
+    AcpConnection, AcpLaunchSpec, AcpPool, AcpPoolConfig, AcpTurn, AcpTurnEvent,
+};
+use crate::config::RunnerConfig;
+use crate::error_classify::classify_error;
+use crate::prompt::build_user_prompt;
+use crate::sandbox::SandboxPolicy;
+use crate::stream::{StreamDeadline, StreamTimeouts};
+use crate::types::{
+    ChatRequest, ChatResponse, ChatStream, ErrorKind, LlmCapabilities, LlmProvider, MessageRole,
+    RequestMetadata, RunnerError, StreamChunk, TokenUsage,
+};
+
+/// Model name reported when neither the request nor the config selects one
+const DEFAULT_MODEL: &str = "default";
+
+/// Environment variable holding whitespace-separated agent arguments for [`crate::create_runner`]
+const ACP_AGENT_ARGS_ENV: &str = "ACP_AGENT_ARGS";
+
+/// Read agent arguments from `ACP_AGENT_ARGS` (e.g. `--experimental-acp`)
+pub(crate) fn agent_args_from_env() -> Vec<String> {
+    std::env::var(ACP_AGENT_ARGS_ENV)
+        .map(|v| v.split_whitespace().map(ToOwned::to_owned).collect())
+        .unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// ACP session lifecycle
+// ---------------------------------------------------------------------------
+
+/// Default session setup timeout (60 seconds).
+///
+/// Agents may need 20–25s for first-run package extraction in containers,
+/// plus time for auth handshake. Override with `EMBACLE_ACP_SESSION_TIMEOUT_SECS`.
+const DEFAULT_ACP_SESSION_TIMEOUT_SECS: u64 = 60;
+
+/// Read session setup timeout from `EMBACLE_ACP_SESSION_TIMEOUT_SECS` env var,
+/// falling back to [`DEFAULT_ACP_SESSION_TIMEOUT_SECS`].
+fn acp_session_timeout() -> Duration {
+    let secs = std::env::var("EMBACLE_ACP_SESSION_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_ACP_SESSION_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Build `session/new` params with the working directory, optional model and system prompt.
+fn session_params(
+    cwd: &std::path::Path,
+    model: Option<&str>,
+    system_prompt: Option<&str>,
+) -> Value {
+    let mut params = json!({
+        "cwd": cwd,
+        "mcpServers": [],
+    });
+    if let Some(model) = model {
+        params["model"] = Value::String(model.to_owned());
+    }
+    if let Some(sys) = system_prompt {
+        params["systemPrompt"] = Value::String(sys.to_owned());
+    }
+    params
+}
+
+/// Upper bound for delivering a `session/cancel` notification and draining the turn.
+const ACP_CANCEL_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Ask the agent to abandon the in-flight turn (best-effort).
+///
+/// Sends the ACP `session/cancel` notification so the agent can stop any
+/// upstream model work, then waits briefly for the agent to close the turn
+/// so the pooled process and session stay usable for later requests.
+async fn cancel_turn(turn: &mut AcpTurn) {
+    let session_id = turn.session_id().to_owned();
+    let sent = tokio::time::timeout(
+        ACP_CANCEL_WRITE_TIMEOUT,
+        turn.connection()
+            .notify("session/cancel", json!({ "sessionId": session_id })),
+    )
+    .await;
+    match sent {
+        Ok(Ok(())) => info!(session_id, "ACP: sent session/cancel"),
+        Ok(Err(e)) => {
+            warn!(session_id, error = %e, "ACP: failed to send session/cancel");
+            return;
+        }
+        Err(_) => {
+            warn!(session_id, "ACP: timed out sending session/cancel");
+            return;
+        }
+    }
+
+    let drained = tokio::time::timeout(ACP_CANCEL_WRITE_TIMEOUT, async {
+        while let Ok(event) = turn.next_event().await {
+            if matches!(event, AcpTurnEvent::Finished(_)) {
+                break;
+            }
+        }
+    })
+    .await;
+    if drained.is_err() {
+        warn!(session_id, "ACP: agent did not acknowledge session/cancel");
+    }
+}
+
+/// A session checked out for one prompt turn.
+///
+/// Holds the conversation's turn lock (if any) so concurrent requests for the
+/// same conversation are serialized on its ACP session.
+struct SessionLease {
+    connection: Arc<AcpConnection>,
+    session_id: String,
+    /// Whether the session already holds the earlier turns of the conversation
+    resumed: bool,
+    _turn_guard: Option<OwnedMutexGuard<()>>,
+}
+
+/// An ACP session bound to a conversation key for reuse across turns.
+struct ConversationSession {
+    connection: Weak<AcpConnection>,
+    session_id: String,
+    model: String,
+    turn_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+// ---------------------------------------------------------------------------
+// Notification and permission handling
+// ---------------------------------------------------------------------------
+
+/// Policy for handling ACP permission requests from the agent subprocess.
+///
+/// Controls whether tool-execution permission prompts are auto-approved or denied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PermissionPolicy {
+    /// Automatically approve permission requests by selecting the best allow option.
+    #[default]
+    AutoApprove,
+    /// Deny all permission requests by cancelling them.
+    DenyAll,
+}
+
+/// Accumulated state from ACP session notifications during a prompt turn.
+struct TurnAccumulator {
+    content: String,
+    tool_calls: Vec<ObservedToolCall>,
+}
+
+impl TurnAccumulator {
+    const fn new() -> Self {
+        Self {
+            content: String::new(),
+            tool_calls: Vec::new(),
+        }
+    }
+}
+
+/// Process a session/update notification, accumulating content and tool calls.
+fn process_notification(params: &Value, acc: &mut TurnAccumulator) {
+    let Some(params) = params.get("params").or(Some(params)) else {
+        return;
+    };
+
+    let Ok(notif) = serde_json::from_value::<schema::SessionNotification>(params.clone()) else {
+        return;
+    };
+
+    match &notif.update {
+        schema::SessionUpdate::AgentMessageChunk(chunk) => {
+            if let schema::ContentBlock::Text(text) = &chunk.content {
+                acc.content.push_str(&text.text);
+            }
+        }
+        schema::SessionUpdate::ToolCall(tc) => {
+            acc.tool_calls.push(ObservedToolCall {
+                id: tc.tool_call_id.0.to_string(),
+                title: tc.title.clone(),
+                status: format!("{:?}", tc.status),
+            });
+        }
+        schema::SessionUpdate::ToolCallUpdate(update) => {
+            let update_id = update.tool_call_id.0.to_string();
+            if let Some(existing) = acc.tool_calls.iter_mut().find(|t| t.id == update_id) {
+                if let Some(ref title) = update.fields.title {
+                    existing.title.clone_from(title);
+                }
+                if let Some(ref status) = update.fields.status {
+                    existing.status = format!("{status:?}");
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Build a permission response based on the configured policy.
+///
+/// With `AutoApprove`: selects `AllowAlways` over `AllowOnce`. If no allow option
+/// exists, cancels the request instead of falling back to a reject option.
+/// With `DenyAll`: always cancels the request.
+fn build_permission_response(params: &Value, policy: PermissionPolicy) -> Value {
+    if policy == PermissionPolicy::DenyAll {
+        debug!("Permission policy is DenyAll, cancelling");
+        return json!({ "outcome": "cancelled" });
+    }
+
+    let Ok(req) = serde_json::from_value::<schema::RequestPermissionRequest>(params.clone()) else {
+        warn!("Failed to parse permission request, cancelling");
+        return json!({ "outcome": "cancelled" });
+    };
+
+    // Prefer AllowAlways over AllowOnce for fewer repeated prompts
+    let option_id = req
+        .options
+        .iter()
+        .find(|o| matches!(o.kind, schema::PermissionOptionKind::AllowAlways))
+        .or_else(|| {
+            req.options
+                .iter()
+                .find(|o| matches!(o.kind, schema::PermissionOptionKind::AllowOnce))
+        })
+        .map(|o| &o.option_id);
+
+    option_id.map_or_else(
+        || {
+            warn!("Permission request had no allow options, cancelling");
+            json!({ "outcome": "cancelled" })
+        },
+        |id| {
+            debug!(?id, "Auto-approving permission request");
+            json!({ "outcome": { "optionId": id.0 } })
+        },
+    )
+}
+
+/// Extract token usage from the prompt response JSON.
+///
+/// ACP returns usage at `/result/usage` with camelCase fields:
+/// `totalTokens`, `inputTokens`, `outputTokens`.
+fn extract_usage(result: &Value) -> Option<TokenUsage> {
+    let usage = result
+        .pointer("/result/usage")
+        .or_else(|| result.get("usage"))?;
+
+    let input = usage.get("inputTokens").and_then(Value::as_u64)?;
+    let output = usage.get("outputTokens").and_then(Value::as_u64)?;
+    let total = usage
+        .get("totalTokens")
+        .and_then(Value::as_u64)
+        .unwrap_or_else(|| input.saturating_add(output));
+
+    let to_u32 = |n: u64| u32::try_from(n).unwrap_or(u32::MAX);
+    let count = |key: &str| usage.get(key).and_then(Value::as_u64).map(to_u32);
+
+    Some(TokenUsage {
+        prompt_tokens: to_u32(input),
+        completion_tokens: to_u32(output),
+        total_tokens: to_u32(total),
+        reasoning_tokens: count("thoughtTokens"),
+        cached_prompt_tokens: count("cachedReadTokens"),
+        cache_creation_tokens: count("cachedWriteTokens"),
+        reported_cost: None,
+    })
+}
+
+fn map_stop_reason(reason: &str) -> &'static str {
+    match reason {
+        "max_tokens" => "length",
+        "max_turn_requests" => "max_turns",
+        "refusal" => "refusal",
+        "cancelled" => "cancelled",
+        _ => "stop",
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Message collection loops
+// ---------------------------------------------------------------------------
+
+/// Read turn events until the prompt completes, collecting all content.
+async fn collect_complete(
+    turn: &mut AcpTurn,
+    model: String,
+    policy: PermissionPolicy,
+    label: &str,
+) -> Result<(ChatResponse, Vec<ObservedToolCall>), RunnerError> {
+    let mut acc = TurnAccumulator::new();
+    let mut message_count: u32 = 0;
+
+    loop {
+        let msg = match turn.next_event().await? {
+            AcpTurnEvent::Message(msg) => msg,
+            // Prompt response — the turn is complete
+            AcpTurnEvent::Finished(msg) => {
+                if let Some(error) = msg.get("error") {
+                    return Err(classify_error(label, &format!("Prompt failed: {error}")));
+                }
+
+                let stop_reason = msg
+                    .pointer("/result/stopReason")
+                    .and_then(Value::as_str)
+                    .unwrap_or("end_turn");
+
+                let usage = extract_usage(&msg);
+
+                debug!(
+                    label,
+                    content_len = acc.content.len(),
+                    tool_calls = acc.tool_calls.len(),
+                    model = %model,
+                    has_usage = usage.is_some(),
+                    "ACP complete() response"
+                );
+
+                let response = ChatResponse {
+                    content: acc.content,
+                    model,
+                    usage,
+                    finish_reason: Some(map_stop_reason(stop_reason).to_owned()),
+                    warnings: None,
+                    tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
+                };
+
+                return Ok((response, acc.tool_calls));
+            }
+        };
+        message_count += 1;
+
+        if message_count == 1 {
+            info!(label, "ACP: receiving first message from agent");
+        }
+        // Log method notifications for visibility (every 10th to avoid spam)
+        if let Some(method) = msg.get("method").and_then(Value::as_str) {
+            if message_count <= 5 || message_count.is_multiple_of(10) {
+                debug!(method, message_count, "ACP notification received");
+            }
+        }
+
+        // Server requests and notifications
+        handle_server_message(&msg, turn.connection(), &mut acc, policy).await?;
+    }
+}
+
+/// Read turn events until the prompt completes, streaming chunks via channel.
+///
+/// Each read is bounded by `deadline`, so a silent agent fails the stream
+/// with a timeout instead of holding it open.
+async fn collect_streaming(
+    turn: &mut AcpTurn,
+    chunk_tx: &mpsc::UnboundedSender<Result<StreamChunk, RunnerError>>,
+    policy: PermissionPolicy,
+    deadline: &StreamDeadline,
+    label: &str,
+) -> Result<(), RunnerError> {
+    let mut acc = TurnAccumulator::new();
+
+    loop {
+        let msg = match deadline.next(turn.next_event()).await?? {
+            AcpTurnEvent::Message(msg) => msg,
+            // Prompt response — the turn is complete
+            AcpTurnEvent::Finished(msg) => {
+                if let Some(error) = msg.get("error") {
+                    return Err(classify_error(label, &format!("Prompt failed: {error}")));
+                }
+
+                let stop_reason = msg
+                    .pointer("/result/stopReason")
+                    .and_then(Value::as_str)
+                    .unwrap_or("end_turn");
+
+                let _ = chunk_tx.send(Ok(StreamChunk {
+                    is_final: true,
+                    finish_reason: Some(map_stop_reason(stop_reason).to_owned()),
+                    usage: extract_usage(&msg),
+                    ..StreamChunk::default()
+                }));
+
+                return Ok(());
+            }
+        };
+
+        // Server requests and notifications
+        if let Some(method) = msg.get("method").and_then(Value::as_str) {
+            match method {
+                "session/update" => {
+                    if let Some(params) = msg.get("params") {
+                        // Try to extract text delta for streaming
+                        if let Ok(notif) =
+                            serde_json::from_value::<schema::SessionNotification>(params.clone())
+                        {
+                            match &notif.update {
+                                schema::SessionUpdate::AgentMessageChunk(chunk) => {
+                                    if let schema::ContentBlock::Text(text) = &chunk.content {
+                                        let _ = chunk_tx.send(Ok(StreamChunk {
+                                            delta: text.text.clone(),
+                                            ..StreamChunk::default()
+                                        }));
+                                    }
+                                }
+                                schema::SessionUpdate::AgentThoughtChunk(chunk) => {
+                                    if let schema::ContentBlock::Text(text) = &chunk.content {
+                                        let _ = chunk_tx.send(Ok(StreamChunk {
+                                            reasoning_delta: Some(text.text.clone()),
+                                            ..StreamChunk::default()
+                                        }));
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        // Also track tool calls for internal accounting
+                        process_notification(params, &mut acc);
+                    }
+                }
+                "session/request_permission" => {
+                    if let (Some(id), Some(params)) = (msg.get("id"), msg.get("params")) {
+                        let response = build_permission_response(params, policy);
+                        turn.connection().respond(id, response).await?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Handle a server-to-client message (notification or request).
+async fn handle_server_message(
+    msg: &Value,
+    connection: &AcpConnection,
+    acc: &mut TurnAccumulator,
+    policy: PermissionPolicy,
+) -> Result<(), RunnerError> {
+    if let Some(method) = msg.get("method").and_then(Value::as_str) {
+        match method {
+            "session/update" => {
+                if let Some(params) = msg.get("params") {
+                    process_notification(params, acc);
+                }
+            }
+            "session/request_permission" => {
+                if let (Some(id), Some(params)) = (msg.get("id"), msg.get("params")) {
+                    let response = build_permission_response(params, policy);
+                    connection.respond(id, response).await?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Public types
+// ---------------------------------------------------------------------------
+
+/// A tool call observed during an ACP session turn.
+#[derive(Debug, Clone)]
+pub struct ObservedToolCall {
+    /// Tool call ID from the ACP protocol.
+    pub id: String,
+    /// Human-readable title describing the tool action.
+    pub title: String,
+    /// Execution status (e.g., "Pending", "`InProgress`", "Completed", "Failed").
+    pub status: String,
+}
+
+/// Response from a headless conversation turn including tool execution metadata.
+#[derive(Debug, Clone)]
+pub struct HeadlessToolResponse {
+    /// Final assistant response content.
+    pub content: String,
+    /// Model that generated the response.
+    pub model: String,
+    /// Tool calls observed during the turn.
+    pub tool_calls: Vec<ObservedToolCall>,
+    /// Token usage for this turn.
+    pub usage: Option<TokenUsage>,
+    /// Finish reason.
+    pub finish_reason: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Public runner
+// ---------------------------------------------------------------------------
+
+/// Generic Agent Client Protocol runner.
+///
+/// Launches `config.binary_path` with `config.extra_args` and exchanges
+/// NDJSON-framed JSON-RPC over its stdio. `config.timeout` bounds each prompt
+/// turn; a process that times out is killed and replaced in the pool.
+///
+/// Agents manage their own tools internally and cannot execute external MCP
+/// tools, so `SDK_TOOL_CALLING` is never advertised. Tool calls are observed
+/// and reported via [`HeadlessToolResponse`] from [`converse()`](Self::converse).
+pub struct AcpRunner {
+    config: RunnerConfig,
+    name: &'static str,
+    display_name: String,
+    capabilities: LlmCapabilities,
+    available_models: Vec<String>,
+    permission_policy: PermissionPolicy,
+    pool_config: AcpPoolConfig,
+    env: Vec<(String, String)>,
+    inherit_env: bool,
+    pool: OnceCell<AcpPool>,
+    conversations: Mutex<HashMap<String, ConversationSession>>,
+}
+
+impl AcpRunner {
+    /// Create a runner for the agent command line in `config`
+    #[must_use]
+    pub fn new(config: RunnerConfig) -> Self {
+        let available_models = config.model.iter().cloned().collect();
+        Self {
+            config,
+            name: "acp",
+            display_name: "ACP Agent".to_owned(),
+            capabilities: LlmCapabilities::STREAMING | LlmCapabilities::SYSTEM_MESSAGES,
+            available_models,
+            permission_policy: PermissionPolicy::default(),
+            pool_config: AcpPoolConfig::default(),
+            env: Vec::new(),
+            inherit_env: false,
+            pool: OnceCell::new(),
+            conversations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the provider name and display name reported by this runner
+    #[must_use]
+    pub fn with_identity(mut self, name: &'static str, display_name: impl Into<String>) -> Self {
+        self.name = name;
+        self.display_name = display_name.into();
+        self
+    }
+
+    /// Set the capabilities advertised for the agent (e.g. add `VISION`)
+    #[must_use]
+    pub const fn with_capabilities(mut self, capabilities: LlmCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Set the models the agent accepts in `session/new`
+    #[must_use]
+    pub fn with_available_models(mut self, models: Vec<String>) -> Self {
+        self.available_models = models;
+        self
+    }
+
+    /// Set how agent permission requests are answered
+    #[must_use]
+    pub const fn with_permission_policy(mut self, policy: PermissionPolicy) -> Self {
+        self.permission_policy = policy;
+        self
+    }
+
+    /// Set the number of warm agent processes and their recycling limit
+    #[must_use]
+    pub const fn with_pool_config(mut self, pool_config: AcpPoolConfig) -> Self {
+        self.pool_config = pool_config;
+        self
+    }
+
+    /// Set an extra environment variable for the agent process
+    #[must_use]
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Pass the full host environment to the agent instead of `allowed_env_keys`
+    #[must_use]
+    pub const fn with_inherited_env(mut self) -> Self {
+        self.inherit_env = true;
+        self
+    }
+
+    /// Spawn the configured number of agent processes ahead of the first request.
+    ///
+    /// Optional: the pool also fills lazily, but warming up moves the agent
+    /// startup out of the first request's latency.
+    pub async fn warm_up(&self) -> Result<(), RunnerError> {
+        self.pool().await?.warm_up().await
+    }
+
+    /// Working directory for the agent and its sessions
+    fn working_directory(&self) -> Result<PathBuf, RunnerError> {
+        match &self.config.working_directory {
+            Some(dir) if dir.exists() => Ok(dir.clone()),
+            _ => std::env::current_dir()
+                .map_err(|e| RunnerError::internal(format!("Failed to get cwd: {e}"))),
+        }
+    }
+
+    /// Get the connection pool, creating it on first use.
+    async fn pool(&self) -> Result<&AcpPool, RunnerError> {
+        self.pool
+            .get_or_try_init(|| async {
+                let sandbox = if self.inherit_env {
+                    None
+                } else {
+                    Some(
+                        SandboxPolicy::new(self.working_directory()?)
+                            .with_env_keys(self.config.allowed_env_keys.clone()),
+                    )
+                };
+                let launch = AcpLaunchSpec {
+                    program: self.config.binary_path.clone(),
+                    args: self.config.extra_args.clone(),
+                    env: self.env.clone(),
+                    sandbox,
+                    label: self.name.to_owned(),
+                };
+                Ok(AcpPool::new(launch, self.pool_config))
+            })
+            .await
+    }
+
+    /// Build ACP prompt content blocks for one turn.
+    ///
+    /// A resumed session already holds the conversation, so only the last
+    /// user message is sent; a new session gets the full message history.
+    /// Always includes a text block. When the last user message has images,
+    /// appends image blocks with `type: "image"`, `data`, and `mimeType`.
+    fn build_prompt_blocks(request: &ChatRequest, resumed: bool) -> Vec<Value> {
+        let system = Self::extract_system_prompt(request);
+        let last_user = request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == MessageRole::User);
+
+        let has_history = request
+            .messages
+            .iter()
+            .filter(|m| m.role != MessageRole::System)
+            .nth(1)
+            .is_some();
+        let user_text = if has_history && !resumed {
+            build_user_prompt(&request.messages)
+        } else {
+            last_user.map(|m| m.content.clone()).unwrap_or_default()
+        };
+
+        // Inject the system prompt into the prompt text so the model sees it
+        // even if the ACP systemPrompt parameter in session/new is deprioritized.
+        let text = system.map_or_else(
+            || user_text.clone(),
+            |sys| format!("<system-instructions>\n{sys}\n</system-instructions>\n\n{user_text}"),
+        );
+
+        let mut blocks = vec![json!({"type": "text", "text": text})];
+
+        if let Some(images) = last_user.and_then(|m| m.images.as_ref()) {
+            for img in images {
+                blocks.push(json!({
+                    "type": "image",
+                    "data": img.data,
+                    "mimeType": img.mime_type,
+                }));
+            }
+        }
+
+        blocks
+    }
+
+    /// Extract the system prompt if present.
+    fn extract_system_prompt(request: &ChatRequest) -> Option<&str> {
+        request
+            .messages
+            .iter()
+            .find(|m| m.role == MessageRole::System)
+            .map(|m| m.content.as_str())
+    }
+
+    /// Model explicitly selected by the request or the config, if any
+    fn requested_model<'a>(&'a self, request: &'a ChatRequest) -> Option<&'a str> {
+        request.model.as_deref().or(self.config.model.as_deref())
+    }
+
+    /// Check out an ACP session for one turn, within the session setup timeout.
+    async fn open_session(
+        &self,
+        request: &ChatRequest,
+        model: Option<&str>,
+    ) -> Result<SessionLease, RunnerError> {
+        tokio::time::timeout(
+            acp_session_timeout(),
+            self.open_session_inner(request, model),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            warn!(
+                label = self.name,
+                timeout_secs = acp_session_timeout().as_secs(),
+                "ACP session setup timed out — agent process may be hung (auth issue?)"
+            );
+            Err(RunnerError::timeout(format!(
+                "{}: session setup timed out after {}s (check agent auth)",
+                self.name,
+                acp_session_timeout().as_secs()
+            )))
+        })
+    }
+
+    /// Reuse the conversation's ACP session if it is still alive, otherwise
+    /// create a new session on a pooled connection.
+    async fn open_session_inner(
+        &self,
+        request: &ChatRequest,
+        model: Option<&str>,
+    ) -> Result<SessionLease, RunnerError> {
+        let model_key = model.unwrap_or_default();
+        if let Some(key) = &request.session_key {
+            if let Some(lease) = self.resume_conversation(key, model_key).await {
+                return Ok(lease);
+            }
+        }
+
+        let connection = self.pool().await?.acquire().await?;
+        let system_prompt = Self::extract_system_prompt(request);
+        info!(
+            label = self.name,
+            model = model_key,
+            has_system_prompt = system_prompt.is_some(),
+            "ACP: creating session"
+        );
+        let params = session_params(&self.working_directory()?, model, system_prompt);
+        let session_id = match connection.new_session(params).await {
+            Ok(id) => id,
+            Err(e) => {
+                warn!(error = %e, stderr = %connection.stderr_tail(), "ACP session setup failed");
+                return Err(e);
+            }
+        };
+        info!(session_id = %session_id, model = model_key, "ACP session created");
+
+        let turn_guard = match &request.session_key {
+            Some(key) => {
+                let turn_lock = Arc::new(tokio::sync::Mutex::new(()));
+                let guard = Arc::clone(&turn_lock).lock_owned().await;
+                let mut conversations = self.conversations.lock().expect("conversations poisoned");
+                conversations.retain(|_, s| s.connection.strong_count() > 0);
+                conversations.insert(
+                    key.clone(),
+                    ConversationSession {
+                        connection: Arc::downgrade(&connection),
+                        session_id: session_id.clone(),
+                        model: model_key.to_owned(),
+                        turn_lock,
+                    },
+                );
+                Some(guard)
+            }
+            None => None,
+        };
+
+        Ok(SessionLease {
+            connection,
+            session_id,
+            resumed: false,
+            _turn_guard: turn_guard,
+        })
+    }
+
+    /// Look up a live ACP session for the conversation and wait for its turn lock.
+    async fn resume_conversation(&self, key: &str, model: &str) -> Option<SessionLease> {
+        let (connection, session_id, turn_lock) = {
+            let conversations = self.conversations.lock().expect("conversations poisoned");
+            let session = conversations.get(key).filter(|s| s.model == model)?;
+            let connection = session.connection.upgrade().filter(|c| c.is_alive())?;
+            (
+                connection,
+                session.session_id.clone(),
+                Arc::clone(&session.turn_lock),
+            )
+        };
+        let guard = turn_lock.lock_owned().await;
+        // The process may have died while we waited for the previous turn
+        if !connection.is_alive() {
+            return None;
+        }
+        debug!(session_id = %session_id, "ACP: reusing conversation session");
+        Some(SessionLease {
+            connection,
+            session_id,
+            resumed: true,
+            _turn_guard: Some(guard),
+        })
+    }
+
+    /// Run a conversation turn and return detailed results including tool call metadata.
+    ///
+    /// Unlike [`complete()`](LlmProvider::complete), this returns an [`HeadlessToolResponse`]
+    /// with observed tool calls that the agent executed internally during the turn.
+    pub async fn converse(
+        &self,
+        request: &ChatRequest,
+    ) -> Result<HeadlessToolResponse, RunnerError> {
+        let (response, tool_calls) = self.run_turn(request, &CancellationToken::new()).await?;
+        Ok(HeadlessToolResponse {
+            content: response.content,
+            model: response.model,
+            tool_calls,
+            usage: response.usage,
+            finish_reason: response.finish_reason,
+        })
+    }
+
+    /// Run a single prompt turn on a pooled agent process.
+    ///
+    /// If `cancel` fires during session setup the request is abandoned; if it
+    /// fires mid-turn a `session/cancel` notification is sent and the process
+    /// stays in the pool. A process that times out is killed and replaced.
+    async fn run_turn(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<(ChatResponse, Vec<ObservedToolCall>), RunnerError> {
+        let model = self.requested_model(request);
+        let prompt_timeout = request.timeout_or(self.config.timeout);
+
+        let lease = tokio::select! {
+            biased;
+            () = cancel.cancelled() => {
+                return Err(RunnerError::cancelled(format!(
+                    "{}: cancelled during session setup",
+                    self.name
+                )));
+            }
+            setup = self.open_session(request, model) => setup?,
+        };
+
+        let prompt_blocks = Self::build_prompt_blocks(request, lease.resumed);
+        info!(session_id = %lease.session_id, "ACP: sending prompt");
+        let mut turn = lease
+            .connection
+            .prompt(&lease.session_id, Value::Array(prompt_blocks))
+            .await?;
+
+        let model = model.unwrap_or_else(|| self.default_model()).to_owned();
+        let outcome = tokio::select! {
+            biased;
+            () = cancel.cancelled() => None,
+            result = tokio::time::timeout(
+                prompt_timeout,
+                collect_complete(&mut turn, model, self.permission_policy, self.name),
+            ) => Some(result),
+        };
+
+        let Some(result) = outcome else {
+            cancel_turn(&mut turn).await;
+            return Err(RunnerError::cancelled(format!(
+                "{}: prompt cancelled",
+                self.name
+            )));
+        };
+
+        match &result {
+            Ok(Ok((response, tool_calls))) => {
+                info!(
+                    content_len = response.content.len(),
+                    tool_calls = tool_calls.len(),
+                    "ACP turn completed successfully"
+                );
+            }
+            Ok(Err(e)) => {
+                warn!(error = %e, stderr = %lease.connection.stderr_tail(), "ACP turn failed");
+                if e.kind == ErrorKind::Timeout {
+                    lease.connection.kill();
+                }
+            }
+            Err(_) => {
+                warn!(
+                    stderr = %lease.connection.stderr_tail(),
+                    timeout_secs = prompt_timeout.as_secs(),
+                    "ACP turn timed out"
+                );
+                lease.connection.kill();
+            }
+        }
+
+        result.map_err(|_| {
+            RunnerError::timeout(format!(
+                "{}: prompt timed out after {}s",
+                self.name,
+                prompt_timeout.as_secs()
+            ))
+        })?
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AcpRunner {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn capabilities(&self) -> LlmCapabilities {
+        self.capabilities - LlmCapabilities::SDK_TOOL_CALLING
+    }
+
+    fn default_model(&self) -> &str {
+        self.config.model.as_deref().unwrap_or(DEFAULT_MODEL)
+    }
+
+    fn available_models(&self) -> &[String] {
+        &self.available_models
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        self.run_turn(request, cancel)
+            .await
+            .map(|(response, _tool_calls)| response)
+    }
+
+    async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+        let lease = self
+            .open_session(request, self.requested_model(request))
+            .await?;
+        let prompt_blocks = Self::build_prompt_blocks(request, lease.resumed);
+        let mut turn = lease
+            .connection
+            .prompt(&lease.session_id, Value::Array(prompt_blocks))
+            .await?;
+
+        let (chunk_tx, chunk_rx) = mpsc::unbounded_channel();
+        let policy = self.permission_policy;
+        let label = self.name;
+        let timeouts = StreamTimeouts::for_request(&self.config, request);
+
+        tokio::spawn(async move {
+            let deadline = StreamDeadline::start(timeouts, label);
+            // A closed channel means the consumer dropped the stream
+            let outcome = tokio::select! {
+                () = chunk_tx.closed() => None,
+                result = collect_streaming(&mut turn, &chunk_tx, policy, &deadline, label) => Some(result),
+            };
+            let Some(result) = outcome else {
+                cancel_turn(&mut turn).await;
+                return;
+            };
+            if let Err(e) = result {
+                // A timed-out agent may be wedged mid-turn; don't return it to the pool
+                if e.kind == ErrorKind::Timeout {
+                    lease.connection.kill();
+                }
+                let _ = chunk_tx.send(Err(e));
+            }
+        });
+
+        let stream = tokio_stream::wrappers::UnboundedReceiverStream::new(chunk_rx);
+        Ok(Box::pin(stream))
+    }
+
+    async fn health_check(&self) -> Result<bool, RunnerError> {
+        which::which(&self.config.binary_path).map_or(Ok(false), |path| {
+            info!(label = self.name, path = %path.display(), "ACP health check: agent binary found");
+            Ok(true)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ChatMessage;
+
+    /// Build a valid ACP permission request JSON with the given option kinds.
+    ///
+    /// Uses camelCase field names matching the `agent-client-protocol-schema` serde config.
+    /// `PermissionOptionKind` uses `snake_case`: `allow_once`, `allow_always`,
+    /// `reject_once`, `reject_always`.
+    fn make_permission_params(kinds: &[&str]) -> Value {
+        let options: Vec<Value> = kinds
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| {
+                json!({
+                    "optionId": format!("opt_{i}"),
+                    "name": format!("Option {i}"),
+                    "kind": kind
+                })
+            })
+            .collect();
+        json!({
+            "sessionId": "test-session",
+            "toolCall": {
+                "toolCallId": "tc_1"
+            },
+            "options": options
+        })
+    }
+
+    #[test]
+    fn permission_only_reject_options_cancels() {
+        let params = make_permission_params(&["reject_once", "reject_always"]);
+        let result = build_permission_response(&params, PermissionPolicy::AutoApprove);
+        assert_eq!(result["outcome"], "cancelled");
+    }
+
+    #[test]
+    fn permission_prefers_allow_always_over_allow_once() {
+        let params = make_permission_params(&["allow_once", "allow_always", "reject_once"]);
+        let result = build_permission_response(&params, PermissionPolicy::AutoApprove);
+        // AllowAlways is at index 1 → opt_1
+        let selected_id = result["outcome"]["optionId"].as_str().unwrap();
+        assert_eq!(selected_id, "opt_1");
+    }
+
+    #[test]
+    fn permission_selects_allow_once_when_no_allow_always() {
+        let params = make_permission_params(&["allow_once", "reject_once"]);
+        let result = build_permission_response(&params, PermissionPolicy::AutoApprove);
+        let selected_id = result["outcome"]["optionId"].as_str().unwrap();
+        assert_eq!(selected_id, "opt_0");
+    }
+
+    #[test]
+    fn permission_empty_options_cancels() {
+        let params = json!({
+            "sessionId": "test-session",
+            "toolCall": {
+                "toolCallId": "tc_1"
+            },
+            "options": []
+        });
+        let result = build_permission_response(&params, PermissionPolicy::AutoApprove);
+        assert_eq!(result["outcome"], "cancelled");
+    }
+
+    #[test]
+    fn permission_deny_all_policy_always_cancels() {
+        let params = make_permission_params(&["allow_once", "allow_always"]);
+        let result = build_permission_response(&params, PermissionPolicy::DenyAll);
+        assert_eq!(result["outcome"], "cancelled");
+    }
+
+    #[test]
+    fn build_prompt_blocks_text_only_no_system() {
+        let request = ChatRequest::new(vec![ChatMessage::user("Hello")]);
+        let blocks = AcpRunner::build_prompt_blocks(&request, false);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0]["type"], "text");
+        assert_eq!(blocks[0]["text"], "Hello");
+    }
+
+    #[test]
+    fn build_prompt_blocks_injects_system_prompt() {
+        let request = ChatRequest::new(vec![
+            ChatMessage::system("You are a fitness assistant"),
+            ChatMessage::user("Hello"),
+        ]);
+        let blocks = AcpRunner::build_prompt_blocks(&request, false);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0]["type"], "text");
+        let text = blocks[0]["text"].as_str().unwrap();
+        assert!(text.contains("<system-instructions>"));
+        assert!(text.contains("You are a fitness assistant"));
+        assert!(text.contains("</system-instructions>"));
+        assert!(text.contains("Hello"));
+    }
+
+    #[test]
+    fn build_prompt_blocks_with_images() {
+        use crate::types::ImagePart;
+
+        let img = ImagePart::new("aGVsbG8=", "image/png").unwrap();
+        let request = ChatRequest::new(vec![ChatMessage::user_with_images(
+            "Describe this image",
+            vec![img],
+        )]);
+        let blocks = AcpRunner::build_prompt_blocks(&request, false);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0]["type"], "text");
+        assert!(blocks[0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Describe this image"));
+        assert_eq!(blocks[1]["type"], "image");
+        assert_eq!(blocks[1]["data"], "aGVsbG8=");
+        assert_eq!(blocks[1]["mimeType"], "image/png");
+    }
+
+    #[test]
+    fn build_prompt_blocks_sends_history_only_to_new_sessions() {
+        let request = ChatRequest::new(vec![
+            ChatMessage::user("first"),
+            ChatMessage::assistant("response"),
+            ChatMessage::user("second"),
+        ]);
+        let resumed = AcpRunner::build_prompt_blocks(&request, true);
+        assert_eq!(resumed[0]["text"], "second");
+
+        let fresh = AcpRunner::build_prompt_blocks(&request, false);
+        let text = fresh[0]["text"].as_str().unwrap();
+        assert!(text.contains("[user]\nfirst"));
+        assert!(text.contains("[assistant]\nresponse"));
+        assert!(text.contains("[user]\nsecond"));
+    }
+
+    #[test]
+    fn session_params_omit_model_when_unset() {
+        let params = session_params(std::path::Path::new("/tmp"), None, None);
+        assert_eq!(params["cwd"], "/tmp");
+        assert!(params.get("model").is_none());
+        assert!(params.get("systemPrompt").is_none());
+
+        let params = session_params(std::path::Path::new("/tmp"), Some("m"), Some("sys"));
+        assert_eq!(params["model"], "m");
+        assert_eq!(params["systemPrompt"], "sys");
+    }
+
+    #[test]
+    fn extract_usage_reads_thought_and_cache_tokens() {
+        let msg = json!({"result": {"usage": {
+            "inputTokens": 100,
+            "outputTokens": 20,
+            "thoughtTokens": 8,
+            "cachedReadTokens": 64
+        }}});
+        let usage = extract_usage(&msg).unwrap();
+        assert_eq!(usage.total_tokens, 120);
+        assert_eq!(usage.reasoning_tokens, Some(8));
+        assert_eq!(usage.cached_prompt_tokens, Some(64));
+        assert_eq!(usage.cache_creation_tokens, None);
+    }
+
+    fn fake_agent_config() -> RunnerConfig {
+        RunnerConfig::new(PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/fake_acp_agent.sh"
+        )))
+    }
+
+    /// Split the fake agent's `pid=.. session=.. turn=..` reply into its fields
+    fn reply_fields(content: &str) -> (String, String, String) {
+        let field = |name: &str| {
+            content
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix(name))
+                .unwrap_or_default()
+                .to_owned()
+        };
+        (field("pid="), field("session="), field("turn="))
+    }
+
+    #[tokio::test]
+    async fn pooled_process_serves_consecutive_requests() {
+        let runner = AcpRunner::new(fake_agent_config());
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let first = runner.complete(&request).await.unwrap();
+        let second = runner.complete(&request).await.unwrap();
+        let (pid_a, session_a, turn_a) = reply_fields(&first.content);
+        let (pid_b, session_b, turn_b) = reply_fields(&second.content);
+
+        assert_eq!(pid_a, pid_b, "process should be reused");
+        assert_ne!(session_a, session_b, "no session key means a fresh session");
+        assert_eq!((turn_a.as_str(), turn_b.as_str()), ("1", "1"));
+        assert_eq!(first.usage.unwrap().total_tokens, 5);
+        assert_eq!(first.model, "default");
+    }
+
+    #[tokio::test]
+    async fn session_key_reuses_acp_session_across_turns() {
+        let runner = AcpRunner::new(fake_agent_config());
+        let turn = |key: &str| {
+            ChatRequest::new(vec![ChatMessage::user("hi")]).with_session_key(key.to_owned())
+        };
+
+        let first = runner.complete(&turn("conv-a")).await.unwrap();
+        let second = runner.complete(&turn("conv-a")).await.unwrap();
+        let other = runner.complete(&turn("conv-b")).await.unwrap();
+
+        let (_, session_1, turn_1) = reply_fields(&first.content);
+        let (_, session_2, turn_2) = reply_fields(&second.content);
+        let (_, session_3, _) = reply_fields(&other.content);
+        assert_eq!(session_1, session_2);
+        assert_eq!((turn_1.as_str(), turn_2.as_str()), ("1", "2"));
+        assert_ne!(session_1, session_3);
+    }
+
+    #[tokio::test]
+    async fn cancelled_turn_leaves_process_in_pool() {
+        let runner = AcpRunner::new(fake_agent_config());
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            trigger.cancel();
+        });
+
+        let hang = ChatRequest::new(vec![ChatMessage::user("HANG")]);
+        let err = runner
+            .complete_with_cancel(&hang, &cancel)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+
+        let pool = runner.pool().await.unwrap();
+        assert_eq!(pool.live_connections().await, 1);
+        let ok = runner
+            .complete(&ChatRequest::new(vec![ChatMessage::user("hi")]))
+            .await
+            .unwrap();
+        assert!(ok.content.starts_with("pid="));
+    }
+
+    #[tokio::test]
+    async fn prompt_timeout_kills_the_agent() {
+        let runner = AcpRunner::new(fake_agent_config().with_timeout(Duration::from_millis(200)));
+        let hang = ChatRequest::new(vec![ChatMessage::user("HANG")]);
+        let err = runner.complete(&hang).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Timeout);
+        assert!(err.message.starts_with("acp:"));
+        assert_eq!(runner.pool().await.unwrap().live_connections().await, 0);
+    }
+
+    #[tokio::test]
+    async fn silent_stream_hits_idle_timeout() {
+        use tokio_stream::StreamExt;
+
+        let runner =
+            AcpRunner::new(fake_agent_config().with_idle_timeout(Duration::from_millis(200)));
+        let hang = ChatRequest::new(vec![ChatMessage::user("HANG")]);
+        let mut stream = runner.complete_stream(&hang).await.unwrap();
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Timeout);
+        assert!(err.message.starts_with("acp: stream produced no output"));
+        assert!(stream.next().await.is_none());
+        assert_eq!(runner.pool().await.unwrap().live_connections().await, 0);
+    }
+
+    #[tokio::test]
+    async fn stream_yields_agent_chunks_from_pool() {
+        use tokio_stream::StreamExt;
+
+        let runner = AcpRunner::new(fake_agent_config());
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+        let mut stream = runner.complete_stream(&request).await.unwrap();
+
+        let mut content = String::new();
+        let mut finished = false;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            content.push_str(&chunk.delta);
+            finished |= chunk.is_final;
+        }
+        assert!(finished);
+        assert!(content.contains("turn=1"));
+    }
+
+    #[tokio::test]
+    async fn agent_receives_args_and_sandboxed_env() {
+        let config = fake_agent_config().with_extra_args(vec!["--experimental-acp".to_owned()]);
+        let runner = AcpRunner::new(config).with_env("FAKE_ACP_MARKER", "set");
+        let response = runner
+            .complete(&ChatRequest::new(vec![ChatMessage::user("ENV")]))
+            .await
+            .unwrap();
+        assert!(response.content.contains("args=--experimental-acp"));
+        assert!(response.content.contains("marker=set"));
+        // Host variables outside allowed_env_keys are not passed through
+        assert!(response.content.contains("manifest=unset"));
+    }
+
+    #[tokio::test]
+    async fn missing_agent_binary_is_reported() {
+        let runner = AcpRunner::new(RunnerConfig::new(PathBuf::from(
+            "/nonexistent/embacle-acp-agent",
+        )));
+        assert!(!runner.health_check().await.unwrap());
+        let err = runner
+            .complete(&ChatRequest::new(vec![ChatMessage::user("hi")]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::BinaryNotFound);
+    }
+
+    #[test]
+    fn identity_and_capabilities_are_configurable() {
+        let runner = AcpRunner::new(fake_agent_config().with_model("gemini-2.5-pro"))
+            .with_identity("gemini_acp", "Gemini (ACP)")
+            .with_capabilities(
+                LlmCapabilities::STREAMING
+                    | LlmCapabilities::VISION
+                    | LlmCapabilities::SDK_TOOL_CALLING,
+            );
+        assert_eq!(runner.name(), "gemini_acp");
+        assert_eq!(runner.display_name(), "Gemini (ACP)");
+        assert_eq!(runner.default_model(), "gemini-2.5-pro");
+        assert_eq!(runner.available_models(), ["gemini-2.5-pro".to_owned()]);
+        assert!(runner.capabilities().supports_vision());
+        assert!(!runner.capabilities().supports_sdk_tool_calling());
+    }
+}
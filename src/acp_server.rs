@@ -0,0 +1,1009 @@
+// ABOUTME: Agent side of the Agent Client Protocol, serving any LlmProvider to ACP-capable editors
+// ABOUTME: Handles initialize, session/new, session/prompt and session/cancel over NDJSON JSON-RPC
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # ACP Agent Server
+//!
+//! [`AcpAgentServer`] lets editors that speak the
+//! [Agent Client Protocol](https://agentclientprotocol.com) use any
+//! [`LlmProvider`] — a single CLI runner, a
+//! [`FallbackProvider`](crate::FallbackProvider) chain or any decorator stack —
+//! as their agent. It is the mirror image of [`AcpRunner`](crate::AcpRunner),
+//! which drives ACP agents as a client.
+//!
+//! Supported methods:
+//!
+//! - `initialize` — advertises prompt capabilities derived from the provider
+//! - `session/new` — creates a conversation; the optional `model` and
+//!   `systemPrompt` extension params select the model and system message
+//! - `session/prompt` — runs a turn, streaming `agent_message_chunk` (and
+//!   `agent_thought_chunk` for reasoning) `session/update` notifications,
+//!   and replies with the stop reason and token usage
+//! - `session/cancel` — cancels the in-flight turn, which then ends with
+//!   stop reason `cancelled`
+//!
+//! When tools are configured with [`AcpAgentServer::with_tools`], each turn
+//! runs through the [`AgentExecutor`] tool loop and executed tools are
+//! reported as `tool_call` updates. MCP servers offered in `session/new` are
+//! not connected.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use agent_client_protocol_schema as schema;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::agent::AgentExecutor;
+use crate::tool_simulation::{FunctionDeclaration, TextToolHandler};
+use crate::types::{
+    ChatMessage, ChatRequest, ErrorKind, ImagePart, LlmProvider, RunnerError, TokenUsage,
+};
+
+/// ACP protocol version implemented by this server
+const PROTOCOL_VERSION: u64 = 1;
+
+/// JSON-RPC parse error code
+const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC invalid request code
+const INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC method-not-found code
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC invalid params code
+const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC internal error code
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Tools run through the [`AgentExecutor`] loop on every prompt
+struct AgentTools {
+    declarations: Vec<FunctionDeclaration>,
+    handler: TextToolHandler,
+    max_turns: Option<u32>,
+}
+
+/// Conversation state for one ACP session
+struct AgentSession {
+    history: Vec<ChatMessage>,
+    model: Option<String>,
+    /// Cancellation handle of the in-flight prompt, if any
+    active: Option<CancellationToken>,
+}
+
+/// Outcome of a prompt turn before it is mapped to an ACP response
+struct TurnOutcome {
+    content: String,
+    finish_reason: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+/// ACP agent serving an [`LlmProvider`] over NDJSON JSON-RPC
+pub struct AcpAgentServer {
+    provider: Arc<dyn LlmProvider>,
+    tools: Option<AgentTools>,
+    sessions: Mutex<HashMap<String, AgentSession>>,
+    next_session: AtomicU64,
+}
+
+impl AcpAgentServer {
+    /// Create a server that answers every prompt with `provider`
+    #[must_use]
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            provider,
+            tools: None,
+            sessions: Mutex::new(HashMap::new()),
+            next_session: AtomicU64::new(1),
+        }
+    }
+
+    /// Run prompts through the [`AgentExecutor`] tool loop with these tools
+    #[must_use]
+    pub fn with_tools(
+        mut self,
+        declarations: Vec<FunctionDeclaration>,
+        handler: TextToolHandler,
+    ) -> Self {
+        self.tools = Some(AgentTools {
+            declarations,
+            handler,
+            max_turns: self.tools.and_then(|t| t.max_turns),
+        });
+        self
+    }
+
+    /// Set the maximum tool-loop turns per prompt (only used with tools)
+    #[must_use]
+    pub fn with_max_turns(mut self, max_turns: u32) -> Self {
+        if let Some(tools) = self.tools.as_mut() {
+            tools.max_turns = Some(max_turns);
+        }
+        self
+    }
+
+    /// Serve the protocol on the process's stdin and stdout until stdin closes
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if reading stdin or writing stdout fails.
+    pub async fn serve_stdio(self: Arc<Self>) -> Result<(), RunnerError> {
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await
+    }
+
+    /// Serve the protocol on an arbitrary byte stream pair until `reader` closes
+    ///
+    /// Prompts run concurrently so `session/cancel` is handled while a turn is
+    /// in flight. Turns still running when the input closes are cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if reading or writing the transport fails.
+    pub async fn serve<R, W>(self: Arc<Self>, reader: R, writer: W) -> Result<(), RunnerError>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (out_tx, out_rx) = mpsc::unbounded_channel();
+        let writer_task = tokio::spawn(write_loop(writer, out_rx));
+        let mut prompts = JoinSet::new();
+        let mut lines = BufReader::new(reader).lines();
+
+        debug!("ACP agent server ready");
+        let read_result = loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(RunnerError::internal(format!("ACP read error: {e}"))),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Value>(&line) {
+                Ok(msg) => self.dispatch(&msg, &out_tx, &mut prompts),
+                Err(e) => {
+                    warn!(error = %e, "ACP: failed to parse message");
+                    let _ = out_tx.send(error_message(
+                        &Value::Null,
+                        PARSE_ERROR,
+                        &format!("Parse error: {e}"),
+                    ));
+                }
+            }
+        };
+
+        debug!("ACP input closed, cancelling in-flight prompts");
+        self.cancel_all();
+        while prompts.join_next().await.is_some() {}
+        drop(out_tx);
+        writer_task
+            .await
+            .map_err(|e| RunnerError::internal(format!("ACP writer task failed: {e}")))??;
+        read_result
+    }
+
+    /// Route one incoming JSON-RPC message
+    fn dispatch(
+        self: &Arc<Self>,
+        msg: &Value,
+        out: &mpsc::UnboundedSender<Value>,
+        prompts: &mut JoinSet<()>,
+    ) {
+        let Some(method) = msg.get("method").and_then(Value::as_str) else {
+            // Responses to client requests: this agent never sends any
+            debug!("ACP: ignoring message without method");
+            return;
+        };
+        let id = msg.get("id").cloned();
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+        debug!(method, "ACP agent request");
+
+        let reply = match (method, &id) {
+            ("session/cancel", _) => {
+                self.cancel_session(&params);
+                return;
+            }
+            ("session/prompt", Some(id)) => match self.start_prompt(&params) {
+                Ok((session_id, request, cancel)) => {
+                    let server = Arc::clone(self);
+                    let out = out.clone();
+                    let id = id.clone();
+                    prompts.spawn(async move {
+                        let reply = server.run_prompt(&session_id, request, &cancel, &out).await;
+                        server.finish_prompt(&session_id);
+                        let _ = out.send(reply.map_or_else(
+                            |e| error_message(&id, INTERNAL_ERROR, &e.to_string()),
+                            |result| result_message(&id, &result),
+                        ));
+                    });
+                    return;
+                }
+                Err((code, message)) => Err((code, message)),
+            },
+            ("initialize", Some(_)) => Ok(self.initialize()),
+            ("authenticate", Some(_)) => Ok(json!({})),
+            ("session/new", Some(_)) => self.new_session(&params),
+            (_, Some(_)) => Err((METHOD_NOT_FOUND, format!("Method not found: {method}"))),
+            (_, None) => {
+                debug!(method, "ACP: ignoring unknown notification");
+                return;
+            }
+        };
+
+        let id = id.unwrap_or(Value::Null);
+        let _ = out.send(match reply {
+            Ok(result) => result_message(&id, &result),
+            Err((code, message)) => error_message(&id, code, &message),
+        });
+    }
+
+    /// Build the `initialize` result
+    fn initialize(&self) -> Value {
+        let capabilities = self.provider.capabilities();
+        json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "agentCapabilities": {
+                "loadSession": false,
+                "promptCapabilities": {
+                    "image": capabilities.supports_vision(),
+                    "audio": false,
+                    "embeddedContext": true,
+                },
+            },
+            "authMethods": [],
+            "agentInfo": {
+                "name": "embacle",
+                "title": self.provider.display_name(),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        })
+    }
+
+    /// Handle `session/new`
+    fn new_session(&self, params: &Value) -> Result<Value, (i64, String)> {
+        if params.get("cwd").and_then(Value::as_str).is_none() {
+            return Err((INVALID_PARAMS, "session/new requires cwd".to_owned()));
+        }
+        let servers = params
+            .get("mcpServers")
+            .and_then(Value::as_array)
+            .map_or(0, Vec::len);
+        if servers > 0 {
+            debug!(servers, "ACP: client MCP servers are not connected");
+        }
+
+        let mut history = Vec::new();
+        if let Some(system) = params.get("systemPrompt").and_then(Value::as_str) {
+            history.push(ChatMessage::system(system));
+        }
+        let model = params
+            .get("model")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned);
+
+        let session_id = format!(
+            "embacle-{}",
+            self.next_session.fetch_add(1, Ordering::Relaxed)
+        );
+        info!(session_id = %session_id, model = ?model, "ACP: session created");
+        self.sessions.lock().expect("sessions poisoned").insert(
+            session_id.clone(),
+            AgentSession {
+                history,
+                model,
+                active: None,
+            },
+        );
+        Ok(json!({ "sessionId": session_id }))
+    }
+
+    /// Validate a `session/prompt` and mark its session busy
+    fn start_prompt(
+        &self,
+        params: &Value,
+    ) -> Result<(String, ChatRequest, CancellationToken), (i64, String)> {
+        let prompt =
+            serde_json::from_value::<schema::PromptRequest>(params.clone()).map_err(|e| {
+                (
+                    INVALID_PARAMS,
+                    format!("Invalid session/prompt params: {e}"),
+                )
+            })?;
+        let session_id = prompt.session_id.0.to_string();
+        let message = prompt_to_message(&prompt.prompt);
+
+        let mut sessions = self.sessions.lock().expect("sessions poisoned");
+        let session = sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown session: {session_id}")))?;
+        if session.active.is_some() {
+            return Err((
+                INVALID_REQUEST,
+                format!("Session {session_id} already has a prompt in flight"),
+            ));
+        }
+
+        let mut messages = session.history.clone();
+        messages.push(message.clone());
+        session.history.push(message);
+        let mut request = ChatRequest::new(messages);
+        request.model.clone_from(&session.model);
+
+        let cancel = CancellationToken::new();
+        session.active = Some(cancel.clone());
+        Ok((session_id, request, cancel))
+    }
+
+    /// Run a prompt turn and build the `session/prompt` result
+    async fn run_prompt(
+        &self,
+        session_id: &str,
+        request: ChatRequest,
+        cancel: &CancellationToken,
+        out: &mpsc::UnboundedSender<Value>,
+    ) -> Result<Value, RunnerError> {
+        let outcome = match &self.tools {
+            Some(tools) => {
+                self.run_tool_loop(session_id, request, tools, cancel, out)
+                    .await
+            }
+            None if self.provider.capabilities().supports_streaming() => {
+                self.run_streaming(session_id, &request, cancel, out).await
+            }
+            None => self
+                .provider
+                .complete_with_cancel(&request, cancel)
+                .await
+                .map(|response| {
+                    send_update(out, session_id, &text_chunk(&response.content));
+                    TurnOutcome {
+                        content: response.content,
+                        finish_reason: response.finish_reason,
+                        usage: response.usage,
+                    }
+                }),
+        };
+
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) if e.kind == ErrorKind::Cancelled || cancel.is_cancelled() => {
+                info!(session_id, "ACP: prompt cancelled");
+                // Forget the unanswered user message so the next turn starts cleanly
+                self.with_session(session_id, |session| {
+                    session.history.pop();
+                });
+                return Ok(json!({ "stopReason": "cancelled" }));
+            }
+            Err(e) => {
+                warn!(session_id, error = %e, "ACP: prompt failed");
+                self.with_session(session_id, |session| {
+                    session.history.pop();
+                });
+                return Err(e);
+            }
+        };
+
+        self.with_session(session_id, |session| {
+            session
+                .history
+                .push(ChatMessage::assistant(outcome.content.clone()));
+        });
+
+        let stop_reason = if cancel.is_cancelled() {
+            "cancelled"
+        } else {
+            stop_reason(outcome.finish_reason.as_deref())
+        };
+        let mut result = json!({ "stopReason": stop_reason });
+        if let Some(usage) = outcome.usage {
+            let mut wire = json!({
+                "inputTokens": usage.prompt_tokens,
+                "outputTokens": usage.completion_tokens,
+                "totalTokens": usage.total_tokens,
+            });
+            let optional = [
+                ("thoughtTokens", usage.reasoning_tokens),
+                ("cachedReadTokens", usage.cached_prompt_tokens),
+                ("cachedWriteTokens", usage.cache_creation_tokens),
+            ];
+            for (key, value) in optional {
+                if let Some(value) = value {
+                    wire[key] = json!(value);
+                }
+            }
+            result["usage"] = wire;
+        }
+        Ok(result)
+    }
+
+    /// Stream provider chunks as `agent_message_chunk` and `agent_thought_chunk` updates
+    async fn run_streaming(
+        &self,
+        session_id: &str,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+        out: &mpsc::UnboundedSender<Value>,
+    ) -> Result<TurnOutcome, RunnerError> {
+        let mut stream = tokio::select! {
+            biased;
+            () = cancel.cancelled() => return Err(RunnerError::cancelled("ACP prompt cancelled")),
+            stream = self.provider.complete_stream(request) => stream?,
+        };
+
+        let mut content = String::new();
+        let mut finish_reason = None;
+        let mut usage = None;
+        loop {
+            let chunk = tokio::select! {
+                biased;
+                () = cancel.cancelled() => {
+                    return Err(RunnerError::cancelled("ACP prompt cancelled"));
+                }
+                chunk = stream.next() => chunk,
+            };
+            let Some(chunk) = chunk else { break };
+            let chunk = chunk?;
+            if let Some(reasoning) = chunk.reasoning_delta.as_deref().filter(|r| !r.is_empty()) {
+                send_update(out, session_id, &thought_chunk(reasoning));
+            }
+            if !chunk.delta.is_empty() {
+                send_update(out, session_id, &text_chunk(&chunk.delta));
+                content.push_str(&chunk.delta);
+            }
+            if chunk.is_final {
+                finish_reason = chunk.finish_reason;
+                usage = chunk.usage;
+                break;
+            }
+        }
+
+        Ok(TurnOutcome {
+            content,
+            finish_reason,
+            usage,
+        })
+    }
+
+    /// Run the [`AgentExecutor`] loop, reporting executed tools as `tool_call` updates
+    async fn run_tool_loop(
+        &self,
+        session_id: &str,
+        request: ChatRequest,
+        tools: &AgentTools,
+        cancel: &CancellationToken,
+        out: &mpsc::UnboundedSender<Value>,
+    ) -> Result<TurnOutcome, RunnerError> {
+        let updates = out.clone();
+        let update_session = session_id.to_owned();
+        let mut executor = AgentExecutor::new(
+            self.provider.as_ref(),
+            tools.declarations.clone(),
+            Arc::clone(&tools.handler),
+        )
+        .with_cancellation(cancel.clone())
+        .with_on_turn(Arc::new(move |turn| {
+            if turn.tool_calls.is_empty() {
+                return;
+            }
+            if !turn.content.is_empty() {
+                send_update(&updates, &update_session, &text_chunk(&turn.content));
+            }
+            for (index, call) in turn.tool_calls.iter().enumerate() {
+                send_update(
+                    &updates,
+                    &update_session,
+                    &json!({
+                        "sessionUpdate": "tool_call",
+                        "toolCallId": format!("call_{}_{index}", turn.turn),
+                        "title": call.name,
+                        "kind": "other",
+                        "status": "completed",
+                        "rawInput": call.args,
+                    }),
+                );
+            }
+        }));
+        if let Some(max_turns) = tools.max_turns {
+            executor = executor.with_max_turns(max_turns);
+        }
+
+        let result = executor.run(request.messages).await?;
+        send_update(out, session_id, &text_chunk(&result.content));
+        Ok(TurnOutcome {
+            content: result.content,
+            finish_reason: result.finish_reason,
+            usage: Some(result.total_usage),
+        })
+    }
+
+    /// Handle `session/cancel`
+    fn cancel_session(&self, params: &Value) {
+        let Some(session_id) = params.get("sessionId").and_then(Value::as_str) else {
+            return;
+        };
+        self.with_session(session_id, |session| {
+            if let Some(cancel) = &session.active {
+                info!(session_id, "ACP: cancelling prompt");
+                cancel.cancel();
+            }
+        });
+    }
+
+    /// Cancel every in-flight prompt
+    fn cancel_all(&self) {
+        let sessions = self.sessions.lock().expect("sessions poisoned");
+        for cancel in sessions.values().filter_map(|s| s.active.as_ref()) {
+            cancel.cancel();
+        }
+    }
+
+    /// Mark a session idle once its prompt has been answered
+    fn finish_prompt(&self, session_id: &str) {
+        self.with_session(session_id, |session| session.active = None);
+    }
+
+    fn with_session(&self, session_id: &str, f: impl FnOnce(&mut AgentSession)) {
+        if let Some(session) = self
+            .sessions
+            .lock()
+            .expect("sessions poisoned")
+            .get_mut(session_id)
+        {
+            f(session);
+        }
+    }
+}
+
+/// Convert ACP prompt content blocks into a user message.
+///
+/// Text blocks are joined, embedded text resources are inlined with their
+/// URI, resource links are listed by URI, and images become [`ImagePart`]s.
+fn prompt_to_message(blocks: &[schema::ContentBlock]) -> ChatMessage {
+    let mut parts = Vec::new();
+    let mut images = Vec::new();
+    for block in blocks {
+        match block {
+            schema::ContentBlock::Text(text) => parts.push(text.text.clone()),
+            schema::ContentBlock::Image(image) => {
+                match ImagePart::new(image.data.clone(), image.mime_type.clone()) {
+                    Ok(part) => images.push(part),
+                    Err(e) => warn!(error = %e, "ACP: skipping unsupported image"),
+                }
+            }
+            schema::ContentBlock::Resource(resource) => {
+                if let schema::EmbeddedResourceResource::TextResourceContents(contents) =
+                    &resource.resource
+                {
+                    parts.push(format!(
+                        "<resource uri=\"{}\">\n{}\n</resource>",
+                        contents.uri, contents.text
+                    ));
+                }
+            }
+            schema::ContentBlock::ResourceLink(link) => parts.push(format!("[{}]", link.uri)),
+            _ => debug!("ACP: skipping unsupported content block"),
+        }
+    }
+    let text = parts.join("\n\n");
+    if images.is_empty() {
+        ChatMessage::user(text)
+    } else {
+        ChatMessage::user_with_images(text, images)
+    }
+}
+
+/// Map an embacle finish reason to an ACP stop reason
+fn stop_reason(finish_reason: Option<&str>) -> &'static str {
+    match finish_reason {
+        Some("length") => "max_tokens",
+        Some("max_turns") => "max_turn_requests",
+        Some("refusal" | "content_filter") => "refusal",
+        Some("cancelled") => "cancelled",
+        _ => "end_turn",
+    }
+}
+
+fn text_chunk(text: &str) -> Value {
+    json!({
+        "sessionUpdate": "agent_message_chunk",
+        "content": { "type": "text", "text": text },
+    })
+}
+
+fn thought_chunk(text: &str) -> Value {
+    json!({
+        "sessionUpdate": "agent_thought_chunk",
+        "content": { "type": "text", "text": text },
+    })
+}
+
+fn send_update(out: &mpsc::UnboundedSender<Value>, session_id: &str, update: &Value) {
+    let _ = out.send(json!({
+        "jsonrpc": "2.0",
+        "method": "session/update",
+        "params": { "sessionId": session_id, "update": update },
+    }));
+}
+
+fn result_message(id: &Value, result: &Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error_message(id: &Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Write outgoing messages as NDJSON until every sender is dropped
+async fn write_loop<W>(
+    mut writer: W,
+    mut rx: mpsc::UnboundedReceiver<Value>,
+) -> Result<(), RunnerError>
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(msg) = rx.recv().await {
+        let mut line = msg.to_string();
+        line.push('\n');
+        writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| RunnerError::internal(format!("ACP write failed: {e}")))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| RunnerError::internal(format!("ACP flush failed: {e}")))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool_simulation::FunctionResponse;
+    use crate::types::{
+        ChatResponse, ChatStream, LlmCapabilities, MessageRole, RequestMetadata, StreamChunk,
+    };
+    use async_trait::async_trait;
+    use tokio::io::{DuplexStream, Lines};
+
+    /// Replies with the number of messages it was sent; `HANG` waits for cancellation
+    struct CountingProvider {
+        streaming: bool,
+        replies: Mutex<Vec<String>>,
+    }
+
+    impl CountingProvider {
+        fn new(streaming: bool) -> Self {
+            Self {
+                streaming,
+                replies: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn scripted(replies: &[&str]) -> Self {
+            Self {
+                streaming: false,
+                replies: Mutex::new(replies.iter().rev().map(|r| (*r).to_owned()).collect()),
+            }
+        }
+
+        fn reply(&self, request: &ChatRequest) -> String {
+            self.replies.lock().unwrap().pop().unwrap_or_else(|| {
+                let system = request
+                    .messages
+                    .iter()
+                    .any(|m| m.role == MessageRole::System);
+                format!(
+                    "messages={} system={system} model={}",
+                    request.messages.len(),
+                    request.model.as_deref().unwrap_or("none")
+                )
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for CountingProvider {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+        fn display_name(&self) -> &str {
+            "Counting"
+        }
+        fn capabilities(&self) -> LlmCapabilities {
+            if self.streaming {
+                LlmCapabilities::STREAMING
+            } else {
+                LlmCapabilities::empty()
+            }
+        }
+        fn default_model(&self) -> &str {
+            "count"
+        }
+        fn available_models(&self) -> &[String] {
+            &[]
+        }
+        async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+            self.complete_with_cancel(request, &CancellationToken::new())
+                .await
+        }
+        async fn complete_with_cancel(
+            &self,
+            request: &ChatRequest,
+            cancel: &CancellationToken,
+        ) -> Result<ChatResponse, RunnerError> {
+            let last = request.messages.last().map(|m| m.content.clone());
+            if last.as_deref() == Some("HANG") {
+                cancel.cancelled().await;
+                return Err(RunnerError::cancelled("hung provider cancelled"));
+            }
+            Ok(ChatResponse {
+                content: self.reply(request),
+                model: "count".to_owned(),
+                usage: Some(TokenUsage {
+                    prompt_tokens: 4,
+                    completion_tokens: 2,
+                    total_tokens: 6,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
+                }),
+                finish_reason: Some("stop".to_owned()),
+                warnings: None,
+                tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
+            })
+        }
+        async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+            let reply = self.reply(request);
+            let (head, tail) = reply.split_at(reply.len() / 2);
+            let chunks = vec![
+                Ok(StreamChunk {
+                    delta: head.to_owned(),
+                    reasoning_delta: Some("counting".to_owned()),
+                    ..StreamChunk::default()
+                }),
+                Ok(StreamChunk {
+                    delta: tail.to_owned(),
+                    is_final: true,
+                    finish_reason: Some("length".to_owned()),
+                    usage: Some(TokenUsage {
+                        prompt_tokens: 3,
+                        completion_tokens: 1,
+                        total_tokens: 4,
+                        reasoning_tokens: Some(1),
+                        cached_prompt_tokens: None,
+                        cache_creation_tokens: None,
+                        reported_cost: None,
+                    }),
+                    ..StreamChunk::default()
+                }),
+            ];
+            Ok(Box::pin(tokio_stream::iter(chunks)))
+        }
+        async fn health_check(&self) -> Result<bool, RunnerError> {
+            Ok(true)
+        }
+    }
+
+    struct Client {
+        input: DuplexStream,
+        output: Lines<BufReader<DuplexStream>>,
+        next_id: u64,
+    }
+
+    impl Client {
+        fn start(server: AcpAgentServer) -> Self {
+            let (input, server_in) = tokio::io::duplex(64 * 1024);
+            let (server_out, output) = tokio::io::duplex(64 * 1024);
+            tokio::spawn(Arc::new(server).serve(server_in, server_out));
+            Self {
+                input,
+                output: BufReader::new(output).lines(),
+                next_id: 0,
+            }
+        }
+
+        async fn send(&mut self, msg: Value) {
+            let mut line = msg.to_string();
+            line.push('\n');
+            self.input.write_all(line.as_bytes()).await.unwrap();
+        }
+
+        async fn request(&mut self, method: &str, params: Value) -> u64 {
+            self.next_id += 1;
+            let id = self.next_id;
+            self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+                .await;
+            id
+        }
+
+        async fn recv(&mut self) -> Value {
+            let line = self.output.next_line().await.unwrap().unwrap();
+            serde_json::from_str(&line).unwrap()
+        }
+
+        /// Read until the response for `id`, returning it and the updates seen before it
+        async fn response(&mut self, id: u64) -> (Value, Vec<Value>) {
+            let mut updates = Vec::new();
+            loop {
+                let msg = self.recv().await;
+                if msg["id"] == id {
+                    return (msg, updates);
+                }
+                updates.push(msg["params"]["update"].clone());
+            }
+        }
+
+        async fn call(&mut self, method: &str, params: Value) -> (Value, Vec<Value>) {
+            let id = self.request(method, params).await;
+            self.response(id).await
+        }
+
+        async fn new_session(&mut self, extra: Value) -> String {
+            let mut params = json!({"cwd": "/tmp", "mcpServers": []});
+            if let (Some(params), Some(extra)) = (params.as_object_mut(), extra.as_object()) {
+                params.extend(extra.clone());
+            }
+            let (resp, _) = self.call("session/new", params).await;
+            resp["result"]["sessionId"].as_str().unwrap().to_owned()
+        }
+
+        async fn prompt(&mut self, session_id: &str, text: &str) -> (Value, Vec<Value>) {
+            self.call(
+                "session/prompt",
+                json!({"sessionId": session_id, "prompt": [{"type": "text", "text": text}]}),
+            )
+            .await
+        }
+    }
+
+    fn message_text(updates: &[Value]) -> String {
+        updates
+            .iter()
+            .filter(|u| u["sessionUpdate"] == "agent_message_chunk")
+            .filter_map(|u| u["content"]["text"].as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn initialize_advertises_protocol_and_capabilities() {
+        let mut client = Client::start(AcpAgentServer::new(Arc::new(CountingProvider::new(true))));
+        let (resp, _) = client
+            .call(
+                "initialize",
+                json!({"protocolVersion": 1, "clientCapabilities": {}}),
+            )
+            .await;
+        assert_eq!(resp["result"]["protocolVersion"], 1);
+        assert_eq!(
+            resp["result"]["agentCapabilities"]["promptCapabilities"]["image"],
+            false
+        );
+        assert_eq!(resp["result"]["agentInfo"]["title"], "Counting");
+    }
+
+    #[tokio::test]
+    async fn prompts_stream_chunks_and_keep_history() {
+        let mut client = Client::start(AcpAgentServer::new(Arc::new(CountingProvider::new(true))));
+        let session = client
+            .new_session(json!({"systemPrompt": "be brief", "model": "m1"}))
+            .await;
+
+        let (resp, updates) = client.prompt(&session, "hello").await;
+        assert_eq!(resp["result"]["stopReason"], "max_tokens");
+        assert_eq!(resp["result"]["usage"]["totalTokens"], 4);
+        assert_eq!(resp["result"]["usage"]["thoughtTokens"], 1);
+        assert!(resp["result"]["usage"].get("cachedReadTokens").is_none());
+        assert!(updates.len() >= 2, "reply should arrive in chunks");
+        assert_eq!(message_text(&updates), "messages=2 system=true model=m1");
+        assert!(updates
+            .iter()
+            .any(|u| u["sessionUpdate"] == "agent_thought_chunk"
+                && u["content"]["text"] == "counting"));
+
+        // system + user + assistant + user
+        let (_, updates) = client.prompt(&session, "again").await;
+        assert_eq!(message_text(&updates), "messages=4 system=true model=m1");
+    }
+
+    #[tokio::test]
+    async fn non_streaming_provider_reports_usage() {
+        let mut client = Client::start(AcpAgentServer::new(Arc::new(CountingProvider::new(false))));
+        let session = client.new_session(json!({})).await;
+        let (resp, updates) = client.prompt(&session, "hello").await;
+        assert_eq!(resp["result"]["stopReason"], "end_turn");
+        assert_eq!(resp["result"]["usage"]["totalTokens"], 6);
+        assert_eq!(message_text(&updates), "messages=1 system=false model=none");
+    }
+
+    #[tokio::test]
+    async fn session_cancel_ends_turn_with_cancelled() {
+        let mut client = Client::start(AcpAgentServer::new(Arc::new(CountingProvider::new(false))));
+        let session = client.new_session(json!({})).await;
+
+        let id = client
+            .request(
+                "session/prompt",
+                json!({"sessionId": session, "prompt": [{"type": "text", "text": "HANG"}]}),
+            )
+            .await;
+        client
+            .send(json!({"jsonrpc": "2.0", "method": "session/cancel", "params": {"sessionId": session}}))
+            .await;
+        let (resp, _) = client.response(id).await;
+        assert_eq!(resp["result"]["stopReason"], "cancelled");
+
+        // The cancelled user message is dropped from the history
+        let (_, updates) = client.prompt(&session, "next").await;
+        assert_eq!(message_text(&updates), "messages=1 system=false model=none");
+    }
+
+    #[tokio::test]
+    async fn tool_loop_reports_tool_calls() {
+        let provider = CountingProvider::scripted(&[
+            "Looking it up\n<tool_call>\n{\"name\": \"lookup\", \"arguments\": {\"q\": \"rust\"}}\n</tool_call>",
+            "Rust is a language",
+        ]);
+        let handler: TextToolHandler = Arc::new(|name, _args| FunctionResponse {
+            name: name.to_owned(),
+            response: json!({"answer": 42}),
+        });
+        let declarations = vec![FunctionDeclaration {
+            name: "lookup".to_owned(),
+            description: "Look something up".to_owned(),
+            parameters: None,
+        }];
+        let server = AcpAgentServer::new(Arc::new(provider)).with_tools(declarations, handler);
+        let mut client = Client::start(server);
+        let session = client.new_session(json!({})).await;
+
+        let (resp, updates) = client.prompt(&session, "what is rust?").await;
+        assert_eq!(resp["result"]["stopReason"], "end_turn");
+        let tool_call = updates
+            .iter()
+            .find(|u| u["sessionUpdate"] == "tool_call")
+            .expect("tool_call update");
+        assert_eq!(tool_call["title"], "lookup");
+        assert_eq!(tool_call["rawInput"]["q"], "rust");
+        assert!(message_text(&updates).ends_with("Rust is a language"));
+    }
+
+    #[tokio::test]
+    async fn protocol_errors_use_json_rpc_codes() {
+        let mut client = Client::start(AcpAgentServer::new(Arc::new(CountingProvider::new(false))));
+        let (resp, _) = client.call("session/load", json!({})).await;
+        assert_eq!(resp["error"]["code"], METHOD_NOT_FOUND);
+
+        let (resp, _) = client.prompt("missing", "hi").await;
+        assert_eq!(resp["error"]["code"], INVALID_PARAMS);
+
+        client.input.write_all(b"not json\n").await.unwrap();
+        assert_eq!(client.recv().await["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn prompt_blocks_inline_resources() {
+        let blocks: Vec<schema::ContentBlock> = serde_json::from_value(json!([
+            {"type": "text", "text": "Review this"},
+            {"type": "resource", "resource": {"uri": "file:///a.rs", "text": "fn main() {}"}},
+            {"type": "resource_link", "uri": "file:///b.rs", "name": "b.rs"},
+        ]))
+        .unwrap();
+        let message = prompt_to_message(&blocks);
+        assert!(message.content.starts_with("Review this"));
+        assert!(message
+            .content
+            .contains("<resource uri=\"file:///a.rs\">\nfn main() {}\n</resource>"));
+        assert!(message.content.ends_with("[file:///b.rs]"));
+    }
+}
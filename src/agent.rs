@@ -23,6 +23,7 @@
 
 use std::sync::Arc;
 
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
 use crate::tool_simulation::{
@@ -103,6 +104,7 @@ pub struct AgentExecutor<'a> {
     tool_handler: TextToolHandler,
     max_turns: u32,
     on_turn: Option<OnTurnCallback>,
+    cancel: CancellationToken,
 }
 
 impl<'a> AgentExecutor<'a> {
@@ -118,6 +120,7 @@ impl<'a> AgentExecutor<'a> {
             tool_handler,
             max_turns: DEFAULT_MAX_TURNS,
             on_turn: None,
+            cancel: CancellationToken::new(),
         }
     }
 
@@ -133,11 +136,18 @@ impl<'a> AgentExecutor<'a> {
         self
     }
 
+    /// Set a cancellation token that aborts the loop and the in-flight provider call
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     /// Run the agent loop with the given initial messages.
     ///
     /// # Errors
     ///
-    /// Returns [`RunnerError`] if any `provider.complete()` call fails.
+    /// Returns [`RunnerError`] if any `provider.complete()` call fails, or
+    /// an `ErrorKind::Cancelled` error once the cancellation token fires.
     pub async fn run(
         &self,
         initial_messages: Vec<ChatMessage>,
@@ -159,6 +169,11 @@ impl<'a> AgentExecutor<'a> {
             prompt_tokens: 0,
             completion_tokens: 0,
             total_tokens: 0,
+            reasoning_tokens: None,
+            cached_prompt_tokens: None,
+            cache_creation_tokens: None,
+            // Nothing spent yet; any turn without a reported cost clears it
+            reported_cost: Some(0.0),
         };
         let mut turn: u32 = 0;
 
@@ -176,13 +191,16 @@ impl<'a> AgentExecutor<'a> {
             }
 
             let request = ChatRequest::new(messages.clone());
-            let response = self.provider.complete(&request).await?;
+            let response = self
+                .provider
+                .complete_with_cancel(&request, &self.cancel)
+                .await?;
 
             // Accumulate token usage
             if let Some(ref usage) = response.usage {
-                total_usage.prompt_tokens += usage.prompt_tokens;
-                total_usage.completion_tokens += usage.completion_tokens;
-                total_usage.total_tokens += usage.total_tokens;
+                total_usage.accumulate(usage);
+            } else {
+                total_usage.reported_cost = None;
             }
 
             // Parse tool calls from the response
@@ -256,7 +274,7 @@ mod tests {
     use crate::tool_simulation::FunctionResponse;
     use crate::types::{
         ChatMessage, ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider,
-        RunnerError, TokenUsage,
+        RequestMetadata, RunnerError, TokenUsage,
     };
     use async_trait::async_trait;
     use serde_json::json;
@@ -319,6 +337,9 @@ mod tests {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         }
     }
 
@@ -337,6 +358,10 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 8,
                 total_tokens: 18,
+                reasoning_tokens: None,
+                cached_prompt_tokens: None,
+                cache_creation_tokens: None,
+                reported_cost: None,
             }),
         ))]);
 
@@ -366,12 +391,28 @@ mod tests {
             // Turn 1: LLM calls a tool
             Ok(make_response(
                 "Let me search for that.\n<tool_call>\n{\"name\": \"search\", \"arguments\": {\"q\": \"rust\"}}\n</tool_call>",
-                Some(TokenUsage { prompt_tokens: 10, completion_tokens: 15, total_tokens: 25 }),
+                Some(TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 15,
+                    total_tokens: 25,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: Some(0.01),
+                }),
             )),
             // Turn 2: LLM responds with the result
             Ok(make_response(
                 "Based on the search results, Rust is a systems programming language.",
-                Some(TokenUsage { prompt_tokens: 30, completion_tokens: 12, total_tokens: 42 }),
+                Some(TokenUsage {
+                    prompt_tokens: 30,
+                    completion_tokens: 12,
+                    total_tokens: 42,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: Some(0.02),
+                }),
             )),
         ]);
 
@@ -391,6 +432,8 @@ mod tests {
         assert_eq!(result.total_turns, 2);
         assert_eq!(result.total_usage.prompt_tokens, 40);
         assert_eq!(result.total_usage.completion_tokens, 27);
+        let cost = result.total_usage.reported_cost.unwrap();
+        assert!((cost - 0.03).abs() < 1e-9);
     }
 
     #[tokio::test]
@@ -425,6 +468,23 @@ mod tests {
         assert_eq!(logged, vec![1, 2]);
     }
 
+    #[tokio::test]
+    async fn cancelled_token_stops_before_provider_call() {
+        let provider = TestProvider::new(vec![Ok(make_response("never returned", None))]);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let executor =
+            AgentExecutor::new(&provider, vec![], noop_handler()).with_cancellation(cancel);
+        let err = executor
+            .run(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, crate::types::ErrorKind::Cancelled);
+        assert_eq!(provider.call_count.load(Ordering::SeqCst), 0);
+    }
+
     #[tokio::test]
     async fn max_turns_exhaustion() {
         // Provider always returns tool calls — should exhaust max_turns
@@ -462,6 +522,10 @@ mod tests {
                     prompt_tokens: 10,
                     completion_tokens: 5,
                     total_tokens: 15,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
                 }),
             )),
             Ok(make_response(
@@ -470,6 +534,10 @@ mod tests {
                     prompt_tokens: 20,
                     completion_tokens: 3,
                     total_tokens: 23,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
                 }),
             )),
         ]);
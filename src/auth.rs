@@ -123,6 +123,8 @@ pub async fn check_readiness(
         CliRunnerType::KiloCli => check_version_probe_unverified(binary_path, "kilo").await,
         #[cfg(feature = "copilot-headless")]
         CliRunnerType::CopilotHeadless => check_copilot_readiness(binary_path).await,
+        #[cfg(feature = "acp")]
+        CliRunnerType::Acp => check_version_probe_unverified(binary_path, "acp-agent").await,
     }
 }
 
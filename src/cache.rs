@@ -1,5 +1,5 @@
-// ABOUTME: Response caching decorator for LlmProvider with TTL and capacity limits
-// ABOUTME: Caches deterministic (temperature=0) non-streaming completions by request hash
+// ABOUTME: Response caching decorator for LlmProvider with TTL and a pluggable storage backend
+// ABOUTME: Caches deterministic (temperature=0) completions by a stable request hash, replaying hits as streams
 //
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
@@ -7,34 +7,65 @@
 //! # Response Caching
 //!
 //! [`CacheProvider`] wraps an inner `Box<dyn LlmProvider>` and caches
-//! responses for identical requests. Cache keys are computed from the
-//! serialized request parameters (messages, model, temperature, `max_tokens`).
+//! responses for identical requests. Cache keys are a versioned, stable
+//! 128-bit FNV-1a hash of the provider name and every request field that
+//! affects the answer (messages, model, sampling parameters, stop sequences,
+//! tools, tool choice, response format, session key, `n` and reasoning
+//! settings), so they stay valid across builds and processes. Path
+//! attachments contribute their size and modification time, so editing a
+//! file at the same path misses the cache.
 //!
 //! ## Behavior
 //!
-//! - Only `complete()` results are cached; `complete_stream()` always delegates.
+//! - `complete()` and `complete_stream()` share entries. Streaming hits are
+//!   replayed as a synthetic stream; streaming misses are stored once the
+//!   inner stream has ended, only if it delivered a final chunk and no error.
 //! - Requests with `temperature > Some(0.0)` bypass the cache by default
-//!   (configurable via `cache_nonzero_temperature`).
+//!   (configurable via `cache_nonzero_temperature`), as do the candidates
+//!   of a fanned-out multi-choice request.
 //! - Entries expire after `ttl` and are evicted on access.
-//! - When at capacity, the oldest entry is evicted to make room.
+//! - Entries live in a [`CacheBackend`]: an in-memory LRU by default, or a
+//!   [`DiskCacheBackend`](crate::cache_backend::DiskCacheBackend) set with
+//!   [`CacheProvider::with_backend`] to survive restarts.
+//! - Cached responses drop the original caller's metadata; hits carry the
+//!   current request's metadata plus a `cache=hit` tag.
 
-use std::collections::HashMap;
-use std::hash::{DefaultHasher, Hash, Hasher};
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, UNIX_EPOCH};
 
 use async_trait::async_trait;
-use tracing::debug;
-
+use serde::Serialize;
+use tokio_stream::Stream;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn, Instrument};
+
+use crate::cache_backend::{CacheBackend, MemoryCacheBackend};
+use crate::choices::is_fan_out_candidate;
+use crate::model_catalog::ModelInfo;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
+    AttachmentSource, ChatMessage, ChatRequest, ChatResponse, ChatStream, LlmCapabilities,
+    LlmProvider, ReasoningEffort, RequestMetadata, ResponseFormat, RunnerError, StreamChunk,
+    TokenUsage, ToolCallDelta, ToolCallRequest, ToolChoice, ToolDefinition,
 };
 
+/// Version of the cache key layout; bump whenever [`KeyFields`] changes
+const CACHE_KEY_VERSION: u32 = 2;
+
+/// FNV-1a 128-bit offset basis
+const FNV_OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+
+/// FNV-1a 128-bit prime
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
 /// Configuration for the response cache
 #[derive(Debug, Clone)]
 pub struct CacheConfig {
-    /// Maximum number of cached entries
+    /// Maximum number of cached entries (default in-memory backend)
     pub max_entries: usize,
+    /// Maximum total size of cached responses in bytes (default in-memory backend)
+    pub max_bytes: Option<u64>,
     /// Time-to-live for cached entries
     pub ttl: Duration,
     /// Whether to cache responses for requests with non-zero temperature
@@ -45,6 +76,7 @@ impl Default for CacheConfig {
     fn default() -> Self {
         Self {
             max_entries: 256,
+            max_bytes: None,
             ttl: Duration::from_secs(300),
             cache_nonzero_temperature: false,
         }
@@ -62,21 +94,8 @@ pub struct CacheStats {
     pub evictions: u64,
     /// Current number of entries in the cache
     pub size: usize,
-}
-
-/// A cached response with its insertion time
-#[derive(Debug, Clone)]
-struct CacheEntry {
-    response: ChatResponse,
-    inserted_at: Instant,
-}
-
-/// Internal cache state
-#[derive(Debug, Default)]
-struct CacheState {
-    entries: HashMap<u64, CacheEntry>,
-    insertion_order: Vec<u64>,
-    stats: CacheStats,
+    /// Current total size of the cached entries in bytes
+    pub bytes: u64,
 }
 
 /// Caching decorator for any `LlmProvider`.
@@ -94,53 +113,352 @@ struct CacheState {
 pub struct CacheProvider {
     inner: Box<dyn LlmProvider>,
     config: CacheConfig,
-    state: Arc<Mutex<CacheState>>,
+    backend: Arc<dyn CacheBackend>,
 }
 
 impl CacheProvider {
-    /// Wrap a provider with response caching
+    /// Wrap a provider with response caching in an in-memory LRU backend
     pub fn new(inner: Box<dyn LlmProvider>, config: CacheConfig) -> Self {
+        let mut backend = MemoryCacheBackend::new(config.max_entries);
+        if let Some(max_bytes) = config.max_bytes {
+            backend = backend.with_max_bytes(max_bytes);
+        }
         Self {
             inner,
             config,
-            state: Arc::new(Mutex::new(CacheState::default())),
+            backend: Arc::new(backend),
         }
     }
 
-    /// Return current cache statistics
+    /// Store entries in `backend` instead of the default in-memory LRU
     ///
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned.
+    /// The backend's own limits apply; `max_entries` and `max_bytes` in
+    /// [`CacheConfig`] only size the default backend. A backend may be shared
+    /// by several providers since keys include the provider name.
+    #[must_use]
+    pub fn with_backend(mut self, backend: Arc<dyn CacheBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Return current cache statistics of the backend
     pub fn cache_stats(&self) -> CacheStats {
-        let state = self.state.lock().expect("cache lock poisoned");
-        let mut snapshot = state.stats.clone();
-        snapshot.size = state.entries.len();
-        snapshot
-    }
-
-    /// Compute a deterministic cache key from request parameters
-    fn cache_key(request: &ChatRequest) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        // Hash the serialized form of the relevant fields
-        serde_json::to_string(&(
-            &request.messages,
-            &request.model,
-            &request.temperature,
-            &request.max_tokens,
-        ))
-        .unwrap_or_default()
-        .hash(&mut hasher);
-        hasher.finish()
+        self.backend.stats()
     }
 
     /// Whether caching should be bypassed for this request
     fn should_bypass(&self, request: &ChatRequest) -> bool {
+        if is_fan_out_candidate(request) {
+            return true;
+        }
         if self.config.cache_nonzero_temperature {
             return false;
         }
         matches!(request.temperature, Some(t) if t > 0.0)
     }
+
+    /// Serve `request` from the cache, or complete and store it on a miss
+    async fn cached_complete(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        if self.should_bypass(request) {
+            return self.inner.complete_with_cancel(request, cancel).await;
+        }
+
+        let key = cache_key(self.inner.name(), request);
+
+        if let Some(mut response) = self.backend.get(&key, self.config.ttl).await {
+            response
+                .metadata
+                .tags
+                .insert("cache".to_owned(), "hit".to_owned());
+            debug!(key, backend = self.backend.name(), "cache hit");
+            return Ok(response);
+        }
+
+        // Cache miss — delegate to inner provider
+        let response = self.inner.complete_with_cancel(request, cancel).await?;
+
+        self.backend
+            .put(
+                &key,
+                ChatResponse {
+                    metadata: RequestMetadata::default(),
+                    ..response.clone()
+                },
+            )
+            .await;
+
+        Ok(response)
+    }
+
+    /// Replay a cached response for `request`, or tee the inner stream into the cache
+    async fn cached_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+        if self.should_bypass(request) {
+            return self.inner.complete_stream(request).await;
+        }
+
+        let key = cache_key(self.inner.name(), request);
+        if let Some(response) = self.backend.get(&key, self.config.ttl).await {
+            debug!(
+                key,
+                backend = self.backend.name(),
+                "cache hit, replaying stream"
+            );
+            return Ok(replay_stream(response));
+        }
+
+        let stream = self.inner.complete_stream(request).await?;
+        let model = request
+            .model
+            .clone()
+            .unwrap_or_else(|| self.inner.default_model().to_owned());
+        Ok(Box::pin(CacheTee {
+            inner: stream,
+            collector: Some(StreamCollector::new(model)),
+            saw_final: false,
+            backend: Arc::clone(&self.backend),
+            key,
+        }))
+    }
+}
+
+/// Forwards a stream while collecting it, storing the response once the stream ends
+///
+/// Nothing is stored if the stream yields an error at any point (including
+/// after its final chunk) or ends without a final chunk.
+struct CacheTee {
+    inner: ChatStream,
+    collector: Option<StreamCollector>,
+    saw_final: bool,
+    backend: Arc<dyn CacheBackend>,
+    key: String,
+}
+
+impl Stream for CacheTee {
+    type Item = Result<StreamChunk, RunnerError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(self.inner.as_mut().poll_next(cx));
+        match &item {
+            Some(Ok(chunk)) => {
+                self.saw_final |= chunk.is_final;
+                if let Some(collector) = self.collector.as_mut() {
+                    collector.push(chunk);
+                }
+            }
+            Some(Err(_)) => self.collector = None,
+            None => {
+                let collected = self.collector.take().filter(|_| self.saw_final);
+                if let Some(response) = collected.and_then(StreamCollector::finish) {
+                    let backend = Arc::clone(&self.backend);
+                    let key = self.key.clone();
+                    tokio::spawn(async move { backend.put(&key, response).await });
+                }
+            }
+        }
+        Poll::Ready(item)
+    }
+}
+
+/// Compute a stable, versioned key from the provider name and the request fields that affect the answer
+pub(crate) fn cache_key(provider: &str, request: &ChatRequest) -> String {
+    let fields = KeyFields {
+        version: CACHE_KEY_VERSION,
+        provider,
+        messages: &request.messages,
+        model: request.model.as_deref(),
+        temperature: request.temperature,
+        max_tokens: request.max_tokens,
+        top_p: request.top_p,
+        stop: request.stop.as_deref(),
+        tools: request.tools.as_deref(),
+        tool_choice: request.tool_choice.as_ref(),
+        response_format: request.response_format.as_ref(),
+        session_key: request.session_key.as_deref(),
+        n: request.n,
+        reasoning_effort: request.reasoning_effort,
+        thinking_budget: request.thinking_budget,
+        file_stamps: file_stamps(&request.messages),
+    };
+    let encoded = serde_json::to_vec(&fields).unwrap_or_default();
+    format!("v{CACHE_KEY_VERSION}-{:032x}", fnv1a_128(&encoded))
+}
+
+/// Request fields that determine a cached answer, in hashing order
+#[derive(Serialize)]
+struct KeyFields<'a> {
+    version: u32,
+    provider: &'a str,
+    messages: &'a [ChatMessage],
+    model: Option<&'a str>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    stop: Option<&'a [String]>,
+    tools: Option<&'a [ToolDefinition]>,
+    tool_choice: Option<&'a ToolChoice>,
+    response_format: Option<&'a ResponseFormat>,
+    session_key: Option<&'a str>,
+    n: Option<u32>,
+    reasoning_effort: Option<ReasoningEffort>,
+    thinking_budget: Option<u32>,
+    file_stamps: Vec<FileStamp>,
+}
+
+/// Size and modification time of a path attachment, `None` when it cannot be read
+type FileStamp = Option<(u64, u128)>;
+
+/// Stamps for every path attachment, in message order
+///
+/// Messages only record the path, so without these a file edited in place
+/// would keep hitting the entry cached for its old contents.
+fn file_stamps(messages: &[ChatMessage]) -> Vec<FileStamp> {
+    messages
+        .iter()
+        .flat_map(|m| m.attachments.iter().flatten())
+        .filter_map(|a| match a.source {
+            AttachmentSource::Path(ref path) => Some(std::fs::metadata(path).ok().map(|meta| {
+                let modified = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_nanos());
+                (meta.len(), modified)
+            })),
+            AttachmentSource::Data(_) => None,
+        })
+        .collect()
+}
+
+/// 128-bit FNV-1a hash, stable across platforms and Rust releases
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Replay a cached response as a content chunk followed by a final chunk
+fn replay_stream(response: ChatResponse) -> ChatStream {
+    let mut chunks = Vec::with_capacity(2);
+    if !response.content.is_empty() || response.reasoning.is_some() {
+        chunks.push(Ok(StreamChunk {
+            delta: response.content,
+            reasoning_delta: response.reasoning,
+            ..StreamChunk::default()
+        }));
+    }
+    let tool_calls = response.tool_calls.map(|calls| {
+        calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| ToolCallDelta {
+                index,
+                id: Some(call.id),
+                function_name: Some(call.function_name),
+                arguments_delta: call.arguments.to_string(),
+            })
+            .collect()
+    });
+    chunks.push(Ok(StreamChunk {
+        is_final: true,
+        finish_reason: response.finish_reason,
+        usage: response.usage,
+        tool_calls,
+        ..StreamChunk::default()
+    }));
+    Box::pin(tokio_stream::iter(chunks))
+}
+
+/// Accumulates streamed chunks into the response stored on a cache miss
+struct StreamCollector {
+    model: String,
+    content: String,
+    reasoning: String,
+    tool_calls: Vec<ToolCallDelta>,
+    finish_reason: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+impl StreamCollector {
+    const fn new(model: String) -> Self {
+        Self {
+            model,
+            content: String::new(),
+            reasoning: String::new(),
+            tool_calls: Vec::new(),
+            finish_reason: None,
+            usage: None,
+        }
+    }
+
+    fn push(&mut self, chunk: &StreamChunk) {
+        self.content.push_str(&chunk.delta);
+        if let Some(ref delta) = chunk.reasoning_delta {
+            self.reasoning.push_str(delta);
+        }
+        for fragment in chunk.tool_calls.iter().flatten() {
+            match self
+                .tool_calls
+                .iter_mut()
+                .find(|c| c.index == fragment.index)
+            {
+                Some(call) => {
+                    call.arguments_delta.push_str(&fragment.arguments_delta);
+                    if call.id.is_none() {
+                        call.id.clone_from(&fragment.id);
+                    }
+                    if call.function_name.is_none() {
+                        call.function_name.clone_from(&fragment.function_name);
+                    }
+                }
+                None => self.tool_calls.push(fragment.clone()),
+            }
+        }
+        if chunk.finish_reason.is_some() {
+            self.finish_reason.clone_from(&chunk.finish_reason);
+        }
+        if chunk.usage.is_some() {
+            self.usage.clone_from(&chunk.usage);
+        }
+    }
+
+    /// The collected response, or `None` if a tool call cannot be reassembled
+    fn finish(mut self) -> Option<ChatResponse> {
+        self.tool_calls.sort_by_key(|call| call.index);
+        let mut tool_calls = Vec::with_capacity(self.tool_calls.len());
+        for call in self.tool_calls {
+            let arguments = if call.arguments_delta.is_empty() {
+                serde_json::Value::Object(serde_json::Map::new())
+            } else {
+                match serde_json::from_str(&call.arguments_delta) {
+                    Ok(arguments) => arguments,
+                    Err(e) => {
+                        warn!(error = %e, "cache: streamed tool call arguments are not JSON, not caching");
+                        return None;
+                    }
+                }
+            };
+            tool_calls.push(ToolCallRequest {
+                id: call.id?,
+                function_name: call.function_name?,
+                arguments,
+            });
+        }
+        Some(ChatResponse {
+            content: self.content,
+            model: self.model,
+            usage: self.usage,
+            finish_reason: self.finish_reason,
+            warnings: None,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            reasoning: (!self.reasoning.is_empty()).then_some(self.reasoning),
+            choices: None,
+            metadata: RequestMetadata::default(),
+        })
+    }
 }
 
 #[async_trait]
@@ -165,70 +483,35 @@ impl LlmProvider for CacheProvider {
         self.inner.available_models()
     }
 
-    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        if self.should_bypass(request) {
-            return self.inner.complete(request).await;
-        }
-
-        let key = Self::cache_key(request);
-
-        // Check cache
-        {
-            let mut state = self.state.lock().expect("cache lock poisoned");
-            let cached = state.entries.get(&key).and_then(|entry| {
-                if entry.inserted_at.elapsed() < self.config.ttl {
-                    Some(entry.response.clone())
-                } else {
-                    None
-                }
-            });
-            if let Some(response) = cached {
-                state.stats.hits += 1;
-                debug!(key, "cache hit");
-                return Ok(response);
-            }
-            // Remove expired entry if it exists
-            if state.entries.remove(&key).is_some() {
-                state.insertion_order.retain(|k| *k != key);
-                state.stats.evictions += 1;
-            }
-            state.stats.misses += 1;
-        }
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        self.inner.model_catalog()
+    }
 
-        // Cache miss — delegate to inner provider
-        let response = self.inner.complete(request).await?;
-
-        // Store in cache
-        {
-            let mut state = self.state.lock().expect("cache lock poisoned");
-
-            // Evict oldest if at capacity
-            while state.entries.len() >= self.config.max_entries {
-                if let Some(oldest_key) = state.insertion_order.first().copied() {
-                    state.entries.remove(&oldest_key);
-                    state.insertion_order.remove(0);
-                    state.stats.evictions += 1;
-                } else {
-                    break;
-                }
-            }
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        self.inner.refresh_models().await
+    }
 
-            state.entries.insert(
-                key,
-                CacheEntry {
-                    response: response.clone(),
-                    inserted_at: Instant::now(),
-                },
-            );
-            state.insertion_order.push(key);
-        }
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
 
-        Ok(response)
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        self.cached_complete(request, cancel)
+            .instrument(request.metadata.span("cache"))
+            .await
+            .map(|response| response.with_request_metadata(request))
     }
 
-    /// Streaming responses are not cached; always delegates to the inner provider
+    /// Replays cached responses; misses are stored once the stream completes
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        self.inner.complete_stream(request).await
+        self.cached_stream(request)
+            .instrument(request.metadata.span("cache"))
+            .await
     }
 
     async fn health_check(&self) -> Result<bool, RunnerError> {
@@ -245,6 +528,8 @@ mod tests {
     };
     use async_trait::async_trait;
     use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+    use tokio_stream::StreamExt;
 
     struct TestProvider {
         responses: Mutex<Vec<Result<ChatResponse, RunnerError>>>,
@@ -288,19 +573,31 @@ mod tests {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 })
             } else {
                 responses.remove(0)
             }
         }
-        async fn complete_stream(&self, _request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-            Err(RunnerError::internal("streaming not supported in test"))
+        async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+            self.complete(request).await.map(replay_stream)
         }
         async fn health_check(&self) -> Result<bool, RunnerError> {
             Ok(true)
         }
     }
 
+    /// Drain `stream`, returning the concatenated content
+    async fn collect_content(mut stream: ChatStream) -> String {
+        let mut content = String::new();
+        while let Some(chunk) = stream.next().await {
+            content.push_str(&chunk.expect("chunk").delta);
+        }
+        content
+    }
+
     fn make_response(content: &str) -> ChatResponse {
         ChatResponse {
             content: content.to_owned(),
@@ -309,6 +606,9 @@ mod tests {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         }
     }
 
@@ -461,14 +761,88 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn streaming_bypasses() {
-        let provider = TestProvider::new(vec![]);
+    async fn streamed_miss_is_stored_and_replayed() {
+        let provider = TestProvider::new(vec![
+            Ok(make_response("streamed")),
+            Ok(make_response("second")),
+        ]);
         let cached = CacheProvider::new(Box::new(provider), CacheConfig::default());
         let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
 
-        // complete_stream always delegates (and errors in our test provider)
-        let result = cached.complete_stream(&request).await;
-        assert!(result.is_err());
+        let stream = cached.complete_stream(&request).await.expect("miss");
+        assert_eq!(collect_content(stream).await, "streamed");
+        // The entry is written by a spawned task once the stream finishes
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while cached.cache_stats().size == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("streamed response is stored");
+
+        let hit = cached.complete(&request).await.expect("hit");
+        assert_eq!(hit.content, "streamed");
+        let replay = cached.complete_stream(&request).await.expect("replay");
+        assert_eq!(collect_content(replay).await, "streamed");
+        assert_eq!(cached.cache_stats().hits, 2);
+    }
+
+    #[tokio::test]
+    async fn stream_error_after_final_chunk_is_not_cached() {
+        let backend: Arc<dyn CacheBackend> = Arc::new(MemoryCacheBackend::new(10));
+        let chunks = vec![
+            Ok(StreamChunk {
+                delta: "partial".to_owned(),
+                is_final: true,
+                finish_reason: Some("stop".to_owned()),
+                ..StreamChunk::default()
+            }),
+            Err(RunnerError::external_service(
+                "test",
+                "exited with status 1",
+            )),
+        ];
+        let mut tee = CacheTee {
+            inner: Box::pin(tokio_stream::iter(chunks)),
+            collector: Some(StreamCollector::new("test-model".to_owned())),
+            saw_final: false,
+            backend: Arc::clone(&backend),
+            key: "k".to_owned(),
+        };
+        while tee.next().await.is_some() {}
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(backend.stats().size, 0);
+    }
+
+    #[tokio::test]
+    async fn replayed_stream_ends_with_final_chunk() {
+        let mut response = make_response("hello");
+        response.tool_calls = Some(vec![ToolCallRequest {
+            id: "call_1".to_owned(),
+            function_name: "lookup".to_owned(),
+            arguments: serde_json::json!({"q": "x"}),
+        }]);
+        let mut stream = replay_stream(response);
+        let mut collector = StreamCollector::new("test-model".to_owned());
+        let mut last_final = false;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.expect("chunk");
+            last_final = chunk.is_final;
+            collector.push(&chunk);
+        }
+        assert!(last_final);
+
+        let rebuilt = collector.finish().expect("reassembled");
+        assert_eq!(rebuilt.content, "hello");
+        assert_eq!(rebuilt.finish_reason.as_deref(), Some("stop"));
+        let calls = rebuilt.tool_calls.expect("tool calls");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function_name, "lookup");
+        assert_eq!(calls[0].arguments, serde_json::json!({"q": "x"}));
     }
 
     #[test]
@@ -477,13 +851,111 @@ mod tests {
         let req2 = ChatRequest::new(vec![ChatMessage::user("hello")]);
         let req3 = ChatRequest::new(vec![ChatMessage::user("different")]);
 
+        assert_eq!(cache_key("test", &req1), cache_key("test", &req2));
+        assert_ne!(cache_key("test", &req1), cache_key("test", &req3));
+        assert_ne!(cache_key("test", &req1), cache_key("other", &req1));
+    }
+
+    #[test]
+    fn key_tracks_path_attachment_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "v1").unwrap();
+        let request = ChatRequest::new(vec![ChatMessage::user_with_attachments(
+            "summarize",
+            vec![crate::types::AttachmentPart::from_path(&path, "text/plain").unwrap()],
+        )]);
+        let before = cache_key("test", &request);
+        assert_eq!(before, cache_key("test", &request));
+
+        std::fs::write(&path, "version two").unwrap();
+        assert_ne!(before, cache_key("test", &request));
+    }
+
+    #[test]
+    fn key_covers_every_semantic_field() {
+        let base = ChatRequest::new(vec![ChatMessage::user("hello")]);
+        let key = |request: &ChatRequest| cache_key("test", request);
+        let variants = [
+            base.clone().with_top_p(0.5),
+            base.clone().with_stop(vec!["END".to_owned()]),
+            base.clone().with_tool_choice(ToolChoice::None),
+            base.clone()
+                .with_response_format(ResponseFormat::JsonObject),
+            base.clone().with_tools(vec![ToolDefinition {
+                name: "lookup".to_owned(),
+                description: "Look something up".to_owned(),
+                parameters: None,
+            }]),
+            base.clone().with_reasoning_effort(ReasoningEffort::High),
+            base.clone().with_session_key("conversation"),
+        ];
+        for variant in &variants {
+            assert_ne!(key(&base), key(variant));
+        }
+
+        // Transport and bookkeeping fields do not change the answer
+        let streamed = base
+            .clone()
+            .with_streaming()
+            .with_metadata(RequestMetadata::new().with_request_id("r1"))
+            .with_timeout(Duration::from_secs(5));
+        assert_eq!(key(&base), key(&streamed));
+    }
+
+    #[test]
+    fn key_is_stable_across_builds() {
+        let request = ChatRequest::new(vec![ChatMessage::user("hello")]);
         assert_eq!(
-            CacheProvider::cache_key(&req1),
-            CacheProvider::cache_key(&req2)
+            cache_key("test", &request),
+            "v2-0cc87b9e020b423a35098d08ddbb297a"
         );
-        assert_ne!(
-            CacheProvider::cache_key(&req1),
-            CacheProvider::cache_key(&req3)
+    }
+
+    #[tokio::test]
+    async fn hits_carry_current_request_metadata() {
+        let provider = TestProvider::new(vec![]);
+        let cached = CacheProvider::new(Box::new(provider), CacheConfig::default());
+        let first = ChatRequest::new(vec![ChatMessage::user("hi")])
+            .with_metadata(RequestMetadata::new().with_request_id("first"));
+        let second = ChatRequest::new(vec![ChatMessage::user("hi")])
+            .with_metadata(RequestMetadata::new().with_request_id("second"));
+
+        let miss = cached.complete(&first).await.expect("miss");
+        assert_eq!(miss.metadata.request_id.as_deref(), Some("first"));
+        assert!(!miss.metadata.tags.contains_key("cache"));
+
+        let hit = cached.complete(&second).await.expect("hit");
+        assert_eq!(hit.metadata.request_id.as_deref(), Some("second"));
+        assert_eq!(
+            hit.metadata.tags.get("cache").map(String::as_str),
+            Some("hit")
         );
     }
+
+    #[tokio::test]
+    async fn disk_backend_serves_hits_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let first = CacheProvider::new(
+            Box::new(TestProvider::new(vec![Ok(make_response("stored"))])),
+            CacheConfig::default(),
+        )
+        .with_backend(Arc::new(
+            crate::cache_backend::DiskCacheBackend::open(dir.path(), 16).unwrap(),
+        ));
+        first.complete(&request).await.expect("miss");
+
+        let restarted = CacheProvider::new(
+            Box::new(TestProvider::new(vec![Ok(make_response("fresh"))])),
+            CacheConfig::default(),
+        )
+        .with_backend(Arc::new(
+            crate::cache_backend::DiskCacheBackend::open(dir.path(), 16).unwrap(),
+        ));
+        let hit = restarted.complete(&request).await.expect("hit");
+        assert_eq!(hit.content, "stored");
+        assert_eq!(restarted.cache_stats().hits, 1);
+    }
 }
@@ -0,0 +1,613 @@
+// ABOUTME: Pluggable storage backends for CacheProvider: in-memory LRU and on-disk JSON files
+// ABOUTME: Enforces entry and byte limits with least-recently-used eviction and tracks CacheStats
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Cache Backends
+//!
+//! [`CacheProvider`](crate::cache::CacheProvider) stores responses in a
+//! [`CacheBackend`]. Two implementations are provided:
+//!
+//! - [`MemoryCacheBackend`] — a process-local map, the default.
+//! - [`DiskCacheBackend`] — one JSON file per entry in a directory, which
+//!   survives restarts and can be shared by processes on the same machine
+//!   (e.g. CI jobs re-running identical prompts).
+//!
+//! Both cap the number of entries and, optionally, the total size in bytes
+//! (measured as the entry's serialized JSON), evicting the least recently
+//! used entry first. Each backend keeps its own [`CacheStats`]. A limit of
+//! zero entries disables storage.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::ErrorKind as IoErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::cache::CacheStats;
+use crate::types::{ChatResponse, RunnerError};
+
+/// Extension of entry files in a [`DiskCacheBackend`] directory
+const ENTRY_EXTENSION: &str = "json";
+
+/// Storage for cached responses
+///
+/// Keys are opaque strings made of ASCII letters, digits, `-` and `_`.
+/// Backends never fail a request: storage errors are logged and treated as
+/// misses.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Short backend name for logs (e.g. `"memory"`)
+    fn name(&self) -> &'static str;
+
+    /// Look up `key`, treating entries older than `ttl` as expired
+    ///
+    /// Expired entries are removed and counted as evictions.
+    async fn get(&self, key: &str, ttl: Duration) -> Option<ChatResponse>;
+
+    /// Store `response` under `key`, evicting least recently used entries to stay within limits
+    async fn put(&self, key: &str, response: ChatResponse);
+
+    /// Current usage statistics
+    fn stats(&self) -> CacheStats;
+}
+
+/// Entry and byte limits shared by the built-in backends
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    max_entries: usize,
+    max_bytes: Option<u64>,
+}
+
+impl Limits {
+    const fn exceeded(self, entries: usize, bytes: u64) -> bool {
+        entries > self.max_entries || matches!(self.max_bytes, Some(max) if bytes > max)
+    }
+
+    /// Whether a single entry of `bytes` can be stored at all
+    const fn fits(self, bytes: u64) -> bool {
+        self.max_entries > 0 && !matches!(self.max_bytes, Some(max) if bytes > max)
+    }
+}
+
+/// Recency bookkeeping shared by the built-in backends
+#[derive(Debug, Default)]
+struct LruIndex {
+    /// Key to `(size in bytes, last-used tick)`
+    entries: HashMap<String, (u64, u64)>,
+    /// Last-used tick to key, oldest first
+    recency: BTreeMap<u64, String>,
+    bytes: u64,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl LruIndex {
+    fn touch(&mut self, key: &str, bytes: u64) {
+        self.clock += 1;
+        let tick = self.clock;
+        if let Some((previous_bytes, previous_tick)) =
+            self.entries.insert(key.to_owned(), (bytes, tick))
+        {
+            self.bytes -= previous_bytes;
+            self.recency.remove(&previous_tick);
+        }
+        self.recency.insert(tick, key.to_owned());
+        self.bytes += bytes;
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some_and(|(bytes, tick)| {
+            self.bytes -= bytes;
+            self.recency.remove(&tick);
+            true
+        })
+    }
+
+    /// Remove least recently used keys until one more entry of `incoming` bytes fits
+    fn evict_for(&mut self, limits: Limits, incoming: u64) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while limits.exceeded(self.entries.len() + 1, self.bytes + incoming) {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some((bytes, _)) = self.entries.remove(&oldest) {
+                self.bytes -= bytes;
+            }
+            self.stats.evictions += 1;
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
+    fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            size: self.entries.len(),
+            bytes: self.bytes,
+            ..self.stats.clone()
+        }
+    }
+}
+
+/// Serialized size of `value` in bytes, used for byte limits
+fn encoded_len<T: Serialize>(value: &T) -> u64 {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len() as u64)
+}
+
+/// Process-local cache backend with least-recently-used eviction
+pub struct MemoryCacheBackend {
+    limits: Limits,
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    responses: HashMap<String, (ChatResponse, Instant)>,
+    index: LruIndex,
+}
+
+impl MemoryCacheBackend {
+    /// Create a backend holding at most `max_entries` responses
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            limits: Limits {
+                max_entries,
+                max_bytes: None,
+            },
+            state: Mutex::new(MemoryState::default()),
+        }
+    }
+
+    /// Also cap the total serialized size of the stored responses
+    #[must_use]
+    pub const fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.limits.max_bytes = Some(max_bytes);
+        self
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCacheBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn get(&self, key: &str, ttl: Duration) -> Option<ChatResponse> {
+        let mut state = self.state.lock().expect("cache lock poisoned");
+        let expired = state
+            .responses
+            .get(key)
+            .map(|(_, stored_at)| stored_at.elapsed() >= ttl);
+        match expired {
+            Some(false) => {
+                let bytes = state.index.entries.get(key).map_or(0, |&(bytes, _)| bytes);
+                state.index.touch(key, bytes);
+                state.index.stats.hits += 1;
+                state
+                    .responses
+                    .get(key)
+                    .map(|(response, _)| response.clone())
+            }
+            Some(true) => {
+                state.responses.remove(key);
+                state.index.remove(key);
+                state.index.stats.evictions += 1;
+                state.index.stats.misses += 1;
+                None
+            }
+            None => {
+                state.index.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    async fn put(&self, key: &str, response: ChatResponse) {
+        let bytes = encoded_len(&response);
+        if !self.limits.fits(bytes) {
+            debug!(
+                key,
+                bytes, "cache: response exceeds the cache limits, not stored"
+            );
+            return;
+        }
+        let mut state = self.state.lock().expect("cache lock poisoned");
+        if state.index.remove(key) {
+            state.responses.remove(key);
+        }
+        for evicted in state.index.evict_for(self.limits, bytes) {
+            state.responses.remove(&evicted);
+        }
+        state
+            .responses
+            .insert(key.to_owned(), (response, Instant::now()));
+        state.index.touch(key, bytes);
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.state
+            .lock()
+            .expect("cache lock poisoned")
+            .index
+            .snapshot()
+    }
+}
+
+/// On-disk format of a [`DiskCacheBackend`] entry
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    /// Unix time the entry was written, in milliseconds
+    stored_at_ms: u64,
+    response: ChatResponse,
+}
+
+/// Cache backend storing one JSON file per entry in a directory
+///
+/// Entries survive restarts and are visible to every process using the same
+/// directory. Limits are enforced by each process against the entries it
+/// knows about: those present when it opened the directory and those it has
+/// since read or written.
+pub struct DiskCacheBackend {
+    dir: PathBuf,
+    limits: Limits,
+    index: Mutex<LruIndex>,
+}
+
+impl DiskCacheBackend {
+    /// Open (creating if needed) a cache directory holding at most `max_entries` responses
+    ///
+    /// Existing entries are indexed oldest-modified first, so they are the
+    /// first to be evicted.
+    pub fn open(dir: impl Into<PathBuf>, max_entries: usize) -> Result<Self, RunnerError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            RunnerError::config(format!(
+                "Failed to create cache directory {}: {e}",
+                dir.display()
+            ))
+        })?;
+
+        let mut existing = Vec::new();
+        let entries = std::fs::read_dir(&dir).map_err(|e| {
+            RunnerError::config(format!(
+                "Failed to read cache directory {}: {e}",
+                dir.display()
+            ))
+        })?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(key) = entry_key(&path) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            existing.push((modified, key.to_owned(), metadata.len()));
+        }
+        existing.sort();
+
+        let mut index = LruIndex::default();
+        for (_, key, bytes) in existing {
+            index.touch(&key, bytes);
+        }
+        debug!(dir = %dir.display(), entries = index.entries.len(), "cache: opened disk backend");
+
+        Ok(Self {
+            dir,
+            limits: Limits {
+                max_entries,
+                max_bytes: None,
+            },
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Also cap the total size of the entry files
+    #[must_use]
+    pub const fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.limits.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Directory holding the entry files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{ENTRY_EXTENSION}"))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruIndex> {
+        self.index.lock().expect("cache lock poisoned")
+    }
+
+    async fn remove_file(&self, key: &str) {
+        if let Err(e) = tokio::fs::remove_file(self.entry_path(key)).await {
+            if e.kind() != IoErrorKind::NotFound {
+                warn!(key, error = %e, "cache: failed to remove entry file");
+            }
+        }
+    }
+
+    /// Read and decode `key`, or `None` if it is missing or unreadable
+    async fn read_entry(&self, key: &str) -> Option<(DiskEntry, u64)> {
+        let bytes = match tokio::fs::read(self.entry_path(key)).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                if e.kind() != IoErrorKind::NotFound {
+                    warn!(key, error = %e, "cache: failed to read entry file");
+                }
+                return None;
+            }
+        };
+        match serde_json::from_slice(&bytes) {
+            Ok(entry) => Some((entry, bytes.len() as u64)),
+            Err(e) => {
+                warn!(key, error = %e, "cache: discarding unreadable entry file");
+                self.remove_file(key).await;
+                None
+            }
+        }
+    }
+
+    /// Write `contents` to the entry file via a temporary file and rename
+    ///
+    /// The temp name is unique per write, so concurrent writers of the same
+    /// key (in this or another process) never share a temp file.
+    async fn write_entry(&self, key: &str, contents: &[u8]) -> std::io::Result<()> {
+        static WRITE_SEQ: AtomicU64 = AtomicU64::new(0);
+        let seq = WRITE_SEQ.fetch_add(1, Ordering::Relaxed);
+        let tmp = self
+            .dir
+            .join(format!(".{key}.{}.{seq}.tmp", std::process::id()));
+        tokio::fs::write(&tmp, contents).await?;
+        if let Err(e) = tokio::fs::rename(&tmp, self.entry_path(key)).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CacheBackend for DiskCacheBackend {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    async fn get(&self, key: &str, ttl: Duration) -> Option<ChatResponse> {
+        let entry = if is_valid_key(key) {
+            self.read_entry(key).await
+        } else {
+            None
+        };
+        let Some((entry, bytes)) = entry else {
+            let mut index = self.lock();
+            index.remove(key);
+            index.stats.misses += 1;
+            return None;
+        };
+
+        let age = Duration::from_millis(unix_millis().saturating_sub(entry.stored_at_ms));
+        if age >= ttl {
+            {
+                let mut index = self.lock();
+                index.remove(key);
+                index.stats.evictions += 1;
+                index.stats.misses += 1;
+            }
+            self.remove_file(key).await;
+            return None;
+        }
+
+        let mut index = self.lock();
+        index.touch(key, bytes);
+        index.stats.hits += 1;
+        Some(entry.response)
+    }
+
+    async fn put(&self, key: &str, response: ChatResponse) {
+        if !is_valid_key(key) {
+            warn!(key, "cache: invalid key, not stored");
+            return;
+        }
+        let entry = DiskEntry {
+            stored_at_ms: unix_millis(),
+            response,
+        };
+        let contents = match serde_json::to_vec(&entry) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!(key, error = %e, "cache: failed to encode entry");
+                return;
+            }
+        };
+        let bytes = contents.len() as u64;
+        if !self.limits.fits(bytes) {
+            debug!(
+                key,
+                bytes, "cache: response exceeds the cache limits, not stored"
+            );
+            return;
+        }
+
+        let evicted = {
+            let mut index = self.lock();
+            index.remove(key);
+            index.evict_for(self.limits, bytes)
+        };
+        for evicted in evicted {
+            self.remove_file(&evicted).await;
+        }
+
+        match self.write_entry(key, &contents).await {
+            Ok(()) => self.lock().touch(key, bytes),
+            Err(e) => warn!(key, error = %e, "cache: failed to write entry file"),
+        }
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.lock().snapshot()
+    }
+}
+
+/// Whether `key` is safe to use as a file name
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Cache key of an entry file path, if it is one
+fn entry_key(path: &Path) -> Option<&str> {
+    if path.extension()? != ENTRY_EXTENSION {
+        return None;
+    }
+    path.file_stem()?.to_str().filter(|key| is_valid_key(key))
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RequestMetadata;
+
+    fn response(content: &str) -> ChatResponse {
+        ChatResponse {
+            content: content.to_owned(),
+            model: "test-model".to_owned(),
+            usage: None,
+            finish_reason: Some("stop".to_owned()),
+            warnings: None,
+            tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
+        }
+    }
+
+    const TTL: Duration = Duration::from_mins(1);
+
+    #[tokio::test]
+    async fn memory_evicts_least_recently_used() {
+        let backend = MemoryCacheBackend::new(2);
+        backend.put("a", response("a")).await;
+        backend.put("b", response("b")).await;
+        // Reading "a" makes "b" the least recently used
+        assert!(backend.get("a", TTL).await.is_some());
+        backend.put("c", response("c")).await;
+
+        assert!(backend.get("a", TTL).await.is_some());
+        assert!(backend.get("b", TTL).await.is_none());
+        let stats = backend.stats();
+        assert_eq!(stats.size, 2);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+    }
+
+    #[tokio::test]
+    async fn memory_enforces_byte_limit() {
+        let entry_bytes = encoded_len(&response("x"));
+        let backend = MemoryCacheBackend::new(100).with_max_bytes(entry_bytes * 2);
+        for key in ["a", "b", "c"] {
+            backend.put(key, response("x")).await;
+        }
+        let stats = backend.stats();
+        assert_eq!(stats.size, 2);
+        assert_eq!(stats.bytes, entry_bytes * 2);
+
+        backend.put("big", response(&"y".repeat(1000))).await;
+        assert!(backend.get("big", TTL).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn disk_entries_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let backend = DiskCacheBackend::open(dir.path(), 10).unwrap();
+            backend.put("abc123", response("persisted")).await;
+            assert_eq!(backend.stats().size, 1);
+        }
+
+        let reopened = DiskCacheBackend::open(dir.path(), 10).unwrap();
+        assert_eq!(reopened.stats().size, 1);
+        let hit = reopened.get("abc123", TTL).await.expect("hit after reopen");
+        assert_eq!(hit.content, "persisted");
+        assert!(reopened.get("abc123", Duration::ZERO).await.is_none());
+        assert!(!dir.path().join("abc123.json").exists());
+    }
+
+    #[tokio::test]
+    async fn disk_evicts_files_beyond_entry_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = DiskCacheBackend::open(dir.path(), 2).unwrap();
+        for key in ["a", "b", "c"] {
+            backend.put(key, response(key)).await;
+        }
+
+        assert!(!dir.path().join("a.json").exists());
+        assert!(backend.get("c", TTL).await.is_some());
+        let stats = backend.stats();
+        assert_eq!(stats.size, 2);
+        assert_eq!(stats.evictions, 1);
+        assert!(stats.bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn disk_rejects_unsafe_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = DiskCacheBackend::open(dir.path(), 10).unwrap();
+        backend.put("../escape", response("x")).await;
+        assert!(backend.get("../escape", TTL).await.is_none());
+        assert_eq!(backend.stats().size, 0);
+    }
+
+    #[tokio::test]
+    async fn zero_entry_limit_stores_nothing() {
+        let memory = MemoryCacheBackend::new(0);
+        memory.put("a", response("a")).await;
+        assert!(memory.get("a", TTL).await.is_none());
+        assert_eq!(memory.stats().size, 0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let disk = DiskCacheBackend::open(dir.path(), 0).unwrap();
+        disk.put("a", response("a")).await;
+        assert!(!dir.path().join("a.json").exists());
+        assert_eq!(disk.stats().size, 0);
+    }
+
+    #[tokio::test]
+    async fn disk_concurrent_writes_of_one_key_do_not_collide() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = DiskCacheBackend::open(dir.path(), 10).unwrap();
+        tokio::join!(
+            backend.put("same", response("1")),
+            backend.put("same", response("2")),
+            backend.put("same", response("3")),
+            backend.put("same", response("4")),
+        );
+
+        assert!(backend.get("same", TTL).await.is_some());
+        assert_eq!(backend.stats().size, 1);
+        let leftovers = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path() != dir.path().join("same.json"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+}
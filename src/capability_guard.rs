@@ -26,6 +26,7 @@ use crate::types::{ChatRequest, LlmCapabilities, RunnerError};
 ///
 /// Returns [`RunnerError`] in strict mode when the request uses
 /// a parameter the provider does not support.
+#[allow(clippy::too_many_lines)] // one flat check per capability
 pub fn validate_capabilities(
     provider_name: &str,
     capabilities: LlmCapabilities,
@@ -126,6 +127,37 @@ pub fn validate_capabilities(
         warnings.push(msg);
     }
 
+    if request.has_attachments() && !capabilities.supports_file_input() {
+        let msg =
+            format!("{provider_name} cannot read file attachments; attached files will be ignored");
+        if strict {
+            return Err(RunnerError::config(msg));
+        }
+        warnings.push(msg);
+    }
+
+    if request.choice_count() > 1 && !capabilities.supports_multiple_choices() {
+        let msg = format!(
+            "{provider_name} does not support multiple choices (n); only one choice will be returned"
+        );
+        if strict {
+            return Err(RunnerError::config(msg));
+        }
+        warnings.push(msg);
+    }
+
+    if (request.reasoning_effort.is_some() || request.thinking_budget.is_some())
+        && !capabilities.supports_reasoning()
+    {
+        let msg = format!(
+            "{provider_name} does not support reasoning effort; requested value will be ignored"
+        );
+        if strict {
+            return Err(RunnerError::config(msg));
+        }
+        warnings.push(msg);
+    }
+
     Ok(warnings)
 }
 
@@ -303,6 +335,41 @@ mod tests {
         assert!(warnings[0].contains("vision"));
     }
 
+    #[test]
+    fn attachments_require_file_input() {
+        let pdf = crate::types::AttachmentPart::from_base64("JVBERi0=", "application/pdf").unwrap();
+        let request = ChatRequest::new(vec![ChatMessage::user_with_attachments(
+            "summarize",
+            vec![pdf],
+        )]);
+
+        let err =
+            validate_capabilities("test", LlmCapabilities::STREAMING, &request, true).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Config);
+        assert!(err.message.contains("file attachments"));
+
+        let caps = LlmCapabilities::STREAMING | LlmCapabilities::FILE_INPUT;
+        assert!(validate_capabilities("test", caps, &request, true)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn reasoning_effort_requires_reasoning_capability() {
+        let request = ChatRequest::new(vec![ChatMessage::user("think")])
+            .with_reasoning_effort(crate::types::ReasoningEffort::High);
+
+        let warnings =
+            validate_capabilities("test", LlmCapabilities::STREAMING, &request, false).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("reasoning effort"));
+
+        let caps = LlmCapabilities::STREAMING | LlmCapabilities::REASONING;
+        assert!(validate_capabilities("test", caps, &request, true)
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn allows_images_with_vision_capability() {
         let caps = LlmCapabilities::STREAMING | LlmCapabilities::VISION;
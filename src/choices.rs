@@ -0,0 +1,419 @@
+// ABOUTME: Multiple-choice (`n`) support by fanning requests out to single-choice providers
+// ABOUTME: Runs candidates concurrently under a cap, aggregates usage and optionally ranks them best-of
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Multiple Choices
+//!
+//! [`ChatRequest::n`] asks for several candidate completions. Providers with
+//! [`LlmCapabilities::MULTIPLE_CHOICES`] return them natively; for everyone
+//! else [`complete_choices`] sends `n` single-choice requests concurrently
+//! (at most [`ChoicesConfig::max_concurrency`] at a time), collects the
+//! results into [`ChatResponse::choices`] and sums their token usage.
+//!
+//! With a [`ChoiceScorer`] configured, candidates are ranked best-first so
+//! the top-level response content is the highest-scoring choice.
+//!
+//! [`ChoicesProvider`] wraps any provider with this behavior. Streaming
+//! requests pass through unchanged and yield a single choice.
+//!
+//! Each candidate carries a [`CHOICE_TAG`] metadata tag with its index.
+//! Candidates are otherwise identical requests, so response caching and
+//! single-flight coalescing skip tagged requests to keep the answers
+//! independent.
+
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::model_catalog::ModelInfo;
+use crate::types::{
+    ChatChoice, ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
+    TokenUsage,
+};
+
+/// Metadata tag holding a fan-out candidate's index
+pub const CHOICE_TAG: &str = "choice";
+
+/// Whether `request` is one candidate of a fanned-out multi-choice request
+pub(crate) fn is_fan_out_candidate(request: &ChatRequest) -> bool {
+    request.metadata.tags.contains_key(CHOICE_TAG)
+}
+
+/// Default number of candidate requests in flight at once
+const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+/// Scores a candidate; higher is better
+pub type ChoiceScorer = Arc<dyn Fn(&ChatChoice) -> f64 + Send + Sync>;
+
+/// Fan-out and ranking settings for multi-choice requests
+#[derive(Clone)]
+pub struct ChoicesConfig {
+    /// Maximum candidate requests running concurrently (minimum 1)
+    pub max_concurrency: usize,
+    /// Optional best-of scorer used to rank candidates
+    pub scorer: Option<ChoiceScorer>,
+}
+
+impl Default for ChoicesConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            scorer: None,
+        }
+    }
+}
+
+impl fmt::Debug for ChoicesConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChoicesConfig")
+            .field("max_concurrency", &self.max_concurrency)
+            .field("scorer", &self.scorer.is_some())
+            .finish()
+    }
+}
+
+impl ChoicesConfig {
+    /// Set the maximum number of concurrent candidate requests
+    #[must_use]
+    pub const fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    /// Rank candidates best-first with `scorer`
+    #[must_use]
+    pub fn with_scorer(mut self, scorer: ChoiceScorer) -> Self {
+        self.scorer = Some(scorer);
+        self
+    }
+}
+
+/// Complete `request` with [`ChatRequest::choice_count`] choices
+///
+/// Providers that support multiple choices natively get a single call;
+/// others are fanned out. The first failing candidate fails the whole
+/// request and aborts the rest.
+///
+/// # Errors
+///
+/// Returns the first [`RunnerError`] reported by a candidate request.
+pub async fn complete_choices(
+    provider: Arc<dyn LlmProvider>,
+    request: &ChatRequest,
+    config: &ChoicesConfig,
+    cancel: &CancellationToken,
+) -> Result<ChatResponse, RunnerError> {
+    let n = request.choice_count();
+    let mut response = if n == 1 || provider.capabilities().supports_multiple_choices() {
+        provider.complete_with_cancel(request, cancel).await?
+    } else {
+        fan_out(provider, request, n, config.max_concurrency, cancel).await?
+    };
+    if let Some(ref scorer) = config.scorer {
+        rank_choices(&mut response, scorer.as_ref());
+    }
+    Ok(response)
+}
+
+/// Run `n` single-choice requests with at most `max_concurrency` in flight
+async fn fan_out(
+    provider: Arc<dyn LlmProvider>,
+    request: &ChatRequest,
+    n: u32,
+    max_concurrency: usize,
+    cancel: &CancellationToken,
+) -> Result<ChatResponse, RunnerError> {
+    // Candidates must not share a CLI session: they would resume the same
+    // conversation concurrently and race to store the new session id.
+    let single = ChatRequest {
+        n: None,
+        session_key: None,
+        ..request.clone()
+    };
+    let mut tasks = JoinSet::new();
+    let mut responses: Vec<Option<ChatResponse>> = vec![None; n as usize];
+    let limit = max_concurrency.max(1);
+    let mut next = 0;
+
+    loop {
+        while next < n && tasks.len() < limit {
+            let mut candidate = single.clone();
+            candidate
+                .metadata
+                .tags
+                .insert(CHOICE_TAG.to_owned(), next.to_string());
+            let (provider, cancel) = (Arc::clone(&provider), cancel.clone());
+            tasks.spawn(async move {
+                (
+                    next,
+                    provider.complete_with_cancel(&candidate, &cancel).await,
+                )
+            });
+            next += 1;
+        }
+        let Some(joined) = tasks.join_next().await else {
+            break;
+        };
+        let (index, result) =
+            joined.map_err(|e| RunnerError::internal(format!("Choice task failed: {e}")))?;
+        responses[index as usize] = Some(result?);
+    }
+
+    let mut responses = responses.into_iter().flatten();
+    let first = responses
+        .next()
+        .ok_or_else(|| RunnerError::internal("no choices completed"))?;
+    let mut combined = ChatResponse {
+        reasoning: None,
+        choices: Some(vec![choice_from(0, &first)]),
+        ..first
+    };
+    combined.metadata.tags.remove(CHOICE_TAG);
+    for (index, response) in (1..).zip(responses) {
+        if let Some(ref usage) = response.usage {
+            combined
+                .usage
+                .get_or_insert_with(TokenUsage::default)
+                .accumulate(usage);
+        } else if let Some(total) = combined.usage.as_mut() {
+            total.reported_cost = None;
+        }
+        if let Some(choices) = combined.choices.as_mut() {
+            choices.push(choice_from(index, &response));
+        }
+    }
+    Ok(combined)
+}
+
+/// Build a choice from a single-choice response
+fn choice_from(index: u32, response: &ChatResponse) -> ChatChoice {
+    ChatChoice {
+        index,
+        content: response.content.clone(),
+        finish_reason: response.finish_reason.clone(),
+        tool_calls: response.tool_calls.clone(),
+    }
+}
+
+/// Sort choices best-first, re-index them and mirror the winner at the top level
+fn rank_choices(response: &mut ChatResponse, scorer: &(dyn Fn(&ChatChoice) -> f64 + Send + Sync)) {
+    let Some(choices) = response.choices.as_mut() else {
+        return;
+    };
+    let mut ranked: Vec<(f64, ChatChoice)> = choices.drain(..).map(|c| (scorer(&c), c)).collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    choices.extend(ranked.into_iter().map(|(_, c)| c));
+    for (index, choice) in (0..).zip(choices.iter_mut()) {
+        choice.index = index;
+    }
+    if let Some(best) = choices.first() {
+        response.content.clone_from(&best.content);
+        response.finish_reason.clone_from(&best.finish_reason);
+        response.tool_calls.clone_from(&best.tool_calls);
+    }
+}
+
+/// Provider wrapper that serves `n > 1` requests by fanning out to its inner provider
+///
+/// Advertises [`LlmCapabilities::MULTIPLE_CHOICES`] on top of the inner
+/// provider's capabilities.
+pub struct ChoicesProvider {
+    inner: Arc<dyn LlmProvider>,
+    config: ChoicesConfig,
+}
+
+impl ChoicesProvider {
+    /// Wrap a provider with multi-choice fan-out
+    pub fn new(inner: Box<dyn LlmProvider>, config: ChoicesConfig) -> Self {
+        Self::from_shared(Arc::from(inner), config)
+    }
+
+    /// Wrap a provider that is already shared behind an `Arc`
+    pub fn from_shared(inner: Arc<dyn LlmProvider>, config: ChoicesConfig) -> Self {
+        Self { inner, config }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ChoicesProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn display_name(&self) -> &str {
+        self.inner.display_name()
+    }
+
+    fn capabilities(&self) -> LlmCapabilities {
+        self.inner.capabilities() | LlmCapabilities::MULTIPLE_CHOICES
+    }
+
+    fn default_model(&self) -> &str {
+        self.inner.default_model()
+    }
+
+    fn available_models(&self) -> &[String] {
+        self.inner.available_models()
+    }
+
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        self.inner.model_catalog()
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        self.inner.refresh_models().await
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        complete_choices(Arc::clone(&self.inner), request, &self.config, cancel).await
+    }
+
+    async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+        self.inner.complete_stream(request).await
+    }
+
+    async fn health_check(&self) -> Result<bool, RunnerError> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::types::{ChatMessage, RequestMetadata};
+
+    /// Returns "answer-<call number>" with 10 prompt and 5 completion tokens
+    struct CountingProvider {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl LlmProvider for CountingProvider {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+        fn display_name(&self) -> &str {
+            "Counting"
+        }
+        fn capabilities(&self) -> LlmCapabilities {
+            LlmCapabilities::STREAMING
+        }
+        fn default_model(&self) -> &str {
+            "m"
+        }
+        fn available_models(&self) -> &[String] {
+            &[]
+        }
+        async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+            assert!(request.n.is_none(), "fan-out requests ask for one choice");
+            assert!(
+                request.session_key.is_none(),
+                "fan-out requests do not resume a shared session"
+            );
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(ChatResponse {
+                content: format!("answer-{call}"),
+                model: "m".to_owned(),
+                usage: Some(TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                    total_tokens: 15,
+                    ..TokenUsage::default()
+                }),
+                finish_reason: Some("stop".to_owned()),
+                warnings: None,
+                tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
+            })
+        }
+        async fn complete_stream(&self, _request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+            Err(RunnerError::internal("not supported"))
+        }
+        async fn health_check(&self) -> Result<bool, RunnerError> {
+            Ok(true)
+        }
+    }
+
+    fn counting() -> Box<dyn LlmProvider> {
+        Box::new(CountingProvider {
+            calls: AtomicU32::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn fans_out_and_aggregates_usage() {
+        let provider =
+            ChoicesProvider::new(counting(), ChoicesConfig::default().with_max_concurrency(2));
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")])
+            .with_n(3)
+            .with_session_key("conversation-1");
+        let response = provider.complete(&request).await.unwrap();
+
+        let choices = response.choices.as_ref().unwrap();
+        assert_eq!(choices.len(), 3);
+        assert_eq!(
+            choices.iter().map(|c| c.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(response.content, choices[0].content);
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 30);
+        assert_eq!(usage.total_tokens, 45);
+        assert!(provider.capabilities().supports_multiple_choices());
+    }
+
+    #[tokio::test]
+    async fn single_choice_is_passed_through() {
+        let provider = ChoicesProvider::new(counting(), ChoicesConfig::default());
+        let response = provider
+            .complete(&ChatRequest::new(vec![ChatMessage::user("hi")]))
+            .await
+            .unwrap();
+        assert!(response.choices.is_none());
+        assert_eq!(response.content, "answer-0");
+    }
+
+    #[tokio::test]
+    async fn scorer_ranks_best_choice_first() {
+        let scorer: ChoiceScorer = Arc::new(
+            |c: &ChatChoice| {
+                if c.content == "answer-1" {
+                    1.0
+                } else {
+                    0.0
+                }
+            },
+        );
+        let provider = ChoicesProvider::new(
+            counting(),
+            ChoicesConfig::default()
+                .with_max_concurrency(1)
+                .with_scorer(scorer),
+        );
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]).with_n(3);
+        let response = provider.complete(&request).await.unwrap();
+
+        assert_eq!(response.content, "answer-1");
+        let choices = response.choices.unwrap();
+        assert_eq!(choices[0].content, "answer-1");
+        assert_eq!(choices[0].index, 0);
+    }
+}
@@ -0,0 +1,497 @@
+// ABOUTME: Per-provider circuit breaker tracking consecutive failures and recent error rate
+// ABOUTME: Opens after repeated failures, then admits trial calls once a cooldown has elapsed
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Circuit Breaker
+//!
+//! [`CircuitBreaker`] tracks the health of one provider:
+//!
+//! - **Closed** — calls flow normally while outcomes are recorded
+//! - **Open** — after `failure_threshold` consecutive failures, or once the
+//!   failure ratio over the last `window_size` calls reaches
+//!   `error_rate_threshold`, calls are rejected for `cooldown`
+//! - **Half-open** — after the cooldown, up to `half_open_trials` trial calls
+//!   are admitted; the first success closes the circuit, a failure reopens it
+//!
+//! Only errors that say something about the provider count as failures.
+//! Cancellations, guardrail rejections, config errors, oversized prompts and
+//! content-filter refusals are ignored, as are timeouts that fire once the
+//! caller's own deadline has passed.
+//!
+//! [`FallbackProvider::with_circuit_breaker`](crate::fallback::FallbackProvider::with_circuit_breaker)
+//! keeps one breaker per inner provider, skips providers whose circuit is
+//! open and tries providers that failed within the last cooldown after the
+//! others.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::types::{ErrorKind, RunnerError};
+
+/// Thresholds and timings for a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit (0 = never on this basis)
+    pub failure_threshold: u32,
+    /// Failure ratio in `0.0..=1.0` over the recent window that opens the circuit
+    pub error_rate_threshold: Option<f64>,
+    /// Number of recent calls the error rate is computed over
+    pub window_size: usize,
+    /// Calls required in the window before the error rate is considered
+    pub min_calls: usize,
+    /// How long an open circuit rejects calls before admitting trials
+    pub cooldown: Duration,
+    /// Trial calls admitted at once while half-open
+    pub half_open_trials: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            error_rate_threshold: Some(0.5),
+            window_size: 20,
+            min_calls: 10,
+            cooldown: Duration::from_secs(30),
+            half_open_trials: 1,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// A configuration whose circuit never opens
+    pub const fn disabled() -> Self {
+        Self {
+            failure_threshold: 0,
+            error_rate_threshold: None,
+            window_size: 0,
+            min_calls: 0,
+            cooldown: Duration::ZERO,
+            half_open_trials: 1,
+        }
+    }
+
+    /// Open after this many consecutive failures
+    #[must_use]
+    pub const fn with_failure_threshold(mut self, failures: u32) -> Self {
+        self.failure_threshold = failures;
+        self
+    }
+
+    /// Open once the failure ratio over `window_size` recent calls reaches `rate`
+    ///
+    /// The rate is only evaluated once `min_calls` outcomes are in the window.
+    #[must_use]
+    pub const fn with_error_rate(
+        mut self,
+        rate: f64,
+        window_size: usize,
+        min_calls: usize,
+    ) -> Self {
+        self.error_rate_threshold = Some(rate);
+        self.window_size = window_size;
+        self.min_calls = min_calls;
+        self
+    }
+
+    /// Keep the circuit open for this long before admitting trials
+    #[must_use]
+    pub const fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Admit this many concurrent trial calls while half-open
+    #[must_use]
+    pub const fn with_half_open_trials(mut self, trials: u32) -> Self {
+        self.half_open_trials = trials;
+        self
+    }
+}
+
+/// Observable state of a circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls flow normally
+    Closed,
+    /// Calls are rejected until the cooldown elapses
+    Open,
+    /// A limited number of trial calls decide whether to close again
+    HalfOpen,
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half_open",
+        })
+    }
+}
+
+#[derive(Debug)]
+enum Phase {
+    Closed,
+    Open { since: Instant },
+    HalfOpen { trials: u32 },
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    phase: Phase,
+    consecutive_failures: u32,
+    /// Recent outcomes, `true` for a failure
+    window: VecDeque<bool>,
+    last_failure: Option<Instant>,
+}
+
+/// Failure tracker for a single provider
+pub struct CircuitBreaker {
+    name: String,
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker; `name` labels state transitions in logs
+    pub fn new(name: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name: name.into(),
+            config,
+            state: Mutex::new(BreakerState {
+                phase: Phase::Closed,
+                consecutive_failures: 0,
+                window: VecDeque::new(),
+                last_failure: None,
+            }),
+        }
+    }
+
+    /// Current state; an open circuit whose cooldown has elapsed reports half-open
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn state(&self) -> CircuitState {
+        let state = self.state.lock().expect("circuit breaker lock poisoned");
+        match state.phase {
+            Phase::Closed => CircuitState::Closed,
+            Phase::Open { since } if since.elapsed() < self.config.cooldown => CircuitState::Open,
+            Phase::Open { .. } | Phase::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Time until an open circuit admits trials, or `None` if it is not open
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn remaining_cooldown(&self) -> Option<Duration> {
+        let state = self.state.lock().expect("circuit breaker lock poisoned");
+        match state.phase {
+            Phase::Open { since } => self
+                .config
+                .cooldown
+                .checked_sub(since.elapsed())
+                .filter(|d| !d.is_zero()),
+            _ => None,
+        }
+    }
+
+    /// Whether a failure was recorded within the last cooldown period
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn failed_recently(&self) -> bool {
+        let state = self.state.lock().expect("circuit breaker lock poisoned");
+        state
+            .last_failure
+            .is_some_and(|at| at.elapsed() < self.config.cooldown)
+    }
+
+    /// Ask to make a call; `None` means the circuit rejects it
+    ///
+    /// The permit keeps the breaker alive, so it can travel with a stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn try_acquire(self: &Arc<Self>) -> Option<CircuitPermit> {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        if let Phase::Open { since } = state.phase {
+            if since.elapsed() < self.config.cooldown {
+                return None;
+            }
+            info!(provider = %self.name, "circuit breaker: cooldown elapsed, half-open");
+            state.phase = Phase::HalfOpen { trials: 0 };
+        }
+        let trial = match &mut state.phase {
+            Phase::Closed => false,
+            Phase::HalfOpen { trials } if *trials < self.config.half_open_trials.max(1) => {
+                *trials += 1;
+                true
+            }
+            Phase::HalfOpen { .. } | Phase::Open { .. } => return None,
+        };
+        Some(CircuitPermit {
+            breaker: Arc::clone(self),
+            trial,
+            settled: false,
+        })
+    }
+
+    fn record(&self, failed: bool, trial: bool) {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        if trial {
+            if let Phase::HalfOpen { trials } = &mut state.phase {
+                *trials = trials.saturating_sub(1);
+            }
+        }
+
+        if self.config.window_size > 0 {
+            state.window.push_back(failed);
+            while state.window.len() > self.config.window_size {
+                state.window.pop_front();
+            }
+        }
+
+        if !failed {
+            state.consecutive_failures = 0;
+            if trial && matches!(state.phase, Phase::HalfOpen { .. }) {
+                info!(provider = %self.name, "circuit breaker: trial succeeded, closing");
+                state.phase = Phase::Closed;
+                state.window.clear();
+            }
+            return;
+        }
+
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        state.last_failure = Some(Instant::now());
+        let reopen = trial && matches!(state.phase, Phase::HalfOpen { .. });
+        let trip = matches!(state.phase, Phase::Closed) && self.should_trip(&state);
+        if reopen || trip {
+            warn!(
+                provider = %self.name,
+                consecutive_failures = state.consecutive_failures,
+                cooldown_ms = u64::try_from(self.config.cooldown.as_millis()).unwrap_or(u64::MAX),
+                "circuit breaker: opening"
+            );
+            state.phase = Phase::Open {
+                since: Instant::now(),
+            };
+            state.window.clear();
+        }
+    }
+
+    /// Release a trial slot without recording an outcome
+    fn release(&self) {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        if let Phase::HalfOpen { trials } = &mut state.phase {
+            *trials = trials.saturating_sub(1);
+        }
+    }
+
+    fn should_trip(&self, state: &BreakerState) -> bool {
+        let config = &self.config;
+        if config.failure_threshold > 0 && state.consecutive_failures >= config.failure_threshold {
+            return true;
+        }
+        let Some(threshold) = config.error_rate_threshold else {
+            return false;
+        };
+        let calls = state.window.len();
+        if calls == 0 || calls < config.min_calls {
+            return false;
+        }
+        let failures = state.window.iter().filter(|&&failed| failed).count();
+        #[allow(clippy::cast_precision_loss)]
+        let rate = failures as f64 / calls as f64;
+        rate >= threshold
+    }
+}
+
+/// Whether an error reflects on the provider's health
+///
+/// A timeout after `deadline` is the caller's budget running out, not the
+/// provider failing.
+fn is_provider_failure(err: &RunnerError, deadline: Option<std::time::Instant>) -> bool {
+    if err.kind == ErrorKind::Timeout && deadline.is_some_and(|d| d <= std::time::Instant::now()) {
+        return false;
+    }
+    !matches!(
+        err.kind,
+        ErrorKind::Cancelled
+            | ErrorKind::Guardrail
+            | ErrorKind::Config
+            | ErrorKind::ContextLengthExceeded
+            | ErrorKind::ContentFiltered
+    )
+}
+
+/// Permission to make one call; report its outcome with
+/// [`success`](Self::success) or [`failure`](Self::failure)
+///
+/// Dropping the permit without reporting records nothing.
+pub struct CircuitPermit {
+    breaker: Arc<CircuitBreaker>,
+    trial: bool,
+    settled: bool,
+}
+
+impl CircuitPermit {
+    /// Record a successful call
+    pub fn success(mut self) {
+        self.settled = true;
+        self.breaker.record(false, self.trial);
+    }
+
+    /// Record a failed call; errors that are not the provider's fault are ignored
+    ///
+    /// `deadline` is the caller's deadline for the call, if any.
+    pub fn failure(mut self, err: &RunnerError, deadline: Option<std::time::Instant>) {
+        if is_provider_failure(err, deadline) {
+            self.settled = true;
+            self.breaker.record(true, self.trial);
+        }
+    }
+}
+
+impl Drop for CircuitPermit {
+    fn drop(&mut self) {
+        if self.trial && !self.settled {
+            self.breaker.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail(breaker: &Arc<CircuitBreaker>) {
+        breaker
+            .try_acquire()
+            .expect("admitted")
+            .failure(&RunnerError::timeout("slow"), None);
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = Arc::new(CircuitBreaker::new(
+            "p",
+            CircuitBreakerConfig::disabled()
+                .with_failure_threshold(3)
+                .with_cooldown(Duration::from_mins(1)),
+        ));
+        fail(&breaker);
+        fail(&breaker);
+        breaker.try_acquire().expect("admitted").success();
+        fail(&breaker);
+        fail(&breaker);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        fail(&breaker);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.try_acquire().is_none());
+        assert!(breaker.remaining_cooldown().unwrap() > Duration::from_secs(50));
+    }
+
+    #[test]
+    fn opens_on_error_rate_once_window_has_enough_calls() {
+        let breaker = Arc::new(CircuitBreaker::new(
+            "p",
+            CircuitBreakerConfig::disabled()
+                .with_error_rate(0.5, 4, 4)
+                .with_cooldown(Duration::from_mins(1)),
+        ));
+        fail(&breaker);
+        breaker.try_acquire().unwrap().success();
+        fail(&breaker);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.try_acquire().unwrap().success();
+        // The oldest outcome drops out, leaving 2 failures in the last 4 calls
+        fail(&breaker);
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn half_open_admits_limited_trials_and_reopens_on_failure() {
+        let breaker = Arc::new(CircuitBreaker::new(
+            "p",
+            CircuitBreakerConfig::disabled()
+                .with_failure_threshold(1)
+                .with_cooldown(Duration::from_millis(20)),
+        ));
+        fail(&breaker);
+        assert!(breaker.try_acquire().is_none());
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        let trial = breaker.try_acquire().expect("trial admitted");
+        assert!(breaker.try_acquire().is_none(), "only one trial at a time");
+        trial.failure(&RunnerError::external_service("p", "still down"), None);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(30));
+        breaker.try_acquire().expect("trial admitted").success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn ignored_errors_and_dropped_trials_leave_state_alone() {
+        let breaker = Arc::new(CircuitBreaker::new(
+            "p",
+            CircuitBreakerConfig::disabled()
+                .with_failure_threshold(1)
+                .with_cooldown(Duration::from_millis(20)),
+        ));
+        breaker
+            .try_acquire()
+            .unwrap()
+            .failure(&RunnerError::cancelled("caller left"), None);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        fail(&breaker);
+        std::thread::sleep(Duration::from_millis(30));
+        drop(breaker.try_acquire().expect("trial admitted"));
+        // The abandoned trial frees its slot for the next caller
+        let trial = breaker.try_acquire().expect("trial admitted again");
+        trial.failure(&RunnerError::guardrail("blocked"), None);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire().is_some());
+    }
+
+    #[test]
+    fn caller_deadline_timeouts_are_not_provider_failures() {
+        let breaker = Arc::new(CircuitBreaker::new(
+            "p",
+            CircuitBreakerConfig::disabled()
+                .with_failure_threshold(1)
+                .with_cooldown(Duration::from_mins(1)),
+        ));
+        let passed = Some(std::time::Instant::now());
+        breaker
+            .try_acquire()
+            .unwrap()
+            .failure(&RunnerError::timeout("deadline"), passed);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(!breaker.failed_recently());
+
+        let pending = std::time::Instant::now().checked_add(Duration::from_mins(1));
+        breaker
+            .try_acquire()
+            .unwrap()
+            .failure(&RunnerError::timeout("provider hung"), pending);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.failed_recently());
+    }
+}
@@ -5,13 +5,12 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::io;
-use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
-    TokenUsage,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, StreamChunk, TokenUsage,
 };
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -19,13 +18,15 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, instrument, warn};
 
-use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::{extract_system_message, prepare_user_prompt};
+use crate::config::{CliRunnerType, PromptDelivery, RunnerConfig};
+use crate::error_classify::classify_error;
+use crate::process::read_stderr_capped;
+use crate::prompt::extract_system_message;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Default model for Claude Code
 const DEFAULT_MODEL: &str = "opus";
@@ -41,13 +42,34 @@ struct ClaudeResponse {
     is_error: bool,
     session_id: Option<String>,
     usage: Option<ClaudeUsage>,
+    total_cost_usd: Option<f64>,
 }
 
-/// Token usage from Claude Code CLI
+/// Token usage from Claude Code CLI (field names match external JSON schema)
 #[derive(Debug, Deserialize)]
+#[allow(clippy::struct_field_names)]
 struct ClaudeUsage {
     input_tokens: Option<u32>,
     output_tokens: Option<u32>,
+    cache_read_input_tokens: Option<u32>,
+    cache_creation_input_tokens: Option<u32>,
+}
+
+impl ClaudeUsage {
+    /// Convert to [`TokenUsage`], attaching the CLI's `total_cost_usd`
+    fn into_token_usage(self, cost: Option<f64>) -> TokenUsage {
+        let input = self.input_tokens.unwrap_or(0);
+        let output = self.output_tokens.unwrap_or(0);
+        TokenUsage {
+            prompt_tokens: input,
+            completion_tokens: output,
+            total_tokens: input.saturating_add(output),
+            reasoning_tokens: None,
+            cached_prompt_tokens: self.cache_read_input_tokens,
+            cache_creation_tokens: self.cache_creation_input_tokens,
+            reported_cost: cost,
+        }
+    }
 }
 
 /// Claude Code CLI runner
@@ -68,7 +90,7 @@ impl ClaudeCodeRunner {
         }
     }
 
-    /// Store a session ID for later resumption
+    /// Store a session ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
@@ -77,15 +99,22 @@ impl ClaudeCodeRunner {
     ///
     /// When `max_tokens` is `Some`, the `CLAUDE_CODE_MAX_OUTPUT_TOKENS` env var
     /// is injected after sandbox application so the CLI limits its output length.
+    /// `thinking_budget` is likewise passed as `MAX_THINKING_TOKENS`.
     fn build_command(
         &self,
         prompt: &str,
         system_prompt: Option<&str>,
         output_format: &str,
         max_tokens: Option<u32>,
+        thinking_budget: Option<u32>,
     ) -> Command {
         let mut cmd = Command::new(&self.base.config.binary_path);
-        cmd.args(["-p", prompt, "--output-format", output_format]);
+        // Without a prompt argument, `claude -p` reads the prompt from stdin
+        match self.base.prompt_delivery(CliRunnerType::ClaudeCode) {
+            PromptDelivery::Stdin => cmd.arg("-p"),
+            PromptDelivery::Argv => cmd.args(["-p", prompt]),
+        };
+        cmd.args(["--output-format", output_format]);
 
         // stream-json requires --verbose flag in Claude Code CLI
         if output_format == "stream-json" {
@@ -126,14 +155,69 @@ impl ClaudeCodeRunner {
             warn!("Failed to build sandbox policy, running with inherited env");
         }
 
-        // Inject max output tokens after sandbox (env_clear) so the value persists
+        // Inject max output and thinking tokens after sandbox (env_clear) so the values persist
         if let Some(tokens) = max_tokens {
             cmd.env("CLAUDE_CODE_MAX_OUTPUT_TOKENS", tokens.to_string());
         }
+        if let Some(tokens) = thinking_budget {
+            cmd.env("MAX_THINKING_TOKENS", tokens.to_string());
+        }
 
         cmd
     }
 
+    /// Convert one `stream-json` event into a stream chunk
+    ///
+    /// `assistant` events carry `text` and `thinking` content blocks; the
+    /// `result` event ends the stream with the total token usage. Tools the
+    /// CLI runs itself (`tool_use` blocks) are not surfaced as tool calls.
+    fn parse_stream_event(value: &serde_json::Value) -> StreamChunk {
+        let chunk_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        match chunk_type {
+            "result" => StreamChunk {
+                is_final: true,
+                finish_reason: Some("stop".to_owned()),
+                usage: value
+                    .get("usage")
+                    .and_then(|u| serde_json::from_value::<ClaudeUsage>(u.clone()).ok())
+                    .map(|u| {
+                        u.into_token_usage(
+                            value
+                                .get("total_cost_usd")
+                                .and_then(serde_json::Value::as_f64),
+                        )
+                    }),
+                ..StreamChunk::default()
+            },
+            "assistant" => {
+                // Collect message.content[] blocks of the given type
+                let blocks = |block_type: &str, field: &str| {
+                    value
+                        .pointer("/message/content")
+                        .and_then(|c| c.as_array())
+                        .map(|arr| {
+                            arr.iter()
+                                .filter(|item| {
+                                    item.get("type").and_then(|t| t.as_str()) == Some(block_type)
+                                })
+                                .filter_map(|item| item.get(field).and_then(|t| t.as_str()))
+                                .collect::<Vec<_>>()
+                                .join("")
+                        })
+                        .unwrap_or_default()
+                };
+                let thinking = blocks("thinking", "thinking");
+                StreamChunk {
+                    delta: blocks("text", "text"),
+                    reasoning_delta: (!thinking.is_empty()).then_some(thinking),
+                    ..StreamChunk::default()
+                }
+            }
+            // system, rate_limit_event, and other event types are ignored
+            _ => StreamChunk::default(),
+        }
+    }
+
     /// Parse a Claude Code JSON response into a `ChatResponse`
     fn parse_response(raw: &[u8]) -> Result<(ChatResponse, Option<String>), RunnerError> {
         let text = str::from_utf8(raw).map_err(|e| {
@@ -145,7 +229,7 @@ impl ClaudeCodeRunner {
         })?;
 
         if parsed.is_error {
-            return Err(RunnerError::external_service(
+            return Err(classify_error(
                 "claude-code",
                 parsed
                     .result
@@ -155,11 +239,9 @@ impl ClaudeCodeRunner {
         }
 
         let content = parsed.result.unwrap_or_default();
-        let usage = parsed.usage.map(|u| TokenUsage {
-            prompt_tokens: u.input_tokens.unwrap_or(0),
-            completion_tokens: u.output_tokens.unwrap_or(0),
-            total_tokens: u.input_tokens.unwrap_or(0) + u.output_tokens.unwrap_or(0),
-        });
+        let usage = parsed
+            .usage
+            .map(|u| u.into_token_usage(parsed.total_cost_usd));
 
         let response = ChatResponse {
             content,
@@ -168,6 +250,9 @@ impl ClaudeCodeRunner {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
 
         Ok((response, parsed.session_id))
@@ -179,19 +264,38 @@ impl LlmProvider for ClaudeCodeRunner {
     crate::delegate_provider_base!(
         "claude-code",
         "Claude Code CLI",
-        LlmCapabilities::STREAMING | LlmCapabilities::TEMPERATURE | LlmCapabilities::MAX_TOKENS
+        LlmCapabilities::STREAMING
+            | LlmCapabilities::TEMPERATURE
+            | LlmCapabilities::MAX_TOKENS
+            | LlmCapabilities::FILE_INPUT
+            | LlmCapabilities::REASONING
     );
 
-    #[instrument(skip_all, fields(runner = "claude_code"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "claude_code"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
         let system = extract_system_message(&request.messages);
-        let prepared = prepare_user_prompt(&request.messages)?;
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
 
-        let mut cmd = self.build_command(prompt, system, "json", request.max_tokens);
+        let mut cmd = self.build_command(
+            prompt,
+            system,
+            "json",
+            request.max_tokens,
+            request.effective_thinking_budget(),
+        );
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--resume", &sid]);
             }
         }
@@ -208,14 +312,22 @@ impl LlmProvider for ClaudeCodeRunner {
             "Spawning claude CLI"
         );
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(
+                request,
+                cmd,
+                self.base.prompt_stdin(CliRunnerType::ClaudeCode, prompt),
+                cancel,
+            )
+            .await?;
         self.base.check_exit_code(&output, "claude-code")?;
 
         let (response, session_id) = Self::parse_response(&output.stdout)?;
 
         if let Some(sid) = session_id {
-            if let Some(model) = &request.model {
-                self.base.set_session(model, &sid).await;
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &sid).await;
             }
         }
 
@@ -225,23 +337,28 @@ impl LlmProvider for ClaudeCodeRunner {
     #[instrument(skip_all, fields(runner = "claude_code"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
         let system = extract_system_message(&request.messages);
-        let prepared = prepare_user_prompt(&request.messages)?;
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
 
-        let mut cmd = self.build_command(prompt, system, "stream-json", request.max_tokens);
+        let mut cmd = self.build_command(
+            prompt,
+            system,
+            "stream-json",
+            request.max_tokens,
+            request.effective_thinking_budget(),
+        );
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--resume", &sid]);
             }
         }
 
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn claude for streaming: {e}"))
-        })?;
+        let (mut child, cleanup) = self.base.spawn_streaming(
+            cmd,
+            self.base.prompt_stdin(CliRunnerType::ClaudeCode, prompt),
+            "claude",
+        )?;
 
         let stdout = child.stdout.take().ok_or_else(|| {
             RunnerError::internal("Failed to capture claude stdout for streaming")
@@ -260,66 +377,64 @@ impl LlmProvider for ClaudeCodeRunner {
                 .map_err(|e| RunnerError::internal(format!("Error reading claude stream: {e}")))?;
 
             if line.trim().is_empty() {
-                return Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                });
+                return Ok(StreamChunk::default());
             }
 
             let value: serde_json::Value = serde_json::from_str(&line).map_err(|e| {
                 RunnerError::internal(format!("Invalid JSON in claude stream: {e}"))
             })?;
 
-            let chunk_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
-            match chunk_type {
-                "result" => Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: true,
-                    finish_reason: Some("stop".to_owned()),
-                }),
-                "assistant" => {
-                    // Extract text from content array: message.content[].text where type == "text"
-                    let text = value
-                        .get("message")
-                        .and_then(|m| m.get("content"))
-                        .and_then(|c| c.as_array())
-                        .map(|arr| {
-                            arr.iter()
-                                .filter(|item| {
-                                    item.get("type").and_then(|t| t.as_str()) == Some("text")
-                                })
-                                .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
-                                .collect::<Vec<_>>()
-                                .join("")
-                        })
-                        .unwrap_or_default();
-                    Ok(StreamChunk {
-                        delta: text,
-                        is_final: false,
-                        finish_reason: None,
-                    })
-                }
-                // system, rate_limit_event, and other event types are ignored
-                _ => Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                }),
-            }
+            Ok(Self::parse_stream_event(&value))
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_build_command_keeps_prompt_off_argv() {
+        let runner = ClaudeCodeRunner::new(RunnerConfig::new(PathBuf::from("claude")));
+        let cmd = runner.build_command("private prompt", None, "json", None, None);
+        let args: Vec<_> = cmd.as_std().get_args().collect();
+        assert!(args.contains(&"-p".as_ref()));
+        assert!(!args.contains(&"private prompt".as_ref()));
+    }
+
+    #[test]
+    fn test_build_command_argv_override() {
+        let config =
+            RunnerConfig::new(PathBuf::from("claude")).with_prompt_delivery(PromptDelivery::Argv);
+        let runner = ClaudeCodeRunner::new(config);
+        let cmd = runner.build_command("private prompt", None, "json", None, None);
+        let args: Vec<_> = cmd.as_std().get_args().collect();
+        let p = args.iter().position(|a| *a == "-p").unwrap();
+        assert_eq!(args[p + 1], "private prompt");
+    }
+
+    #[test]
+    fn test_build_command_sets_thinking_budget_env() {
+        let runner = ClaudeCodeRunner::new(RunnerConfig::new(PathBuf::from("claude")));
+        let cmd = runner.build_command("q", None, "json", None, Some(10_000));
+        let budget = cmd
+            .as_std()
+            .get_envs()
+            .find(|(key, _)| *key == "MAX_THINKING_TOKENS")
+            .and_then(|(_, value)| value);
+        assert_eq!(budget, Some("10000".as_ref()));
+    }
 
     #[test]
     fn test_parse_response_valid_json() {
-        let json = br#"{"result":"Hello world","is_error":false,"session_id":"abc123","usage":{"input_tokens":10,"output_tokens":5}}"#;
+        let json = br#"{"result":"Hello world","is_error":false,"session_id":"abc123","total_cost_usd":0.0123,"usage":{"input_tokens":10,"output_tokens":5,"cache_read_input_tokens":300,"cache_creation_input_tokens":40}}"#;
         let (response, session_id) = ClaudeCodeRunner::parse_response(json).unwrap();
 
         assert_eq!(response.content, "Hello world");
@@ -329,6 +444,37 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 10);
         assert_eq!(usage.completion_tokens, 5);
         assert_eq!(usage.total_tokens, 15);
+        assert_eq!(usage.cached_prompt_tokens, Some(300));
+        assert_eq!(usage.cache_creation_tokens, Some(40));
+        assert_eq!(usage.reported_cost, Some(0.0123));
+    }
+
+    #[test]
+    fn test_parse_stream_event_thinking_and_usage() {
+        let assistant = serde_json::json!({
+            "type": "assistant",
+            "message": {"content": [
+                {"type": "thinking", "thinking": "Let me check."},
+                {"type": "text", "text": "Done."},
+                {"type": "tool_use", "id": "toolu_1", "name": "Bash", "input": {}}
+            ]}
+        });
+        let chunk = ClaudeCodeRunner::parse_stream_event(&assistant);
+        assert_eq!(chunk.delta, "Done.");
+        assert_eq!(chunk.reasoning_delta.as_deref(), Some("Let me check."));
+        assert!(chunk.tool_calls.is_none());
+
+        let result = serde_json::json!({
+            "type": "result",
+            "total_cost_usd": 0.002,
+            "usage": {"input_tokens": 12, "output_tokens": 3}
+        });
+        let chunk = ClaudeCodeRunner::parse_stream_event(&result);
+        assert!(chunk.is_final);
+        let usage = chunk.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 12);
+        assert_eq!(usage.total_tokens, 15);
+        assert_eq!(usage.reported_cost, Some(0.002));
     }
 
     #[test]
@@ -336,7 +482,7 @@ mod tests {
         let json = br#"{"result":"rate limited","is_error":true}"#;
         let err = ClaudeCodeRunner::parse_response(json).unwrap_err();
 
-        assert_eq!(err.kind, crate::types::ErrorKind::ExternalService);
+        assert_eq!(err.kind, crate::types::ErrorKind::RateLimited);
         assert!(err.message.contains("rate limited"));
     }
 
@@ -11,17 +11,23 @@
 //! uses [`delegate_provider_base!`] to auto-generate the repetitive
 //! [`LlmProvider`](crate::types::LlmProvider) trait methods.
 
-use std::collections::HashMap;
+use std::process::Stdio;
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::process::{Child, Command};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
 
-use crate::config::RunnerConfig;
-use crate::process::{run_cli_command, CliOutput};
-use crate::types::RunnerError;
+use crate::config::{CliRunnerType, PromptDelivery, RunnerConfig};
+use crate::error_classify::classify_error_text;
+use crate::execution::{CleanupGuard, ExecutionBackend, LocalBackend};
+use crate::model_discovery::{merge_models, ModelDiscovery, ModelListCache};
+use crate::process::{run_cli_command_with_stdin, spawn_in_process_group, CliOutput};
+use crate::prompt::{prepare_prompt_in, prepare_user_prompt_in, PreparedPrompt};
+use crate::sandbox::{apply_sandbox, build_policy};
+use crate::session::{InMemorySessionStore, SessionStore};
+use crate::types::{ChatMessage, ChatRequest, RunnerError};
 
 /// Maximum output size for a single CLI invocation (50 MiB)
 pub const MAX_OUTPUT_BYTES: usize = 50 * 1024 * 1024;
@@ -32,6 +38,12 @@ pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
 /// Health check output limit (4 KiB)
 pub const HEALTH_CHECK_MAX_OUTPUT: usize = 4096;
 
+/// Model listing command timeout (30 seconds)
+pub const MODEL_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Model listing output limit (1 MiB)
+pub const MODEL_DISCOVERY_MAX_OUTPUT: usize = 1024 * 1024;
+
 /// Shared base struct for all CLI runners.
 ///
 /// Holds the common fields (config, model info, session tracking) that every
@@ -44,8 +56,12 @@ pub struct CliRunnerBase {
     pub(crate) default_model: String,
     /// List of available models for this provider
     pub(crate) available_models: Vec<String>,
-    /// Session ID cache keyed by model name (for multi-turn sessions)
-    pub(crate) session_ids: Arc<Mutex<HashMap<String, String>>>,
+    /// Conversation-keyed session store (for multi-turn resume)
+    pub(crate) session_store: Arc<dyn SessionStore>,
+    /// How the CLI lists its models at runtime, if it can
+    pub(crate) model_discovery: Option<ModelDiscovery>,
+    /// Models found by the last discovery run
+    pub(crate) discovered_models: ModelListCache,
 }
 
 impl CliRunnerBase {
@@ -56,14 +72,27 @@ impl CliRunnerBase {
             .clone()
             .unwrap_or_else(|| default_model.to_owned());
         let available_models = fallback_models.iter().map(|s| (*s).to_owned()).collect();
+        let session_store = config
+            .session_store
+            .clone()
+            .unwrap_or_else(|| Arc::new(InMemorySessionStore::default()));
         Self {
             config,
             default_model: resolved_model,
             available_models,
-            session_ids: Arc::new(Mutex::new(HashMap::new())),
+            session_store,
+            model_discovery: None,
+            discovered_models: ModelListCache::default(),
         }
     }
 
+    /// Let `refresh_models()` ask the CLI for its model list
+    #[must_use]
+    pub const fn with_model_discovery(mut self, discovery: ModelDiscovery) -> Self {
+        self.model_discovery = Some(discovery);
+        self
+    }
+
     /// Get the default model identifier
     pub fn default_model(&self) -> &str {
         &self.default_model
@@ -74,16 +103,231 @@ impl CliRunnerBase {
         &self.available_models
     }
 
-    /// Store a session ID for later resumption
+    /// Last discovered models merged with the fallback list
+    pub fn current_models(&self) -> Vec<String> {
+        self.discovered_models.latest().map_or_else(
+            || self.available_models.clone(),
+            |d| merge_models(&d, &self.available_models),
+        )
+    }
+
+    /// Ask the CLI for its models unless a list fresher than the TTL is cached
+    ///
+    /// Returns the discovered models merged with the fallback list, or just
+    /// the fallback list when the runner has no discovery command.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if the listing command fails to run or exits
+    /// non-zero.
+    pub async fn refresh_models(&self, runner_name: &str) -> Result<Vec<String>, RunnerError> {
+        let Some(discovery) = self.model_discovery else {
+            return Ok(self.available_models.clone());
+        };
+        if let Some(fresh) = self.discovered_models.fresh(self.config.model_cache_ttl) {
+            return Ok(merge_models(&fresh, &self.available_models));
+        }
+
+        let mut cmd = discovery
+            .program
+            .map_or_else(|| Command::new(&self.config.binary_path), Command::new);
+        cmd.args(discovery.args);
+        if let Ok(policy) = build_policy(
+            self.config.working_directory.as_deref(),
+            &self.config.allowed_env_keys,
+        ) {
+            apply_sandbox(&mut cmd, &policy);
+        }
+
+        let output = self
+            .run_with_limits(
+                cmd,
+                None,
+                MODEL_DISCOVERY_TIMEOUT,
+                MODEL_DISCOVERY_MAX_OUTPUT,
+                &CancellationToken::new(),
+            )
+            .await?;
+        self.check_exit_code(&output, runner_name)?;
+
+        let discovered = (discovery.parse)(&String::from_utf8_lossy(&output.stdout));
+        if discovered.is_empty() {
+            debug!("{runner_name} model listing was empty, keeping fallback list");
+            return Ok(self.current_models());
+        }
+        debug!(
+            count = discovered.len(),
+            "{runner_name} discovered models at runtime"
+        );
+        let merged = merge_models(&discovered, &self.available_models);
+        self.discovered_models.store(discovered);
+        Ok(merged)
+    }
+
+    /// Resolve how `runner_type` should deliver the prompt under this config
+    ///
+    /// Stdin is used when the CLI supports it unless the config forces argv.
+    pub fn prompt_delivery(&self, runner_type: CliRunnerType) -> PromptDelivery {
+        match (runner_type.prompt_delivery(), self.config.prompt_delivery) {
+            (PromptDelivery::Stdin, Some(PromptDelivery::Argv)) => PromptDelivery::Argv,
+            (supported, _) => supported,
+        }
+    }
+
+    /// Prompt bytes to write to the CLI's stdin, or `None` when the prompt goes on argv
+    pub fn prompt_stdin<'a>(
+        &self,
+        runner_type: CliRunnerType,
+        prompt: &'a str,
+    ) -> Option<&'a [u8]> {
+        (self.prompt_delivery(runner_type) == PromptDelivery::Stdin).then_some(prompt.as_bytes())
+    }
+
+    /// Backend that spawns this runner's subprocesses
+    fn execution_backend(&self) -> &dyn ExecutionBackend {
+        self.config
+            .execution_backend
+            .as_deref()
+            .unwrap_or(&LocalBackend)
+    }
+
+    /// Build the full prompt, writing attachments where the backend's process can read them
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if an attachment cannot be decoded, read or written.
+    pub fn prepare_prompt(&self, messages: &[ChatMessage]) -> Result<PreparedPrompt, RunnerError> {
+        let root = self
+            .execution_backend()
+            .attachment_root(self.config.working_directory.as_deref());
+        prepare_prompt_in(messages, root.as_deref())
+    }
+
+    /// Build the prompt without system messages, writing attachments where the backend's process can read them
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if an attachment cannot be decoded, read or written.
+    pub fn prepare_user_prompt(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<PreparedPrompt, RunnerError> {
+        let root = self
+            .execution_backend()
+            .attachment_root(self.config.working_directory.as_deref());
+        prepare_user_prompt_in(messages, root.as_deref())
+    }
+
+    /// Run a one-shot CLI command through the configured execution backend
+    ///
+    /// Applies the runner's timeout and [`MAX_OUTPUT_BYTES`]; `stdin` is
+    /// handled as in [`run_cli_command_with_stdin`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if the backend rejects the command, or if it
+    /// fails to spawn, times out, or is cancelled.
+    pub async fn run_command(
+        &self,
+        cmd: Command,
+        stdin: Option<&[u8]>,
+        cancel: &CancellationToken,
+    ) -> Result<CliOutput, RunnerError> {
+        self.run_with_limits(cmd, stdin, self.config.timeout, MAX_OUTPUT_BYTES, cancel)
+            .await
+    }
+
+    /// Run the CLI command serving `request`
+    ///
+    /// Like [`run_command`](Self::run_command), but the request's remaining
+    /// deadline, when set, replaces the runner's timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if the deadline has already passed, or for any
+    /// reason [`run_command`](Self::run_command) fails.
+    pub async fn run_request_command(
+        &self,
+        request: &ChatRequest,
+        cmd: Command,
+        stdin: Option<&[u8]>,
+        cancel: &CancellationToken,
+    ) -> Result<CliOutput, RunnerError> {
+        if request.deadline_passed() {
+            return Err(RunnerError::timeout(
+                "request deadline passed before the command started",
+            ));
+        }
+        let timeout = request.timeout_or(self.config.timeout);
+        self.run_with_limits(cmd, stdin, timeout, MAX_OUTPUT_BYTES, cancel)
+            .await
+    }
+
+    async fn run_with_limits(
+        &self,
+        cmd: Command,
+        stdin: Option<&[u8]>,
+        timeout: Duration,
+        max_output_bytes: usize,
+        cancel: &CancellationToken,
+    ) -> Result<CliOutput, RunnerError> {
+        let (mut cmd, mut cleanup) = self
+            .execution_backend()
+            .prepare(cmd, stdin.is_some())?
+            .into_parts();
+        let output =
+            run_cli_command_with_stdin(&mut cmd, stdin, timeout, max_output_bytes, cancel).await?;
+        cleanup.disarm();
+        Ok(output)
+    }
+
+    /// Spawn a streaming CLI command through the configured execution backend
+    ///
+    /// Stdout and stderr are piped. Hand the returned guard to
+    /// [`GuardedStream::with_cleanup`](crate::stream::GuardedStream::with_cleanup)
+    /// so an abandoned stream also cleans up after the backend.
+    pub(crate) fn spawn_streaming(
+        &self,
+        cmd: Command,
+        stdin: Option<&[u8]>,
+        runner_name: &str,
+    ) -> Result<(Child, CleanupGuard), RunnerError> {
+        let (mut cmd, cleanup) = self
+            .execution_backend()
+            .prepare(cmd, stdin.is_some())?
+            .into_parts();
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+        let child = spawn_in_process_group(&mut cmd, stdin).map_err(|e| {
+            RunnerError::internal(format!("Failed to spawn {runner_name} for streaming: {e}"))
+        })?;
+        Ok((child, cleanup))
+    }
+
+    /// Store a session ID for the given conversation key
+    ///
+    /// Persistence failures are logged and otherwise ignored: losing a
+    /// session only means the next turn starts a fresh CLI session.
     pub async fn set_session(&self, key: &str, session_id: &str) {
-        let mut sessions = self.session_ids.lock().await;
-        sessions.insert(key.to_owned(), session_id.to_owned());
+        let scoped = self.scoped_session_key(key);
+        if let Err(e) = self.session_store.set(&scoped, session_id).await {
+            warn!(key = %scoped, error = %e, "Failed to store CLI session");
+        }
     }
 
-    /// Get a stored session ID
+    /// Get the stored session ID for the given conversation key
     pub async fn get_session(&self, key: &str) -> Option<String> {
-        let sessions = self.session_ids.lock().await;
-        sessions.get(key).cloned()
+        self.session_store.get(&self.scoped_session_key(key)).await
+    }
+
+    /// Prefix a conversation key with the binary name so runners sharing a
+    /// store never resume each other's sessions
+    fn scoped_session_key(&self, key: &str) -> String {
+        let scope = self.config.binary_path.file_name().map_or_else(
+            || self.config.binary_path.to_string_lossy(),
+            |n| n.to_string_lossy(),
+        );
+        format!("{scope}:{key}")
     }
 
     /// Run a `--version` health check against the runner binary.
@@ -97,8 +341,15 @@ impl CliRunnerBase {
         let mut cmd = Command::new(&self.config.binary_path);
         cmd.arg("--version");
 
-        let output =
-            run_cli_command(&mut cmd, HEALTH_CHECK_TIMEOUT, HEALTH_CHECK_MAX_OUTPUT).await?;
+        let output = self
+            .run_with_limits(
+                cmd,
+                None,
+                HEALTH_CHECK_TIMEOUT,
+                HEALTH_CHECK_MAX_OUTPUT,
+                &CancellationToken::new(),
+            )
+            .await?;
 
         if output.exit_code == 0 {
             debug!("{runner_name} health check passed");
@@ -117,10 +368,14 @@ impl CliRunnerBase {
     /// Logs output byte lengths (not content) and constructs a standard error message.
     /// The first line of stderr is included in the error for diagnostics, with
     /// the remainder omitted to avoid leaking prompt content or tool output.
+    /// The full stderr is classified so rate limits, context overflow, quota
+    /// and content-filter failures surface as their typed error kinds.
     ///
     /// # Errors
     ///
-    /// Returns [`RunnerError::external_service`] when `output.exit_code != 0`.
+    /// Returns a [`RunnerError`] classified by
+    /// [`classify_error`](crate::error_classify::classify_error) when
+    /// `output.exit_code != 0`.
     pub fn check_exit_code(
         &self,
         output: &CliOutput,
@@ -138,12 +393,13 @@ impl CliRunnerBase {
         );
         let stderr = String::from_utf8_lossy(&output.stderr);
         let first_line = stderr.lines().next().unwrap_or("(no output)");
-        Err(RunnerError::external_service(
+        Err(classify_error_text(
             runner_name,
-            format!(
+            &format!(
                 "{runner_name} exited with code {}: {first_line}",
                 output.exit_code
             ),
+            &stderr,
         ))
     }
 }
@@ -154,7 +410,7 @@ impl CliRunnerBase {
 /// The implementing struct must have a field named `base` of type [`CliRunnerBase`].
 ///
 /// Generates: `name()`, `display_name()`, `capabilities()`, `default_model()`,
-/// `available_models()`, and `health_check()`.
+/// `available_models()`, `model_catalog()`, `refresh_models()`, and `health_check()`.
 ///
 /// The caller still provides `complete()` and `complete_stream()`.
 #[macro_export]
@@ -180,6 +436,26 @@ macro_rules! delegate_provider_base {
             self.base.available_models()
         }
 
+        fn model_catalog(&self) -> Vec<$crate::model_catalog::ModelInfo> {
+            $crate::model_catalog::default_catalog(&self.base.current_models(), $caps)
+        }
+
+        fn refresh_models<'life0, 'async_trait>(
+            &'life0 self,
+        ) -> ::core::pin::Pin<
+            Box<
+                dyn ::core::future::Future<Output = Result<Vec<String>, $crate::types::RunnerError>>
+                    + ::core::marker::Send
+                    + 'async_trait,
+            >,
+        >
+        where
+            'life0: 'async_trait,
+            Self: 'async_trait,
+        {
+            Box::pin(async move { self.base.refresh_models($runner_name).await })
+        }
+
         fn health_check<'life0, 'async_trait>(
             &'life0 self,
         ) -> ::core::pin::Pin<
@@ -197,3 +473,77 @@ macro_rules! delegate_provider_base {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn sessions_are_scoped_per_conversation_and_binary() {
+        let store: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::default());
+        let claude = CliRunnerBase::new(
+            RunnerConfig::new(PathBuf::from("/usr/bin/claude")).with_session_store(store.clone()),
+            "opus",
+            &[],
+        );
+        let gemini = CliRunnerBase::new(
+            RunnerConfig::new(PathBuf::from("/usr/bin/gemini")).with_session_store(store),
+            "auto",
+            &[],
+        );
+
+        claude.set_session("conv-1", "claude-sid").await;
+        assert_eq!(
+            claude.get_session("conv-1").await.as_deref(),
+            Some("claude-sid")
+        );
+        assert!(claude.get_session("conv-2").await.is_none());
+        assert!(gemini.get_session("conv-1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn refresh_models_merges_discovered_with_fallback() {
+        const LISTING: &str = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/models/kilo_models.txt"
+        );
+        let base = CliRunnerBase::new(
+            RunnerConfig::new(PathBuf::from("cat")),
+            "fallback-model",
+            &["fallback-model"],
+        )
+        .with_model_discovery(ModelDiscovery::subcommand(&[LISTING]));
+        assert_eq!(base.current_models(), vec!["fallback-model"]);
+
+        let models = base.refresh_models("kilo").await.unwrap();
+        assert_eq!(models[0], "anthropic/claude-opus-4-6");
+        assert_eq!(models.last().map(String::as_str), Some("fallback-model"));
+        assert_eq!(base.current_models(), models);
+    }
+
+    #[tokio::test]
+    async fn request_deadline_overrides_runner_timeout() {
+        let base = CliRunnerBase::new(
+            RunnerConfig::new(PathBuf::from("sleep")).with_timeout(Duration::from_mins(1)),
+            "model",
+            &[],
+        );
+        let cancel = CancellationToken::new();
+        let request = ChatRequest::new(Vec::new()).with_timeout(Duration::from_millis(100));
+        let mut cmd = Command::new("sleep");
+        cmd.arg("5");
+        let err = base
+            .run_request_command(&request, cmd, None, &cancel)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, crate::types::ErrorKind::Timeout);
+
+        let expired = ChatRequest::new(Vec::new()).with_deadline(std::time::Instant::now());
+        let err = base
+            .run_request_command(&expired, Command::new("true"), None, &cancel)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, crate::types::ErrorKind::Timeout);
+    }
+}
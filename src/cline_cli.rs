@@ -5,25 +5,25 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::io;
-use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, StreamChunk,
 };
 use async_trait::async_trait;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
 use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::prepare_user_prompt;
+use crate::process::read_stderr_capped;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Default model for Cline CLI (provider-agnostic)
 const DEFAULT_MODEL: &str = "auto";
@@ -50,7 +50,7 @@ impl ClineCliRunner {
         }
     }
 
-    /// Store a task ID for later session resumption
+    /// Store a task ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, task_id: &str) {
         self.base.set_session(key, task_id).await;
     }
@@ -127,6 +127,9 @@ impl ClineCliRunner {
                 finish_reason: Some("stop".to_owned()),
                 warnings: None,
                 tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
             },
             task_id,
         ))
@@ -135,28 +138,44 @@ impl ClineCliRunner {
 
 #[async_trait]
 impl LlmProvider for ClineCliRunner {
-    crate::delegate_provider_base!("cline", "Cline CLI", LlmCapabilities::STREAMING);
+    crate::delegate_provider_base!(
+        "cline",
+        "Cline CLI",
+        LlmCapabilities::STREAMING | LlmCapabilities::FILE_INPUT
+    );
 
-    #[instrument(skip_all, fields(runner = "cline"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "cline"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt);
 
-        if let Some(model) = &request.model {
-            if let Some(tid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(tid) = self.base.get_session(key).await {
                 cmd.args(["--taskId", &tid]);
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(request, cmd, None, cancel)
+            .await?;
         self.base.check_exit_code(&output, "cline")?;
 
         let (response, task_id) = Self::parse_ndjson_response(&output.stdout)?;
 
         if let Some(tid) = task_id {
-            if let Some(model) = &request.model {
-                self.base.set_session(model, &tid).await;
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &tid).await;
             }
         }
 
@@ -165,22 +184,17 @@ impl LlmProvider for ClineCliRunner {
 
     #[instrument(skip_all, fields(runner = "cline"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt);
 
-        if let Some(model) = &request.model {
-            if let Some(tid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(tid) = self.base.get_session(key).await {
                 cmd.args(["--taskId", &tid]);
             }
         }
 
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn cline for streaming: {e}"))
-        })?;
+        let (mut child, cleanup) = self.base.spawn_streaming(cmd, None, "cline")?;
 
         let stdout = child
             .stdout
@@ -200,11 +214,7 @@ impl LlmProvider for ClineCliRunner {
                 .map_err(|e| RunnerError::internal(format!("Error reading cline stream: {e}")))?;
 
             if line.trim().is_empty() {
-                return Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                });
+                return Ok(StreamChunk::default());
             }
 
             let value: serde_json::Value = serde_json::from_str(&line)
@@ -222,8 +232,7 @@ impl LlmProvider for ClineCliRunner {
                             .to_owned();
                         Ok(StreamChunk {
                             delta,
-                            is_final: false,
-                            finish_reason: None,
+                            ..StreamChunk::default()
                         })
                     }
                     "completion_result" => Ok(StreamChunk {
@@ -234,23 +243,20 @@ impl LlmProvider for ClineCliRunner {
                             .to_owned(),
                         is_final: true,
                         finish_reason: Some("stop".to_owned()),
+                        ..StreamChunk::default()
                     }),
-                    _ => Ok(StreamChunk {
-                        delta: String::new(),
-                        is_final: false,
-                        finish_reason: None,
-                    }),
+                    _ => Ok(StreamChunk::default()),
                 }
             } else {
-                Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                })
+                Ok(StreamChunk::default())
             }
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
 
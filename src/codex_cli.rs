@@ -5,26 +5,25 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::io;
-use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
-    TokenUsage,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, ReasoningEffort,
+    RequestMetadata, RunnerError, StreamChunk, TokenUsage,
 };
 use async_trait::async_trait;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
-use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::prepare_user_prompt;
+use crate::config::{CliRunnerType, PromptDelivery, RunnerConfig};
+use crate::process::read_stderr_capped;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Default model for Codex CLI
 const DEFAULT_MODEL: &str = "o4-mini";
@@ -50,15 +49,34 @@ impl CodexCliRunner {
         }
     }
 
-    /// Store a session ID for later resumption
+    /// Store a session ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
 
     /// Build the base command with common arguments
-    fn build_command(&self, prompt: &str) -> Command {
+    ///
+    /// When `resume_thread` is set, the prompt is sent to that thread via
+    /// `codex exec ... resume <thread_id> <prompt>`. A reasoning effort is
+    /// passed as the `model_reasoning_effort` config override.
+    fn build_command(
+        &self,
+        prompt: &str,
+        resume_thread: Option<&str>,
+        reasoning_effort: Option<ReasoningEffort>,
+    ) -> Command {
+        // `-` makes codex read the prompt from stdin
+        let prompt_arg = match self.base.prompt_delivery(CliRunnerType::CodexCli) {
+            PromptDelivery::Stdin => "-",
+            PromptDelivery::Argv => prompt,
+        };
+
         let mut cmd = Command::new(&self.base.config.binary_path);
-        cmd.args(["exec", prompt, "--json", "--full-auto"]);
+        cmd.arg("exec");
+        if resume_thread.is_none() {
+            cmd.arg(prompt_arg);
+        }
+        cmd.args(["--json", "--full-auto"]);
 
         let model = self
             .base
@@ -68,10 +86,18 @@ impl CodexCliRunner {
             .unwrap_or_else(|| self.base.default_model());
         cmd.args(["-m", model]);
 
+        if let Some(effort) = reasoning_effort {
+            cmd.args(["-c", &format!("model_reasoning_effort={effort}")]);
+        }
+
         for arg in &self.base.config.extra_args {
             cmd.arg(arg);
         }
 
+        if let Some(thread_id) = resume_thread {
+            cmd.args(["resume", thread_id, prompt_arg]);
+        }
+
         if let Ok(policy) = build_policy(
             self.base.config.working_directory.as_deref(),
             &self.base.config.allowed_env_keys,
@@ -82,17 +108,29 @@ impl CodexCliRunner {
         cmd
     }
 
-    /// Parse JSONL output from Codex CLI into a `ChatResponse`
+    /// Look up the thread to resume for this request's conversation, if any
+    async fn resume_thread(&self, request: &ChatRequest) -> Option<String> {
+        match &request.session_key {
+            Some(key) => self.base.get_session(key).await,
+            None => None,
+        }
+    }
+
+    /// Parse JSONL output from Codex CLI into a `ChatResponse` and thread ID
     ///
     /// Scans through JSONL lines looking for `item.completed` events with
-    /// `agent_message` type for content, and `turn.completed` for usage stats.
-    fn parse_jsonl_response(raw: &[u8]) -> Result<ChatResponse, RunnerError> {
+    /// `agent_message` type for content and `reasoning` type for reasoning
+    /// text, `turn.completed` for usage stats, and `thread.started` for the
+    /// thread ID used to resume.
+    fn parse_jsonl_response(raw: &[u8]) -> Result<(ChatResponse, Option<String>), RunnerError> {
         let text = str::from_utf8(raw).map_err(|e| {
             RunnerError::internal(format!("Codex CLI output is not valid UTF-8: {e}"))
         })?;
 
         let mut content_parts: Vec<String> = Vec::new();
+        let mut reasoning_parts: Vec<String> = Vec::new();
         let mut usage: Option<TokenUsage> = None;
+        let mut thread_id: Option<String> = None;
 
         for line in text.lines() {
             let trimmed = line.trim();
@@ -106,13 +144,19 @@ impl CodexCliRunner {
 
             let line_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
             match line_type {
+                "thread.started" => {
+                    if let Some(tid) = value.get("thread_id").and_then(|v| v.as_str()) {
+                        thread_id = Some(tid.to_owned());
+                    }
+                }
                 "item.completed" => {
                     if let Some(item) = value.get("item") {
                         let item_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                        if item_type == "agent_message" {
-                            if let Some(text_content) = item.get("text").and_then(|v| v.as_str()) {
-                                content_parts.push(text_content.to_owned());
-                            }
+                        let text_content = item.get("text").and_then(|v| v.as_str());
+                        match (item_type, text_content) {
+                            ("agent_message", Some(t)) => content_parts.push(t.to_owned()),
+                            ("reasoning", Some(t)) => reasoning_parts.push(t.to_owned()),
+                            _ => {}
                         }
                     }
                 }
@@ -126,12 +170,22 @@ impl CodexCliRunner {
                             .get("output_tokens")
                             .and_then(serde_json::Value::as_u64)
                             .unwrap_or(0);
+                        let count = |key: &str| {
+                            #[allow(clippy::cast_possible_truncation)]
+                            u.get(key)
+                                .and_then(serde_json::Value::as_u64)
+                                .map(|n| n as u32)
+                        };
                         #[allow(clippy::cast_possible_truncation)]
                         {
                             usage = Some(TokenUsage {
                                 prompt_tokens: input as u32,
                                 completion_tokens: output as u32,
                                 total_tokens: (input + output) as u32,
+                                reasoning_tokens: count("reasoning_output_tokens"),
+                                cached_prompt_tokens: count("cached_input_tokens"),
+                                cache_creation_tokens: None,
+                                reported_cost: None,
                             });
                         }
                     }
@@ -141,46 +195,91 @@ impl CodexCliRunner {
         }
 
         let content = content_parts.join("");
-
-        Ok(ChatResponse {
-            content,
-            model: "codex".to_owned(),
-            usage,
-            finish_reason: Some("stop".to_owned()),
-            warnings: None,
-            tool_calls: None,
-        })
+        let reasoning = (!reasoning_parts.is_empty()).then(|| reasoning_parts.join("\n"));
+
+        Ok((
+            ChatResponse {
+                content,
+                model: "codex".to_owned(),
+                usage,
+                finish_reason: Some("stop".to_owned()),
+                warnings: None,
+                tool_calls: None,
+                reasoning,
+                choices: None,
+                metadata: RequestMetadata::default(),
+            },
+            thread_id,
+        ))
     }
 }
 
 #[async_trait]
 impl LlmProvider for CodexCliRunner {
-    crate::delegate_provider_base!("codex", "Codex CLI", LlmCapabilities::STREAMING);
+    crate::delegate_provider_base!(
+        "codex",
+        "Codex CLI",
+        LlmCapabilities::STREAMING | LlmCapabilities::FILE_INPUT | LlmCapabilities::REASONING
+    );
 
-    #[instrument(skip_all, fields(runner = "codex"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
-        let prompt = &prepared.prompt;
-        let mut cmd = self.build_command(prompt);
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+    #[instrument(skip_all, fields(runner = "codex"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
+        let prompt = &prepared.prompt;
+        let resume_thread = self.resume_thread(request).await;
+        let cmd = self.build_command(
+            prompt,
+            resume_thread.as_deref(),
+            request.effective_reasoning_effort(),
+        );
+
+        let output = self
+            .base
+            .run_request_command(
+                request,
+                cmd,
+                self.base.prompt_stdin(CliRunnerType::CodexCli, prompt),
+                cancel,
+            )
+            .await?;
         self.base.check_exit_code(&output, "codex")?;
 
-        Self::parse_jsonl_response(&output.stdout)
+        let (response, thread_id) = Self::parse_jsonl_response(&output.stdout)?;
+
+        if let Some(tid) = thread_id {
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &tid).await;
+            }
+        }
+
+        Ok(response)
     }
 
     #[instrument(skip_all, fields(runner = "codex"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
-        let mut cmd = self.build_command(prompt);
-
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn codex for streaming: {e}"))
-        })?;
+        let resume_thread = self.resume_thread(request).await;
+        let cmd = self.build_command(
+            prompt,
+            resume_thread.as_deref(),
+            request.effective_reasoning_effort(),
+        );
+
+        let (mut child, cleanup) = self.base.spawn_streaming(
+            cmd,
+            self.base.prompt_stdin(CliRunnerType::CodexCli, prompt),
+            "codex",
+        )?;
 
         let stdout = child
             .stdout
@@ -200,11 +299,7 @@ impl LlmProvider for CodexCliRunner {
                 .map_err(|e| RunnerError::internal(format!("Error reading codex stream: {e}")))?;
 
             if line.trim().is_empty() {
-                return Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                });
+                return Ok(StreamChunk::default());
             }
 
             let value: serde_json::Value = serde_json::from_str(&line)
@@ -226,31 +321,32 @@ impl LlmProvider for CodexCliRunner {
                                 .and_then(|v| v.as_str())
                                 .unwrap_or("")
                                 .to_owned(),
-                            is_final: false,
-                            finish_reason: None,
+                            ..StreamChunk::default()
                         })
                     } else {
+                        let reasoning = (item_type == "reasoning")
+                            .then(|| value.pointer("/item/text").and_then(|v| v.as_str()))
+                            .flatten();
                         Ok(StreamChunk {
-                            delta: String::new(),
-                            is_final: false,
-                            finish_reason: None,
+                            reasoning_delta: reasoning.map(str::to_owned),
+                            ..StreamChunk::default()
                         })
                     }
                 }
                 "turn.completed" => Ok(StreamChunk {
-                    delta: String::new(),
                     is_final: true,
                     finish_reason: Some("stop".to_owned()),
+                    ..StreamChunk::default()
                 }),
-                _ => Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                }),
+                _ => Ok(StreamChunk::default()),
             }
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
 
@@ -264,14 +360,17 @@ mod tests {
         let jsonl = br#"{"type":"thread.started","thread_id":"t-123"}
 {"type":"turn.started"}
 {"type":"item.completed","item":{"id":"msg-1","type":"agent_message","text":"hello from codex"}}
-{"type":"turn.completed","usage":{"input_tokens":11764,"output_tokens":22}}"#;
+{"type":"turn.completed","usage":{"input_tokens":11764,"cached_input_tokens":11008,"output_tokens":22}}"#;
 
-        let resp = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
+        let (resp, thread_id) = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
         assert_eq!(resp.content, "hello from codex");
+        assert_eq!(thread_id.as_deref(), Some("t-123"));
         let usage = resp.usage.unwrap();
         assert_eq!(usage.prompt_tokens, 11764);
         assert_eq!(usage.completion_tokens, 22);
         assert_eq!(usage.total_tokens, 11786);
+        assert_eq!(usage.cached_prompt_tokens, Some(11008));
+        assert_eq!(usage.reasoning_tokens, None);
     }
 
     #[test]
@@ -280,7 +379,7 @@ mod tests {
 {"type":"turn.started"}
 {"type":"turn.completed","usage":{"input_tokens":100,"output_tokens":0}}"#;
 
-        let resp = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
+        let (resp, _) = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
         assert_eq!(resp.content, "");
         assert!(resp.usage.is_some());
     }
@@ -292,7 +391,7 @@ mod tests {
 {"type":"item.completed","item":{"id":"2","type":"agent_message","text":"part2"}}
 {"type":"turn.completed","usage":{"input_tokens":50,"output_tokens":10}}"#;
 
-        let resp = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
+        let (resp, _) = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
         assert_eq!(resp.content, "part1part2");
     }
 
@@ -303,8 +402,62 @@ mod tests {
 {"type":"item.completed","item":{"id":"2","type":"agent_message","text":"kept"}}
 {"type":"turn.completed","usage":{"input_tokens":10,"output_tokens":5}}"#;
 
-        let resp = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
+        let (resp, _) = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
         assert_eq!(resp.content, "kept");
+        assert!(resp.reasoning.is_none());
+    }
+
+    #[test]
+    fn test_parse_jsonl_separates_reasoning() {
+        let jsonl =
+            br#"{"type":"item.completed","item":{"id":"1","type":"reasoning","text":"**Planning**"}}
+{"type":"item.completed","item":{"id":"2","type":"agent_message","text":"answer"}}
+{"type":"turn.completed","usage":{"input_tokens":10,"output_tokens":5}}"#;
+
+        let (resp, _) = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
+        assert_eq!(resp.content, "answer");
+        assert_eq!(resp.reasoning.as_deref(), Some("**Planning**"));
+    }
+
+    #[test]
+    fn test_build_command_sets_reasoning_effort() {
+        let runner = CodexCliRunner::new(RunnerConfig::new(PathBuf::from("codex")));
+        let cmd = runner.build_command("q", None, Some(ReasoningEffort::High));
+        let args: Vec<_> = cmd.as_std().get_args().collect();
+        let pos = args.iter().position(|a| *a == "-c").expect("-c flag");
+        assert_eq!(args[pos + 1], "model_reasoning_effort=high");
+    }
+
+    #[test]
+    fn test_parse_jsonl_without_thread_started() {
+        let jsonl = br#"{"type":"turn.completed","usage":{"input_tokens":1,"output_tokens":1}}"#;
+        let (_, thread_id) = CodexCliRunner::parse_jsonl_response(jsonl).unwrap();
+        assert!(thread_id.is_none());
+    }
+
+    #[test]
+    fn test_build_command_resume_places_thread_before_prompt() {
+        let config =
+            RunnerConfig::new(PathBuf::from("codex")).with_prompt_delivery(PromptDelivery::Argv);
+        let runner = CodexCliRunner::new(config);
+        let cmd = runner.build_command("next question", Some("t-123"), None);
+        let args: Vec<_> = cmd.as_std().get_args().collect();
+        let tail: Vec<_> = args.iter().rev().take(3).rev().copied().collect();
+        assert_eq!(tail, ["resume", "t-123", "next question"]);
+        assert_eq!(args[0], "exec");
+    }
+
+    #[test]
+    fn test_build_command_reads_prompt_from_stdin_by_default() {
+        let runner = CodexCliRunner::new(RunnerConfig::new(PathBuf::from("codex")));
+        let cmd = runner.build_command("secret question", None, None);
+        let args: Vec<_> = cmd.as_std().get_args().collect();
+        assert_eq!(args[..2], ["exec", "-"]);
+        assert!(!args.iter().any(|a| *a == "secret question"));
+
+        let cmd = runner.build_command("secret question", Some("t-123"), None);
+        let args: Vec<_> = cmd.as_std().get_args().collect();
+        assert_eq!(args.last().copied(), Some("-".as_ref()));
     }
 
     #[test]
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 use tracing::{debug, warn};
 
-use crate::config::CliRunnerType;
+use crate::config::{CliRunnerType, PromptDelivery};
 use crate::types::RunnerError;
 
 /// Minimum supported version per CLI runner
@@ -46,6 +46,8 @@ pub struct CliCapabilities {
     pub system_prompt: bool,
     /// Whether session resume is supported
     pub session_resume: bool,
+    /// How the runner passes the prompt to this CLI
+    pub prompt_delivery: PromptDelivery,
     /// Whether the binary meets the minimum version requirement
     pub meets_minimum_version: bool,
 }
@@ -97,6 +99,7 @@ pub async fn detect_capabilities(
         streaming,
         system_prompt,
         session_resume,
+        prompt_delivery: runner_type.prompt_delivery(),
         meets_minimum_version: meets_minimum,
     })
 }
@@ -121,6 +124,8 @@ async fn detect_version(
         | CliRunnerType::KiloCli => "--version",
         #[cfg(feature = "copilot-headless")]
         CliRunnerType::CopilotHeadless => "--version",
+        #[cfg(feature = "acp")]
+        CliRunnerType::Acp => "--version",
     };
 
     let output = Command::new(binary_path)
@@ -193,6 +198,9 @@ const fn minimum_version(runner_type: CliRunnerType) -> (u32, u32, u32) {
         CliRunnerType::KiloCli => parse_const_version(KILO_CLI_MIN_VERSION),
         #[cfg(feature = "copilot-headless")]
         CliRunnerType::CopilotHeadless => parse_const_version(COPILOT_MIN_VERSION),
+        // Generic ACP agents have no known versioning scheme
+        #[cfg(feature = "acp")]
+        CliRunnerType::Acp => (0, 0, 0),
     }
 }
 
@@ -260,6 +268,9 @@ const fn capabilities_for_runner(runner_type: CliRunnerType) -> (bool, bool, boo
         // Copilot Headless: ACP protocol, not a CLI runner — capabilities managed by LlmProvider
         #[cfg(feature = "copilot-headless")]
         CliRunnerType::CopilotHeadless => (true, true, true, false),
+        // Generic ACP agent: same protocol, system prompt sent in session/new and the prompt
+        #[cfg(feature = "acp")]
+        CliRunnerType::Acp => (true, true, true, false),
     }
 }
 
@@ -364,6 +375,7 @@ mod tests {
             streaming: true,
             system_prompt: true,
             session_resume: true,
+            prompt_delivery: PromptDelivery::Stdin,
             meets_minimum_version: true,
         };
         assert!(caps.is_compatible());
@@ -424,6 +436,7 @@ mod tests {
             streaming: true,
             system_prompt: true,
             session_resume: true,
+            prompt_delivery: PromptDelivery::Stdin,
             meets_minimum_version: false,
         };
         assert!(!caps.is_compatible());
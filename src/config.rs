@@ -6,13 +6,20 @@
 
 use std::fmt;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use crate::execution::ExecutionBackend;
+use crate::session::SessionStore;
+
 /// Default timeout for CLI command execution (120 seconds)
 const DEFAULT_TIMEOUT_SECS: u64 = 120;
 
+/// Default lifetime of a runtime-discovered model list (10 minutes)
+pub const DEFAULT_MODEL_CACHE_TTL: Duration = Duration::from_mins(10);
+
 /// Supported CLI runner types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CliRunnerType {
@@ -43,6 +50,9 @@ pub enum CliRunnerType {
     /// GitHub Copilot Headless via ACP protocol (`copilot --acp`)
     #[cfg(feature = "copilot-headless")]
     CopilotHeadless,
+    /// Any Agent Client Protocol agent (`ACP_AGENT_BINARY`, e.g. `gemini --experimental-acp`)
+    #[cfg(feature = "acp")]
+    Acp,
 }
 
 impl CliRunnerType {
@@ -64,6 +74,8 @@ impl CliRunnerType {
             Self::KiloCli => "kilo",
             #[cfg(feature = "copilot-headless")]
             Self::CopilotHeadless => "copilot",
+            #[cfg(feature = "acp")]
+            Self::Acp => "acp-agent",
         }
     }
 
@@ -85,10 +97,51 @@ impl CliRunnerType {
             Self::KiloCli => "KILO_CLI_BINARY",
             #[cfg(feature = "copilot-headless")]
             Self::CopilotHeadless => "COPILOT_CLI_PATH",
+            #[cfg(feature = "acp")]
+            Self::Acp => "ACP_AGENT_BINARY",
+        }
+    }
+
+    /// How this runner hands the prompt to its CLI
+    ///
+    /// Runners whose CLI reads the prompt from stdin use [`PromptDelivery::Stdin`]
+    /// so prompts stay out of `ps` output and are not bounded by `ARG_MAX`.
+    #[must_use]
+    pub const fn prompt_delivery(&self) -> PromptDelivery {
+        match self {
+            // claude -p, gemini and opencode/kilo run read piped stdin;
+            // codex exec and goose run take `-` for stdin
+            Self::ClaudeCode
+            | Self::GeminiCli
+            | Self::CodexCli
+            | Self::GooseCli
+            | Self::OpenCode
+            | Self::KiloCli => PromptDelivery::Stdin,
+            Self::CursorAgent
+            | Self::Copilot
+            | Self::ClineCli
+            | Self::ContinueCli
+            | Self::WarpCli
+            | Self::KiroCli => PromptDelivery::Argv,
+            // ACP sends prompts as JSON-RPC over the agent's stdin
+            #[cfg(feature = "copilot-headless")]
+            Self::CopilotHeadless => PromptDelivery::Stdin,
+            #[cfg(feature = "acp")]
+            Self::Acp => PromptDelivery::Stdin,
         }
     }
 }
 
+/// How a CLI runner passes the prompt to its subprocess
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptDelivery {
+    /// Prompt is a command-line argument (visible in `ps`, limited by `ARG_MAX`)
+    Argv,
+    /// Prompt is written to the subprocess's stdin
+    Stdin,
+}
+
 impl fmt::Display for CliRunnerType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -106,6 +159,8 @@ impl fmt::Display for CliRunnerType {
             Self::KiloCli => write!(f, "kilo_cli"),
             #[cfg(feature = "copilot-headless")]
             Self::CopilotHeadless => write!(f, "copilot_headless"),
+            #[cfg(feature = "acp")]
+            Self::Acp => write!(f, "acp"),
         }
     }
 }
@@ -117,14 +172,26 @@ pub struct RunnerConfig {
     pub binary_path: PathBuf,
     /// Model override (provider-specific format)
     pub model: Option<String>,
-    /// Maximum time to wait for a CLI command to complete
+    /// Maximum time to wait for a CLI command (or a whole stream) to complete
     pub timeout: Duration,
+    /// Maximum silence between streamed chunks before the stream is aborted
+    pub idle_timeout: Option<Duration>,
+    /// Force prompt delivery over argv for CLIs whose installed version
+    /// cannot read the prompt from stdin (`None` uses the runner's default)
+    pub prompt_delivery: Option<PromptDelivery>,
     /// Additional CLI arguments appended to every invocation
     pub extra_args: Vec<String>,
     /// Environment variable keys passed through to the subprocess
     pub allowed_env_keys: Vec<String>,
     /// Working directory for the subprocess
     pub working_directory: Option<PathBuf>,
+    /// Conversation session store shared with other runners (defaults to a
+    /// private in-memory store per runner)
+    pub session_store: Option<Arc<dyn SessionStore>>,
+    /// Where the CLI subprocess runs (`None` runs it directly on the host)
+    pub execution_backend: Option<Arc<dyn ExecutionBackend>>,
+    /// How long a model list discovered by `refresh_models()` is reused
+    pub model_cache_ttl: Duration,
 }
 
 impl RunnerConfig {
@@ -135,9 +202,14 @@ impl RunnerConfig {
             binary_path,
             model: None,
             timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            idle_timeout: None,
+            prompt_delivery: None,
             extra_args: Vec::new(),
             allowed_env_keys: default_allowed_env_keys(),
             working_directory: None,
+            session_store: None,
+            execution_backend: None,
+            model_cache_ttl: DEFAULT_MODEL_CACHE_TTL,
         }
     }
 
@@ -155,6 +227,24 @@ impl RunnerConfig {
         self
     }
 
+    /// Set the maximum silence between streamed chunks
+    #[must_use]
+    pub const fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Override how the prompt reaches the CLI
+    ///
+    /// Only [`PromptDelivery::Argv`] changes behaviour: it is the fallback for
+    /// CLI versions that predate stdin support. Runners whose CLI only accepts
+    /// the prompt on argv ignore a [`PromptDelivery::Stdin`] override.
+    #[must_use]
+    pub const fn with_prompt_delivery(mut self, delivery: PromptDelivery) -> Self {
+        self.prompt_delivery = Some(delivery);
+        self
+    }
+
     /// Set extra CLI arguments
     #[must_use]
     pub fn with_extra_args(mut self, args: Vec<String>) -> Self {
@@ -175,6 +265,28 @@ impl RunnerConfig {
         self.working_directory = Some(dir);
         self
     }
+
+    /// Set the store used to resume CLI sessions across conversation turns
+    #[must_use]
+    pub fn with_session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.session_store = Some(store);
+        self
+    }
+
+    /// Run the CLI through an execution backend, e.g. a
+    /// [`ContainerExecutor`](crate::container::ContainerExecutor)
+    #[must_use]
+    pub fn with_execution_backend(mut self, backend: Arc<dyn ExecutionBackend>) -> Self {
+        self.execution_backend = Some(backend);
+        self
+    }
+
+    /// Set how long a discovered model list is cached before the CLI is asked again
+    #[must_use]
+    pub const fn with_model_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.model_cache_ttl = ttl;
+        self
+    }
 }
 
 /// Default set of environment variable keys safe to pass through to subprocesses
@@ -346,4 +458,32 @@ mod tests {
         assert_eq!(format!("{}", CliRunnerType::KiroCli), "kiro_cli");
         assert_eq!(format!("{}", CliRunnerType::KiloCli), "kilo_cli");
     }
+
+    #[test]
+    fn test_prompt_delivery_per_runner() {
+        assert_eq!(
+            CliRunnerType::ClaudeCode.prompt_delivery(),
+            PromptDelivery::Stdin
+        );
+        assert_eq!(
+            CliRunnerType::GooseCli.prompt_delivery(),
+            PromptDelivery::Stdin
+        );
+        assert_eq!(
+            CliRunnerType::Copilot.prompt_delivery(),
+            PromptDelivery::Argv
+        );
+        assert_eq!(
+            CliRunnerType::WarpCli.prompt_delivery(),
+            PromptDelivery::Argv
+        );
+    }
+
+    #[test]
+    fn test_prompt_delivery_override_defaults_to_none() {
+        let config = RunnerConfig::new(PathBuf::from("claude"));
+        assert!(config.prompt_delivery.is_none());
+        let config = config.with_prompt_delivery(PromptDelivery::Argv);
+        assert_eq!(config.prompt_delivery, Some(PromptDelivery::Argv));
+    }
 }
@@ -20,15 +20,41 @@
 //! type = "claude_code"
 //! model = "opus"
 //! timeout = 180
+//! idle_timeout = 60
 //!
 //! [[providers]]
 //! type = "copilot"
+//! # Bound how hard the provider is driven (all fields optional)
+//! rate_limit = { max_concurrent = 4, requests_per_minute = 30, tokens_per_minute = 200000, max_queued = 16 }
+//!
+//! # Correct or extend the provider's model catalog
+//! [[providers.models]]
+//! id = "claude-sonnet-4.6"
+//! aliases = ["smart"]
+//! context_window = 1000000
+//! capabilities = ["streaming", "system_messages", "vision"]
+//! pricing = { prompt_price_per_1k = 0.003, completion_price_per_1k = 0.015 }
+//!
+//! # Run the CLI in an ephemeral container instead of on the host
+//! [[providers]]
+//! type = "codex_cli"
+//! container = { image = "ghcr.io/acme/codex:latest", network = "bridge" }
+//!
+//! # Any ACP agent (requires the `acp` feature)
+//! [[providers]]
+//! type = "acp"
+//! binary_path = "gemini"
+//! extra_args = ["--experimental-acp"]
+//! env_keys = ["HOME", "PATH", "GEMINI_API_KEY"]
 //!
 //! [fallback]
 //! providers = ["claude_code", "copilot"]
 //! retry_per_provider = 2
 //! base_delay_ms = 500
 //! max_delay_ms = 5000
+//! # Skip a provider for 30s after 5 consecutive failures
+//! circuit_failure_threshold = 5
+//! circuit_cooldown_secs = 30
 //!
 //! [aliases]
 //! fast = "gemini_cli"
@@ -37,14 +63,19 @@
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde::Deserialize;
 
-use crate::config::RunnerConfig;
+use crate::circuit_breaker::CircuitBreakerConfig;
+use crate::config::{CliRunnerType, RunnerConfig};
+use crate::container::{ContainerConfig, ContainerExecutor, NetworkMode};
 use crate::discovery::resolve_binary;
 use crate::factory::parse_runner_type;
 use crate::fallback::{FallbackProvider, RetryConfig};
+use crate::model_catalog::ModelOverride;
+use crate::rate_limit::RateLimitConfig;
 use crate::types::{LlmProvider, RunnerError};
 
 /// Top-level configuration loaded from an embacle TOML file
@@ -82,6 +113,8 @@ pub struct ProviderConfig {
     pub model: Option<String>,
     /// Timeout override in seconds
     pub timeout: Option<u64>,
+    /// Maximum silence between streamed chunks in seconds
+    pub idle_timeout: Option<u64>,
     /// Explicit binary path
     pub binary_path: Option<PathBuf>,
     /// Extra CLI arguments
@@ -90,6 +123,38 @@ pub struct ProviderConfig {
     /// Environment variable keys to pass through
     #[serde(default)]
     pub env_keys: Vec<String>,
+    /// Run the CLI inside an ephemeral container
+    pub container: Option<ProviderContainerConfig>,
+    /// Overrides applied to the provider's model catalog
+    #[serde(default)]
+    pub models: Vec<ModelOverride>,
+    /// Concurrency and rate limits applied to the provider
+    pub rate_limit: Option<RateLimitConfig>,
+}
+
+/// Container settings for a provider whose CLI runs under Docker
+#[derive(Debug, Deserialize)]
+pub struct ProviderContainerConfig {
+    /// Image that provides the CLI binary
+    pub image: String,
+    /// Network mode: `none` (default), `host`, or a named network
+    pub network: Option<String>,
+    /// Memory limit (e.g. `"2g"`)
+    pub memory: Option<String>,
+    /// Maximum number of processes inside the container
+    pub pids_limit: Option<u32>,
+}
+
+impl ProviderContainerConfig {
+    fn to_container_config(&self) -> ContainerConfig {
+        let mut config = ContainerConfig::new(&self.image);
+        if let Some(ref network) = self.network {
+            config.network_mode = NetworkMode::from(network.as_str());
+        }
+        config.memory_limit.clone_from(&self.memory);
+        config.pids_limit = self.pids_limit;
+        config
+    }
 }
 
 /// Configuration for a fallback provider chain
@@ -103,6 +168,10 @@ pub struct FallbackConfig {
     pub base_delay_ms: Option<u64>,
     /// Maximum delay between retries in milliseconds
     pub max_delay_ms: Option<u64>,
+    /// Consecutive failures that open a provider's circuit (enables circuit breaking)
+    pub circuit_failure_threshold: Option<u32>,
+    /// Seconds an open circuit skips its provider (enables circuit breaking)
+    pub circuit_cooldown_secs: Option<u64>,
 }
 
 /// Load configuration from the default search path.
@@ -175,6 +244,10 @@ pub fn build_runner_config(
         config.timeout = Duration::from_secs(timeout);
     }
 
+    if let Some(idle_timeout) = provider.idle_timeout {
+        config.idle_timeout = Some(Duration::from_secs(idle_timeout));
+    }
+
     if !provider.extra_args.is_empty() {
         config.extra_args.clone_from(&provider.extra_args);
     }
@@ -183,6 +256,12 @@ pub fn build_runner_config(
         config.allowed_env_keys.clone_from(&provider.env_keys);
     }
 
+    if let Some(ref container) = provider.container {
+        config.execution_backend = Some(Arc::new(ContainerExecutor::new(
+            container.to_container_config(),
+        )));
+    }
+
     Ok(config)
 }
 
@@ -229,7 +308,62 @@ pub async fn build_fallback_from_config(
         max_delay: Duration::from_millis(fallback_config.max_delay_ms.unwrap_or(5000)),
     };
 
-    FallbackProvider::with_retry(providers, retry).map(Some)
+    let fallback = FallbackProvider::with_retry(providers, retry)?;
+    Ok(Some(match circuit_breaker_config(fallback_config) {
+        Some(breaker) => fallback.with_circuit_breaker(breaker),
+        None => fallback,
+    }))
+}
+
+/// Circuit-breaker settings from `[fallback]`, if any are configured
+fn circuit_breaker_config(fallback_config: &FallbackConfig) -> Option<CircuitBreakerConfig> {
+    if fallback_config.circuit_failure_threshold.is_none()
+        && fallback_config.circuit_cooldown_secs.is_none()
+    {
+        return None;
+    }
+    let mut config = CircuitBreakerConfig::default();
+    if let Some(failures) = fallback_config.circuit_failure_threshold {
+        config = config.with_failure_threshold(failures);
+    }
+    if let Some(secs) = fallback_config.circuit_cooldown_secs {
+        config = config.with_cooldown(Duration::from_secs(secs));
+    }
+    Some(config)
+}
+
+/// Collect each provider's `[[providers.models]]` catalog overrides, keyed by runner type.
+pub fn model_overrides(
+    config: &EmbacleConfig,
+) -> Result<HashMap<CliRunnerType, Vec<ModelOverride>>, RunnerError> {
+    let mut overrides: HashMap<CliRunnerType, Vec<ModelOverride>> = HashMap::new();
+    for provider in config.providers.iter().filter(|p| !p.models.is_empty()) {
+        let runner_type = parse_runner_type(&provider.provider_type).ok_or_else(|| {
+            RunnerError::config(format!("unknown provider type: {}", provider.provider_type))
+        })?;
+        overrides
+            .entry(runner_type)
+            .or_default()
+            .extend(provider.models.iter().cloned());
+    }
+    Ok(overrides)
+}
+
+/// Collect each provider's `rate_limit` table, keyed by runner type.
+pub fn rate_limits(
+    config: &EmbacleConfig,
+) -> Result<HashMap<CliRunnerType, RateLimitConfig>, RunnerError> {
+    let mut limits = HashMap::new();
+    for provider in &config.providers {
+        let Some(limit) = &provider.rate_limit else {
+            continue;
+        };
+        let runner_type = parse_runner_type(&provider.provider_type).ok_or_else(|| {
+            RunnerError::config(format!("unknown provider type: {}", provider.provider_type))
+        })?;
+        limits.insert(runner_type, limit.clone());
+    }
+    Ok(limits)
 }
 
 /// Resolve a short alias to a provider type name, if one exists.
@@ -308,13 +442,118 @@ smart = "claude_code"
             provider_type: "claude_code".to_owned(),
             model: Some("override-model".to_owned()),
             timeout: None,
+            idle_timeout: Some(30),
             binary_path: Some(PathBuf::from("/usr/bin/claude")),
             extra_args: vec![],
             env_keys: vec![],
+            container: None,
+            models: vec![],
+            rate_limit: None,
         };
         let config = build_runner_config(&provider, &defaults).unwrap();
         assert_eq!(config.model.as_deref(), Some("override-model"));
         assert_eq!(config.timeout, Duration::from_secs(60));
+        assert_eq!(config.idle_timeout, Some(Duration::from_secs(30)));
+        assert!(config.execution_backend.is_none());
+    }
+
+    #[test]
+    fn container_section_selects_container_backend() {
+        let toml_str = r#"
+[[providers]]
+type = "codex_cli"
+binary_path = "/usr/bin/codex"
+container = { image = "ghcr.io/acme/codex:latest", network = "bridge", pids_limit = 64 }
+"#;
+        let config: EmbacleConfig = toml::from_str(toml_str).unwrap();
+        let container = config.providers[0].container.as_ref().unwrap();
+        let container_config = container.to_container_config();
+        assert_eq!(container_config.image, "ghcr.io/acme/codex:latest");
+        assert_eq!(
+            container_config.network_mode,
+            NetworkMode::Custom("bridge".to_owned())
+        );
+        assert_eq!(container_config.pids_limit, Some(64));
+
+        let runner_config = build_runner_config(&config.providers[0], &config.defaults).unwrap();
+        let backend = runner_config.execution_backend.unwrap();
+        assert_eq!(backend.name(), "container");
+    }
+
+    #[test]
+    fn model_overrides_keyed_by_runner_type() {
+        let toml_str = r#"
+[[providers]]
+type = "claude_code"
+
+[[providers.models]]
+id = "opus"
+aliases = ["big"]
+context_window = 1000000
+capabilities = ["streaming", "vision"]
+pricing = { prompt_price_per_1k = 0.01, completion_price_per_1k = 0.05 }
+
+[[providers]]
+type = "copilot"
+"#;
+        let config: EmbacleConfig = toml::from_str(toml_str).unwrap();
+        let overrides = model_overrides(&config).unwrap();
+        assert_eq!(overrides.len(), 1);
+        let opus = &overrides[&CliRunnerType::ClaudeCode][0];
+        assert_eq!(opus.id, "opus");
+        assert_eq!(opus.aliases, vec!["big"]);
+        assert_eq!(opus.context_window, Some(1_000_000));
+        assert!(opus.capabilities.unwrap().supports_vision());
+        assert!(opus.pricing.is_some());
+
+        let bad = r#"
+[[providers]]
+type = "claude_code"
+[[providers.models]]
+id = "opus"
+capabilities = ["telepathy"]
+"#;
+        assert!(toml::from_str::<EmbacleConfig>(bad).is_err());
+    }
+
+    #[test]
+    fn rate_limits_keyed_by_runner_type() {
+        let toml_str = r#"
+[[providers]]
+type = "claude_code"
+rate_limit = { max_concurrent = 2, requests_per_minute = 30 }
+
+[[providers]]
+type = "copilot"
+[providers.rate_limit]
+tokens_per_minute = 100000
+max_queued = 4
+
+[[providers]]
+type = "gemini_cli"
+"#;
+        let config: EmbacleConfig = toml::from_str(toml_str).unwrap();
+        let limits = rate_limits(&config).unwrap();
+        assert_eq!(limits.len(), 2);
+        assert_eq!(
+            limits[&CliRunnerType::ClaudeCode],
+            RateLimitConfig::default()
+                .with_max_concurrent(2)
+                .with_requests_per_minute(30)
+        );
+        assert_eq!(
+            limits[&CliRunnerType::Copilot],
+            RateLimitConfig::default()
+                .with_tokens_per_minute(100_000)
+                .with_max_queued(4)
+        );
+
+        let bad = r#"
+[[providers]]
+type = "claude_code"
+rate_limit = { max_parallel = 2 }
+"#;
+        assert!(toml::from_str::<EmbacleConfig>(bad).is_err());
     }
 
     #[test]
@@ -355,9 +594,13 @@ smart = "claude_code"
             provider_type: "nonexistent_provider".to_owned(),
             model: None,
             timeout: None,
+            idle_timeout: None,
             binary_path: None,
             extra_args: vec![],
             env_keys: vec![],
+            container: None,
+            models: vec![],
+            rate_limit: None,
         };
         let result = build_runner_config(&provider, &defaults);
         assert!(result.is_err());
@@ -375,15 +618,44 @@ smart = "claude_code"
             provider_type: "claude_code".to_owned(),
             model: None,
             timeout: Some(90),
+            idle_timeout: None,
             binary_path: Some(PathBuf::from("/custom/claude")),
             extra_args: vec![],
             env_keys: vec![],
+            container: None,
+            models: vec![],
+            rate_limit: None,
         };
         let config = build_runner_config(&provider, &defaults).unwrap();
         assert_eq!(config.timeout, Duration::from_secs(90));
         assert_eq!(config.binary_path, PathBuf::from("/custom/claude"));
     }
 
+    #[cfg(feature = "acp")]
+    #[tokio::test]
+    async fn acp_provider_runs_configured_agent_command() {
+        let toml_str = format!(
+            r#"
+[[providers]]
+type = "acp"
+binary_path = "{}/tests/fixtures/fake_acp_agent.sh"
+extra_args = ["--experimental-acp"]
+
+[fallback]
+providers = ["acp"]
+"#,
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let config: EmbacleConfig = toml::from_str(&toml_str).unwrap();
+        let runner_config = build_runner_config(&config.providers[0], &config.defaults).unwrap();
+        assert_eq!(runner_config.extra_args, vec!["--experimental-acp"]);
+
+        let fallback = build_fallback_from_config(&config).await.unwrap().unwrap();
+        let request = crate::types::ChatRequest::new(vec![crate::types::ChatMessage::user("ENV")]);
+        let response = fallback.complete(&request).await.unwrap();
+        assert!(response.content.contains("args=--experimental-acp"));
+    }
+
     #[test]
     fn fallback_config_maps_to_retry_config() {
         let fb = FallbackConfig {
@@ -391,6 +663,8 @@ smart = "claude_code"
             retry_per_provider: Some(3),
             base_delay_ms: Some(200),
             max_delay_ms: Some(2000),
+            circuit_failure_threshold: None,
+            circuit_cooldown_secs: None,
         };
         let retry = RetryConfig {
             max_retries: fb.retry_per_provider.unwrap_or(0),
@@ -400,5 +674,17 @@ smart = "claude_code"
         assert_eq!(retry.max_retries, 3);
         assert_eq!(retry.base_delay, Duration::from_millis(200));
         assert_eq!(retry.max_delay, Duration::from_millis(2000));
+        assert!(circuit_breaker_config(&fb).is_none());
+
+        let fb = FallbackConfig {
+            circuit_cooldown_secs: Some(10),
+            ..fb
+        };
+        let breaker = circuit_breaker_config(&fb).unwrap();
+        assert_eq!(breaker.cooldown, Duration::from_secs(10));
+        assert_eq!(
+            breaker.failure_threshold,
+            CircuitBreakerConfig::default().failure_threshold
+        );
     }
 }
@@ -1,12 +1,14 @@
 // ABOUTME: Container-based execution backend for CLI runners
-// ABOUTME: Spawns CLI commands in ephemeral containers with security isolation
+// ABOUTME: Runs CLI commands in ephemeral Docker containers with security isolation and cleanup
 //
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
 
 use std::env;
+use std::ffi::OsStr;
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use crate::types::RunnerError;
@@ -14,6 +16,7 @@ use tokio::fs;
 use tokio::process::Command;
 use tracing::{debug, warn};
 
+use crate::execution::{ExecutionBackend, PreparedCommand};
 use crate::process::{run_cli_command, CliOutput};
 
 /// Environment variable for the container image
@@ -25,6 +28,13 @@ const ENV_CONTAINER_PIDS_LIMIT: &str = "CLI_LLM_CONTAINER_PIDS_LIMIT";
 /// Environment variable for the network mode
 const ENV_CONTAINER_NETWORK: &str = "CLI_LLM_CONTAINER_NETWORK";
 
+/// Host variables never forwarded into a container: the image owns `PATH`,
+/// and `HOME` points at the writable `/scratch` tmpfs instead
+const CONTAINER_OWNED_ENV: [&str; 2] = ["HOME", "PATH"];
+
+/// Sequence number making container names unique within this process
+static CONTAINER_SEQ: AtomicU64 = AtomicU64::new(0);
+
 /// Network isolation mode for the container
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NetworkMode {
@@ -36,6 +46,17 @@ pub enum NetworkMode {
     Custom(String),
 }
 
+impl From<&str> for NetworkMode {
+    /// Parse `none`, `host`, or a custom network name (case-insensitive)
+    fn from(name: &str) -> Self {
+        match name.trim().to_lowercase().as_str() {
+            "none" => Self::None,
+            "host" => Self::Host,
+            other => Self::Custom(other.to_owned()),
+        }
+    }
+}
+
 impl fmt::Display for NetworkMode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -75,6 +96,19 @@ pub struct ContainerConfig {
 }
 
 impl ContainerConfig {
+    /// Create a configuration for `image` with no limits, mounts, or network access
+    #[must_use]
+    pub fn new(image: impl Into<String>) -> Self {
+        Self {
+            image: image.into(),
+            memory_limit: None,
+            pids_limit: None,
+            network_mode: NetworkMode::None,
+            extra_mounts: Vec::new(),
+            env_vars: Vec::new(),
+        }
+    }
+
     /// Build a container configuration from environment variables
     ///
     /// Reads:
@@ -108,14 +142,8 @@ impl ContainerConfig {
             Err(_) => Option::None,
         };
 
-        let network_mode =
-            env::var(ENV_CONTAINER_NETWORK).map_or(NetworkMode::None, |val| {
-                match val.trim().to_lowercase().as_str() {
-                    "none" => NetworkMode::None,
-                    "host" => NetworkMode::Host,
-                    other => NetworkMode::Custom(other.to_owned()),
-                }
-            });
+        let network_mode = env::var(ENV_CONTAINER_NETWORK)
+            .map_or(NetworkMode::None, |val| NetworkMode::from(val.as_str()));
 
         Ok(Self {
             image,
@@ -133,18 +161,41 @@ impl ContainerConfig {
 /// Each invocation creates a fresh `docker run --rm` container with
 /// a read-only root filesystem, all capabilities dropped, and
 /// `no-new-privileges` enforced. A writable scratch directory is
-/// bind-mounted at `/scratch` for temporary files.
+/// mounted at `/scratch` for temporary files.
+///
+/// As an [`ExecutionBackend`], it runs every command of a CLI runner in its
+/// own container: the runner's working directory is bind-mounted at the same
+/// path, its sandboxed environment is forwarded with `docker run -e`, and a
+/// run that is cancelled or times out is force-removed with `docker rm -f`.
 #[derive(Debug, Clone)]
 pub struct ContainerExecutor {
     /// Container configuration controlling image, limits, and mounts
     config: ContainerConfig,
+    /// Docker CLI to invoke (defaults to `docker` on `PATH`)
+    docker_binary: Option<PathBuf>,
 }
 
 impl ContainerExecutor {
     /// Create a new container executor with the given configuration
     #[must_use]
     pub const fn new(config: ContainerConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            docker_binary: None,
+        }
+    }
+
+    /// Use a specific Docker-compatible CLI (e.g. `podman`) instead of `docker`
+    #[must_use]
+    pub fn with_docker_binary(mut self, path: PathBuf) -> Self {
+        self.docker_binary = Some(path);
+        self
+    }
+
+    fn docker_binary(&self) -> &OsStr {
+        self.docker_binary
+            .as_deref()
+            .map_or_else(|| OsStr::new("docker"), Path::as_os_str)
     }
 
     /// Execute a CLI command inside an ephemeral container
@@ -205,7 +256,7 @@ impl ContainerExecutor {
             "Launching container"
         );
 
-        let mut cmd = Command::new("docker");
+        let mut cmd = Command::new(self.docker_binary());
         cmd.args(&docker_args);
 
         let result = run_cli_command(&mut cmd, timeout, max_output_bytes).await;
@@ -219,14 +270,75 @@ impl ContainerExecutor {
     }
 }
 
-/// Build the full `docker run` argument list
-fn build_docker_args(
-    config: &ContainerConfig,
-    scratch_path: &Path,
-    binary_name: &str,
-    args: &[&str],
-    stdin_file_container_path: Option<&str>,
-) -> Vec<String> {
+impl ExecutionBackend for ContainerExecutor {
+    fn name(&self) -> &'static str {
+        "container"
+    }
+
+    /// The working directory is bind-mounted at the same path, so files written there are visible
+    fn attachment_root(&self, working_directory: Option<&Path>) -> Option<PathBuf> {
+        working_directory.map(Path::to_path_buf)
+    }
+
+    fn prepare(&self, cmd: Command, stdin: bool) -> Result<PreparedCommand, RunnerError> {
+        let local = cmd.as_std();
+        let binary = Path::new(local.get_program())
+            .file_name()
+            .ok_or_else(|| RunnerError::config("CLI binary path has no file name"))?;
+        let name = format!(
+            "embacle-{}-{}",
+            std::process::id(),
+            CONTAINER_SEQ.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut docker = Command::new(self.docker_binary());
+        docker.args(hardening_args(&self.config));
+        docker.args([
+            "--init",
+            "--tmpfs=/scratch",
+            "-e",
+            "HOME=/scratch",
+            &format!("--name={name}"),
+        ]);
+        if stdin {
+            docker.arg("-i");
+        }
+        if let Some(dir) = local.get_current_dir() {
+            docker.arg("-v");
+            docker.arg(format!("{}:{}", dir.display(), dir.display()));
+            docker.arg("-w");
+            docker.arg(dir);
+        }
+        // `-e KEY` without a value makes docker copy it from its own
+        // environment, which keeps secrets off the docker command line
+        for (key, value) in local.get_envs() {
+            let Some(value) = value else { continue };
+            if CONTAINER_OWNED_ENV.iter().any(|owned| key == *owned) {
+                continue;
+            }
+            docker.arg("-e");
+            docker.arg(key);
+            docker.env(key, value);
+        }
+        docker.arg(&self.config.image);
+        docker.arg(binary);
+        docker.args(local.get_args());
+
+        debug!(
+            image = %self.config.image,
+            binary = %binary.to_string_lossy(),
+            container = %name,
+            "Prepared containerized CLI command"
+        );
+
+        let mut cleanup = std::process::Command::new(self.docker_binary());
+        cleanup.args(["rm", "-f", &name]);
+        Ok(PreparedCommand::new(docker).with_cleanup(cleanup))
+    }
+}
+
+/// `docker run` flags shared by every container: isolation, limits, mounts, env
+fn hardening_args(config: &ContainerConfig) -> Vec<String> {
     let mut docker_args: Vec<String> = vec![
         "run".to_owned(),
         "--rm".to_owned(),
@@ -248,10 +360,6 @@ fn build_docker_args(
     // Network mode
     docker_args.push(format!("--network={}", config.network_mode));
 
-    // Scratch mount (writable)
-    docker_args.push("-v".to_owned());
-    docker_args.push(format!("{}:/scratch", scratch_path.display()));
-
     // Extra mounts from configuration
     for mount in &config.extra_mounts {
         docker_args.push("-v".to_owned());
@@ -270,6 +378,23 @@ fn build_docker_args(
         docker_args.push(format!("{key}={value}"));
     }
 
+    docker_args
+}
+
+/// Build the full `docker run` argument list
+fn build_docker_args(
+    config: &ContainerConfig,
+    scratch_path: &Path,
+    binary_name: &str,
+    args: &[&str],
+    stdin_file_container_path: Option<&str>,
+) -> Vec<String> {
+    let mut docker_args = hardening_args(config);
+
+    // Scratch mount (writable)
+    docker_args.push("-v".to_owned());
+    docker_args.push(format!("{}:/scratch", scratch_path.display()));
+
     // If stdin content was provided, use shell to redirect it
     if let Some(stdin_path) = stdin_file_container_path {
         docker_args.push("-i".to_owned());
@@ -302,6 +427,55 @@ fn shell_escape(arg: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::cli_common::CliRunnerBase;
+    use crate::config::RunnerConfig;
+    use crate::stream::GuardedStream;
+    use crate::types::ErrorKind;
+
+    /// Copy the fake docker CLI into `dir`, where it writes `docker.log`
+    fn fake_docker(dir: &Path) -> PathBuf {
+        let path = dir.join("docker");
+        std::fs::copy(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fake_docker.sh"),
+            &path,
+        )
+        .unwrap();
+        path
+    }
+
+    /// Wait for a line starting with `prefix` in the fake docker log
+    async fn logged_call(dir: &Path, prefix: &str) -> Option<String> {
+        for _ in 0..100 {
+            let log = std::fs::read_to_string(dir.join("docker.log")).unwrap_or_default();
+            if let Some(line) = log.lines().find(|l| l.starts_with(prefix)) {
+                return Some(line.to_owned());
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        None
+    }
+
+    fn containerized_base(dir: &Path) -> CliRunnerBase {
+        let executor = ContainerExecutor::new(base_config()).with_docker_binary(fake_docker(dir));
+        let config = RunnerConfig::new(PathBuf::from("/opt/bin/claude"))
+            .with_execution_backend(Arc::new(executor));
+        CliRunnerBase::new(config, "opus", &[])
+    }
+
+    fn sandboxed_command(dir: &Path, args: &[&str]) -> Command {
+        let mut cmd = Command::new("/opt/bin/claude");
+        cmd.args(args)
+            .env_clear()
+            .env("PATH", "/usr/bin")
+            .env("HOME", "/home/me")
+            .env("API_TOKEN", "s3cret")
+            .current_dir(dir);
+        cmd
+    }
 
     fn base_config() -> ContainerConfig {
         ContainerConfig {
@@ -457,4 +631,121 @@ mod tests {
             "my-net"
         );
     }
+
+    #[test]
+    fn test_prepare_wraps_command_in_docker_run() {
+        let executor = ContainerExecutor::new(base_config());
+        let cmd = sandboxed_command(Path::new("/work"), &["-p", "--model", "opus"]);
+        let prepared = executor.prepare(cmd, true).unwrap();
+        let docker = prepared.command.as_std();
+        let args: Vec<_> = docker
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+
+        assert_eq!(docker.get_program(), "docker");
+        assert_eq!(args[0], "run");
+        for flag in ["--rm", "--read-only", "--init", "-i", "/work:/work"] {
+            assert!(args.contains(&flag.to_owned()), "missing {flag}");
+        }
+        let workdir = args.iter().position(|a| a == "-w").unwrap();
+        assert_eq!(args[workdir + 1], "/work");
+        let token = args.iter().position(|a| a == "API_TOKEN").unwrap();
+        assert_eq!(args[token - 1], "-e");
+        assert!(!args.iter().any(|a| a.contains("s3cret") || a == "PATH"));
+        assert!(args.contains(&"HOME=/scratch".to_owned()));
+        assert_eq!(
+            args[args.len() - 5..],
+            [
+                "ghcr.io/test/runner:latest",
+                "claude",
+                "-p",
+                "--model",
+                "opus"
+            ]
+        );
+        assert!(docker
+            .get_envs()
+            .any(|(k, v)| k == "API_TOKEN" && v == Some(OsStr::new("s3cret"))));
+    }
+
+    #[test]
+    fn test_prepare_without_stdin_is_not_interactive() {
+        let executor = ContainerExecutor::new(base_config());
+        let prepared = executor
+            .prepare(Command::new("/opt/bin/claude"), false)
+            .unwrap();
+        assert!(!prepared.command.as_std().get_args().any(|a| a == "-i"));
+    }
+
+    #[tokio::test]
+    async fn runner_command_runs_in_container_with_env_and_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = containerized_base(dir.path());
+        let cmd = sandboxed_command(dir.path(), &["-p"]);
+
+        let output = base
+            .run_command(cmd, Some(b"hello prompt"), &CancellationToken::new())
+            .await
+            .unwrap();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("env API_TOKEN=s3cret"));
+        assert!(stdout.contains("hello prompt"));
+        assert!(logged_call(dir.path(), "run --rm").await.is_some());
+        assert!(!std::fs::read_to_string(dir.path().join("docker.log"))
+            .unwrap()
+            .contains("rm -f"));
+    }
+
+    #[tokio::test]
+    async fn health_check_goes_through_container() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = containerized_base(dir.path());
+        assert!(base.health_check("claude").await.unwrap());
+        let run = logged_call(dir.path(), "run ").await.unwrap();
+        assert!(run.ends_with("ghcr.io/test/runner:latest claude --version"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_run_removes_container() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = containerized_base(dir.path());
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            trigger.cancel();
+        });
+
+        let err = base
+            .run_command(sandboxed_command(dir.path(), &["hang"]), None, &cancel)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+        let run = logged_call(dir.path(), "run ").await.unwrap();
+        let rm = logged_call(dir.path(), "rm -f embacle-").await.unwrap();
+        let name = rm.trim_start_matches("rm -f ");
+        assert!(run.contains(&format!("--name={name}")));
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_removes_container() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = containerized_base(dir.path());
+        let (child, cleanup) = base
+            .spawn_streaming(sandboxed_command(dir.path(), &["hang"]), None, "claude")
+            .unwrap();
+        let stream = GuardedStream::new(
+            tokio_stream::pending(),
+            child,
+            tokio::spawn(async { Vec::new() }),
+        )
+        .with_cleanup(cleanup);
+
+        assert!(logged_call(dir.path(), "run ").await.is_some());
+        drop(stream);
+        assert!(logged_call(dir.path(), "rm -f embacle-").await.is_some());
+    }
 }
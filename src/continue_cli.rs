@@ -6,17 +6,17 @@
 
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, StreamChunk,
 };
 use async_trait::async_trait;
 use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
 use crate::config::RunnerConfig;
-use crate::process::run_cli_command;
-use crate::prompt::prepare_user_prompt;
 use crate::sandbox::{apply_sandbox, build_policy};
 
 /// Default model for Continue CLI (provider-agnostic)
@@ -44,7 +44,7 @@ impl ContinueCliRunner {
         }
     }
 
-    /// Store a session marker for later resumption
+    /// Store a session marker to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
@@ -107,6 +107,9 @@ impl ContinueCliRunner {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 });
             }
         }
@@ -119,6 +122,9 @@ impl ContinueCliRunner {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })
     }
 }
@@ -127,26 +133,38 @@ impl ContinueCliRunner {
 impl LlmProvider for ContinueCliRunner {
     crate::delegate_provider_base!("continue", "Continue CLI", LlmCapabilities::empty());
 
-    #[instrument(skip_all, fields(runner = "continue"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "continue"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt);
 
-        if let Some(model) = &request.model {
-            if self.base.get_session(model).await.is_some() {
+        if let Some(key) = &request.session_key {
+            if self.base.get_session(key).await.is_some() {
                 cmd.arg("--resume");
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(request, cmd, None, cancel)
+            .await?;
         self.base.check_exit_code(&output, "continue")?;
 
         let response = Self::parse_json_response(&output.stdout)?;
 
-        // Mark session as active for this model key (Continue uses `--resume` flag)
-        if let Some(model) = &request.model {
-            self.base.set_session(model, "active").await;
+        // Mark session as active for this conversation (Continue uses `--resume` flag)
+        if let Some(key) = &request.session_key {
+            self.base.set_session(key, "active").await;
         }
 
         Ok(response)
@@ -160,6 +178,7 @@ impl LlmProvider for ContinueCliRunner {
             delta: response.content,
             is_final: true,
             finish_reason: Some("stop".to_owned()),
+            ..StreamChunk::default()
         };
         Ok(Box::pin(tokio_stream::once(Ok(chunk))))
     }
@@ -8,22 +8,24 @@ use std::io;
 use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, StreamChunk,
 };
 use async_trait::async_trait;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, instrument};
 
 use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::prepare_prompt;
+use crate::model_discovery::{parse_model_lines, ModelDiscovery};
+use crate::process::read_stderr_capped;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Default model for Copilot CLI
 const DEFAULT_MODEL: &str = "claude-opus-4.6-fast";
@@ -72,12 +74,7 @@ pub async fn discover_copilot_models() -> Option<Vec<String>> {
     }
 
     let stdout = str::from_utf8(&output.stdout).ok()?;
-    let models: Vec<String> = stdout
-        .lines()
-        .map(str::trim)
-        .filter(|line| !line.is_empty())
-        .map(ToOwned::to_owned)
-        .collect();
+    let models = parse_model_lines(stdout);
 
     if models.is_empty() {
         debug!("gh copilot models returned empty output, falling back to static list");
@@ -113,9 +110,13 @@ impl CopilotRunner {
     /// Create a new Copilot CLI runner with the given configuration.
     ///
     /// Attempts to discover available models by running `gh copilot models`.
-    /// Falls back to a static list if discovery fails.
+    /// Falls back to a static list if discovery fails; `refresh_models()`
+    /// repeats the discovery once the cached list expires.
     pub async fn new(config: RunnerConfig) -> Self {
-        let mut base = CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS);
+        let mut base = CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS)
+            .with_model_discovery(
+                ModelDiscovery::subcommand(&["copilot", "models"]).with_program("gh"),
+            );
         if let Some(models) = discover_copilot_models().await {
             base.available_models = models;
         }
@@ -187,6 +188,9 @@ impl CopilotRunner {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })
     }
 }
@@ -196,15 +200,31 @@ impl LlmProvider for CopilotRunner {
     // Copilot CLI has no --system-prompt flag; system messages are
     // embedded into the prompt via prepare_prompt(). Streaming is
     // supported by reading stdout line by line.
-    crate::delegate_provider_base!("copilot", "GitHub Copilot CLI", LlmCapabilities::STREAMING);
+    crate::delegate_provider_base!(
+        "copilot",
+        "GitHub Copilot CLI",
+        LlmCapabilities::STREAMING | LlmCapabilities::FILE_INPUT
+    );
 
-    #[instrument(skip_all, fields(runner = "copilot"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "copilot"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
-        let mut cmd = self.build_command(prompt, true);
+        let cmd = self.build_command(prompt, true);
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(request, cmd, None, cancel)
+            .await?;
         self.base.check_exit_code(&output, "copilot")?;
 
         Self::parse_response(&output.stdout)
@@ -212,19 +232,14 @@ impl LlmProvider for CopilotRunner {
 
     #[instrument(skip_all, fields(runner = "copilot"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let prepared = prepare_prompt(&request.messages)?;
+        let prepared = self.base.prepare_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt, true);
 
         // Enable streaming
         cmd.args(["--stream", "on"]);
 
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn copilot for streaming: {e}"))
-        })?;
+        let (mut child, cleanup) = self.base.spawn_streaming(cmd, None, "copilot")?;
 
         let stdout = child.stdout.take().ok_or_else(|| {
             RunnerError::internal("Failed to capture copilot stdout for streaming")
@@ -244,11 +259,14 @@ impl LlmProvider for CopilotRunner {
 
             Ok(StreamChunk {
                 delta: line,
-                is_final: false,
-                finish_reason: None,
+                ..StreamChunk::default()
             })
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
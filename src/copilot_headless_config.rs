@@ -7,17 +7,8 @@
 use std::env;
 use std::path::PathBuf;
 
-/// Policy for handling ACP permission requests from the copilot subprocess.
-///
-/// Controls whether tool-execution permission prompts are auto-approved or denied.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-pub enum PermissionPolicy {
-    /// Automatically approve permission requests by selecting the best allow option.
-    #[default]
-    AutoApprove,
-    /// Deny all permission requests by cancelling them.
-    DenyAll,
-}
+use crate::acp_pool::{DEFAULT_ACP_MAX_SESSIONS_PER_CONNECTION, DEFAULT_ACP_POOL_SIZE};
+pub use crate::acp_runner::PermissionPolicy;
 
 /// Configuration for the Copilot Headless (ACP) provider.
 #[derive(Debug, Clone)]
@@ -30,6 +21,10 @@ pub struct CopilotHeadlessConfig {
     pub github_token: Option<String>,
     /// Policy for handling permission requests from the copilot subprocess.
     pub permission_policy: PermissionPolicy,
+    /// Number of warm `copilot --acp` processes kept in the connection pool.
+    pub pool_size: usize,
+    /// Sessions created on one copilot process before it is recycled.
+    pub max_sessions_per_process: usize,
 }
 
 impl CopilotHeadlessConfig {
@@ -39,6 +34,8 @@ impl CopilotHeadlessConfig {
     /// - `COPILOT_CLI_PATH` — Override path to copilot binary
     /// - `COPILOT_HEADLESS_MODEL` — Default model (default: `claude-opus-4.6-fast`)
     /// - `COPILOT_GITHUB_TOKEN` / `GH_TOKEN` / `GITHUB_TOKEN` — GitHub auth token
+    /// - `COPILOT_HEADLESS_POOL_SIZE` — Warm copilot processes (default: 1)
+    /// - `COPILOT_HEADLESS_MAX_SESSIONS_PER_PROCESS` — Sessions before a process is recycled (default: 64)
     #[must_use]
     pub fn from_env() -> Self {
         let cli_path = env::var("COPILOT_CLI_PATH").ok().map(PathBuf::from);
@@ -60,11 +57,25 @@ impl CopilotHeadlessConfig {
             _ => PermissionPolicy::AutoApprove,
         };
 
+        let pool_size = env::var("COPILOT_HEADLESS_POOL_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_ACP_POOL_SIZE);
+
+        let max_sessions_per_process = env::var("COPILOT_HEADLESS_MAX_SESSIONS_PER_PROCESS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_ACP_MAX_SESSIONS_PER_CONNECTION);
+
         Self {
             cli_path,
             model,
             github_token,
             permission_policy,
+            pool_size,
+            max_sessions_per_process,
         }
     }
 }
@@ -76,6 +87,8 @@ impl Default for CopilotHeadlessConfig {
             model: "claude-opus-4.6-fast".to_owned(),
             github_token: None,
             permission_policy: PermissionPolicy::default(),
+            pool_size: DEFAULT_ACP_POOL_SIZE,
+            max_sessions_per_process: DEFAULT_ACP_MAX_SESSIONS_PER_CONNECTION,
         }
     }
 }
@@ -5,13 +5,12 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::io;
-use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
-    TokenUsage,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, StreamChunk, TokenUsage,
 };
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -19,13 +18,15 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
 use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::prepare_user_prompt;
+use crate::error_classify::classify_error;
+use crate::model_discovery::{parse_id_dash_name_lines, ModelDiscovery};
+use crate::process::read_stderr_capped;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Cursor Agent CLI response JSON structure
 #[derive(Debug, Deserialize)]
@@ -64,11 +65,13 @@ impl CursorAgentRunner {
     #[must_use]
     pub fn new(config: RunnerConfig) -> Self {
         Self {
-            base: CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS),
+            base: CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS).with_model_discovery(
+                ModelDiscovery::subcommand(&["models"]).with_parser(parse_id_dash_name_lines),
+            ),
         }
     }
 
-    /// Store a session ID for later resumption
+    /// Store a session ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
@@ -114,7 +117,7 @@ impl CursorAgentRunner {
         })?;
 
         if parsed.is_error {
-            return Err(RunnerError::external_service(
+            return Err(classify_error(
                 "cursor-agent",
                 parsed
                     .result
@@ -128,6 +131,10 @@ impl CursorAgentRunner {
             prompt_tokens: u.input_tokens.unwrap_or(0),
             completion_tokens: u.output_tokens.unwrap_or(0),
             total_tokens: u.input_tokens.unwrap_or(0) + u.output_tokens.unwrap_or(0),
+            reasoning_tokens: None,
+            cached_prompt_tokens: None,
+            cache_creation_tokens: None,
+            reported_cost: None,
         });
 
         let response = ChatResponse {
@@ -137,6 +144,9 @@ impl CursorAgentRunner {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
 
         Ok((response, parsed.session_id))
@@ -151,26 +161,38 @@ impl LlmProvider for CursorAgentRunner {
         LlmCapabilities::STREAMING | LlmCapabilities::TEMPERATURE | LlmCapabilities::MAX_TOKENS
     );
 
-    #[instrument(skip_all, fields(runner = "cursor_agent"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "cursor_agent"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt, "json");
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--resume", &sid]);
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(request, cmd, None, cancel)
+            .await?;
         self.base.check_exit_code(&output, "cursor-agent")?;
 
         let (response, session_id) = Self::parse_response(&output.stdout)?;
 
         if let Some(sid) = session_id {
-            if let Some(model) = &request.model {
-                self.base.set_session(model, &sid).await;
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &sid).await;
             }
         }
 
@@ -179,22 +201,17 @@ impl LlmProvider for CursorAgentRunner {
 
     #[instrument(skip_all, fields(runner = "cursor_agent"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt, "stream-json");
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--resume", &sid]);
             }
         }
 
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn cursor-agent for streaming: {e}"))
-        })?;
+        let (mut child, cleanup) = self.base.spawn_streaming(cmd, None, "cursor-agent")?;
 
         let stdout = child.stdout.take().ok_or_else(|| {
             RunnerError::internal("Failed to capture cursor-agent stdout for streaming")
@@ -214,11 +231,7 @@ impl LlmProvider for CursorAgentRunner {
             })?;
 
             if line.trim().is_empty() {
-                return Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                });
+                return Ok(StreamChunk::default());
             }
 
             let value: serde_json::Value = serde_json::from_str(&line).map_err(|e| {
@@ -235,6 +248,7 @@ impl LlmProvider for CursorAgentRunner {
                         .to_owned(),
                     is_final: true,
                     finish_reason: Some("stop".to_owned()),
+                    ..StreamChunk::default()
                 }),
                 "content" => Ok(StreamChunk {
                     delta: value
@@ -242,17 +256,16 @@ impl LlmProvider for CursorAgentRunner {
                         .and_then(|v| v.as_str())
                         .unwrap_or("")
                         .to_owned(),
-                    is_final: false,
-                    finish_reason: None,
-                }),
-                _ => Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
+                    ..StreamChunk::default()
                 }),
+                _ => Ok(StreamChunk::default()),
             }
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
@@ -0,0 +1,252 @@
+// ABOUTME: Heuristic classification of provider error text into typed RunnerError kinds
+// ABOUTME: Detects rate limits, context overflow, exhausted quota and content filtering, plus retry-after hints
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Error Classification
+//!
+//! CLI tools report upstream API failures as free-form stderr lines or JSON
+//! error messages. [`classify_error`] maps that text onto the typed
+//! [`ErrorKind`](crate::types::ErrorKind) variants so callers can react to
+//! rate limits, oversized prompts, exhausted quota and content filtering
+//! without parsing messages themselves. Text that matches no known pattern
+//! stays an `ExternalService` error.
+
+use std::time::Duration;
+
+use crate::types::RunnerError;
+
+/// Phrases reporting a prompt larger than the model's context window
+const CONTEXT_PATTERNS: &[&str] = &[
+    "context length",
+    "context_length",
+    "context window",
+    "context overflow",
+    "contextoverflow",
+    "maximum context",
+    "prompt is too long",
+    "input is too long",
+    "too many input tokens",
+    "too many prompt tokens",
+];
+
+/// Phrases reporting exhausted account quota, credits or usage allowance
+const QUOTA_PATTERNS: &[&str] = &[
+    "insufficient_quota",
+    "quota exceeded",
+    "exceeded your current quota",
+    "credit balance",
+    "out of credits",
+    "usage limit reached",
+    "billing details",
+    "billing_hard_limit",
+    "billing hard limit",
+];
+
+/// Phrases reporting provider-side rate limiting
+const RATE_LIMIT_PATTERNS: &[&str] = &[
+    "rate limit",
+    "rate_limit",
+    "ratelimit",
+    "rate-limit",
+    "resource_exhausted",
+    "tokens per min",
+    "requests per min",
+];
+
+/// Phrases reporting a content-policy refusal
+const CONTENT_FILTER_PATTERNS: &[&str] = &[
+    "content filter",
+    "content_filter",
+    "content policy",
+    "content_policy",
+    "content management policy",
+    "blocked by safety",
+];
+
+/// Phrases that introduce a retry-after hint
+const RETRY_AFTER_MARKERS: &[&str] = &[
+    "retry-after",
+    "retry after",
+    "retry_after",
+    "retrydelay",
+    "retry in",
+    "try again in",
+];
+
+/// Classify a provider error message into a typed [`RunnerError`]
+///
+/// The resulting message is always `"{service}: {message}"`, matching
+/// [`RunnerError::external_service`]. Rate-limit errors carry any
+/// retry-after hint found in the text.
+pub fn classify_error(service: &str, message: &str) -> RunnerError {
+    classify_error_text(service, message, message)
+}
+
+/// Classify using `haystack` for detection while reporting `message`
+///
+/// Lets callers inspect a full stderr capture but surface only its
+/// first line.
+pub(crate) fn classify_error_text(service: &str, message: &str, haystack: &str) -> RunnerError {
+    let lower = haystack.to_ascii_lowercase();
+    let matches_any = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+
+    // Explicit rate-limit wording wins: TPM limits mention tokens and quota.
+    // A bare 429 / "Too Many Requests" status is checked last since providers
+    // also use it for exhausted quota.
+    let rate_limited = || {
+        RunnerError::rate_limited(format!("{service}: {message}"))
+            .with_retry_after(parse_retry_after(haystack))
+    };
+    if matches_any(RATE_LIMIT_PATTERNS) {
+        rate_limited()
+    } else if matches_any(CONTEXT_PATTERNS) {
+        RunnerError::context_length_exceeded(format!("{service}: {message}"))
+    } else if matches_any(QUOTA_PATTERNS) {
+        RunnerError::quota_exhausted(format!("{service}: {message}"))
+    } else if contains_status_code(&lower, "429") || lower.contains("too many requests") {
+        rate_limited()
+    } else if matches_any(CONTENT_FILTER_PATTERNS) {
+        RunnerError::content_filtered(format!("{service}: {message}"))
+    } else {
+        RunnerError::external_service(service, message)
+    }
+}
+
+/// Extract a retry-after hint such as `Retry-After: 30` or `try again in 1.5s`
+///
+/// Bare numbers are seconds; `ms`, `m`/`min` and `h` suffixes are honored.
+#[must_use]
+pub fn parse_retry_after(text: &str) -> Option<Duration> {
+    let lower = text.to_ascii_lowercase();
+    RETRY_AFTER_MARKERS.iter().find_map(|marker| {
+        let start = lower.find(marker)? + marker.len();
+        parse_duration_prefix(&lower[start..])
+    })
+}
+
+/// Parse `<number><unit>` after skipping separators like `:`, `=`, quotes and spaces
+fn parse_duration_prefix(text: &str) -> Option<Duration> {
+    let rest = text.trim_start_matches([' ', ':', '=', '"', '\'']);
+    let number_len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    let value: f64 = rest[..number_len].parse().ok()?;
+    let unit = rest[number_len..].trim_start();
+    let seconds = if unit.starts_with("ms") || unit.starts_with("millisecond") {
+        value / 1000.0
+    } else if unit.starts_with('h') {
+        value * 3600.0
+    } else if unit.starts_with('m') {
+        value * 60.0
+    } else {
+        value
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Whether `code` appears in `text` as a standalone number (not inside a longer one)
+fn contains_status_code(text: &str, code: &str) -> bool {
+    text.match_indices(code).any(|(idx, _)| {
+        let before = text[..idx].chars().next_back();
+        let after = text[idx + code.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_digit()) && !after.is_some_and(|c| c.is_ascii_digit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ErrorKind;
+
+    #[test]
+    fn classifies_rate_limit_with_retry_after() {
+        let err = classify_error(
+            "claude-code",
+            "API Error: 429 rate limited, retry after 12s",
+        );
+        assert_eq!(err.kind, ErrorKind::RateLimited);
+        assert_eq!(err.retry_after, Some(Duration::from_secs(12)));
+        assert!(err.message.starts_with("claude-code: "));
+    }
+
+    #[test]
+    fn classifies_bare_429_but_not_longer_numbers() {
+        assert_eq!(
+            classify_error("gemini", "HTTP 429").kind,
+            ErrorKind::RateLimited
+        );
+        assert_eq!(
+            classify_error("gemini", "used 14290 tokens").kind,
+            ErrorKind::ExternalService
+        );
+    }
+
+    #[test]
+    fn classifies_context_quota_and_content_filter() {
+        assert_eq!(
+            classify_error(
+                "codex",
+                "This model's maximum context length is 128000 tokens"
+            )
+            .kind,
+            ErrorKind::ContextLengthExceeded
+        );
+        assert_eq!(
+            classify_error("codex", "You exceeded your current quota").kind,
+            ErrorKind::QuotaExhausted
+        );
+        assert_eq!(
+            classify_error("copilot", "Response blocked by content filter").kind,
+            ErrorKind::ContentFiltered
+        );
+        assert_eq!(
+            classify_error("copilot", "segfault").kind,
+            ErrorKind::ExternalService
+        );
+    }
+
+    #[test]
+    fn token_rate_limits_are_not_context_overflow() {
+        let err = classify_error(
+            "codex",
+            "Rate limit reached: too many tokens per minute, try again in 20s",
+        );
+        assert_eq!(err.kind, ErrorKind::RateLimited);
+        assert_eq!(err.retry_after, Some(Duration::from_secs(20)));
+        assert_eq!(
+            classify_error("codex", "Limit 30000 tokens per min exceeded").kind,
+            ErrorKind::RateLimited
+        );
+        assert_eq!(
+            classify_error("codex", "429: You exceeded your current quota").kind,
+            ErrorKind::QuotaExhausted
+        );
+        assert_eq!(
+            classify_error("copilot", "failed to load billing page").kind,
+            ErrorKind::ExternalService
+        );
+    }
+
+    #[test]
+    fn parses_retry_after_units() {
+        assert_eq!(
+            parse_retry_after("Retry-After: 30"),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("please try again in 250ms"),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            parse_retry_after("\"retryDelay\": \"1.5s\""),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_retry_after("retry in 2 minutes"),
+            Some(Duration::from_mins(2))
+        );
+        assert_eq!(parse_retry_after("retry later"), None);
+    }
+}
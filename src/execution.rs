@@ -0,0 +1,128 @@
+// ABOUTME: Pluggable execution backends deciding where CLI runner subprocesses actually run
+// ABOUTME: Defines the ExecutionBackend trait, the default LocalBackend, and cleanup for early-stopped runs
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Execution Backends
+//!
+//! CLI runners build a local [`Command`] (binary, arguments, sandboxed
+//! environment, working directory) and hand it to the configured
+//! [`ExecutionBackend`] right before spawning. [`LocalBackend`] runs the
+//! command unchanged; [`ContainerExecutor`](crate::container::ContainerExecutor)
+//! rewrites it into an ephemeral `docker run`. Select a backend with
+//! [`RunnerConfig::with_execution_backend`](crate::config::RunnerConfig::with_execution_backend).
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use crate::types::RunnerError;
+
+/// Where a CLI runner's subprocess is executed
+pub trait ExecutionBackend: Send + Sync + fmt::Debug {
+    /// Short backend name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Turn a fully configured local command into the command to spawn
+    ///
+    /// `stdin` is true when the runner will write the prompt to the
+    /// process's stdin.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if the command cannot be run on this backend.
+    fn prepare(&self, cmd: Command, stdin: bool) -> Result<PreparedCommand, RunnerError>;
+
+    /// Host directory to write attachment files under so the process can read them
+    ///
+    /// `working_directory` is the runner's configured working directory.
+    /// `None` (the default) means the process sees the host filesystem and
+    /// files can stay in the system temp dir and at their original paths.
+    fn attachment_root(&self, working_directory: Option<&Path>) -> Option<PathBuf> {
+        let _ = working_directory;
+        None
+    }
+}
+
+/// Runs commands directly on the host (the default backend)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalBackend;
+
+impl ExecutionBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn prepare(&self, cmd: Command, _stdin: bool) -> Result<PreparedCommand, RunnerError> {
+        Ok(PreparedCommand::new(cmd))
+    }
+}
+
+/// A command ready to spawn, plus the cleanup to run if it is stopped early
+#[derive(Debug)]
+pub struct PreparedCommand {
+    /// Command to spawn
+    pub command: Command,
+    cleanup: Option<std::process::Command>,
+}
+
+impl PreparedCommand {
+    /// Wrap a command that needs no cleanup
+    #[must_use]
+    pub const fn new(command: Command) -> Self {
+        Self {
+            command,
+            cleanup: None,
+        }
+    }
+
+    /// Run `cleanup` if the process is cancelled, times out or is dropped before exiting
+    ///
+    /// For work that can outlive the spawned process, such as a container
+    /// whose client was killed.
+    #[must_use]
+    pub fn with_cleanup(mut self, cleanup: std::process::Command) -> Self {
+        self.cleanup = Some(cleanup);
+        self
+    }
+
+    pub(crate) fn into_parts(self) -> (Command, CleanupGuard) {
+        (self.command, CleanupGuard(self.cleanup))
+    }
+}
+
+/// Runs a backend's cleanup command on drop unless the process exited on its own
+pub(crate) struct CleanupGuard(Option<std::process::Command>);
+
+impl CleanupGuard {
+    /// The process finished normally; skip the cleanup
+    pub(crate) fn disarm(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        let Some(mut cleanup) = self.0.take() else {
+            return;
+        };
+        cleanup
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        debug!(program = ?cleanup.get_program(), "Running execution backend cleanup");
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Err(e) = Command::from(cleanup).status().await {
+                    warn!(error = %e, "Execution backend cleanup failed");
+                }
+            });
+        } else if let Err(e) = cleanup.status() {
+            warn!(error = %e, "Execution backend cleanup failed");
+        }
+    }
+}
@@ -38,6 +38,13 @@ pub async fn create_runner(
 
     let binary_path = resolve_binary(binary_name, env_override.as_deref())?;
     let config = RunnerConfig::new(binary_path);
+    // Generic ACP agents take their command-line arguments from ACP_AGENT_ARGS
+    #[cfg(feature = "acp")]
+    let config = if runner_type == CliRunnerType::Acp {
+        config.with_extra_args(crate::acp_runner::agent_args_from_env())
+    } else {
+        config
+    };
 
     let runner: Box<dyn LlmProvider> = match runner_type {
         CliRunnerType::ClaudeCode => Box::new(ClaudeCodeRunner::new(config)),
@@ -54,6 +61,8 @@ pub async fn create_runner(
         CliRunnerType::KiloCli => Box::new(KiloCliRunner::new(config)),
         #[cfg(feature = "copilot-headless")]
         CliRunnerType::CopilotHeadless => unreachable!("handled above"),
+        #[cfg(feature = "acp")]
+        CliRunnerType::Acp => Box::new(crate::AcpRunner::new(config)),
     };
 
     Ok(runner)
@@ -83,11 +92,13 @@ pub async fn create_runner_with_config(
         // CopilotHeadless ignores RunnerConfig — uses env-based config
         #[cfg(feature = "copilot-headless")]
         CliRunnerType::CopilotHeadless => Box::new(crate::CopilotHeadlessRunner::from_env().await),
+        #[cfg(feature = "acp")]
+        CliRunnerType::Acp => Box::new(crate::AcpRunner::new(config)),
     }
 }
 
 /// All provider types supported by embacle, in discovery priority order
-#[cfg(not(feature = "copilot-headless"))]
+#[cfg(not(feature = "acp"))]
 pub const ALL_PROVIDERS: &[CliRunnerType] = &[
     CliRunnerType::ClaudeCode,
     CliRunnerType::Copilot,
@@ -103,6 +114,24 @@ pub const ALL_PROVIDERS: &[CliRunnerType] = &[
     CliRunnerType::KiloCli,
 ];
 
+/// All provider types supported by embacle, in discovery priority order
+#[cfg(all(feature = "acp", not(feature = "copilot-headless")))]
+pub const ALL_PROVIDERS: &[CliRunnerType] = &[
+    CliRunnerType::ClaudeCode,
+    CliRunnerType::Copilot,
+    CliRunnerType::CursorAgent,
+    CliRunnerType::OpenCode,
+    CliRunnerType::GeminiCli,
+    CliRunnerType::CodexCli,
+    CliRunnerType::GooseCli,
+    CliRunnerType::ClineCli,
+    CliRunnerType::ContinueCli,
+    CliRunnerType::WarpCli,
+    CliRunnerType::KiroCli,
+    CliRunnerType::KiloCli,
+    CliRunnerType::Acp,
+];
+
 /// All provider types supported by embacle, in discovery priority order
 #[cfg(feature = "copilot-headless")]
 pub const ALL_PROVIDERS: &[CliRunnerType] = &[
@@ -119,6 +148,7 @@ pub const ALL_PROVIDERS: &[CliRunnerType] = &[
     CliRunnerType::WarpCli,
     CliRunnerType::KiroCli,
     CliRunnerType::KiloCli,
+    CliRunnerType::Acp,
 ];
 
 /// Parse a provider name string into a `CliRunnerType`
@@ -145,6 +175,8 @@ pub fn parse_runner_type(s: &str) -> Option<CliRunnerType> {
         "copilot_headless" | "copilot-headless" | "copilotheadless" | "headless" => {
             Some(CliRunnerType::CopilotHeadless)
         }
+        #[cfg(feature = "acp")]
+        "acp" | "acp_agent" | "acp-agent" | "acpagent" => Some(CliRunnerType::Acp),
         _ => None,
     }
 }
@@ -152,7 +184,9 @@ pub fn parse_runner_type(s: &str) -> Option<CliRunnerType> {
 /// Format the list of valid provider names for error messages
 pub const fn valid_provider_names() -> &'static str {
     if cfg!(feature = "copilot-headless") {
-        "claude_code, copilot, copilot_headless, cursor_agent, opencode, gemini_cli, codex_cli, goose_cli, cline_cli, continue_cli, warp_cli, kiro_cli, kilo_cli"
+        "claude_code, copilot, copilot_headless, cursor_agent, opencode, gemini_cli, codex_cli, goose_cli, cline_cli, continue_cli, warp_cli, kiro_cli, kilo_cli, acp"
+    } else if cfg!(feature = "acp") {
+        "claude_code, copilot, cursor_agent, opencode, gemini_cli, codex_cli, goose_cli, cline_cli, continue_cli, warp_cli, kiro_cli, kilo_cli, acp"
     } else {
         "claude_code, copilot, cursor_agent, opencode, gemini_cli, codex_cli, goose_cli, cline_cli, continue_cli, warp_cli, kiro_cli, kilo_cli"
     }
@@ -233,6 +267,16 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "acp")]
+    #[test]
+    fn parse_acp_variants() {
+        assert_eq!(parse_runner_type("acp"), Some(CliRunnerType::Acp));
+        assert_eq!(parse_runner_type("acp-agent"), Some(CliRunnerType::Acp));
+        assert_eq!(parse_runner_type("ACP_AGENT"), Some(CliRunnerType::Acp));
+        assert_eq!(CliRunnerType::Acp.to_string(), "acp");
+        assert_eq!(CliRunnerType::Acp.env_override_key(), "ACP_AGENT_BINARY");
+    }
+
     #[test]
     fn parse_unknown_returns_none() {
         assert_eq!(parse_runner_type("gpt4"), None);
@@ -242,6 +286,8 @@ mod tests {
     #[test]
     fn all_providers_count() {
         if cfg!(feature = "copilot-headless") {
+            assert_eq!(ALL_PROVIDERS.len(), 14);
+        } else if cfg!(feature = "acp") {
             assert_eq!(ALL_PROVIDERS.len(), 13);
         } else {
             assert_eq!(ALL_PROVIDERS.len(), 12);
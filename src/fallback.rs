@@ -12,18 +12,46 @@
 //!
 //! Optional per-provider retry with exponential backoff can be configured
 //! via [`RetryConfig`] and [`FallbackProvider::with_retry()`]. Retries
-//! are only attempted for transient errors (see [`ErrorKind::is_transient()`]).
+//! are only attempted for transient errors (see [`ErrorKind::is_transient()`]);
+//! rate-limit errors wait out the provider's retry-after hint when it is
+//! within `max_delay`. Context-length, quota and content-filter errors move
+//! straight to the next provider.
 //!
-//! Health checks pass if ANY provider is healthy. Capabilities are the
-//! bitwise OR of all inner providers.
+//! A request deadline (see [`ChatRequest::with_timeout`]) is one budget for
+//! the whole chain: every provider sees only the time left, backoffs that
+//! would outlast it are skipped, and once it passes no further provider is
+//! tried.
+//!
+//! [`FallbackProvider::with_circuit_breaker()`] gives each provider a
+//! [`CircuitBreaker`]: providers whose circuit is open are skipped without
+//! being called, providers that failed within the last cooldown are tried
+//! after the rest (keeping chain order within each group), and
+//! [`FallbackProvider::circuit_states()`] reports each provider's state.
+//! When every circuit is open the request fails fast with a retry-after
+//! hint for the earliest trial. A stream counts against its provider when
+//! it errors and for it once it finishes.
+//!
+//! Successful responses carry the request's metadata plus a
+//! `fallback_provider` tag naming the provider that answered.
+//!
+//! Health checks pass if ANY provider whose circuit is not open is healthy.
+//! Capabilities are the bitwise OR of all inner providers.
 
-use std::time::Duration;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use tracing::warn;
+use tokio_stream::Stream;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn, Instrument};
 
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitPermit, CircuitState};
+use crate::model_catalog::ModelInfo;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
+    ChatRequest, ChatResponse, ChatStream, ErrorKind, LlmCapabilities, LlmProvider, RunnerError,
+    StreamChunk,
 };
 
 /// Configuration for per-provider retry with exponential backoff
@@ -55,9 +83,11 @@ impl Default for RetryConfig {
 /// An empty vec is rejected with a config error.
 ///
 /// Use [`FallbackProvider::with_retry()`] to enable per-provider retry
-/// with exponential backoff on transient errors.
+/// with exponential backoff on transient errors, and
+/// [`FallbackProvider::with_circuit_breaker()`] to skip failing providers.
 pub struct FallbackProvider {
     providers: Vec<Box<dyn LlmProvider>>,
+    breakers: Vec<Arc<CircuitBreaker>>,
     display_name: String,
     combined_models: Vec<String>,
     retry_config: RetryConfig,
@@ -107,60 +137,140 @@ impl FallbackProvider {
             }
         }
 
+        let breakers = providers
+            .iter()
+            .map(|p| {
+                Arc::new(CircuitBreaker::new(
+                    p.name(),
+                    CircuitBreakerConfig::disabled(),
+                ))
+            })
+            .collect();
+
         Ok(Self {
             providers,
+            breakers,
             display_name,
             combined_models,
             retry_config,
         })
     }
 
-    /// Compute the backoff delay for a given attempt (0-indexed)
-    fn backoff_delay(&self, attempt: u32) -> Duration {
-        let delay = self
-            .retry_config
-            .base_delay
-            .saturating_mul(2u32.saturating_pow(attempt));
-        delay.min(self.retry_config.max_delay)
+    /// Track each provider's health with a circuit breaker using `config`
+    ///
+    /// Providers whose circuit is open are skipped until their cooldown
+    /// elapses, and providers that failed within the cooldown are tried
+    /// after the others. Replaces any breaker state collected so far.
+    #[must_use]
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breakers = self
+            .providers
+            .iter()
+            .map(|p| Arc::new(CircuitBreaker::new(p.name(), config)))
+            .collect();
+        self
     }
-}
 
-#[async_trait]
-impl LlmProvider for FallbackProvider {
-    fn name(&self) -> &'static str {
-        "fallback"
+    /// Circuit state of each provider, in chain order
+    pub fn circuit_states(&self) -> Vec<(&'static str, CircuitState)> {
+        self.providers
+            .iter()
+            .zip(&self.breakers)
+            .map(|(provider, breaker)| (provider.name(), breaker.state()))
+            .collect()
     }
 
-    fn display_name(&self) -> &str {
-        &self.display_name
+    /// Providers in the order to try them: chain order, with providers that
+    /// failed recently moved behind the rest
+    fn by_health(&self) -> Vec<(&dyn LlmProvider, &Arc<CircuitBreaker>)> {
+        let mut order: Vec<_> = self
+            .providers
+            .iter()
+            .map(AsRef::as_ref)
+            .zip(&self.breakers)
+            .collect();
+        order.sort_by_key(|(_, breaker)| breaker.failed_recently());
+        order
     }
 
-    fn capabilities(&self) -> LlmCapabilities {
-        self.providers
+    /// Error for a request that found every provider's circuit open
+    fn all_circuits_open(&self) -> RunnerError {
+        let retry_after = self
+            .breakers
             .iter()
-            .fold(LlmCapabilities::empty(), |acc, p| acc | p.capabilities())
+            .filter_map(|breaker| breaker.remaining_cooldown())
+            .min();
+        RunnerError::external_service("fallback", "every provider's circuit is open")
+            .with_retry_after(retry_after)
     }
 
-    fn default_model(&self) -> &str {
-        self.providers[0].default_model()
+    /// Compute the backoff delay for a given attempt (0-indexed)
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .retry_config
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt));
+        delay.min(self.retry_config.max_delay)
     }
 
-    fn available_models(&self) -> &[String] {
-        &self.combined_models
+    /// Delay before retrying `err` on the same provider, or `None` to move on
+    ///
+    /// A provider's retry-after hint is honored when it fits within
+    /// `max_delay`; a longer hint, or a delay that would outlast the
+    /// request's deadline, sends the request to the next provider.
+    fn retry_delay(
+        &self,
+        request: &ChatRequest,
+        attempt: u32,
+        err: &RunnerError,
+    ) -> Option<Duration> {
+        if !err.kind.is_transient() || attempt >= self.retry_config.max_retries {
+            return None;
+        }
+        let backoff = self.backoff_delay(attempt);
+        let delay = match err.retry_after {
+            Some(hint) if hint > self.retry_config.max_delay => None,
+            Some(hint) => Some(hint.max(backoff)),
+            None => Some(backoff),
+        };
+        delay.filter(|&delay| delay < request.timeout_or(Duration::MAX))
     }
 
-    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+    /// Try each provider in order, retrying transient errors per `retry_config`
+    async fn complete_in_order(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
         let mut last_error = RunnerError::internal("no providers configured");
+        let mut attempted = false;
 
-        for provider in &self.providers {
+        for (provider, breaker) in self.by_health() {
             for attempt in 0..=self.retry_config.max_retries {
-                match provider.complete(request).await {
-                    Ok(response) => return Ok(response),
+                if request.deadline_passed() {
+                    return Err(deadline_exceeded(provider, &last_error));
+                }
+                let Some(permit) = breaker.try_acquire() else {
+                    debug!(
+                        provider = provider.name(),
+                        "fallback: circuit open, skipping"
+                    );
+                    break;
+                };
+                attempted = true;
+                match provider.complete_with_cancel(request, cancel).await {
+                    Ok(mut response) => {
+                        permit.success();
+                        response
+                            .metadata
+                            .tags
+                            .insert("fallback_provider".to_owned(), provider.name().to_owned());
+                        return Ok(response);
+                    }
+                    Err(err) if err.kind == ErrorKind::Cancelled => return Err(err),
                     Err(err) => {
-                        let is_retryable =
-                            err.kind.is_transient() && attempt < self.retry_config.max_retries;
-                        if is_retryable {
-                            let delay = self.backoff_delay(attempt);
+                        permit.failure(&err, request.deadline);
+                        if let Some(delay) = self.retry_delay(request, attempt, &err) {
                             #[allow(clippy::cast_possible_truncation)]
                             let delay_ms = delay.as_millis() as u64;
                             warn!(
@@ -170,7 +280,14 @@ impl LlmProvider for FallbackProvider {
                                 delay_ms,
                                 "fallback: transient error, retrying after backoff"
                             );
-                            tokio::time::sleep(delay).await;
+                            tokio::select! {
+                                () = cancel.cancelled() => {
+                                    return Err(RunnerError::cancelled(
+                                        "fallback: cancelled during retry backoff",
+                                    ));
+                                }
+                                () = tokio::time::sleep(delay) => {}
+                            }
                         } else {
                             warn!(
                                 provider = provider.name(),
@@ -185,21 +302,41 @@ impl LlmProvider for FallbackProvider {
             }
         }
 
+        if !attempted {
+            return Err(self.all_circuits_open());
+        }
         Err(last_error)
     }
 
-    async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+    /// Open a stream from the first provider that accepts the request
+    async fn stream_in_order(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
         let mut last_error = RunnerError::internal("no providers configured");
+        let mut attempted = false;
 
-        for provider in &self.providers {
+        for (provider, breaker) in self.by_health() {
             for attempt in 0..=self.retry_config.max_retries {
+                if request.deadline_passed() {
+                    return Err(deadline_exceeded(provider, &last_error));
+                }
+                let Some(permit) = breaker.try_acquire() else {
+                    debug!(
+                        provider = provider.name(),
+                        "fallback: circuit open, skipping"
+                    );
+                    break;
+                };
+                attempted = true;
                 match provider.complete_stream(request).await {
-                    Ok(stream) => return Ok(stream),
+                    Ok(stream) => {
+                        return Ok(Box::pin(OutcomeStream {
+                            inner: stream,
+                            permit: Some(permit),
+                            deadline: request.deadline,
+                        }));
+                    }
                     Err(err) => {
-                        let is_retryable =
-                            err.kind.is_transient() && attempt < self.retry_config.max_retries;
-                        if is_retryable {
-                            let delay = self.backoff_delay(attempt);
+                        permit.failure(&err, request.deadline);
+                        if let Some(delay) = self.retry_delay(request, attempt, &err) {
                             #[allow(clippy::cast_possible_truncation)]
                             let delay_ms = delay.as_millis() as u64;
                             warn!(
@@ -224,11 +361,136 @@ impl LlmProvider for FallbackProvider {
             }
         }
 
+        if !attempted {
+            return Err(self.all_circuits_open());
+        }
         Err(last_error)
     }
+}
 
-    async fn health_check(&self) -> Result<bool, RunnerError> {
+/// Forwards a provider's stream, reporting its outcome to the circuit breaker
+///
+/// The first error counts as a failure; a final chunk or a clean end counts
+/// as a success. Dropping the stream before either records nothing.
+struct OutcomeStream {
+    inner: ChatStream,
+    permit: Option<CircuitPermit>,
+    deadline: Option<Instant>,
+}
+
+impl Stream for OutcomeStream {
+    type Item = Result<StreamChunk, RunnerError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(self.inner.as_mut().poll_next(cx));
+        match &item {
+            Some(Ok(chunk)) if !chunk.is_final => {}
+            Some(Ok(_)) | None => {
+                if let Some(permit) = self.permit.take() {
+                    permit.success();
+                }
+            }
+            Some(Err(err)) => {
+                if let Some(permit) = self.permit.take() {
+                    permit.failure(err, self.deadline);
+                }
+            }
+        }
+        Poll::Ready(item)
+    }
+}
+
+/// Timeout error for a chain whose deadline passed before `next` was tried
+fn deadline_exceeded(next: &dyn LlmProvider, last_error: &RunnerError) -> RunnerError {
+    warn!(
+        provider = next.name(),
+        last_error = %last_error,
+        "fallback: request deadline passed, not trying further providers"
+    );
+    RunnerError::timeout(format!(
+        "fallback: request deadline passed before trying {}",
+        next.name()
+    ))
+}
+
+#[async_trait]
+impl LlmProvider for FallbackProvider {
+    fn name(&self) -> &'static str {
+        "fallback"
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn capabilities(&self) -> LlmCapabilities {
+        self.providers
+            .iter()
+            .fold(LlmCapabilities::empty(), |acc, p| acc | p.capabilities())
+    }
+
+    fn default_model(&self) -> &str {
+        self.providers[0].default_model()
+    }
+
+    fn available_models(&self) -> &[String] {
+        &self.combined_models
+    }
+
+    /// Catalog entries from every provider, first provider winning on duplicate ids
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        let mut catalog: Vec<ModelInfo> = Vec::new();
+        for info in self.providers.iter().flat_map(|p| p.model_catalog()) {
+            if !catalog.iter().any(|m| m.id == info.id) {
+                catalog.push(info);
+            }
+        }
+        catalog
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        let mut models: Vec<String> = Vec::new();
         for provider in &self.providers {
+            let refreshed = provider.refresh_models().await.unwrap_or_else(|e| {
+                warn!(provider = provider.name(), error = %e, "Model refresh failed");
+                provider.available_models().to_vec()
+            });
+            for model in refreshed {
+                if !models.contains(&model) {
+                    models.push(model);
+                }
+            }
+        }
+        Ok(models)
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        self.complete_in_order(request, cancel)
+            .instrument(request.metadata.span("fallback"))
+            .await
+            .map(|response| response.with_request_metadata(request))
+    }
+
+    async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+        self.stream_in_order(request)
+            .instrument(request.metadata.span("fallback"))
+            .await
+    }
+
+    async fn health_check(&self) -> Result<bool, RunnerError> {
+        for (provider, breaker) in self.providers.iter().zip(&self.breakers) {
+            if breaker.state() == CircuitState::Open {
+                continue;
+            }
             if matches!(provider.health_check().await, Ok(true)) {
                 return Ok(true);
             }
@@ -242,7 +504,7 @@ mod tests {
     use super::*;
     use crate::types::{
         ChatMessage, ChatRequest, ChatResponse, ChatStream, ErrorKind, LlmCapabilities,
-        LlmProvider, RunnerError,
+        LlmProvider, RequestMetadata, RunnerError,
     };
     use async_trait::async_trait;
     use std::sync::atomic::{AtomicU32, Ordering};
@@ -256,6 +518,7 @@ mod tests {
         responses: Mutex<Vec<Result<ChatResponse, RunnerError>>>,
         call_count: AtomicU32,
         healthy: bool,
+        delay: Duration,
     }
 
     impl TestProvider {
@@ -272,9 +535,13 @@ mod tests {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 })]),
                 call_count: AtomicU32::new(0),
                 healthy: true,
+                delay: Duration::ZERO,
             }
         }
 
@@ -286,6 +553,7 @@ mod tests {
             let err = RunnerError {
                 kind,
                 message: format!("{name}: down"),
+                retry_after: None,
             };
             Self {
                 provider_name: name,
@@ -295,6 +563,7 @@ mod tests {
                 responses: Mutex::new(vec![Err(err)]),
                 call_count: AtomicU32::new(0),
                 healthy: false,
+                delay: Duration::ZERO,
             }
         }
 
@@ -310,8 +579,14 @@ mod tests {
                 responses: Mutex::new(responses),
                 call_count: AtomicU32::new(0),
                 healthy: true,
+                delay: Duration::ZERO,
             }
         }
+
+        fn with_delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
     }
 
     fn make_response(content: &str) -> ChatResponse {
@@ -322,6 +597,9 @@ mod tests {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         }
     }
 
@@ -344,6 +622,7 @@ mod tests {
         }
         async fn complete(&self, _request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
             self.call_count.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
             let mut responses = self.responses.lock().expect("test lock");
             if responses.is_empty() {
                 Err(RunnerError::internal("no more responses"))
@@ -399,6 +678,14 @@ mod tests {
             .await
             .expect("second should work");
         assert_eq!(response.content, "fallback response");
+        assert_eq!(
+            response
+                .metadata
+                .tags
+                .get("fallback_provider")
+                .map(String::as_str),
+            Some("secondary")
+        );
     }
 
     #[tokio::test]
@@ -470,6 +757,7 @@ mod tests {
             responses: Mutex::new(vec![]),
             call_count: AtomicU32::new(0),
             healthy: true,
+            delay: Duration::ZERO,
         };
         let b = TestProvider {
             provider_name: "b",
@@ -479,6 +767,7 @@ mod tests {
             responses: Mutex::new(vec![]),
             call_count: AtomicU32::new(0),
             healthy: true,
+            delay: Duration::ZERO,
         };
 
         let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(a), Box::new(b)];
@@ -489,6 +778,10 @@ mod tests {
         assert!(models.contains(&"shared-model".to_owned()));
         assert!(models.contains(&"a-only".to_owned()));
         assert!(models.contains(&"b-only".to_owned()));
+
+        let catalog = fallback.model_catalog();
+        let ids: Vec<&str> = catalog.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["shared-model", "a-only", "b-only"]);
     }
 
     // ========================================================================
@@ -570,6 +863,78 @@ mod tests {
         assert_eq!(response.content, "secondary response");
     }
 
+    #[tokio::test]
+    async fn rate_limit_waits_for_retry_after_hint() {
+        let provider = TestProvider::with_responses(
+            "alpha",
+            vec![
+                Err(RunnerError::rate_limited("slow down")
+                    .with_retry_after(Some(Duration::from_millis(30)))),
+                Ok(make_response("after wait")),
+            ],
+        );
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(provider)];
+        let retry = RetryConfig {
+            max_retries: 1,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_secs(1),
+        };
+        let fallback = FallbackProvider::with_retry(providers, retry).expect("non-empty");
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let started = std::time::Instant::now();
+        let response = fallback.complete(&request).await.expect("should recover");
+        assert_eq!(response.content, "after wait");
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn long_retry_after_moves_to_next_provider() {
+        let primary = TestProvider::with_responses(
+            "primary",
+            vec![
+                Err(RunnerError::rate_limited("slow down")
+                    .with_retry_after(Some(Duration::from_mins(1)))),
+                Ok(make_response("should not reach")),
+            ],
+        );
+        let secondary = TestProvider::ok("secondary", "secondary response");
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(primary), Box::new(secondary)];
+        let retry = RetryConfig {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+        };
+        let fallback = FallbackProvider::with_retry(providers, retry).expect("non-empty");
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let response = fallback.complete(&request).await.expect("secondary");
+        assert_eq!(response.content, "secondary response");
+    }
+
+    #[tokio::test]
+    async fn context_overflow_skips_retries() {
+        let primary = TestProvider::with_responses(
+            "primary",
+            vec![
+                Err(RunnerError::context_length_exceeded("prompt too long")),
+                Ok(make_response("should not reach")),
+            ],
+        );
+        let secondary = TestProvider::ok("secondary", "bigger window");
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(primary), Box::new(secondary)];
+        let retry = RetryConfig {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+        };
+        let fallback = FallbackProvider::with_retry(providers, retry).expect("non-empty");
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let response = fallback.complete(&request).await.expect("secondary");
+        assert_eq!(response.content, "bigger window");
+    }
+
     #[tokio::test]
     async fn zero_retries_matches_original_behavior() {
         let provider = TestProvider::with_responses(
@@ -591,6 +956,53 @@ mod tests {
         assert_eq!(err.kind, ErrorKind::Timeout);
     }
 
+    #[tokio::test]
+    async fn cancelled_error_does_not_fall_through() {
+        let primary = TestProvider::with_responses(
+            "primary",
+            vec![Err(RunnerError::cancelled("primary: cancelled"))],
+        );
+        let backup = TestProvider::ok("backup", "should not reach");
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(primary), Box::new(backup)];
+        let fallback = FallbackProvider::new(providers).expect("non-empty");
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let err = fallback.complete(&request).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn cancel_token_aborts_retry_backoff() {
+        let provider = TestProvider::with_responses(
+            "alpha",
+            vec![
+                Err(RunnerError::timeout("t1")),
+                Ok(make_response("should not reach")),
+            ],
+        );
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(provider)];
+        let retry = RetryConfig {
+            max_retries: 1,
+            base_delay: Duration::from_mins(1),
+            max_delay: Duration::from_mins(1),
+        };
+        let fallback = FallbackProvider::with_retry(providers, retry).expect("non-empty");
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            trigger.cancel();
+        });
+
+        let err = fallback
+            .complete_with_cancel(&request, &cancel)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+    }
+
     #[test]
     fn backoff_respects_max_delay() {
         let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(TestProvider::ok("a", "ok"))];
@@ -638,4 +1050,122 @@ mod tests {
             Ok(_) => panic!("expected error"),
         }
     }
+
+    #[tokio::test]
+    async fn deadline_is_shared_across_providers() {
+        let primary = TestProvider::failing("primary").with_delay(Duration::from_millis(100));
+        let secondary = TestProvider::ok("secondary", "too late");
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(primary), Box::new(secondary)];
+        let fallback = FallbackProvider::new(providers).expect("non-empty");
+        let request =
+            ChatRequest::new(vec![ChatMessage::user("hi")]).with_timeout(Duration::from_millis(50));
+
+        let err = fallback.complete(&request).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Timeout);
+        assert!(err.message.contains("secondary"));
+    }
+
+    #[tokio::test]
+    async fn backoff_past_deadline_moves_to_next_provider() {
+        let primary = TestProvider::with_responses(
+            "primary",
+            vec![
+                Err(RunnerError::external_service("primary", "503")),
+                Ok(make_response("should not reach")),
+            ],
+        );
+        let secondary = TestProvider::ok("secondary", "secondary response");
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(primary), Box::new(secondary)];
+        let retry = RetryConfig {
+            max_retries: 3,
+            base_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(10),
+        };
+        let fallback = FallbackProvider::with_retry(providers, retry).expect("non-empty");
+        let request =
+            ChatRequest::new(vec![ChatMessage::user("hi")]).with_timeout(Duration::from_secs(1));
+
+        let response = fallback.complete(&request).await.expect("secondary");
+        assert_eq!(response.content, "secondary response");
+    }
+
+    /// Opens a stream that fails after its first chunk
+    struct BrokenStreamProvider;
+
+    #[async_trait]
+    impl LlmProvider for BrokenStreamProvider {
+        fn name(&self) -> &'static str {
+            "broken"
+        }
+        fn display_name(&self) -> &str {
+            "Broken"
+        }
+        fn capabilities(&self) -> LlmCapabilities {
+            LlmCapabilities::STREAMING
+        }
+        fn default_model(&self) -> &'static str {
+            "broken-model"
+        }
+        fn available_models(&self) -> &[String] {
+            &[]
+        }
+        async fn complete(&self, _request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+            Err(RunnerError::internal("not used"))
+        }
+        async fn complete_stream(&self, _request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+            let chunks = vec![
+                Ok(StreamChunk {
+                    delta: "partial".to_owned(),
+                    ..StreamChunk::default()
+                }),
+                Err(RunnerError::external_service("broken", "connection reset")),
+            ];
+            Ok(Box::pin(tokio_stream::iter(chunks)))
+        }
+        async fn health_check(&self) -> Result<bool, RunnerError> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn mid_stream_error_counts_against_provider() {
+        use tokio_stream::StreamExt;
+
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(BrokenStreamProvider)];
+        let fallback = FallbackProvider::new(providers)
+            .expect("non-empty")
+            .with_circuit_breaker(
+                CircuitBreakerConfig::disabled()
+                    .with_failure_threshold(1)
+                    .with_cooldown(Duration::from_mins(1)),
+            );
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+
+        let Ok(mut stream) = fallback.complete_stream(&request).await else {
+            panic!("stream should open");
+        };
+        assert_eq!(fallback.circuit_states()[0].1, CircuitState::Closed);
+        while stream.next().await.is_some() {}
+        assert_eq!(fallback.circuit_states()[0].1, CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn caller_deadline_timeout_leaves_circuit_closed() {
+        let primary = TestProvider::failing_with_kind("primary", ErrorKind::Timeout)
+            .with_delay(Duration::from_millis(100));
+        let providers: Vec<Box<dyn LlmProvider>> = vec![Box::new(primary)];
+        let fallback = FallbackProvider::new(providers)
+            .expect("non-empty")
+            .with_circuit_breaker(
+                CircuitBreakerConfig::disabled()
+                    .with_failure_threshold(1)
+                    .with_cooldown(Duration::from_mins(1)),
+            );
+        let request =
+            ChatRequest::new(vec![ChatMessage::user("hi")]).with_timeout(Duration::from_millis(20));
+
+        let err = fallback.complete(&request).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Timeout);
+        assert_eq!(fallback.circuit_states()[0].1, CircuitState::Closed);
+    }
 }
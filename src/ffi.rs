@@ -577,10 +577,17 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 5,
                 total_tokens: 15,
+                reasoning_tokens: None,
+                cached_prompt_tokens: None,
+                cache_creation_tokens: None,
+                reported_cost: None,
             }),
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: crate::types::RequestMetadata::default(),
         };
         let json = build_response_json(&response);
         let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
@@ -602,6 +609,9 @@ mod tests {
             finish_reason: None,
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: crate::types::RequestMetadata::default(),
         };
         let json = build_response_json(&response);
         let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
@@ -5,13 +5,12 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::io;
-use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
-    TokenUsage,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, StreamChunk, TokenUsage,
 };
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -19,13 +18,13 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
-use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::prepare_user_prompt;
+use crate::config::{CliRunnerType, PromptDelivery, RunnerConfig};
+use crate::process::read_stderr_capped;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Gemini CLI JSON response structure (`-o json`)
 #[derive(Debug, Deserialize)]
@@ -46,6 +45,25 @@ struct GeminiStats {
     input_tokens: Option<u32>,
     #[serde(default)]
     output_tokens: Option<u32>,
+    /// Prompt tokens served from the context cache
+    #[serde(default)]
+    cached: Option<u32>,
+}
+
+impl From<GeminiStats> for TokenUsage {
+    fn from(s: GeminiStats) -> Self {
+        let input = s.input_tokens.unwrap_or(0);
+        let output = s.output_tokens.unwrap_or(0);
+        Self {
+            prompt_tokens: input,
+            completion_tokens: output,
+            total_tokens: s.total_tokens.unwrap_or(input + output),
+            reasoning_tokens: None,
+            cached_prompt_tokens: s.cached,
+            cache_creation_tokens: None,
+            reported_cost: None,
+        }
+    }
 }
 
 /// Default model for Gemini CLI
@@ -72,7 +90,7 @@ impl GeminiCliRunner {
         }
     }
 
-    /// Store a session ID for later resumption
+    /// Store a session ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
@@ -80,7 +98,11 @@ impl GeminiCliRunner {
     /// Build the base command with common arguments
     fn build_command(&self, prompt: &str, output_format: &str) -> Command {
         let mut cmd = Command::new(&self.base.config.binary_path);
-        cmd.args(["-p", prompt, "-o", output_format]);
+        // Gemini runs non-interactively on piped stdin, taking it as the prompt
+        if self.base.prompt_delivery(CliRunnerType::GeminiCli) == PromptDelivery::Argv {
+            cmd.args(["-p", prompt]);
+        }
+        cmd.args(["-o", output_format]);
 
         // -y (yolo mode) auto-approves tool usage
         cmd.arg("-y");
@@ -118,16 +140,7 @@ impl GeminiCliRunner {
         // Try single JSON object first
         if let Ok(parsed) = serde_json::from_str::<GeminiResponse>(text) {
             let content = parsed.response.unwrap_or_default();
-            let usage = parsed.stats.map(|s| {
-                let input = s.input_tokens.unwrap_or(0);
-                let output = s.output_tokens.unwrap_or(0);
-                let total = s.total_tokens.unwrap_or(input + output);
-                TokenUsage {
-                    prompt_tokens: input,
-                    completion_tokens: output,
-                    total_tokens: total,
-                }
-            });
+            let usage = parsed.stats.map(TokenUsage::from);
             return Ok((
                 ChatResponse {
                     content,
@@ -136,6 +149,9 @@ impl GeminiCliRunner {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 },
                 parsed.session_id,
             ));
@@ -172,27 +188,11 @@ impl GeminiCliRunner {
                     }
                 }
                 "result" => {
-                    if let Some(stats) = value.get("stats") {
-                        let input = stats
-                            .get("input_tokens")
-                            .and_then(serde_json::Value::as_u64)
-                            .unwrap_or(0);
-                        let output = stats
-                            .get("output_tokens")
-                            .and_then(serde_json::Value::as_u64)
-                            .unwrap_or(0);
-                        let total = stats
-                            .get("total_tokens")
-                            .and_then(serde_json::Value::as_u64)
-                            .unwrap_or(input + output);
-                        #[allow(clippy::cast_possible_truncation)]
-                        {
-                            usage = Some(TokenUsage {
-                                prompt_tokens: input as u32,
-                                completion_tokens: output as u32,
-                                total_tokens: total as u32,
-                            });
-                        }
+                    if let Some(stats) = value
+                        .get("stats")
+                        .and_then(|s| serde_json::from_value::<GeminiStats>(s.clone()).ok())
+                    {
+                        usage = Some(stats.into());
                     }
                 }
                 _ => {}
@@ -208,6 +208,9 @@ impl GeminiCliRunner {
                 finish_reason: Some("stop".to_owned()),
                 warnings: None,
                 tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
             },
             session_id,
         ))
@@ -222,26 +225,43 @@ impl LlmProvider for GeminiCliRunner {
         LlmCapabilities::STREAMING | LlmCapabilities::TEMPERATURE | LlmCapabilities::MAX_TOKENS
     );
 
-    #[instrument(skip_all, fields(runner = "gemini"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "gemini"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt, "json");
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--resume", &sid]);
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(
+                request,
+                cmd,
+                self.base.prompt_stdin(CliRunnerType::GeminiCli, prompt),
+                cancel,
+            )
+            .await?;
         self.base.check_exit_code(&output, "gemini")?;
 
         let (response, session_id) = Self::parse_jsonl_response(&output.stdout)?;
 
         if let Some(sid) = session_id {
-            if let Some(model) = &request.model {
-                self.base.set_session(model, &sid).await;
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &sid).await;
             }
         }
 
@@ -250,22 +270,21 @@ impl LlmProvider for GeminiCliRunner {
 
     #[instrument(skip_all, fields(runner = "gemini"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt, "stream-json");
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--resume", &sid]);
             }
         }
 
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn gemini for streaming: {e}"))
-        })?;
+        let (mut child, cleanup) = self.base.spawn_streaming(
+            cmd,
+            self.base.prompt_stdin(CliRunnerType::GeminiCli, prompt),
+            "gemini",
+        )?;
 
         let stdout = child.stdout.take().ok_or_else(|| {
             RunnerError::internal("Failed to capture gemini stdout for streaming")
@@ -284,11 +303,7 @@ impl LlmProvider for GeminiCliRunner {
                 .map_err(|e| RunnerError::internal(format!("Error reading gemini stream: {e}")))?;
 
             if line.trim().is_empty() {
-                return Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                });
+                return Ok(StreamChunk::default());
             }
 
             let value: serde_json::Value = serde_json::from_str(&line).map_err(|e| {
@@ -306,31 +321,26 @@ impl LlmProvider for GeminiCliRunner {
                                 .and_then(|v| v.as_str())
                                 .unwrap_or("")
                                 .to_owned(),
-                            is_final: false,
-                            finish_reason: None,
+                            ..StreamChunk::default()
                         })
                     } else {
-                        Ok(StreamChunk {
-                            delta: String::new(),
-                            is_final: false,
-                            finish_reason: None,
-                        })
+                        Ok(StreamChunk::default())
                     }
                 }
                 "result" => Ok(StreamChunk {
-                    delta: String::new(),
                     is_final: true,
                     finish_reason: Some("stop".to_owned()),
+                    ..StreamChunk::default()
                 }),
-                _ => Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                }),
+                _ => Ok(StreamChunk::default()),
             }
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
 
@@ -366,7 +376,7 @@ mod tests {
 {\"type\":\"init\",\"session_id\":\"sess-42\",\"model\":\"auto-gemini-3\"}
 {\"type\":\"message\",\"role\":\"user\",\"content\":\"hi\"}
 {\"type\":\"message\",\"role\":\"assistant\",\"content\":\"hello from gemini\",\"delta\":true}
-{\"type\":\"result\",\"status\":\"success\",\"stats\":{\"total_tokens\":8628,\"input_tokens\":100,\"output_tokens\":50}}
+{\"type\":\"result\",\"status\":\"success\",\"stats\":{\"total_tokens\":8628,\"input_tokens\":100,\"output_tokens\":50,\"cached\":40}}
 ";
         let (resp, sid) = GeminiCliRunner::parse_jsonl_response(jsonl).unwrap();
         assert_eq!(resp.content, "hello from gemini");
@@ -375,6 +385,7 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 100);
         assert_eq!(usage.completion_tokens, 50);
         assert_eq!(usage.total_tokens, 8628);
+        assert_eq!(usage.cached_prompt_tokens, Some(40));
     }
 
     #[test]
@@ -5,26 +5,26 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::io;
-use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, StreamChunk,
 };
 use async_trait::async_trait;
 use tempfile::NamedTempFile;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
-use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::prepare_user_prompt;
+use crate::config::{CliRunnerType, PromptDelivery, RunnerConfig};
+use crate::process::read_stderr_capped;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Default model for Goose CLI (provider-agnostic)
 const DEFAULT_MODEL: &str = "auto";
@@ -51,13 +51,21 @@ impl GooseCliRunner {
         }
     }
 
-    /// Store a session ID for later resumption
+    /// Store a session ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
 
-    /// Build the base command with common arguments (without prompt delivery)
-    fn build_command_base(&self, output_format: &str) -> Command {
+    /// Build the command with common arguments and prompt delivery
+    ///
+    /// Without stdin delivery the prompt is written to a temp file read via
+    /// `-i <path>`, keeping it out of argv; the returned file must outlive the
+    /// process.
+    fn build_command(
+        &self,
+        prompt: &str,
+        output_format: &str,
+    ) -> Result<(Command, Option<NamedTempFile>), RunnerError> {
         let mut cmd = Command::new(&self.base.config.binary_path);
         cmd.args([
             "run",
@@ -66,6 +74,25 @@ impl GooseCliRunner {
             "--output-format",
             output_format,
         ]);
+        // `-i -` reads the instructions from stdin, `-i <path>` from a file
+        let prompt_file = match self.base.prompt_delivery(CliRunnerType::GooseCli) {
+            PromptDelivery::Stdin => {
+                cmd.args(["-i", "-"]);
+                None
+            }
+            PromptDelivery::Argv => {
+                let mut file = NamedTempFile::new().map_err(|e| {
+                    RunnerError::internal(format!(
+                        "Failed to create temp file for Goose prompt: {e}"
+                    ))
+                })?;
+                io::Write::write_all(&mut file, prompt.as_bytes()).map_err(|e| {
+                    RunnerError::internal(format!("Failed to write Goose prompt to temp file: {e}"))
+                })?;
+                cmd.arg("-i").arg(file.path());
+                Some(file)
+            }
+        };
 
         for arg in &self.base.config.extra_args {
             cmd.arg(arg);
@@ -78,7 +105,7 @@ impl GooseCliRunner {
             apply_sandbox(&mut cmd, &policy);
         }
 
-        cmd
+        Ok((cmd, prompt_file))
     }
 
     /// Parse a JSON response from `goose run --output-format json`
@@ -125,6 +152,9 @@ impl GooseCliRunner {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })
     }
 }
@@ -133,29 +163,37 @@ impl GooseCliRunner {
 impl LlmProvider for GooseCliRunner {
     crate::delegate_provider_base!("goose", "Goose CLI", LlmCapabilities::STREAMING);
 
-    #[instrument(skip_all, fields(runner = "goose"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
-        let prompt = &prepared.prompt;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
 
-        // Write prompt to a temp file since Goose reads from `-i <path>`
-        let mut prompt_file = NamedTempFile::new().map_err(|e| {
-            RunnerError::internal(format!("Failed to create temp file for Goose prompt: {e}"))
-        })?;
-        std::io::Write::write_all(&mut prompt_file, prompt.as_bytes()).map_err(|e| {
-            RunnerError::internal(format!("Failed to write Goose prompt to temp file: {e}"))
-        })?;
+    #[instrument(skip_all, fields(runner = "goose"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
+        let prompt = &prepared.prompt;
 
-        let mut cmd = self.build_command_base("json");
-        cmd.args(["-i", &prompt_file.path().display().to_string()]);
+        let (mut cmd, _prompt_file) = self.build_command(prompt, "json")?;
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--session-id", &sid, "--resume"]);
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(
+                request,
+                cmd,
+                self.base.prompt_stdin(CliRunnerType::GooseCli, prompt),
+                cancel,
+            )
+            .await?;
         self.base.check_exit_code(&output, "goose")?;
 
         Self::parse_json_response(&output.stdout)
@@ -163,36 +201,22 @@ impl LlmProvider for GooseCliRunner {
 
     #[instrument(skip_all, fields(runner = "goose"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let prepared = prepare_user_prompt(&request.messages)?;
+        let prepared = self.base.prepare_user_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
 
-        let mut cmd = self.build_command_base("stream-json");
-        cmd.args(["-i", "-"]);
+        let (mut cmd, prompt_file) = self.build_command(prompt, "stream-json")?;
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--session-id", &sid, "--resume"]);
             }
         }
 
-        cmd.stdin(Stdio::piped());
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn goose for streaming: {e}"))
-        })?;
-
-        // Write prompt to stdin then close it
-        let mut stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| RunnerError::internal("Failed to capture goose stdin for streaming"))?;
-        let prompt_owned = prompt.to_owned();
-        tokio::spawn(async move {
-            let _ = stdin.write_all(prompt_owned.as_bytes()).await;
-            let _ = stdin.shutdown().await;
-        });
+        let (mut child, cleanup) = self.base.spawn_streaming(
+            cmd,
+            self.base.prompt_stdin(CliRunnerType::GooseCli, prompt),
+            "goose",
+        )?;
 
         let stdout = child
             .stdout
@@ -208,15 +232,13 @@ impl LlmProvider for GooseCliRunner {
         let lines = LinesStream::new(reader.lines());
 
         let stream = lines.map(move |line_result: Result<String, io::Error>| {
+            // Keep the prompt file alive for as long as goose may read it
+            let _prompt_file = &prompt_file;
             let line = line_result
                 .map_err(|e| RunnerError::internal(format!("Error reading goose stream: {e}")))?;
 
             if line.trim().is_empty() {
-                return Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                });
+                return Ok(StreamChunk::default());
             }
 
             let value: serde_json::Value = serde_json::from_str(&line)
@@ -241,24 +263,23 @@ impl LlmProvider for GooseCliRunner {
                     }
                     Ok(StreamChunk {
                         delta,
-                        is_final: false,
-                        finish_reason: None,
+                        ..StreamChunk::default()
                     })
                 }
                 "complete" => Ok(StreamChunk {
-                    delta: String::new(),
                     is_final: true,
                     finish_reason: Some("stop".to_owned()),
+                    ..StreamChunk::default()
                 }),
-                _ => Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                }),
+                _ => Ok(StreamChunk::default()),
             }
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
 
@@ -310,4 +331,20 @@ mod tests {
         assert_eq!(runner.name(), "goose");
         assert_eq!(runner.display_name(), "Goose CLI");
     }
+
+    #[test]
+    fn argv_delivery_uses_prompt_file() {
+        let config =
+            RunnerConfig::new(PathBuf::from("goose")).with_prompt_delivery(PromptDelivery::Argv);
+        let runner = GooseCliRunner::new(config);
+        let (cmd, file) = runner.build_command("secret prompt", "json").unwrap();
+        let file = file.expect("prompt file");
+        let args: Vec<_> = cmd.as_std().get_args().collect();
+        assert!(args.iter().all(|a| *a != "secret prompt"));
+        assert!(args.contains(&file.path().as_os_str()));
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            "secret prompt"
+        );
+    }
 }
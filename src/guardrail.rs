@@ -23,8 +23,10 @@
 //!   since the full content is not available upfront.
 
 use async_trait::async_trait;
-use tracing::warn;
+use tokio_util::sync::CancellationToken;
+use tracing::{warn, Instrument};
 
+use crate::model_catalog::ModelInfo;
 use crate::types::{
     ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
 };
@@ -144,17 +146,39 @@ impl LlmProvider for GuardrailProvider {
         self.inner.available_models()
     }
 
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        self.inner.model_catalog()
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        self.inner.refresh_models().await
+    }
+
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        self.check_all_requests(request)?;
-        let response = self.inner.complete(request).await?;
-        self.check_all_responses(request, &response)?;
-        Ok(response)
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        async {
+            self.check_all_requests(request)?;
+            let response = self.inner.complete_with_cancel(request, cancel).await?;
+            self.check_all_responses(request, &response)?;
+            Ok(response.with_request_metadata(request))
+        }
+        .instrument(request.metadata.span("guardrail"))
+        .await
     }
 
     /// Pre-request guardrails run; post-response checks are skipped (documented limitation)
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        self.check_all_requests(request)?;
-        self.inner.complete_stream(request).await
+        let span = request.metadata.span("guardrail");
+        span.in_scope(|| self.check_all_requests(request))?;
+        self.inner.complete_stream(request).instrument(span).await
     }
 
     async fn health_check(&self) -> Result<bool, RunnerError> {
@@ -361,7 +385,7 @@ mod tests {
     use super::*;
     use crate::types::{
         ChatMessage, ChatRequest, ChatResponse, ChatStream, ErrorKind, LlmCapabilities,
-        LlmProvider, RunnerError,
+        LlmProvider, RequestMetadata, RunnerError,
     };
     use async_trait::async_trait;
     use std::sync::atomic::{AtomicU32, Ordering};
@@ -382,6 +406,9 @@ mod tests {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 })]),
                 call_count: AtomicU32::new(0),
             }
@@ -416,6 +443,9 @@ mod tests {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 })
             } else {
                 responses.remove(0)
@@ -484,6 +514,9 @@ mod tests {
             finish_reason: None,
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
         // ContentLengthGuardrail does not check responses
         assert!(guard.check_response(&request, &response).is_ok());
@@ -535,6 +568,9 @@ mod tests {
             finish_reason: None,
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
         assert!(guard.check_response(&request, &response).is_err());
     }
@@ -596,6 +632,9 @@ mod tests {
             finish_reason: None,
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
         assert!(guard.check_response(&request, &response).is_err());
     }
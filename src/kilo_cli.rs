@@ -5,13 +5,12 @@
 // Copyright (c) 2026 dravr.ai
 
 use std::io;
-use std::process::Stdio;
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, StreamChunk,
-    TokenUsage,
+    ChatRequest, ChatResponse, ChatStream, ErrorKind, LlmCapabilities, LlmProvider,
+    RequestMetadata, RunnerError, StreamChunk, TokenUsage,
 };
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -19,13 +18,15 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio_stream::wrappers::LinesStream;
 use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
-use crate::config::RunnerConfig;
-use crate::process::{read_stderr_capped, run_cli_command};
-use crate::prompt::prepare_prompt;
+use crate::config::{CliRunnerType, PromptDelivery, RunnerConfig};
+use crate::error_classify::{classify_error, parse_retry_after};
+use crate::model_discovery::ModelDiscovery;
+use crate::process::read_stderr_capped;
 use crate::sandbox::{apply_sandbox, build_policy};
-use crate::stream::{GuardedStream, MAX_STREAMING_STDERR_BYTES};
+use crate::stream::{GuardedStream, StreamTimeouts, MAX_STREAMING_STDERR_BYTES};
 
 /// Default model for Kilo CLI (uses Kilo Gateway routing)
 const DEFAULT_MODEL: &str = "anthropic/claude-sonnet-4-6";
@@ -44,6 +45,39 @@ struct KiloTokens {
     output: Option<u64>,
     reasoning: Option<u64>,
     total: Option<u64>,
+    cache: Option<KiloCacheTokens>,
+}
+
+/// Prompt-cache token counts nested under `tokens.cache`
+#[derive(Debug, Deserialize)]
+struct KiloCacheTokens {
+    read: Option<u64>,
+    write: Option<u64>,
+}
+
+impl KiloTokens {
+    /// Read the token counts and `part.cost` from a `step_finish` event, if present
+    fn from_event(value: &serde_json::Value) -> Option<TokenUsage> {
+        let t = serde_json::from_value::<Self>(value.pointer("/part/tokens")?.clone()).ok()?;
+        let input = t.input.unwrap_or(0);
+        let output = t.output.unwrap_or(0);
+        let reasoning = t.reasoning.unwrap_or(0);
+        let total = t
+            .total
+            .unwrap_or_else(|| input.saturating_add(output).saturating_add(reasoning));
+        let count = |n: u64| u32::try_from(n).unwrap_or(u32::MAX);
+        Some(TokenUsage {
+            prompt_tokens: count(input),
+            completion_tokens: count(output),
+            total_tokens: count(total),
+            reasoning_tokens: t.reasoning.map(count),
+            cached_prompt_tokens: t.cache.as_ref().and_then(|c| c.read).map(count),
+            cache_creation_tokens: t.cache.as_ref().and_then(|c| c.write).map(count),
+            reported_cost: value
+                .pointer("/part/cost")
+                .and_then(serde_json::Value::as_f64),
+        })
+    }
 }
 
 /// Kilo Code CLI runner
@@ -61,11 +95,12 @@ impl KiloCliRunner {
     #[must_use]
     pub fn new(config: RunnerConfig) -> Self {
         Self {
-            base: CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS),
+            base: CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS)
+                .with_model_discovery(ModelDiscovery::subcommand(&["models"])),
         }
     }
 
-    /// Store a session ID for later resumption
+    /// Store a session ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
@@ -73,7 +108,11 @@ impl KiloCliRunner {
     /// Build the command with all arguments
     fn build_command(&self, prompt: &str) -> Command {
         let mut cmd = Command::new(&self.base.config.binary_path);
-        cmd.args(["run", "--auto", "--format", "json", prompt]);
+        cmd.args(["run", "--auto", "--format", "json"]);
+        // `kilo run` takes piped stdin as the message
+        if self.base.prompt_delivery(CliRunnerType::KiloCli) == PromptDelivery::Argv {
+            cmd.arg(prompt);
+        }
 
         let model = self
             .base
@@ -97,13 +136,97 @@ impl KiloCliRunner {
         cmd
     }
 
+    /// Map a Kilo `error` event to a typed [`RunnerError`]
+    ///
+    /// `APIError` carries the upstream HTTP `statusCode` and an `isRetryable`
+    /// flag; `ContextOverflowError` marks a prompt larger than the model's
+    /// context window. Anything else falls back to message classification.
+    fn classify_error_event(value: &serde_json::Value) -> RunnerError {
+        let name = value
+            .pointer("/error/name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown error");
+        let message = value
+            .pointer("/error/data/message")
+            .and_then(|v| v.as_str())
+            .unwrap_or(name);
+        let status = value
+            .pointer("/error/data/statusCode")
+            .and_then(serde_json::Value::as_u64);
+        let retryable = value
+            .pointer("/error/data/isRetryable")
+            .and_then(serde_json::Value::as_bool);
+        let detail = format!("kilo: {message}");
+
+        match (name, status) {
+            ("ContextOverflowError", _) => RunnerError::context_length_exceeded(detail),
+            (_, Some(429)) => RunnerError::rate_limited(detail)
+                .with_retry_after(parse_retry_after(&value.to_string())),
+            (_, Some(401 | 403)) => RunnerError::auth_failure(detail),
+            (_, Some(402)) => RunnerError::quota_exhausted(detail),
+            _ => {
+                let err = classify_error("kilo", message);
+                let client_error = status.is_some_and(|s| (400..500).contains(&s));
+                if err.kind == ErrorKind::ExternalService
+                    && retryable == Some(false)
+                    && client_error
+                {
+                    RunnerError::config(detail)
+                } else {
+                    err
+                }
+            }
+        }
+    }
+
+    /// Convert one NDJSON event into a stream chunk
+    ///
+    /// `text` parts become content deltas, `reasoning` parts reasoning
+    /// deltas, and `step_finish` ends the stream with its token usage.
+    fn parse_stream_event(value: &serde_json::Value) -> Result<StreamChunk, RunnerError> {
+        let part_text = || {
+            value
+                .pointer("/part/text")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_owned()
+        };
+        let line_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        match line_type {
+            "text" => Ok(StreamChunk {
+                delta: part_text(),
+                ..StreamChunk::default()
+            }),
+            "reasoning" => Ok(StreamChunk {
+                reasoning_delta: Some(part_text()),
+                ..StreamChunk::default()
+            }),
+            "step_finish" => {
+                let reason = value
+                    .pointer("/part/reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("stop")
+                    .to_owned();
+                Ok(StreamChunk {
+                    is_final: true,
+                    finish_reason: Some(reason),
+                    usage: KiloTokens::from_event(value),
+                    ..StreamChunk::default()
+                })
+            }
+            "error" => Err(Self::classify_error_event(value)),
+            _ => Ok(StreamChunk::default()),
+        }
+    }
+
     /// Parse NDJSON output from `kilo run --format json`
     ///
     /// Kilo emits NDJSON lines with `type` discriminator:
     /// - `text` — content in `part.text`
+    /// - `reasoning` — reasoning text in `part.text`, kept separate from content
     /// - `step_finish` — finish reason in `part.reason`, token counts in `part.tokens`, cost in `part.cost`
     /// - `error` — error info in `error.name` and `error.data.message`
-    /// - `step_start`, `tool_use`, `reasoning` — ignored
+    /// - `step_start`, `tool_use` — ignored
     ///
     /// The `sessionID` from any line is captured for session resumption.
     fn parse_ndjson_response(raw: &[u8]) -> Result<(ChatResponse, Option<String>), RunnerError> {
@@ -112,10 +235,11 @@ impl KiloCliRunner {
         })?;
 
         let mut content_parts: Vec<String> = Vec::new();
+        let mut reasoning_parts: Vec<String> = Vec::new();
         let mut usage: Option<TokenUsage> = None;
         let mut session_id: Option<String> = None;
         let mut finish_reason: Option<String> = None;
-        let mut error_message: Option<String> = None;
+        let mut error: Option<RunnerError> = None;
 
         for line in text.lines() {
             let trimmed = line.trim();
@@ -141,43 +265,29 @@ impl KiloCliRunner {
                         content_parts.push(t.to_owned());
                     }
                 }
+                "reasoning" => {
+                    if let Some(t) = value.pointer("/part/text").and_then(|v| v.as_str()) {
+                        reasoning_parts.push(t.to_owned());
+                    }
+                }
                 "step_finish" => {
                     if let Some(reason) = value.pointer("/part/reason").and_then(|v| v.as_str()) {
                         finish_reason = Some(reason.to_owned());
                     }
-                    if let Some(tokens) = value.pointer("/part/tokens") {
-                        if let Ok(t) = serde_json::from_value::<KiloTokens>(tokens.clone()) {
-                            #[allow(clippy::cast_possible_truncation)]
-                            {
-                                let input = t.input.unwrap_or(0);
-                                let output = t.output.unwrap_or(0);
-                                let reasoning = t.reasoning.unwrap_or(0);
-                                let total = t.total.unwrap_or(input + output + reasoning);
-                                usage = Some(TokenUsage {
-                                    prompt_tokens: input as u32,
-                                    completion_tokens: output as u32,
-                                    total_tokens: total as u32,
-                                });
-                            }
-                        }
+                    if let Some(u) = KiloTokens::from_event(&value) {
+                        usage = Some(u);
                     }
                 }
                 "error" => {
-                    let msg = value
-                        .pointer("/error/data/message")
-                        .and_then(|v| v.as_str())
-                        .or_else(|| value.pointer("/error/name").and_then(|v| v.as_str()));
-                    if let Some(m) = msg {
-                        error_message = Some(m.to_owned());
-                    }
+                    error = Some(Self::classify_error_event(&value));
                 }
                 _ => {}
             }
         }
 
-        if let Some(err) = error_message {
+        if let Some(err) = error {
             if content_parts.is_empty() {
-                return Err(RunnerError::external_service("kilo", err));
+                return Err(err);
             }
         }
 
@@ -190,6 +300,9 @@ impl KiloCliRunner {
             finish_reason: finish_reason.or_else(|| Some("stop".to_owned())),
             warnings: None,
             tool_calls: None,
+            reasoning: (!reasoning_parts.is_empty()).then(|| reasoning_parts.join("")),
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
 
         Ok((response, session_id))
@@ -198,28 +311,57 @@ impl KiloCliRunner {
 
 #[async_trait]
 impl LlmProvider for KiloCliRunner {
-    crate::delegate_provider_base!("kilo", "Kilo Code CLI", LlmCapabilities::STREAMING);
+    crate::delegate_provider_base!(
+        "kilo",
+        "Kilo Code CLI",
+        LlmCapabilities::STREAMING | LlmCapabilities::FILE_INPUT
+    );
 
-    #[instrument(skip_all, fields(runner = "kilo"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "kilo"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt);
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--session", &sid]);
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(
+                request,
+                cmd,
+                self.base.prompt_stdin(CliRunnerType::KiloCli, prompt),
+                cancel,
+            )
+            .await?;
+        if output.exit_code != 0 {
+            // Prefer the typed error event Kilo printed before exiting
+            if let Err(err) = Self::parse_ndjson_response(&output.stdout) {
+                if err.kind != ErrorKind::Internal {
+                    return Err(err);
+                }
+            }
+        }
         self.base.check_exit_code(&output, "kilo")?;
 
         let (response, session_id) = Self::parse_ndjson_response(&output.stdout)?;
 
         if let Some(sid) = session_id {
-            if let Some(model) = &request.model {
-                self.base.set_session(model, &sid).await;
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &sid).await;
             }
         }
 
@@ -228,22 +370,21 @@ impl LlmProvider for KiloCliRunner {
 
     #[instrument(skip_all, fields(runner = "kilo"))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let prepared = prepare_prompt(&request.messages)?;
+        let prepared = self.base.prepare_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt);
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--session", &sid]);
             }
         }
 
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            RunnerError::internal(format!("Failed to spawn kilo for streaming: {e}"))
-        })?;
+        let (mut child, cleanup) = self.base.spawn_streaming(
+            cmd,
+            self.base.prompt_stdin(CliRunnerType::KiloCli, prompt),
+            "kilo",
+        )?;
 
         let stdout = child
             .stdout
@@ -263,67 +404,24 @@ impl LlmProvider for KiloCliRunner {
                 .map_err(|e| RunnerError::internal(format!("Error reading kilo stream: {e}")))?;
 
             if line.trim().is_empty() {
-                return Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                });
+                return Ok(StreamChunk::default());
             }
 
             let value: serde_json::Value = match serde_json::from_str(&line) {
                 Ok(v) => v,
                 Err(_) => {
-                    return Ok(StreamChunk {
-                        delta: String::new(),
-                        is_final: false,
-                        finish_reason: None,
-                    });
+                    return Ok(StreamChunk::default());
                 }
             };
 
-            let line_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
-            match line_type {
-                "text" => {
-                    let delta = value
-                        .pointer("/part/text")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_owned();
-                    Ok(StreamChunk {
-                        delta,
-                        is_final: false,
-                        finish_reason: None,
-                    })
-                }
-                "step_finish" => {
-                    let reason = value
-                        .pointer("/part/reason")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("stop")
-                        .to_owned();
-                    Ok(StreamChunk {
-                        delta: String::new(),
-                        is_final: true,
-                        finish_reason: Some(reason),
-                    })
-                }
-                "error" => {
-                    let msg = value
-                        .pointer("/error/data/message")
-                        .and_then(|v| v.as_str())
-                        .or_else(|| value.pointer("/error/name").and_then(|v| v.as_str()))
-                        .unwrap_or("unknown error");
-                    Err(RunnerError::external_service("kilo", msg))
-                }
-                _ => Ok(StreamChunk {
-                    delta: String::new(),
-                    is_final: false,
-                    finish_reason: None,
-                }),
-            }
+            Self::parse_stream_event(&value)
         });
 
-        Ok(Box::pin(GuardedStream::new(stream, child, stderr_task)))
+        Ok(Box::pin(
+            GuardedStream::new(stream, child, stderr_task)
+                .with_cleanup(cleanup)
+                .with_timeouts(StreamTimeouts::for_request(&self.base.config, request)),
+        ))
     }
 }
 
@@ -336,7 +434,7 @@ mod tests {
     fn test_parse_ndjson_response_basic() {
         let ndjson = br#"{"type":"step_start","timestamp":1710000000000,"sessionID":"ses_kilo1","part":{"type":"step-start"}}
 {"type":"text","timestamp":1710000001000,"sessionID":"ses_kilo1","part":{"type":"text","text":"Hello from Kilo!"}}
-{"type":"step_finish","timestamp":1710000002000,"sessionID":"ses_kilo1","part":{"type":"step-finish","reason":"endTurn","cost":0.0042,"tokens":{"total":1500,"input":1000,"output":400,"reasoning":100}}}"#;
+{"type":"step_finish","timestamp":1710000002000,"sessionID":"ses_kilo1","part":{"type":"step-finish","reason":"endTurn","cost":0.0042,"tokens":{"total":1500,"input":1000,"output":400,"reasoning":100,"cache":{"read":600,"write":0}}}}"#;
 
         let (resp, sid) = KiloCliRunner::parse_ndjson_response(ndjson).unwrap();
         assert_eq!(resp.content, "Hello from Kilo!");
@@ -346,6 +444,10 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 1000);
         assert_eq!(usage.completion_tokens, 400);
         assert_eq!(usage.total_tokens, 1500);
+        assert_eq!(usage.reasoning_tokens, Some(100));
+        assert_eq!(usage.cached_prompt_tokens, Some(600));
+        assert_eq!(usage.cache_creation_tokens, Some(0));
+        assert_eq!(usage.reported_cost, Some(0.0042));
     }
 
     #[test]
@@ -385,6 +487,68 @@ mod tests {
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("Rate limit exceeded"));
+        assert_eq!(err.kind, ErrorKind::RateLimited);
+    }
+
+    #[test]
+    fn test_parse_stream_event_reasoning_and_usage() {
+        let reasoning = serde_json::json!({
+            "type": "reasoning",
+            "part": {"type": "reasoning", "text": "Considering options"}
+        });
+        let chunk = KiloCliRunner::parse_stream_event(&reasoning).unwrap();
+        assert!(chunk.delta.is_empty());
+        assert_eq!(
+            chunk.reasoning_delta.as_deref(),
+            Some("Considering options")
+        );
+
+        let finish = serde_json::json!({
+            "type": "step_finish",
+            "part": {"reason": "stop", "tokens": {"input": 40, "output": 8, "reasoning": 2}}
+        });
+        let chunk = KiloCliRunner::parse_stream_event(&finish).unwrap();
+        assert!(chunk.is_final);
+        let usage = chunk.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 40);
+        assert_eq!(usage.total_tokens, 50);
+    }
+
+    #[test]
+    fn test_parse_ndjson_response_context_overflow() {
+        let ndjson = br#"{"type":"error","sessionID":"ses_k7","error":{"name":"ContextOverflowError","data":{"message":"context too long"}}}"#;
+
+        let err = KiloCliRunner::parse_ndjson_response(ndjson).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::ContextLengthExceeded);
+        assert_eq!(err.message, "kilo: context too long");
+    }
+
+    #[test]
+    fn test_classify_error_event_status_codes() {
+        let event = |data: serde_json::Value| serde_json::json!({"type": "error", "error": {"name": "APIError", "data": data}});
+
+        let limited = KiloCliRunner::classify_error_event(&event(serde_json::json!({
+            "message": "slow down",
+            "statusCode": 429,
+            "isRetryable": true,
+            "responseHeaders": {"retry-after": "7"}
+        })));
+        assert_eq!(limited.kind, ErrorKind::RateLimited);
+        assert_eq!(limited.retry_after, Some(std::time::Duration::from_secs(7)));
+
+        let rejected = KiloCliRunner::classify_error_event(&event(serde_json::json!({
+            "message": "unknown model",
+            "statusCode": 400,
+            "isRetryable": false
+        })));
+        assert_eq!(rejected.kind, ErrorKind::Config);
+
+        let overloaded = KiloCliRunner::classify_error_event(&event(serde_json::json!({
+            "message": "overloaded",
+            "statusCode": 529,
+            "isRetryable": true
+        })));
+        assert_eq!(overloaded.kind, ErrorKind::ExternalService);
     }
 
     #[test]
@@ -414,11 +578,13 @@ mod tests {
 
     #[test]
     fn test_parse_ndjson_response_with_reasoning() {
-        let ndjson = br#"{"type":"text","sessionID":"ses_k7","part":{"type":"text","text":"result"}}
+        let ndjson = br#"{"type":"reasoning","sessionID":"ses_k7","part":{"type":"reasoning","text":"Weighing options"}}
+{"type":"text","sessionID":"ses_k7","part":{"type":"text","text":"result"}}
 {"type":"step_finish","sessionID":"ses_k7","part":{"type":"step-finish","reason":"stop","tokens":{"input":200,"output":50,"reasoning":100,"total":350}}}"#;
 
         let (resp, _) = KiloCliRunner::parse_ndjson_response(ndjson).unwrap();
         assert_eq!(resp.content, "result");
+        assert_eq!(resp.reasoning.as_deref(), Some("Weighing options"));
         let usage = resp.usage.unwrap();
         assert_eq!(usage.total_tokens, 350);
     }
@@ -9,11 +9,11 @@
 //! Standalone library providing pluggable [`LlmProvider`](types::LlmProvider)
 //! implementations that delegate to CLI tools (Claude Code, Copilot, Cursor Agent,
 //! `OpenCode`, Gemini, Codex, Goose, Cline, Continue, Warp, Kiro, Kilo Code), an HTTP API client
-//! (OpenAI-compatible), and ACP (Copilot Headless) for LLM completions.
+//! (OpenAI-compatible), and ACP agents (Copilot Headless or any other) for LLM completions.
 //!
 //! CLI runners wrap a binary, build prompts from [`ChatMessage`](types::ChatMessage)
-//! sequences, parse JSON output, and manage session continuity. The Copilot Headless
-//! runner communicates via NDJSON-framed JSON-RPC with `copilot --acp`.
+//! sequences, parse JSON output, and manage session continuity. The ACP runner
+//! communicates via NDJSON-framed JSON-RPC with any ACP agent (e.g. `copilot --acp`).
 //!
 //! Two companion binary crates build on this library:
 //! - **`embacle-server`** — OpenAI-compatible REST API + MCP Streamable HTTP on a single port
@@ -108,6 +108,7 @@
 //!
 //! - [`agent`] — Multi-turn agent loop with configurable tool calling
 //! - [`fallback`] — Ordered provider failover chains
+//! - [`circuit_breaker`] — Per-provider failure tracking that skips unhealthy providers
 //! - [`metrics`] — Latency, token, and error tracking decorator
 //! - [`quality_gate`] — Response validation with retry on refusal
 //! - [`structured_output`] — Schema-enforced JSON extraction from any provider
@@ -116,6 +117,11 @@
 //! - [`capability_guard`] — Request/provider capability validation
 //! - [`guardrail`] — Pluggable pre/post request validation middleware
 //! - [`cache`] — Response caching with TTL and capacity limits
+//! - [`cache_backend`] — In-memory LRU and on-disk response cache backends
+//! - [`single_flight`] — Coalescing of concurrent identical requests
+//! - [`rate_limit`] — Per-provider concurrency, request and token rate limits
+//! - [`choices`] — Multiple-choice (`n`) fan-out with best-of ranking
+//! - [`model_catalog`] — Per-model context window, capability and pricing metadata
 //!
 //! ### Runner Infrastructure
 //!
@@ -123,8 +129,13 @@
 //! - [`discovery`] — Automatic binary detection on the host
 //! - [`process`] — Subprocess spawning with timeout and output limits
 //! - [`sandbox`] — Environment variable whitelisting and working directory control
+//! - `linux_sandbox` — Landlock, network namespace and rlimit enforcement (Linux only)
+//! - [`session`] — Conversation-keyed session store with TTL and LRU eviction
 //! - [`prompt`] — Prompt building from `ChatMessage` slices
 //! - [`compat`] — Version compatibility and capability detection
+//! - [`model_discovery`] — Runtime model listing from CLIs with TTL caching
+//! - [`error_classify`] — Rate-limit, context-length, quota and content-filter error detection
+//! - [`execution`] — Pluggable execution backends (local host, containers)
 //! - [`container`] — Container-based execution backend
 //!
 //! ### CLI Runners
@@ -146,6 +157,9 @@
 //!
 //! - `openai_api` — OpenAI-compatible HTTP API client (requires `openai-api` feature)
 //! - `copilot_headless` — GitHub Copilot Headless ACP runner (requires `copilot-headless` feature)
+//! - `acp_pool` — Warm ACP agent process pool shared across requests (requires `acp` feature)
+//! - `acp_runner` — Generic ACP runner for any Agent Client Protocol agent (requires `acp` feature)
+//! - `acp_server` — Serve any provider as an ACP agent over stdio (requires `acp` feature)
 
 /// Core types: traits, messages, requests, responses, and errors
 pub mod types;
@@ -156,8 +170,14 @@ pub mod agent;
 pub mod auth;
 /// Response caching decorator
 pub mod cache;
+/// Storage backends for the response cache
+pub mod cache_backend;
 /// Request/provider capability validation
 pub mod capability_guard;
+/// Multiple-choice fan-out decorator
+pub mod choices;
+/// Per-provider circuit breaking on repeated failures
+pub mod circuit_breaker;
 /// Claude Code CLI runner
 pub mod claude_code;
 /// Shared base struct and macro for CLI runner boilerplate
@@ -180,6 +200,10 @@ pub mod copilot;
 pub mod cursor_agent;
 /// Binary auto-detection and discovery
 pub mod discovery;
+/// Typed classification of provider error messages
+pub mod error_classify;
+/// Pluggable execution backends for CLI subprocesses
+pub mod execution;
 /// Runner factory, provider parsing, and provider enumeration
 pub mod factory;
 /// Provider fallback chains
@@ -194,10 +218,17 @@ pub mod guardrail;
 pub mod kilo_cli;
 /// Kiro CLI runner
 pub mod kiro_cli;
+/// Linux-native sandbox backend (Landlock, namespaces, rlimits)
+#[cfg(target_os = "linux")]
+pub mod linux_sandbox;
 /// MCP tool definition to text-tool-simulation bridge
 pub mod mcp_tool_bridge;
 /// Cost/latency normalization decorator
 pub mod metrics;
+/// Structured model metadata catalog
+pub mod model_catalog;
+/// Runtime model discovery for CLI runners
+pub mod model_discovery;
 /// `OpenCode` CLI runner
 pub mod opencode;
 /// Subprocess spawning with safety limits
@@ -206,9 +237,15 @@ pub mod process;
 pub mod prompt;
 /// Response quality validation with retry
 pub mod quality_gate;
+/// Concurrency and rate limiting with bounded queueing
+pub mod rate_limit;
 /// Environment sandboxing and tool policy
 pub mod sandbox;
-/// Stream wrapper for child process lifecycle management
+/// Conversation-scoped session storage for CLI resume support
+pub mod session;
+/// Coalescing of concurrent identical requests into one in-flight call
+pub mod single_flight;
+/// Stream wrapper for child process lifecycle management and stream timeouts
 pub mod stream;
 /// Schema-enforced JSON output from any provider
 pub mod structured_output;
@@ -227,7 +264,16 @@ pub mod config_file;
 #[cfg(feature = "openai-api")]
 pub mod openai_api;
 
-// Copilot Headless modules (behind feature flag)
+// ACP modules (behind feature flags)
+/// Pooled long-lived ACP agent connections with multiplexed sessions
+#[cfg(feature = "acp")]
+pub mod acp_pool;
+/// Generic runner for any Agent Client Protocol agent
+#[cfg(feature = "acp")]
+pub mod acp_runner;
+/// Agent side of ACP: serve any provider to ACP-capable editors
+#[cfg(feature = "acp")]
+pub mod acp_server;
 /// Configuration for the Copilot Headless (ACP) provider
 #[cfg(feature = "copilot-headless")]
 pub mod copilot_headless;
@@ -244,18 +290,22 @@ mod ffi;
 pub use agent::{AgentExecutor, AgentResult, OnTurnCallback, TurnInfo};
 pub use auth::ProviderReadiness;
 pub use cache::{CacheConfig, CacheProvider, CacheStats};
+pub use cache_backend::{CacheBackend, DiskCacheBackend, MemoryCacheBackend};
 pub use capability_guard::validate_capabilities;
+pub use choices::{complete_choices, ChoiceScorer, ChoicesConfig, ChoicesProvider, CHOICE_TAG};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
 pub use claude_code::ClaudeCodeRunner;
 pub use cli_common::CliRunnerBase;
 pub use cline_cli::ClineCliRunner;
 pub use codex_cli::CodexCliRunner;
 pub use compat::CliCapabilities;
-pub use config::{CliRunnerType, RunnerConfig};
+pub use config::{CliRunnerType, PromptDelivery, RunnerConfig};
 pub use container::{ContainerConfig, ContainerExecutor, NetworkMode};
 pub use continue_cli::ContinueCliRunner;
 pub use copilot::{copilot_fallback_models, discover_copilot_models, CopilotRunner};
 pub use cursor_agent::CursorAgentRunner;
 pub use discovery::{discover_runner, resolve_binary};
+pub use execution::{ExecutionBackend, LocalBackend, PreparedCommand};
 pub use factory::{
     create_runner, create_runner_with_config, parse_runner_type, valid_provider_names,
     ALL_PROVIDERS,
@@ -269,17 +319,30 @@ pub use guardrail::{
 };
 pub use kilo_cli::KiloCliRunner;
 pub use kiro_cli::KiroCliRunner;
+#[cfg(target_os = "linux")]
+pub use linux_sandbox::LinuxSandbox;
 pub use mcp_tool_bridge::{McpToolDefinition, McpToolExecutor};
 pub use metrics::{
     default_pricing_table, MetricsProvider, MetricsReport, PricingTable, TokenPricing,
 };
+pub use model_catalog::{ModelInfo, ModelOverride};
 pub use opencode::OpenCodeRunner;
 pub use quality_gate::{QualityGateProvider, QualityPolicy};
+pub use rate_limit::{RateLimitConfig, RateLimitProvider, RateLimitStats};
+pub use session::{FileSessionStore, InMemorySessionStore, SessionConfig, SessionStore};
+pub use single_flight::SingleFlightProvider;
+pub use stream::{GuardedStream, StreamTimeouts};
 pub use structured_output::{request_structured_output, StructuredOutputRequest};
 pub use warp_cli::WarpCliRunner;
 
 // Core tool calling type re-exports
-pub use types::{ImagePart, ResponseFormat, ToolCallRequest, ToolChoice, ToolDefinition};
+pub use types::{
+    AttachmentPart, AttachmentSource, ImagePart, ReasoningEffort, RequestMetadata, ResponseFormat,
+    ToolCallDelta, ToolCallRequest, ToolChoice, ToolDefinition,
+};
+
+// Cooperative cancellation token accepted by `LlmProvider::complete_with_cancel`
+pub use tokio_util::sync::CancellationToken;
 
 // Tool simulation re-exports
 pub use tool_simulation::{
@@ -291,16 +354,23 @@ pub use tool_simulation::{
 // Config file re-exports (behind feature flag)
 #[cfg(feature = "config-file")]
 pub use config_file::{
-    build_fallback_from_config, build_runner_config, load_config, load_config_from, resolve_alias,
-    DefaultsConfig, EmbacleConfig, FallbackConfig, ProviderConfig,
+    build_fallback_from_config, build_runner_config, load_config, load_config_from,
+    model_overrides, rate_limits, resolve_alias, DefaultsConfig, EmbacleConfig, FallbackConfig,
+    ProviderConfig,
 };
 
 // OpenAI API re-exports (behind feature flag)
 #[cfg(feature = "openai-api")]
 pub use openai_api::{OpenAiApiConfig, OpenAiApiRunner};
 
-// Copilot Headless re-exports (behind feature flag)
+// ACP re-exports (behind feature flags)
+#[cfg(feature = "acp")]
+pub use acp_pool::{AcpLaunchSpec, AcpPool, AcpPoolConfig};
+#[cfg(feature = "acp")]
+pub use acp_runner::{AcpRunner, HeadlessToolResponse, ObservedToolCall, PermissionPolicy};
+#[cfg(feature = "acp")]
+pub use acp_server::AcpAgentServer;
 #[cfg(feature = "copilot-headless")]
-pub use copilot_headless::{CopilotHeadlessRunner, HeadlessToolResponse, ObservedToolCall};
+pub use copilot_headless::CopilotHeadlessRunner;
 #[cfg(feature = "copilot-headless")]
-pub use copilot_headless_config::{CopilotHeadlessConfig, PermissionPolicy};
+pub use copilot_headless_config::CopilotHeadlessConfig;
@@ -0,0 +1,420 @@
+// ABOUTME: Linux-native execution backend enforcing SandboxPolicy restrictions without containers
+// ABOUTME: Applies Landlock path rules, a private network namespace, and setrlimit limits before exec
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Linux Sandbox Backend
+//!
+//! [`LinuxSandbox`] enforces the restrictions of a [`SandboxPolicy`] on each
+//! CLI subprocess, for hosts where Docker is not available:
+//!
+//! - **Filesystem** — Landlock rules allow the read-only and read-write path
+//!   lists and deny everything else (Linux 5.13+ with Landlock enabled)
+//! - **Network** — a new user and network namespace with no usable interfaces
+//! - **Resources** — CPU, memory, file-size and process-count `setrlimit` limits
+//!   (the memory limit is `RLIMIT_AS`, which Node/V8-based CLIs trip over; see
+//!   [`ResourceLimits::memory_bytes`])
+//!
+//! Support for each requested restriction is checked before spawning; a kernel
+//! that cannot enforce one fails the request with [`ErrorKind::Config`](crate::types::ErrorKind::Config)
+//! instead of silently running unrestricted.
+
+use std::ffi::CStr;
+use std::fs;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
+
+use landlock::{
+    path_beneath_rules, Access, AccessFs, CompatLevel, Compatible, Ruleset, RulesetAttr,
+    RulesetCreated, RulesetCreatedAttr, ABI,
+};
+use nix::libc;
+use nix::sched::{unshare, CloneFlags};
+use nix::sys::resource::{getrlimit, setrlimit, Resource};
+use nix::unistd::{getegid, geteuid};
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::execution::{ExecutionBackend, PreparedCommand};
+use crate::sandbox::{ResourceLimits, SandboxPolicy};
+use crate::types::RunnerError;
+
+/// Oldest Landlock ABI that filesystem restrictions require (Linux 5.13)
+const REQUIRED_ABI: ABI = ABI::V1;
+/// Newest Landlock ABI whose access rights are handled when the kernel has them
+const TARGET_ABI: ABI = ABI::V6;
+
+/// Execution backend that confines CLI subprocesses with Linux kernel features
+///
+/// The policy's filesystem, network and resource restrictions are enforced
+/// here; its environment whitelist and working directory are applied by the
+/// runner through [`apply_sandbox`](crate::sandbox::apply_sandbox). When the
+/// filesystem is restricted, the command's working directory and `/dev/null`
+/// stay writable and its binary stays executable.
+#[derive(Debug, Clone)]
+pub struct LinuxSandbox {
+    policy: SandboxPolicy,
+}
+
+impl LinuxSandbox {
+    /// Create a backend enforcing `policy`
+    #[must_use]
+    pub const fn new(policy: SandboxPolicy) -> Self {
+        Self { policy }
+    }
+
+    /// Build the Landlock ruleset for `cmd` in the parent, where errors can be reported
+    fn landlock_ruleset(&self, cmd: &std::process::Command) -> Result<RulesetCreated, RunnerError> {
+        let mut read_only = self.policy.read_only_paths.clone();
+        let program = Path::new(cmd.get_program());
+        if program.is_absolute() {
+            read_only.push(program.to_path_buf());
+        }
+        let mut read_write = self.policy.read_write_paths.clone();
+        read_write.push(self.policy.working_directory.clone());
+        if let Some(dir) = cmd.get_current_dir() {
+            read_write.push(dir.to_path_buf());
+        }
+        read_write.push(PathBuf::from("/dev/null"));
+
+        if let Some(missing) = read_only.iter().chain(&read_write).find(|p| !p.exists()) {
+            return Err(RunnerError::config(format!(
+                "linux sandbox path does not exist: {}",
+                missing.display()
+            )));
+        }
+
+        let ruleset = Ruleset::default()
+            .set_compatibility(CompatLevel::HardRequirement)
+            .handle_access(AccessFs::from_all(REQUIRED_ABI))
+            .and_then(|r| {
+                r.set_compatibility(CompatLevel::BestEffort)
+                    .handle_access(AccessFs::from_all(TARGET_ABI))
+            })
+            .and_then(Ruleset::create)
+            .map_err(|e| {
+                unsupported(
+                    "filesystem restrictions",
+                    &format!("Landlock needs Linux 5.13+ booted with Landlock enabled ({e})"),
+                )
+            })?;
+
+        ruleset
+            .add_rules(path_beneath_rules(
+                &read_only,
+                AccessFs::from_read(TARGET_ABI),
+            ))
+            .and_then(|r| {
+                r.add_rules(path_beneath_rules(
+                    &read_write,
+                    AccessFs::from_all(TARGET_ABI),
+                ))
+            })
+            .map_err(|e| RunnerError::config(format!("linux sandbox rejected a path rule: {e}")))
+    }
+}
+
+impl ExecutionBackend for LinuxSandbox {
+    fn name(&self) -> &'static str {
+        "linux_sandbox"
+    }
+
+    /// The policy's working directory is always readable by the sandboxed process
+    fn attachment_root(&self, _working_directory: Option<&Path>) -> Option<PathBuf> {
+        Some(self.policy.working_directory.clone())
+    }
+
+    fn prepare(&self, mut cmd: Command, _stdin: bool) -> Result<PreparedCommand, RunnerError> {
+        let id_maps = if self.policy.isolate_network {
+            check_user_namespaces()?;
+            Some(IdMaps::current())
+        } else {
+            None
+        };
+        let ruleset_fd: Option<OwnedFd> = if self.policy.restricts_filesystem() {
+            let fd: Option<OwnedFd> = self.landlock_ruleset(cmd.as_std())?.into();
+            Some(fd.ok_or_else(|| {
+                unsupported("filesystem restrictions", "Landlock created no ruleset")
+            })?)
+        } else {
+            None
+        };
+        let limits = resolve_limits(self.policy.limits)
+            .map_err(|e| RunnerError::internal(format!("linux sandbox: getrlimit failed: {e}")))?;
+
+        debug!(
+            filesystem = ruleset_fd.is_some(),
+            network = id_maps.is_some(),
+            ?limits,
+            "Applying linux sandbox"
+        );
+
+        // SAFETY: the hook runs in the forked child just before exec, where
+        // only async-signal-safe operations are allowed. Everything it needs
+        // (id maps, limit values, the Landlock ruleset fd) is prepared above;
+        // the hook itself only issues raw syscalls (unshare, open/write/close
+        // on /proc/self, setrlimit, prctl, landlock_restrict_self) and builds
+        // errors from errno, so it never allocates or takes a lock.
+        #[allow(unsafe_code)]
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(maps) = &id_maps {
+                    maps.enter_network_namespace()?;
+                }
+                for &(resource, value) in limits.iter().flatten() {
+                    setrlimit(resource, value, value)?;
+                }
+                if let Some(fd) = &ruleset_fd {
+                    restrict_self(fd)?;
+                }
+                Ok(())
+            });
+        }
+        Ok(PreparedCommand::new(cmd))
+    }
+}
+
+/// Error for a restriction the running kernel cannot enforce
+fn unsupported(restriction: &str, reason: &str) -> RunnerError {
+    RunnerError::config(format!(
+        "linux sandbox cannot enforce {restriction}: {reason}"
+    ))
+}
+
+/// Verify that this process may create a user namespace for network isolation
+fn check_user_namespaces() -> Result<(), RunnerError> {
+    let reason = if !Path::new("/proc/self/ns/user").exists()
+        || !Path::new("/proc/self/ns/net").exists()
+    {
+        Some("the kernel was built without user or network namespaces")
+    } else if read_sysctl("user/max_user_namespaces").as_deref() == Some("0") {
+        Some("user namespaces are disabled (user.max_user_namespaces = 0)")
+    } else if geteuid().is_root() {
+        None
+    } else if read_sysctl("kernel/unprivileged_userns_clone").as_deref() == Some("0") {
+        Some("unprivileged user namespaces are disabled (kernel.unprivileged_userns_clone = 0)")
+    } else if read_sysctl("kernel/apparmor_restrict_unprivileged_userns").as_deref() == Some("1") {
+        Some("AppArmor restricts unprivileged user namespaces (kernel.apparmor_restrict_unprivileged_userns = 1)")
+    } else {
+        None
+    };
+    reason.map_or(Ok(()), |reason| {
+        Err(unsupported("network isolation", reason))
+    })
+}
+
+fn read_sysctl(name: &str) -> Option<String> {
+    fs::read_to_string(Path::new("/proc/sys").join(name))
+        .ok()
+        .map(|v| v.trim().to_owned())
+}
+
+/// Identity mappings that keep the caller's uid and gid inside the new user namespace
+struct IdMaps {
+    uid_map: String,
+    gid_map: String,
+}
+
+impl IdMaps {
+    fn current() -> Self {
+        let uid = geteuid();
+        let gid = getegid();
+        Self {
+            uid_map: format!("{uid} {uid} 1"),
+            gid_map: format!("{gid} {gid} 1"),
+        }
+    }
+
+    /// Move the calling process into fresh user and network namespaces
+    ///
+    /// Runs between fork and exec: only raw syscalls on pre-built buffers.
+    fn enter_network_namespace(&self) -> io::Result<()> {
+        unshare(CloneFlags::CLONE_NEWUSER | CloneFlags::CLONE_NEWNET)?;
+        write_proc_file(c"/proc/self/setgroups", b"deny")?;
+        write_proc_file(c"/proc/self/gid_map", self.gid_map.as_bytes())?;
+        write_proc_file(c"/proc/self/uid_map", self.uid_map.as_bytes())
+    }
+}
+
+/// Write `data` to `path` with raw `open`/`write`/`close` (no allocation)
+fn write_proc_file(path: &CStr, data: &[u8]) -> io::Result<()> {
+    // SAFETY: `path` is NUL-terminated and `data` is a valid buffer for the
+    // duration of the calls; the fd is closed before returning.
+    #[allow(unsafe_code)]
+    unsafe {
+        let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let written = libc::write(fd, data.as_ptr().cast(), data.len());
+        let result = match usize::try_from(written) {
+            Ok(n) if n == data.len() => Ok(()),
+            Ok(_) => Err(io::Error::from_raw_os_error(libc::EIO)),
+            Err(_) => Err(io::Error::last_os_error()),
+        };
+        libc::close(fd);
+        result
+    }
+}
+
+/// Enforce a created Landlock ruleset on the calling process (no allocation)
+fn restrict_self(ruleset: &OwnedFd) -> io::Result<()> {
+    // SAFETY: plain syscalls on integer arguments; `ruleset` stays open for
+    // the duration of the call.
+    #[allow(unsafe_code)]
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Values to set as both soft and hard limits, capped at the current hard limit
+///
+/// Resolved in the parent so the child only has to call `setrlimit`; the
+/// forked child inherits the same hard limits.
+fn resolve_limits(limits: ResourceLimits) -> io::Result<[Option<(Resource, u64)>; 4]> {
+    let requested = [
+        (Resource::RLIMIT_CPU, limits.cpu_seconds),
+        (Resource::RLIMIT_AS, limits.memory_bytes),
+        (Resource::RLIMIT_FSIZE, limits.file_size_bytes),
+        (Resource::RLIMIT_NPROC, limits.max_processes),
+    ];
+    let mut resolved = [None; 4];
+    for (slot, (resource, limit)) in resolved.iter_mut().zip(requested) {
+        let Some(limit) = limit else { continue };
+        let (_, hard) = getrlimit(resource)?;
+        *slot = Some((resource, limit.min(hard)));
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::process::{run_cli_command, CliOutput};
+    use crate::sandbox::default_read_only_paths;
+    use crate::types::ErrorKind;
+
+    /// Kernel features the ignored tests below need
+    const NEEDS_KERNEL: &str = "requires Landlock and unprivileged user namespaces";
+
+    /// Run `script` under `policy`, failing loudly when the kernel cannot enforce it
+    async fn run_sandboxed(policy: SandboxPolicy, script: &str) -> CliOutput {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", script])
+            .current_dir(&policy.working_directory);
+        let prepared = LinuxSandbox::new(policy)
+            .prepare(cmd, false)
+            .unwrap_or_else(|e| panic!("{NEEDS_KERNEL}: {}", e.message));
+        let mut cmd = prepared.command;
+        run_cli_command(&mut cmd, Duration::from_secs(10), 0)
+            .await
+            .unwrap()
+    }
+
+    /// The soft limit column of `name` in `/proc/self/limits` output
+    fn soft_limit<'a>(limits: &'a str, name: &str) -> Option<&'a str> {
+        limits
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| rest.split_whitespace().next())
+    }
+
+    #[tokio::test]
+    async fn limits_are_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = SandboxPolicy::new(dir.path().to_path_buf()).with_limits(ResourceLimits {
+            cpu_seconds: Some(7),
+            file_size_bytes: Some(1024 * 1024),
+            ..ResourceLimits::default()
+        });
+
+        // `/proc/self/limits` reports bytes; `ulimit -f` block size differs between shells
+        let output = run_sandboxed(policy, "cat /proc/self/limits").await;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(soft_limit(&stdout, "Max cpu time"), Some("7"));
+        assert_eq!(soft_limit(&stdout, "Max file size"), Some("1048576"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Landlock and unprivileged user namespaces"]
+    async fn filesystem_is_limited_to_allowed_paths() {
+        let work = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let policy = SandboxPolicy::new(work.path().to_path_buf())
+            .with_read_only_paths(default_read_only_paths());
+        let script = format!(
+            "echo inside > inside.txt && echo outside > {}/outside.txt",
+            outside.path().display()
+        );
+
+        let output = run_sandboxed(policy, &script).await;
+
+        assert_ne!(output.exit_code, 0);
+        assert!(work.path().join("inside.txt").exists());
+        assert!(!outside.path().join("outside.txt").exists());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Landlock and unprivileged user namespaces"]
+    async fn network_namespace_hides_host_interfaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let host_ns = fs::read_link("/proc/self/ns/net").unwrap();
+        let policy = SandboxPolicy::new(dir.path().to_path_buf()).with_network_isolation(true);
+
+        let output = run_sandboxed(policy, "readlink /proc/self/ns/net; id -u").await;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        assert_ne!(lines.next(), Some(host_ns.to_string_lossy().as_ref()));
+        assert_eq!(lines.next(), Some(geteuid().to_string().as_str()));
+    }
+
+    #[test]
+    fn missing_sandbox_path_is_a_config_error() {
+        let policy = SandboxPolicy::new(std::env::temp_dir())
+            .with_read_only_paths(vec![PathBuf::from("/nonexistent/embacle-sandbox")]);
+        let err = LinuxSandbox::new(policy)
+            .prepare(Command::new("/bin/true"), false)
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Config);
+        assert!(err.message.contains("/nonexistent/embacle-sandbox"));
+    }
+
+    #[tokio::test]
+    async fn runner_commands_use_the_sandbox_backend() {
+        use std::sync::Arc;
+
+        use crate::cli_common::CliRunnerBase;
+        use crate::config::RunnerConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let policy = SandboxPolicy::new(dir.path().to_path_buf()).with_limits(ResourceLimits {
+            cpu_seconds: Some(9),
+            ..ResourceLimits::default()
+        });
+        let config = RunnerConfig::new(PathBuf::from("/bin/sh"))
+            .with_execution_backend(Arc::new(LinuxSandbox::new(policy)));
+        let base = CliRunnerBase::new(config, "default", &[]);
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", "ulimit -t"]);
+
+        let output = base
+            .run_command(cmd, None, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "9");
+    }
+}
@@ -21,16 +21,27 @@
 //! When built with `--features otel`, instruments are created via the
 //! global meter `embacle` and recorded on each `complete()` call.
 //!
+//! ## Tag Breakdown
+//!
+//! Each [`RequestMetadata`](crate::types::RequestMetadata) tag is counted
+//! under a `key=value` entry in [`MetricsReport::by_tag`], so calls can be
+//! attributed to tenants, features or jobs. Tags should be low-cardinality:
+//! after [`MAX_TAG_ENTRIES`] distinct entries, calls with new tags are
+//! counted under [`OVERFLOW_TAG`] instead of growing the map.
+//!
 //! Token estimation: when `TokenUsage` is not provided by the inner provider,
 //! tokens are estimated at ~4 characters per token.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use async_trait::async_trait;
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, Instrument};
 
+use crate::model_catalog::ModelInfo;
 use crate::types::{
     ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
 };
@@ -39,7 +50,7 @@ use crate::types::{
 const CHARS_PER_TOKEN_ESTIMATE: u32 = 4;
 
 /// Per-model token pricing (cost per 1000 tokens)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TokenPricing {
     /// Cost per 1000 prompt (input) tokens
     pub prompt_price_per_1k: f64,
@@ -108,6 +119,22 @@ pub fn default_pricing_table() -> PricingTable {
     table
 }
 
+/// Find the pricing for `model`: exact match first, then substring match for partial model names
+pub(crate) fn lookup_pricing<'a>(table: &'a PricingTable, model: &str) -> Option<&'a TokenPricing> {
+    table.get(model).or_else(|| {
+        table
+            .iter()
+            .find(|(key, _)| model.contains(key.as_str()))
+            .map(|(_, v)| v)
+    })
+}
+
+/// Most distinct `key=value` entries tracked in [`MetricsReport::by_tag`]
+pub const MAX_TAG_ENTRIES: usize = 1024;
+
+/// `by_tag` entry collecting calls whose tags arrived after the map was full
+pub const OVERFLOW_TAG: &str = "_overflow";
+
 /// Accumulated metrics state protected by a mutex
 #[derive(Debug, Default)]
 struct MetricsState {
@@ -118,6 +145,35 @@ struct MetricsState {
     total_tokens: u64,
     errors_count: u64,
     total_cost: f64,
+    by_tag: BTreeMap<String, TagMetrics>,
+}
+
+impl MetricsState {
+    /// Entry for `key=value`, or the overflow entry once [`MAX_TAG_ENTRIES`] is reached
+    fn tag_entry(&mut self, key: &str, value: &str) -> &mut TagMetrics {
+        let name = format!("{key}={value}");
+        let name = if self.by_tag.len() < MAX_TAG_ENTRIES || self.by_tag.contains_key(&name) {
+            name
+        } else {
+            OVERFLOW_TAG.to_owned()
+        };
+        self.by_tag.entry(name).or_default()
+    }
+}
+
+/// Metrics accumulated for calls carrying one `key=value` tag
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagMetrics {
+    /// Number of calls carrying the tag
+    pub call_count: u64,
+    /// Number of those calls that returned an error
+    pub errors_count: u64,
+    /// Total latency of those calls (milliseconds)
+    pub total_latency_ms: u64,
+    /// Total tokens (prompt + completion) of successful calls
+    pub total_tokens: u64,
+    /// Accumulated cost in USD of successful calls
+    pub total_cost: f64,
 }
 
 /// Snapshot of accumulated metrics for a provider
@@ -139,8 +195,13 @@ pub struct MetricsReport {
     pub total_tokens: u64,
     /// Number of calls that returned an error
     pub errors_count: u64,
-    /// Accumulated cost in USD (0.0 if no pricing table configured)
+    /// Accumulated cost in USD
+    ///
+    /// Uses the provider-reported cost when a response carries one, otherwise
+    /// the pricing-table estimate (0.0 if no pricing table configured).
     pub total_cost: f64,
+    /// Per-tag breakdown keyed by `key=value`
+    pub by_tag: BTreeMap<String, TagMetrics>,
 }
 
 /// OpenTelemetry instruments for metrics export
@@ -250,6 +311,7 @@ impl MetricsProvider {
             total_tokens: state.total_tokens,
             errors_count: state.errors_count,
             total_cost: state.total_cost,
+            by_tag: state.by_tag.clone(),
         }
     }
 
@@ -265,17 +327,11 @@ impl MetricsProvider {
 
     /// Compute cost for a single call based on token counts and model name
     fn compute_cost(&self, model: &str, prompt_tokens: u64, completion_tokens: u64) -> f64 {
-        let Some(table) = &self.pricing else {
-            return 0.0;
-        };
-        // Try exact match first, then try substring matching for partial model names
-        let pricing = table.get(model).or_else(|| {
-            table
-                .iter()
-                .find(|(key, _)| model.contains(key.as_str()))
-                .map(|(_, v)| v)
-        });
-        let Some(pricing) = pricing else {
+        let Some(pricing) = self
+            .pricing
+            .as_ref()
+            .and_then(|table| lookup_pricing(table, model))
+        else {
             return 0.0;
         };
         #[allow(clippy::cast_precision_loss)]
@@ -283,43 +339,14 @@ impl MetricsProvider {
             + (completion_tokens as f64 * pricing.completion_price_per_1k / 1000.0);
         cost
     }
-}
-
-/// Estimate token count from character length (~4 chars per token)
-fn estimate_tokens(text: &str) -> u32 {
-    #[allow(clippy::cast_possible_truncation)]
-    let len = text.len() as u32;
-    len / CHARS_PER_TOKEN_ESTIMATE.max(1)
-}
-
-#[async_trait]
-impl LlmProvider for MetricsProvider {
-    fn name(&self) -> &'static str {
-        self.inner.name()
-    }
-
-    fn display_name(&self) -> &str {
-        self.inner.display_name()
-    }
-
-    fn capabilities(&self) -> LlmCapabilities {
-        self.inner.capabilities()
-    }
-
-    fn default_model(&self) -> &str {
-        self.inner.default_model()
-    }
-
-    fn available_models(&self) -> &[String] {
-        self.inner.available_models()
-    }
-
-    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let start = Instant::now();
-        let result = self.inner.complete(request).await;
-        #[allow(clippy::cast_possible_truncation)]
-        let elapsed_ms = start.elapsed().as_millis() as u64;
 
+    /// Accumulate one finished `complete()` call into the report and `OTel` instruments
+    fn record(
+        &self,
+        request: &ChatRequest,
+        result: Result<&ChatResponse, &RunnerError>,
+        elapsed_ms: u64,
+    ) {
         let mut state = self.state.lock().expect("metrics lock poisoned");
         state.call_count += 1;
         state.total_latency_ms += elapsed_ms;
@@ -327,7 +354,7 @@ impl LlmProvider for MetricsProvider {
         #[cfg(feature = "otel")]
         let provider_attr = opentelemetry::KeyValue::new("provider", self.inner.name());
 
-        if let Ok(response) = &result {
+        if let Ok(response) = result {
             let usage = response.usage.as_ref();
             let prompt_tokens = u64::from(
                 usage.map_or_else(|| estimate_prompt_tokens(request), |u| u.prompt_tokens),
@@ -342,8 +369,17 @@ impl LlmProvider for MetricsProvider {
             state.total_completion_tokens += completion_tokens;
             state.total_tokens += total;
 
-            let cost = self.compute_cost(&response.model, prompt_tokens, completion_tokens);
+            let cost = usage.and_then(|u| u.reported_cost).unwrap_or_else(|| {
+                self.compute_cost(&response.model, prompt_tokens, completion_tokens)
+            });
             state.total_cost += cost;
+            for (key, value) in &request.metadata.tags {
+                let tag = state.tag_entry(key, value);
+                tag.call_count += 1;
+                tag.total_latency_ms += elapsed_ms;
+                tag.total_tokens += total;
+                tag.total_cost += cost;
+            }
 
             info!(
                 provider = self.inner.name(),
@@ -366,6 +402,12 @@ impl LlmProvider for MetricsProvider {
             }
         } else {
             state.errors_count += 1;
+            for (key, value) in &request.metadata.tags {
+                let tag = state.tag_entry(key, value);
+                tag.call_count += 1;
+                tag.errors_count += 1;
+                tag.total_latency_ms += elapsed_ms;
+            }
             info!(
                 provider = self.inner.name(),
                 elapsed_ms, "metrics: complete() failed"
@@ -380,12 +422,75 @@ impl LlmProvider for MetricsProvider {
         }
 
         drop(state);
-        result
+    }
+}
+
+/// Estimate token count from character length (~4 chars per token)
+fn estimate_tokens(text: &str) -> u32 {
+    #[allow(clippy::cast_possible_truncation)]
+    let len = text.len() as u32;
+    len / CHARS_PER_TOKEN_ESTIMATE.max(1)
+}
+
+#[async_trait]
+impl LlmProvider for MetricsProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn display_name(&self) -> &str {
+        self.inner.display_name()
+    }
+
+    fn capabilities(&self) -> LlmCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn default_model(&self) -> &str {
+        self.inner.default_model()
+    }
+
+    fn available_models(&self) -> &[String] {
+        self.inner.available_models()
+    }
+
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        self.inner.model_catalog()
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        self.inner.refresh_models().await
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let span = request.metadata.span("metrics");
+        let start = Instant::now();
+        let result = self
+            .inner
+            .complete_with_cancel(request, cancel)
+            .instrument(span.clone())
+            .await;
+        #[allow(clippy::cast_possible_truncation)]
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        span.in_scope(|| self.record(request, result.as_ref(), elapsed_ms));
+        result.map(|response| response.with_request_metadata(request))
     }
 
     /// Delegate streaming directly; only measures stream setup time (documented limitation)
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        self.inner.complete_stream(request).await
+        self.inner
+            .complete_stream(request)
+            .instrument(request.metadata.span("metrics"))
+            .await
     }
 
     async fn health_check(&self) -> Result<bool, RunnerError> {
@@ -406,7 +511,7 @@ mod tests {
     use super::*;
     use crate::types::{
         ChatMessage, ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider,
-        RunnerError, TokenUsage,
+        RequestMetadata, RunnerError, TokenUsage,
     };
     use async_trait::async_trait;
     use std::sync::atomic::{AtomicU32, Ordering};
@@ -454,6 +559,9 @@ mod tests {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 })
             } else {
                 responses.remove(0)
@@ -495,10 +603,17 @@ mod tests {
                     prompt_tokens: 10,
                     completion_tokens: 5,
                     total_tokens: 15,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
                 }),
                 finish_reason: Some("stop".to_owned()),
                 warnings: None,
                 tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
             }),
             Ok(ChatResponse {
                 content: "second".to_owned(),
@@ -507,10 +622,17 @@ mod tests {
                     prompt_tokens: 8,
                     completion_tokens: 3,
                     total_tokens: 11,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
                 }),
                 finish_reason: Some("stop".to_owned()),
                 warnings: None,
                 tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
             }),
         ]);
         let metered = MetricsProvider::new(Box::new(provider));
@@ -550,6 +672,9 @@ mod tests {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })]);
         let metered = MetricsProvider::new(Box::new(provider));
         let request = ChatRequest::new(vec![ChatMessage::user("12345678")]); // 8 chars => 2 tokens
@@ -580,10 +705,17 @@ mod tests {
                 prompt_tokens: 5,
                 completion_tokens: 2,
                 total_tokens: 7,
+                reasoning_tokens: None,
+                cached_prompt_tokens: None,
+                cache_creation_tokens: None,
+                reported_cost: None,
             }),
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })]);
         let metered = MetricsProvider::new(Box::new(provider));
         let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
@@ -612,10 +744,17 @@ mod tests {
                 prompt_tokens: 1000,
                 completion_tokens: 500,
                 total_tokens: 1500,
+                reasoning_tokens: None,
+                cached_prompt_tokens: None,
+                cache_creation_tokens: None,
+                reported_cost: None,
             }),
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })]);
         let metered = MetricsProvider::new(Box::new(provider)).with_default_pricing();
         let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
@@ -636,10 +775,17 @@ mod tests {
                 prompt_tokens: 1000,
                 completion_tokens: 500,
                 total_tokens: 1500,
+                reasoning_tokens: None,
+                cached_prompt_tokens: None,
+                cache_creation_tokens: None,
+                reported_cost: None,
             }),
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })]);
         let metered = MetricsProvider::new(Box::new(provider)).with_default_pricing();
         let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
@@ -659,10 +805,17 @@ mod tests {
                     prompt_tokens: 1000,
                     completion_tokens: 500,
                     total_tokens: 1500,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
                 }),
                 finish_reason: Some("stop".to_owned()),
                 warnings: None,
                 tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
             }),
             Ok(ChatResponse {
                 content: "r2".to_owned(),
@@ -671,10 +824,17 @@ mod tests {
                     prompt_tokens: 2000,
                     completion_tokens: 1000,
                     total_tokens: 3000,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
                 }),
                 finish_reason: Some("stop".to_owned()),
                 warnings: None,
                 tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
             }),
         ]);
         let metered = MetricsProvider::new(Box::new(provider)).with_default_pricing();
@@ -698,10 +858,17 @@ mod tests {
                 prompt_tokens: 1000,
                 completion_tokens: 500,
                 total_tokens: 1500,
+                reasoning_tokens: None,
+                cached_prompt_tokens: None,
+                cache_creation_tokens: None,
+                reported_cost: None,
             }),
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })]);
         let metered = MetricsProvider::new(Box::new(provider));
         let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
@@ -711,6 +878,35 @@ mod tests {
         assert!(report.total_cost == 0.0);
     }
 
+    #[tokio::test]
+    async fn cost_prefers_reported_cost() {
+        let provider = TestProvider::new(vec![Ok(ChatResponse {
+            content: "response".to_owned(),
+            model: "opus".to_owned(),
+            usage: Some(TokenUsage {
+                prompt_tokens: 1000,
+                completion_tokens: 500,
+                total_tokens: 1500,
+                reasoning_tokens: None,
+                cached_prompt_tokens: Some(900),
+                cache_creation_tokens: None,
+                reported_cost: Some(0.004),
+            }),
+            finish_reason: Some("stop".to_owned()),
+            warnings: None,
+            tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
+        })]);
+        let metered = MetricsProvider::new(Box::new(provider)).with_default_pricing();
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+        metered.complete(&request).await.expect("call");
+
+        let report = metered.report();
+        assert!((report.total_cost - 0.004).abs() < 1e-10);
+    }
+
     #[tokio::test]
     async fn cost_with_estimated_tokens() {
         let provider = TestProvider::new(vec![Ok(ChatResponse {
@@ -720,6 +916,9 @@ mod tests {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })]);
         let metered = MetricsProvider::new(Box::new(provider)).with_default_pricing();
         let request = ChatRequest::new(vec![ChatMessage::user("12345678")]); // 8 chars => 2 tokens
@@ -753,10 +952,17 @@ mod tests {
                 prompt_tokens: 1000,
                 completion_tokens: 500,
                 total_tokens: 1500,
+                reasoning_tokens: None,
+                cached_prompt_tokens: None,
+                cache_creation_tokens: None,
+                reported_cost: None,
             }),
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         })]);
         let metered = MetricsProvider::new(Box::new(provider)).with_default_pricing();
         let request = ChatRequest::new(vec![ChatMessage::user("hi")]);
@@ -766,4 +972,58 @@ mod tests {
         metered.reset();
         assert!(metered.report().total_cost == 0.0);
     }
+
+    #[tokio::test]
+    async fn tags_break_down_calls_and_errors() {
+        let provider = TestProvider::new(vec![
+            Ok(ChatResponse {
+                content: "ok".to_owned(),
+                model: "test-model".to_owned(),
+                usage: Some(TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                    total_tokens: 15,
+                    reasoning_tokens: None,
+                    cached_prompt_tokens: None,
+                    cache_creation_tokens: None,
+                    reported_cost: None,
+                }),
+                finish_reason: Some("stop".to_owned()),
+                warnings: None,
+                tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
+            }),
+            Err(RunnerError::internal("boom")),
+        ]);
+        let metered = MetricsProvider::new(Box::new(provider));
+        let metadata = RequestMetadata::new()
+            .with_request_id("req-1")
+            .with_tag("tenant", "acme");
+        let request = ChatRequest::new(vec![ChatMessage::user("hi")]).with_metadata(metadata);
+
+        let response = metered.complete(&request).await.expect("first call");
+        assert_eq!(response.metadata.request_id.as_deref(), Some("req-1"));
+        assert!(metered.complete(&request).await.is_err());
+
+        let report = metered.report();
+        let tenant = &report.by_tag["tenant=acme"];
+        assert_eq!(tenant.call_count, 2);
+        assert_eq!(tenant.errors_count, 1);
+        assert_eq!(tenant.total_tokens, 15);
+    }
+
+    #[test]
+    fn tag_entries_are_capped() {
+        let mut state = MetricsState::default();
+        for i in 0..MAX_TAG_ENTRIES + 10 {
+            state.tag_entry("request", &i.to_string()).call_count += 1;
+        }
+        state.tag_entry("request", "0").call_count += 1;
+
+        assert_eq!(state.by_tag.len(), MAX_TAG_ENTRIES + 1);
+        assert_eq!(state.by_tag["request=0"].call_count, 2);
+        assert_eq!(state.by_tag[OVERFLOW_TAG].call_count, 10);
+    }
 }
@@ -0,0 +1,294 @@
+// ABOUTME: Structured model catalog entries with context window, output limit, capability and pricing metadata
+// ABOUTME: Builds default catalogs from runner model lists and applies declarative per-model overrides
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Model Catalog
+//!
+//! [`LlmProvider::model_catalog`](crate::types::LlmProvider::model_catalog)
+//! describes each available model as a [`ModelInfo`]: its id, aliases,
+//! context window, maximum output tokens, capabilities and pricing. The
+//! default catalog is built from
+//! [`available_models`](crate::types::LlmProvider::available_models), filling
+//! in limits and prices for well-known model families. Deployments correct or
+//! extend it with [`ModelOverride`] entries, e.g. `[[providers.models]]` in
+//! `embacle.toml`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::{default_pricing_table, lookup_pricing, TokenPricing};
+use crate::types::LlmCapabilities;
+
+/// Known `(substring, context window, max output tokens)` limits, most specific first
+const KNOWN_LIMITS: &[(&str, u32, u32)] = &[
+    ("opus", 200_000, 32_000),
+    ("sonnet", 200_000, 64_000),
+    ("haiku", 200_000, 64_000),
+    ("gpt-5", 400_000, 128_000),
+    ("gpt-4.1", 1_047_576, 32_768),
+    ("gpt-4o", 128_000, 16_384),
+    ("o4-mini", 200_000, 100_000),
+    ("o3", 200_000, 100_000),
+    ("gemini-2.0-flash", 1_048_576, 8_192),
+    ("gemini", 1_048_576, 65_536),
+];
+
+/// Metadata describing a single model offered by a provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// Model identifier passed to the provider
+    pub id: String,
+    /// Alternative names that resolve to this model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Maximum prompt plus completion tokens, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    /// Maximum completion tokens per response, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    /// Features this model supports, serialized as lowercase flag names
+    #[serde(with = "capability_serde")]
+    pub capabilities: LlmCapabilities,
+    /// Token pricing, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<TokenPricing>,
+}
+
+impl ModelInfo {
+    /// Create an entry with no limits, aliases or pricing
+    pub fn new(id: impl Into<String>, capabilities: LlmCapabilities) -> Self {
+        Self {
+            id: id.into(),
+            aliases: Vec::new(),
+            context_window: None,
+            max_output_tokens: None,
+            capabilities,
+            pricing: None,
+        }
+    }
+
+    /// Set alternative names for this model
+    #[must_use]
+    pub fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Set the context window size in tokens
+    #[must_use]
+    pub const fn with_context_window(mut self, tokens: u32) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    /// Set the maximum completion tokens per response
+    #[must_use]
+    pub const fn with_max_output_tokens(mut self, tokens: u32) -> Self {
+        self.max_output_tokens = Some(tokens);
+        self
+    }
+
+    /// Set the token pricing
+    #[must_use]
+    pub fn with_pricing(mut self, pricing: TokenPricing) -> Self {
+        self.pricing = Some(pricing);
+        self
+    }
+
+    /// Whether `name` is this model's id or one of its aliases
+    pub fn matches(&self, name: &str) -> bool {
+        self.id == name || self.aliases.iter().any(|a| a == name)
+    }
+}
+
+/// Partial model metadata that corrects or extends a provider's catalog
+///
+/// Fields left unset keep the catalog's value. An override whose `id`
+/// matches no catalog entry adds a new model.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelOverride {
+    /// Model id (or existing alias) to override
+    pub id: String,
+    /// Additional aliases for the model
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Context window size in tokens
+    pub context_window: Option<u32>,
+    /// Maximum completion tokens per response
+    pub max_output_tokens: Option<u32>,
+    /// Replacement capability set, as lowercase flag names
+    #[serde(default, deserialize_with = "capability_serde::deserialize_option")]
+    pub capabilities: Option<LlmCapabilities>,
+    /// Token pricing
+    pub pricing: Option<TokenPricing>,
+}
+
+impl ModelOverride {
+    fn apply(&self, catalog: &mut Vec<ModelInfo>, provider_capabilities: LlmCapabilities) {
+        let idx = catalog
+            .iter()
+            .position(|m| m.matches(&self.id))
+            .unwrap_or_else(|| {
+                catalog.push(ModelInfo::new(&self.id, provider_capabilities));
+                catalog.len() - 1
+            });
+        let entry = &mut catalog[idx];
+        for alias in &self.aliases {
+            if !entry.matches(alias) {
+                entry.aliases.push(alias.clone());
+            }
+        }
+        entry.context_window = self.context_window.or(entry.context_window);
+        entry.max_output_tokens = self.max_output_tokens.or(entry.max_output_tokens);
+        entry.capabilities = self.capabilities.unwrap_or(entry.capabilities);
+        if let Some(ref pricing) = self.pricing {
+            entry.pricing = Some(pricing.clone());
+        }
+    }
+}
+
+/// Build a catalog from bare model ids, filling in known limits and prices
+///
+/// Every entry inherits the provider's `capabilities`.
+pub fn default_catalog(models: &[String], capabilities: LlmCapabilities) -> Vec<ModelInfo> {
+    let pricing = default_pricing_table();
+    models
+        .iter()
+        .map(|id| {
+            let mut info = ModelInfo::new(id, capabilities);
+            if let Some(&(_, context, output)) = KNOWN_LIMITS.iter().find(|(k, ..)| id.contains(k))
+            {
+                info = info
+                    .with_context_window(context)
+                    .with_max_output_tokens(output);
+            }
+            info.pricing = lookup_pricing(&pricing, id).cloned();
+            info
+        })
+        .collect()
+}
+
+/// Apply `overrides` in order to `catalog`
+///
+/// New models added by an override inherit `provider_capabilities` unless
+/// the override sets its own.
+pub fn apply_overrides(
+    catalog: &mut Vec<ModelInfo>,
+    overrides: &[ModelOverride],
+    provider_capabilities: LlmCapabilities,
+) {
+    for model_override in overrides {
+        model_override.apply(catalog, provider_capabilities);
+    }
+}
+
+/// Lowercase names of the flags set in `capabilities` (e.g. `"vision"`)
+pub fn capability_names(capabilities: LlmCapabilities) -> Vec<String> {
+    capabilities
+        .iter_names()
+        .map(|(name, _)| name.to_ascii_lowercase())
+        .collect()
+}
+
+/// Serialize capabilities as a list of lowercase flag names
+mod capability_serde {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::types::LlmCapabilities;
+
+    #[allow(clippy::trivially_copy_pass_by_ref)] // signature required by `#[serde(with)]`
+    pub fn serialize<S: Serializer>(
+        caps: &LlmCapabilities,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(super::capability_names(*caps))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<LlmCapabilities, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        names
+            .iter()
+            .try_fold(LlmCapabilities::empty(), |caps, name| {
+                LlmCapabilities::from_name(&name.to_ascii_uppercase())
+                    .map(|flag| caps | flag)
+                    .ok_or_else(|| D::Error::custom(format!("unknown capability: {name}")))
+            })
+    }
+
+    pub fn deserialize_option<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<LlmCapabilities>, D::Error> {
+        deserialize(deserializer).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_catalog_fills_known_limits_and_pricing() {
+        let models = vec!["opus".to_owned(), "mystery-model".to_owned()];
+        let catalog = default_catalog(&models, LlmCapabilities::text_only());
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(catalog[0].context_window, Some(200_000));
+        assert!(catalog[0].pricing.is_some());
+        assert_eq!(catalog[0].capabilities, LlmCapabilities::text_only());
+        assert_eq!(catalog[1].context_window, None);
+        assert!(catalog[1].pricing.is_none());
+    }
+
+    #[test]
+    fn overrides_update_matching_entries_and_add_new_ones() {
+        let mut catalog = default_catalog(&["sonnet".to_owned()], LlmCapabilities::text_only());
+        let overrides = vec![
+            ModelOverride {
+                id: "sonnet".to_owned(),
+                aliases: vec!["smart".to_owned()],
+                context_window: Some(1_000_000),
+                capabilities: Some(LlmCapabilities::full_featured()),
+                ..ModelOverride::default()
+            },
+            ModelOverride {
+                id: "smart".to_owned(),
+                max_output_tokens: Some(8_000),
+                ..ModelOverride::default()
+            },
+            ModelOverride {
+                id: "local-llama".to_owned(),
+                context_window: Some(32_768),
+                ..ModelOverride::default()
+            },
+        ];
+        apply_overrides(&mut catalog, &overrides, LlmCapabilities::STREAMING);
+
+        assert_eq!(catalog.len(), 2);
+        let sonnet = &catalog[0];
+        assert!(sonnet.matches("smart"));
+        assert_eq!(sonnet.context_window, Some(1_000_000));
+        assert_eq!(sonnet.max_output_tokens, Some(8_000));
+        assert!(sonnet.capabilities.supports_vision());
+        assert_eq!(catalog[1].id, "local-llama");
+        assert_eq!(catalog[1].capabilities, LlmCapabilities::STREAMING);
+    }
+
+    #[test]
+    fn capabilities_serialize_as_lowercase_names() {
+        let info = ModelInfo::new("m", LlmCapabilities::STREAMING | LlmCapabilities::VISION);
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(
+            json["capabilities"],
+            serde_json::json!(["streaming", "vision"])
+        );
+        let back: ModelInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(back, info);
+
+        let bad = serde_json::json!({"id": "m", "capabilities": ["telepathy"]});
+        assert!(serde_json::from_value::<ModelInfo>(bad).is_err());
+    }
+}
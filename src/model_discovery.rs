@@ -0,0 +1,198 @@
+// ABOUTME: Runtime model discovery by asking CLI tools which models they can serve
+// ABOUTME: Parses model-listing command output and caches the result with a TTL, merged with fallback lists
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Model Discovery
+//!
+//! Runners advertise a static fallback model list that goes stale as vendors
+//! ship new models. CLIs that can list their models (`opencode models`,
+//! `kilo models`, `cursor-agent models`, `gh copilot models`) declare a
+//! [`ModelDiscovery`]; [`LlmProvider::refresh_models`](crate::types::LlmProvider::refresh_models)
+//! runs it, caches the parsed ids in a [`ModelListCache`] for the runner's
+//! [`model_cache_ttl`](crate::config::RunnerConfig::model_cache_ttl), and
+//! merges them ahead of the fallback list.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// How a runner lists the models its CLI can serve
+#[derive(Debug, Clone, Copy)]
+pub struct ModelDiscovery {
+    /// Program to run instead of the runner's own binary (e.g. `gh`)
+    pub program: Option<&'static str>,
+    /// Arguments that make the program print its model list
+    pub args: &'static [&'static str],
+    /// Extract model ids from the command's stdout
+    pub parse: fn(&str) -> Vec<String>,
+}
+
+impl ModelDiscovery {
+    /// Run the runner's binary with `args`, parsing one model id per line
+    pub const fn subcommand(args: &'static [&'static str]) -> Self {
+        Self {
+            program: None,
+            args,
+            parse: parse_model_lines,
+        }
+    }
+
+    /// Use a custom output parser
+    #[must_use]
+    pub const fn with_parser(mut self, parse: fn(&str) -> Vec<String>) -> Self {
+        self.parse = parse;
+        self
+    }
+
+    /// Run `program` instead of the runner's binary
+    #[must_use]
+    pub const fn with_program(mut self, program: &'static str) -> Self {
+        self.program = Some(program);
+        self
+    }
+}
+
+/// Discovered model ids with the time they were fetched
+#[derive(Debug, Default)]
+pub struct ModelListCache {
+    entry: RwLock<Option<(Instant, Vec<String>)>>,
+}
+
+impl ModelListCache {
+    /// Cached ids if they were fetched less than `ttl` ago
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn fresh(&self, ttl: Duration) -> Option<Vec<String>> {
+        let entry = self.entry.read().expect("model cache lock poisoned");
+        entry
+            .as_ref()
+            .filter(|(fetched, _)| fetched.elapsed() < ttl)
+            .map(|(_, models)| models.clone())
+    }
+
+    /// The most recently discovered ids, however old
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn latest(&self) -> Option<Vec<String>> {
+        let entry = self.entry.read().expect("model cache lock poisoned");
+        entry.as_ref().map(|(_, models)| models.clone())
+    }
+
+    /// Record a freshly discovered list
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    pub fn store(&self, models: Vec<String>) {
+        *self.entry.write().expect("model cache lock poisoned") = Some((Instant::now(), models));
+    }
+}
+
+/// Discovered ids first, then fallback ids the CLI did not report
+pub fn merge_models(discovered: &[String], fallback: &[String]) -> Vec<String> {
+    let mut merged = discovered.to_vec();
+    for model in fallback {
+        if !merged.contains(model) {
+            merged.push(model.clone());
+        }
+    }
+    merged
+}
+
+/// Parse output that lists one model id per line (`opencode models`, `gh copilot models`)
+///
+/// Lines containing whitespace, such as headers or hints, are skipped.
+pub fn parse_model_lines(stdout: &str) -> Vec<String> {
+    let mut models = Vec::new();
+    for line in stdout.lines().map(str::trim) {
+        if !line.is_empty()
+            && !line.contains(char::is_whitespace)
+            && !models.iter().any(|m| m == line)
+        {
+            models.push(line.to_owned());
+        }
+    }
+    models
+}
+
+/// Parse `cursor-agent models` output, which lists `<id> - <display name>` lines
+pub fn parse_id_dash_name_lines(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim().split_once(" - "))
+        .map(|(id, _)| id.trim())
+        .filter(|id| !id.is_empty() && !id.contains(char::is_whitespace))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_opencode_models_fixture() {
+        let models =
+            parse_model_lines(include_str!("../tests/fixtures/models/opencode_models.txt"));
+        assert!(models.contains(&"anthropic/claude-sonnet-4-6".to_owned()));
+        assert!(models.contains(&"github-copilot/gpt-5".to_owned()));
+        assert!(models.iter().all(|m| m.contains('/')));
+    }
+
+    #[test]
+    fn parses_kilo_models_fixture() {
+        let models = parse_model_lines(include_str!("../tests/fixtures/models/kilo_models.txt"));
+        assert_eq!(
+            models.first().map(String::as_str),
+            Some("anthropic/claude-opus-4-6")
+        );
+        assert!(models.contains(&"openai/gpt-5.4".to_owned()));
+    }
+
+    #[test]
+    fn parses_cursor_agent_models_fixture() {
+        let models = parse_id_dash_name_lines(include_str!(
+            "../tests/fixtures/models/cursor_agent_models.txt"
+        ));
+        assert_eq!(models.first().map(String::as_str), Some("auto"));
+        assert!(models.contains(&"sonnet-4.5-thinking".to_owned()));
+        assert!(!models.iter().any(|m| m.contains("Tip")));
+    }
+
+    #[test]
+    fn parses_gh_copilot_models_fixture() {
+        let models = parse_model_lines(include_str!(
+            "../tests/fixtures/models/gh_copilot_models.txt"
+        ));
+        assert!(models.contains(&"claude-sonnet-4.6".to_owned()));
+        assert!(models.contains(&"gpt-5.4".to_owned()));
+    }
+
+    #[test]
+    fn merge_puts_discovered_first_without_duplicates() {
+        let discovered = vec!["new-model".to_owned(), "sonnet".to_owned()];
+        let fallback = vec!["sonnet".to_owned(), "opus".to_owned()];
+        assert_eq!(
+            merge_models(&discovered, &fallback),
+            vec!["new-model", "sonnet", "opus"]
+        );
+    }
+
+    #[test]
+    fn cache_expires_after_ttl() {
+        let cache = ModelListCache::default();
+        assert!(cache.latest().is_none());
+        cache.store(vec!["m".to_owned()]);
+        assert_eq!(
+            cache.fresh(Duration::from_mins(1)),
+            Some(vec!["m".to_owned()])
+        );
+        assert!(cache.fresh(Duration::ZERO).is_none());
+        assert_eq!(cache.latest(), Some(vec!["m".to_owned()]));
+    }
+}
@@ -31,6 +31,7 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
+use base64::Engine;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
@@ -38,10 +39,16 @@ use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 use tracing::{debug, instrument, warn};
 
+use crate::config::DEFAULT_MODEL_CACHE_TTL;
+use crate::error_classify::classify_error_text;
+use crate::model_catalog::{default_catalog, ModelInfo};
+use crate::model_discovery::{merge_models, ModelListCache};
+use crate::stream::{StreamDeadline, StreamTimeouts};
 use crate::types::{
-    ChatMessage, ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider,
-    ResponseFormat, RunnerError, StreamChunk, TokenUsage, ToolCallRequest, ToolChoice,
-    ToolDefinition,
+    AttachmentPart, AttachmentSource, ChatChoice, ChatMessage, ChatRequest, ChatResponse,
+    ChatStream, ErrorKind, LlmCapabilities, LlmProvider, ReasoningEffort, RequestMetadata,
+    ResponseFormat, RunnerError, StreamChunk, TokenUsage, ToolCallDelta, ToolCallRequest,
+    ToolChoice, ToolDefinition,
 };
 
 // ============================================================================
@@ -81,6 +88,9 @@ const ENV_MODEL: &str = "OPENAI_API_MODEL";
 /// Environment variable for the request timeout (in seconds)
 const ENV_TIMEOUT_SECS: &str = "OPENAI_API_TIMEOUT_SECS";
 
+/// Environment variable for the streaming idle timeout (in seconds)
+const ENV_IDLE_TIMEOUT_SECS: &str = "OPENAI_API_IDLE_TIMEOUT_SECS";
+
 // ============================================================================
 // Configuration
 // ============================================================================
@@ -95,6 +105,7 @@ const ENV_TIMEOUT_SECS: &str = "OPENAI_API_TIMEOUT_SECS";
 /// - `OPENAI_API_KEY` — Bearer token for authentication
 /// - `OPENAI_API_MODEL` — Default model to use
 /// - `OPENAI_API_TIMEOUT_SECS` — Request timeout in seconds (default: 120)
+/// - `OPENAI_API_IDLE_TIMEOUT_SECS` — Maximum silence between SSE events (default: none)
 #[derive(Debug, Clone)]
 pub struct OpenAiApiConfig {
     /// Base URL for the API (e.g., `https://api.openai.com`)
@@ -103,8 +114,12 @@ pub struct OpenAiApiConfig {
     pub api_key: Option<String>,
     /// Default model to use when not specified in the request
     pub model: String,
-    /// HTTP request timeout
+    /// HTTP request timeout, also bounding the whole SSE stream
     pub timeout: Duration,
+    /// Maximum silence between SSE events before the stream is aborted
+    pub idle_timeout: Option<Duration>,
+    /// How long a model list fetched by `refresh_models()` is reused
+    pub model_cache_ttl: Duration,
 }
 
 impl OpenAiApiConfig {
@@ -116,6 +131,8 @@ impl OpenAiApiConfig {
             api_key: None,
             model: DEFAULT_MODEL.to_owned(),
             timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            idle_timeout: None,
+            model_cache_ttl: DEFAULT_MODEL_CACHE_TTL,
         }
     }
 
@@ -131,12 +148,18 @@ impl OpenAiApiConfig {
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let idle_timeout = std::env::var(ENV_IDLE_TIMEOUT_SECS)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs);
 
         Self {
             base_url: normalize_base_url(&base_url),
             api_key,
             model,
             timeout: Duration::from_secs(timeout_secs),
+            idle_timeout,
+            model_cache_ttl: DEFAULT_MODEL_CACHE_TTL,
         }
     }
 
@@ -160,6 +183,20 @@ impl OpenAiApiConfig {
         self.timeout = timeout;
         self
     }
+
+    /// Set the maximum silence between SSE events
+    #[must_use]
+    pub const fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Set how long a refreshed model list is cached before `/v1/models` is queried again
+    #[must_use]
+    pub const fn with_model_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.model_cache_ttl = ttl;
+        self
+    }
 }
 
 impl Default for OpenAiApiConfig {
@@ -189,8 +226,14 @@ struct ApiRequest {
     top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<ReasoningEffort>,
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<ApiStreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ApiTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<serde_json::Value>,
@@ -198,6 +241,12 @@ struct ApiRequest {
     response_format: Option<serde_json::Value>,
 }
 
+/// Asks the API to append a usage-only chunk to the stream
+#[derive(Serialize)]
+struct ApiStreamOptions {
+    include_usage: bool,
+}
+
 #[derive(Serialize)]
 struct ApiMessage {
     role: String,
@@ -258,6 +307,8 @@ struct ApiChoice {
 #[derive(Deserialize)]
 struct ApiResponseMessage {
     content: Option<String>,
+    #[serde(alias = "reasoning")]
+    reasoning_content: Option<String>,
     tool_calls: Option<Vec<ApiResponseToolCall>>,
 }
 
@@ -280,6 +331,34 @@ struct ApiUsage {
     prompt_tokens: u32,
     completion_tokens: u32,
     total_tokens: u32,
+    prompt_tokens_details: Option<ApiPromptTokensDetails>,
+    completion_tokens_details: Option<ApiCompletionTokensDetails>,
+    /// Billed cost, reported by some gateways such as `OpenRouter`
+    cost: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ApiPromptTokensDetails {
+    cached_tokens: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ApiCompletionTokensDetails {
+    reasoning_tokens: Option<u32>,
+}
+
+impl From<ApiUsage> for TokenUsage {
+    fn from(u: ApiUsage) -> Self {
+        Self {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+            reasoning_tokens: u.completion_tokens_details.and_then(|d| d.reasoning_tokens),
+            cached_prompt_tokens: u.prompt_tokens_details.and_then(|d| d.cached_tokens),
+            cache_creation_tokens: None,
+            reported_cost: u.cost,
+        }
+    }
 }
 
 // ============================================================================
@@ -288,7 +367,9 @@ struct ApiUsage {
 
 #[derive(Deserialize)]
 struct ApiStreamResponse {
+    #[serde(default)]
     choices: Vec<ApiStreamChoice>,
+    usage: Option<ApiUsage>,
 }
 
 #[derive(Deserialize)]
@@ -300,6 +381,23 @@ struct ApiStreamChoice {
 #[derive(Deserialize)]
 struct ApiStreamDelta {
     content: Option<String>,
+    #[serde(alias = "reasoning")]
+    reasoning_content: Option<String>,
+    tool_calls: Option<Vec<ApiStreamToolCall>>,
+}
+
+#[derive(Deserialize)]
+struct ApiStreamToolCall {
+    #[serde(default)]
+    index: usize,
+    id: Option<String>,
+    function: Option<ApiStreamFunction>,
+}
+
+#[derive(Deserialize)]
+struct ApiStreamFunction {
+    name: Option<String>,
+    arguments: Option<String>,
 }
 
 // ============================================================================
@@ -314,6 +412,8 @@ struct ApiErrorResponse {
 #[derive(Deserialize)]
 struct ApiError {
     message: String,
+    #[serde(default)]
+    code: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -340,6 +440,7 @@ pub struct OpenAiApiRunner {
     config: OpenAiApiConfig,
     client: reqwest::Client,
     models: Vec<String>,
+    discovered_models: ModelListCache,
 }
 
 impl OpenAiApiRunner {
@@ -390,18 +491,27 @@ impl OpenAiApiRunner {
             config,
             client,
             models,
+            discovered_models: ModelListCache::default(),
         }
     }
 
     /// Build an API request body from a `ChatRequest`
-    fn build_api_request(&self, request: &ChatRequest, stream: bool) -> ApiRequest {
+    fn build_api_request(
+        &self,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<ApiRequest, RunnerError> {
         let model = request
             .model
             .as_deref()
             .unwrap_or(&self.config.model)
             .to_owned();
 
-        let messages = request.messages.iter().map(map_message).collect();
+        let messages = request
+            .messages
+            .iter()
+            .map(map_message)
+            .collect::<Result<_, _>>()?;
 
         let tools = request
             .tools
@@ -411,28 +521,43 @@ impl OpenAiApiRunner {
         let tool_choice = request.tool_choice.as_ref().map(map_tool_choice);
         let response_format = request.response_format.as_ref().map(map_response_format);
 
-        ApiRequest {
+        Ok(ApiRequest {
             model,
             messages,
             temperature: request.temperature,
             max_tokens: request.max_tokens,
             top_p: request.top_p,
             stop: request.stop.clone(),
+            // Streamed chunks carry no choice index, so streams stay single-choice
+            n: request.n.filter(|&n| n > 1 && !stream),
+            reasoning_effort: request.effective_reasoning_effort(),
             stream,
+            stream_options: stream.then_some(ApiStreamOptions {
+                include_usage: true,
+            }),
             tools,
             tool_choice,
             response_format,
-        }
+        })
     }
 
     /// Send an API request and return the raw HTTP response
+    ///
+    /// `timeout` bounds the whole exchange, including reading the body, and
+    /// replaces the client's configured timeout.
     async fn send_request(
         &self,
         api_request: &ApiRequest,
+        timeout: Duration,
     ) -> Result<reqwest::Response, RunnerError> {
+        if timeout.is_zero() {
+            return Err(RunnerError::timeout(
+                "request deadline passed before the request was sent",
+            ));
+        }
         let url = format!("{}{CHAT_COMPLETIONS_PATH}", self.config.base_url);
 
-        let mut req = self.client.post(&url).json(api_request);
+        let mut req = self.client.post(&url).timeout(timeout).json(api_request);
         if let Some(ref key) = self.config.api_key {
             req = req.bearer_auth(key);
         }
@@ -452,9 +577,16 @@ impl OpenAiApiRunner {
             return Ok(response);
         }
 
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+
         // Attempt to parse the error body for a better message
         let body = response.text().await.unwrap_or_default();
-        Err(map_http_error(status, &body))
+        Err(map_http_error(status, retry_after, &body))
     }
 }
 
@@ -482,6 +614,9 @@ impl LlmProvider for OpenAiApiRunner {
             | LlmCapabilities::TOP_P
             | LlmCapabilities::STOP_SEQUENCES
             | LlmCapabilities::RESPONSE_FORMAT
+            | LlmCapabilities::FILE_INPUT
+            | LlmCapabilities::MULTIPLE_CHOICES
+            | LlmCapabilities::REASONING
     }
 
     fn default_model(&self) -> &str {
@@ -492,10 +627,35 @@ impl LlmProvider for OpenAiApiRunner {
         &self.models
     }
 
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        let models = self
+            .discovered_models
+            .latest()
+            .map_or_else(|| self.models.clone(), |d| merge_models(&d, &self.models));
+        default_catalog(&models, self.capabilities())
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        if let Some(fresh) = self.discovered_models.fresh(self.config.model_cache_ttl) {
+            return Ok(merge_models(&fresh, &self.models));
+        }
+        let discovered = discover_models(&self.client, &self.config).await;
+        if discovered.is_empty() {
+            return Err(RunnerError::external_service(
+                "openai_api",
+                format!("No models listed at {}{MODELS_PATH}", self.config.base_url),
+            ));
+        }
+        let merged = merge_models(&discovered, &self.models);
+        self.discovered_models.store(discovered);
+        Ok(merged)
+    }
+
     #[instrument(skip(self, request), fields(model))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let api_request = self.build_api_request(request, false);
-        let response = self.send_request(&api_request).await?;
+        let api_request = self.build_api_request(request, false)?;
+        let timeout = request.timeout_or(self.config.timeout);
+        let response = self.send_request(&api_request, timeout).await?;
 
         let body = response.text().await.map_err(|e| {
             RunnerError::external_service("openai_api", format!("Failed to read response: {e}"))
@@ -505,80 +665,87 @@ impl LlmProvider for OpenAiApiRunner {
             RunnerError::external_service("openai_api", format!("Invalid response JSON: {e}"))
         })?;
 
-        let choice =
-            api_response.choices.into_iter().next().ok_or_else(|| {
-                RunnerError::external_service("openai_api", "No choices in response")
-            })?;
-
-        let tool_calls = choice.message.tool_calls.map(|tcs| {
-            tcs.into_iter()
-                .map(|tc| ToolCallRequest {
-                    id: tc.id,
-                    function_name: tc.function.name,
-                    arguments: serde_json::from_str(&tc.function.arguments)
-                        .unwrap_or(serde_json::Value::String(tc.function.arguments)),
-                })
-                .collect()
-        });
+        let reasoning = api_response
+            .choices
+            .first()
+            .and_then(|c| c.message.reasoning_content.clone())
+            .filter(|r| !r.is_empty());
+        let mut choices: Vec<ChatChoice> = (0..)
+            .zip(api_response.choices)
+            .map(|(index, choice)| map_choice(index, choice))
+            .collect();
+        let first = choices
+            .first()
+            .cloned()
+            .ok_or_else(|| RunnerError::external_service("openai_api", "No choices in response"))?;
+        if choices.len() == 1 {
+            choices.clear();
+        }
 
-        let usage = api_response.usage.map(|u| TokenUsage {
-            prompt_tokens: u.prompt_tokens,
-            completion_tokens: u.completion_tokens,
-            total_tokens: u.total_tokens,
-        });
+        let usage = api_response.usage.map(TokenUsage::from);
 
         Ok(ChatResponse {
-            content: choice.message.content.unwrap_or_default(),
+            content: first.content,
             model: api_response.model,
             usage,
-            finish_reason: choice.finish_reason,
+            finish_reason: first.finish_reason,
             warnings: None,
-            tool_calls,
+            tool_calls: first.tool_calls,
+            reasoning,
+            choices: (!choices.is_empty()).then_some(choices),
+            metadata: RequestMetadata::default(),
         })
     }
 
     #[instrument(skip(self, request), fields(model))]
     async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
-        let api_request = self.build_api_request(request, true);
-        let response = self.send_request(&api_request).await?;
+        let api_request = self.build_api_request(request, true)?;
+        let timeout = request.timeout_or(self.config.timeout);
+        let response = self.send_request(&api_request, timeout).await?;
 
         let (tx, rx) = mpsc::channel::<Result<StreamChunk, RunnerError>>(STREAM_CHANNEL_CAPACITY);
         let byte_stream = response.bytes_stream();
+        let mut timeouts = StreamTimeouts::new(request.timeout_or(self.config.timeout));
+        timeouts.idle = self.config.idle_timeout;
 
         tokio::spawn(async move {
             let mut stream = byte_stream;
             let mut buffer = String::new();
+            let deadline = StreamDeadline::start(timeouts, "openai_api");
+            // The finish reason and the usage-only chunk arrive separately;
+            // both are folded into the single final chunk sent at [DONE]
+            let mut finish_reason: Option<String> = None;
+            let mut usage: Option<TokenUsage> = None;
 
             loop {
-                let chunk = stream.next().await;
+                // Dropping the byte stream on timeout closes the HTTP connection
+                let chunk = match deadline.next(stream.next()).await {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
                 match chunk {
                     Some(Ok(bytes)) => {
                         buffer.push_str(&String::from_utf8_lossy(&bytes));
 
                         for event_data in extract_sse_events(&mut buffer) {
                             if event_data == "[DONE]" {
-                                let _ = tx
-                                    .send(Ok(StreamChunk {
-                                        delta: String::new(),
-                                        is_final: true,
-                                        finish_reason: Some("stop".to_owned()),
-                                    }))
-                                    .await;
+                                let _ = tx.send(Ok(final_stream_chunk(finish_reason, usage))).await;
                                 return;
                             }
 
                             match serde_json::from_str::<ApiStreamResponse>(&event_data) {
                                 Ok(resp) => {
+                                    if let Some(u) = resp.usage {
+                                        usage = Some(u.into());
+                                    }
                                     for choice in resp.choices {
-                                        let delta = choice.delta.content.unwrap_or_default();
-                                        let is_final = choice.finish_reason.is_some();
-
-                                        if !delta.is_empty() || is_final {
-                                            let chunk = StreamChunk {
-                                                delta,
-                                                is_final,
-                                                finish_reason: choice.finish_reason,
-                                            };
+                                        if choice.finish_reason.is_some() {
+                                            finish_reason = choice.finish_reason;
+                                        }
+                                        if let Some(chunk) = stream_delta_chunk(choice.delta) {
                                             if tx.send(Ok(chunk)).await.is_err() {
                                                 return;
                                             }
@@ -608,13 +775,7 @@ impl LlmProvider for OpenAiApiRunner {
                     }
                     None => {
                         // Byte stream ended without [DONE]; emit final chunk
-                        let _ = tx
-                            .send(Ok(StreamChunk {
-                                delta: String::new(),
-                                is_final: true,
-                                finish_reason: Some("stop".to_owned()),
-                            }))
-                            .await;
+                        let _ = tx.send(Ok(final_stream_chunk(finish_reason, usage))).await;
                         return;
                     }
                 }
@@ -648,15 +809,17 @@ impl LlmProvider for OpenAiApiRunner {
 
 /// Convert a `ChatMessage` into the API wire format
 ///
-/// When the message has attached images, emits a multipart content array
-/// per the `OpenAI` vision API: `[{"type":"text","text":"..."},{"type":"image_url","image_url":{"url":"data:..."}}]`.
+/// When the message has attached images or files, emits a multipart content array
+/// per the `OpenAI` vision and file APIs: `[{"type":"text","text":"..."},{"type":"image_url","image_url":{"url":"data:..."}},{"type":"file","file":{"file_data":"data:..."}}]`.
+/// Text attachments marked `inline` become additional text parts.
 /// Otherwise, emits a simple string content value.
-fn map_message(msg: &ChatMessage) -> ApiMessage {
+fn map_message(msg: &ChatMessage) -> Result<ApiMessage, RunnerError> {
     let has_images = msg.images.as_ref().is_some_and(|imgs| !imgs.is_empty());
+    let has_attachments = msg.attachments.as_ref().is_some_and(|a| !a.is_empty());
 
     let content = if msg.content.is_empty() && msg.tool_calls.is_some() {
         serde_json::Value::Null
-    } else if has_images {
+    } else if has_images || has_attachments {
         let mut parts = vec![serde_json::json!({
             "type": "text",
             "text": msg.content,
@@ -671,6 +834,11 @@ fn map_message(msg: &ChatMessage) -> ApiMessage {
                 }));
             }
         }
+        if let Some(ref attachments) = msg.attachments {
+            for attachment in attachments {
+                parts.push(map_attachment(attachment)?);
+            }
+        }
         serde_json::Value::Array(parts)
     } else {
         serde_json::Value::String(msg.content.clone())
@@ -689,12 +857,61 @@ fn map_message(msg: &ChatMessage) -> ApiMessage {
             .collect()
     });
 
-    ApiMessage {
+    Ok(ApiMessage {
         role: msg.role.as_str().to_owned(),
         content,
         tool_calls,
         tool_call_id: msg.tool_call_id.clone(),
+    })
+}
+
+/// Convert a response choice into a `ChatChoice`
+fn map_choice(index: u32, choice: ApiChoice) -> ChatChoice {
+    let tool_calls = choice.message.tool_calls.map(|tcs| {
+        tcs.into_iter()
+            .map(|tc| ToolCallRequest {
+                id: tc.id,
+                function_name: tc.function.name,
+                arguments: serde_json::from_str(&tc.function.arguments)
+                    .unwrap_or(serde_json::Value::String(tc.function.arguments)),
+            })
+            .collect()
+    });
+    ChatChoice {
+        index,
+        content: choice.message.content.unwrap_or_default(),
+        finish_reason: choice.finish_reason,
+        tool_calls,
+    }
+}
+
+/// Convert an attachment into a `file` content part, or a text part when inlined
+fn map_attachment(attachment: &AttachmentPart) -> Result<serde_json::Value, RunnerError> {
+    let name = attachment.display_name();
+    if attachment.inline && attachment.is_text() {
+        let bytes = attachment.read_bytes()?;
+        return Ok(serde_json::json!({
+            "type": "text",
+            "text": format!(
+                "[Attached file: {}]\n{}",
+                name.unwrap_or("attachment"),
+                String::from_utf8_lossy(&bytes)
+            ),
+        }));
+    }
+    let data = match attachment.source {
+        AttachmentSource::Data(ref data) => data.clone(),
+        AttachmentSource::Path(_) => {
+            base64::engine::general_purpose::STANDARD.encode(attachment.read_bytes()?)
+        }
+    };
+    let mut file = serde_json::json!({
+        "file_data": format!("data:{};base64,{data}", attachment.mime_type),
+    });
+    if let Some(name) = name {
+        file["filename"] = serde_json::Value::String(name.to_owned());
     }
+    Ok(serde_json::json!({"type": "file", "file": file}))
 }
 
 /// Convert a `ToolDefinition` into the API wire format
@@ -739,16 +956,31 @@ fn map_response_format(format: &ResponseFormat) -> serde_json::Value {
 }
 
 /// Map an HTTP error status to a `RunnerError`
-fn map_http_error(status: StatusCode, body: &str) -> RunnerError {
-    let api_message = serde_json::from_str::<ApiErrorResponse>(body)
-        .map_or_else(|_| body.to_owned(), |e| e.error.message);
+///
+/// The API error `code` (e.g. `context_length_exceeded`, `insufficient_quota`)
+/// takes part in classification; `retry_after` is the `Retry-After` header.
+fn map_http_error(status: StatusCode, retry_after: Option<Duration>, body: &str) -> RunnerError {
+    let (api_message, api_code) = serde_json::from_str::<ApiErrorResponse>(body).map_or_else(
+        |_| (body.to_owned(), None),
+        |e| (e.error.message, e.error.code),
+    );
 
     let detail = format!("HTTP {status}: {api_message}");
+    let haystack = format!("{detail} {}", api_code.as_deref().unwrap_or_default());
 
     match status.as_u16() {
         401 | 403 => RunnerError::auth_failure(detail),
         408 | 504 => RunnerError::timeout(detail),
-        _ => RunnerError::external_service("openai_api", detail),
+        402 => RunnerError::quota_exhausted(format!("openai_api: {detail}")),
+        _ => {
+            // `detail` carries the status line, so a 429 classifies as a rate limit
+            let err = classify_error_text("openai_api", &detail, &haystack);
+            if err.kind == ErrorKind::RateLimited && retry_after.is_some() {
+                err.with_retry_after(retry_after)
+            } else {
+                err
+            }
+        }
     }
 }
 
@@ -756,6 +988,48 @@ fn map_http_error(status: StatusCode, body: &str) -> RunnerError {
 // SSE Parsing
 // ============================================================================
 
+/// Convert a streamed delta into a non-final chunk, or `None` if it is empty
+fn stream_delta_chunk(delta: ApiStreamDelta) -> Option<StreamChunk> {
+    let content = delta.content.unwrap_or_default();
+    let reasoning_delta = delta.reasoning_content.filter(|r| !r.is_empty());
+    let tool_calls: Option<Vec<ToolCallDelta>> = delta.tool_calls.map(|calls| {
+        calls
+            .into_iter()
+            .map(|tc| {
+                let (function_name, arguments) =
+                    tc.function.map_or((None, None), |f| (f.name, f.arguments));
+                ToolCallDelta {
+                    index: tc.index,
+                    id: tc.id,
+                    function_name,
+                    arguments_delta: arguments.unwrap_or_default(),
+                }
+            })
+            .collect()
+    });
+    let tool_calls = tool_calls.filter(|calls| !calls.is_empty());
+
+    if content.is_empty() && reasoning_delta.is_none() && tool_calls.is_none() {
+        return None;
+    }
+    Some(StreamChunk {
+        delta: content,
+        tool_calls,
+        reasoning_delta,
+        ..StreamChunk::default()
+    })
+}
+
+/// Build the chunk that ends the stream
+fn final_stream_chunk(finish_reason: Option<String>, usage: Option<TokenUsage>) -> StreamChunk {
+    StreamChunk {
+        is_final: true,
+        finish_reason: Some(finish_reason.unwrap_or_else(|| "stop".to_owned())),
+        usage,
+        ..StreamChunk::default()
+    }
+}
+
 /// Extract complete SSE event data payloads from a buffer
 ///
 /// Consumes complete events (delimited by `\n\n` or `\r\n\r\n`) from the
@@ -933,7 +1207,7 @@ mod tests {
     #[test]
     fn map_message_user() {
         let msg = ChatMessage::user("Hello");
-        let api_msg = map_message(&msg);
+        let api_msg = map_message(&msg).unwrap();
         assert_eq!(api_msg.role, "user");
         assert_eq!(
             api_msg.content,
@@ -952,7 +1226,7 @@ mod tests {
             arguments: serde_json::json!({"city": "Paris"}),
         }]);
 
-        let api_msg = map_message(&msg);
+        let api_msg = map_message(&msg).unwrap();
         assert_eq!(api_msg.content, serde_json::Value::Null);
         assert!(api_msg.tool_calls.is_some());
         let tcs = api_msg.tool_calls.as_ref().unwrap();
@@ -965,7 +1239,7 @@ mod tests {
     #[test]
     fn map_message_tool_result() {
         let msg = ChatMessage::tool("get_weather", "call_1", r#"{"temp": 72}"#);
-        let api_msg = map_message(&msg);
+        let api_msg = map_message(&msg).unwrap();
         assert_eq!(api_msg.role, "tool");
         assert_eq!(
             api_msg.content,
@@ -1029,6 +1303,7 @@ mod tests {
     fn map_http_error_auth() {
         let err = map_http_error(
             StatusCode::UNAUTHORIZED,
+            None,
             r#"{"error":{"message":"bad key"}}"#,
         );
         assert_eq!(err.kind, crate::types::ErrorKind::AuthFailure);
@@ -1037,7 +1312,7 @@ mod tests {
 
     #[test]
     fn map_http_error_timeout() {
-        let err = map_http_error(StatusCode::GATEWAY_TIMEOUT, "timeout");
+        let err = map_http_error(StatusCode::GATEWAY_TIMEOUT, None, "timeout");
         assert_eq!(err.kind, crate::types::ErrorKind::Timeout);
     }
 
@@ -1045,19 +1320,91 @@ mod tests {
     fn map_http_error_server() {
         let err = map_http_error(
             StatusCode::INTERNAL_SERVER_ERROR,
+            None,
             r#"{"error":{"message":"overloaded"}}"#,
         );
         assert_eq!(err.kind, crate::types::ErrorKind::ExternalService);
         assert!(err.message.contains("overloaded"));
     }
 
+    #[test]
+    fn map_http_error_rate_limit_uses_retry_after_header() {
+        let err = map_http_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(20)),
+            r#"{"error":{"message":"Slow down"}}"#,
+        );
+        assert_eq!(err.kind, crate::types::ErrorKind::RateLimited);
+        assert_eq!(err.retry_after, Some(Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn map_http_error_uses_api_error_code() {
+        let quota = map_http_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            None,
+            r#"{"error":{"message":"Out of budget","code":"insufficient_quota"}}"#,
+        );
+        assert_eq!(quota.kind, crate::types::ErrorKind::QuotaExhausted);
+
+        let context = map_http_error(
+            StatusCode::BAD_REQUEST,
+            None,
+            r#"{"error":{"message":"Too big","code":"context_length_exceeded"}}"#,
+        );
+        assert_eq!(context.kind, crate::types::ErrorKind::ContextLengthExceeded);
+    }
+
     #[test]
     fn map_http_error_unparseable_body() {
-        let err = map_http_error(StatusCode::BAD_REQUEST, "not json");
+        let err = map_http_error(StatusCode::BAD_REQUEST, None, "not json");
         assert_eq!(err.kind, crate::types::ErrorKind::ExternalService);
         assert!(err.message.contains("not json"));
     }
 
+    #[test]
+    fn streaming_request_asks_for_usage() {
+        let runner = OpenAiApiRunner {
+            config: OpenAiApiConfig::new("https://example.com"),
+            client: reqwest::Client::new(),
+            models: Vec::new(),
+            discovered_models: ModelListCache::default(),
+        };
+        let request = ChatRequest::new(vec![ChatMessage::user("test")]);
+        let json = serde_json::to_value(runner.build_api_request(&request, true).unwrap()).unwrap();
+        assert_eq!(json["stream_options"]["include_usage"], true);
+    }
+
+    #[test]
+    fn stream_delta_chunk_carries_tool_calls_and_reasoning() {
+        let event = r#"{"choices":[{"delta":{"reasoning_content":"Need weather.","tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"ci"}}]},"finish_reason":null}]}"#;
+        let resp: ApiStreamResponse = serde_json::from_str(event).unwrap();
+        let chunk = stream_delta_chunk(resp.choices.into_iter().next().unwrap().delta).unwrap();
+
+        assert!(chunk.delta.is_empty());
+        assert_eq!(chunk.reasoning_delta.as_deref(), Some("Need weather."));
+        let calls = chunk.tool_calls.unwrap();
+        assert_eq!(calls[0].id.as_deref(), Some("call_1"));
+        assert_eq!(calls[0].function_name.as_deref(), Some("get_weather"));
+        assert_eq!(calls[0].arguments_delta, "{\"ci");
+
+        let empty: ApiStreamDelta = serde_json::from_str(r#"{"content":""}"#).unwrap();
+        assert!(stream_delta_chunk(empty).is_none());
+    }
+
+    #[test]
+    fn usage_only_stream_event_parses() {
+        let event =
+            r#"{"choices":[],"usage":{"prompt_tokens":9,"completion_tokens":4,"total_tokens":13}}"#;
+        let resp: ApiStreamResponse = serde_json::from_str(event).unwrap();
+        assert!(resp.choices.is_empty());
+        assert_eq!(resp.usage.unwrap().total_tokens, 13);
+
+        let chunk = final_stream_chunk(Some("tool_calls".to_owned()), None);
+        assert!(chunk.is_final);
+        assert_eq!(chunk.finish_reason.as_deref(), Some("tool_calls"));
+    }
+
     #[test]
     fn api_request_serialization() {
         let config = OpenAiApiConfig::new("https://example.com").with_model("gpt-4o");
@@ -1065,6 +1412,7 @@ mod tests {
             config,
             client: reqwest::Client::new(),
             models: vec!["gpt-4o".to_owned()],
+            discovered_models: ModelListCache::default(),
         };
 
         let request = ChatRequest::new(vec![ChatMessage::user("test")])
@@ -1072,9 +1420,10 @@ mod tests {
             .with_max_tokens(100)
             .with_top_p(0.9)
             .with_stop(vec!["END".to_owned()])
-            .with_response_format(ResponseFormat::JsonObject);
+            .with_response_format(ResponseFormat::JsonObject)
+            .with_thinking_budget(4_000);
 
-        let api_req = runner.build_api_request(&request, false);
+        let api_req = runner.build_api_request(&request, false).unwrap();
         let json = serde_json::to_value(&api_req).unwrap();
 
         assert_eq!(json["model"], "gpt-4o");
@@ -1087,7 +1436,9 @@ mod tests {
             .is_some_and(|v| (v - 0.9).abs() < 0.01));
         assert_eq!(json["stop"], serde_json::json!(["END"]));
         assert!(!json["stream"].as_bool().unwrap());
+        assert!(json.get("stream_options").is_none());
         assert_eq!(json["response_format"]["type"], "json_object");
+        assert_eq!(json["reasoning_effort"], "low");
         assert!(json.get("tools").is_none());
         assert!(json.get("tool_choice").is_none());
     }
@@ -1099,6 +1450,7 @@ mod tests {
             config,
             client: reqwest::Client::new(),
             models: vec![],
+            discovered_models: ModelListCache::default(),
         };
 
         let request = ChatRequest::new(vec![ChatMessage::user("test")])
@@ -1109,7 +1461,7 @@ mod tests {
             }])
             .with_tool_choice(ToolChoice::Required);
 
-        let api_req = runner.build_api_request(&request, false);
+        let api_req = runner.build_api_request(&request, false).unwrap();
         let json = serde_json::to_value(&api_req).unwrap();
 
         assert_eq!(json["tools"][0]["type"], "function");
@@ -1124,6 +1476,7 @@ mod tests {
             "choices": [{
                 "message": {
                     "content": "Hello!",
+                    "reasoning_content": "Greet back.",
                     "tool_calls": null
                 },
                 "finish_reason": "stop"
@@ -1131,7 +1484,10 @@ mod tests {
             "usage": {
                 "prompt_tokens": 10,
                 "completion_tokens": 5,
-                "total_tokens": 15
+                "total_tokens": 15,
+                "prompt_tokens_details": {"cached_tokens": 4},
+                "completion_tokens_details": {"reasoning_tokens": 2},
+                "cost": 0.0007
             }
         }"#;
 
@@ -1139,8 +1495,16 @@ mod tests {
         assert_eq!(resp.model, "gpt-4o");
         assert_eq!(resp.choices.len(), 1);
         assert_eq!(resp.choices[0].message.content.as_deref(), Some("Hello!"));
+        assert_eq!(
+            resp.choices[0].message.reasoning_content.as_deref(),
+            Some("Greet back.")
+        );
         assert_eq!(resp.choices[0].finish_reason.as_deref(), Some("stop"));
-        assert_eq!(resp.usage.as_ref().unwrap().total_tokens, 15);
+        let usage = TokenUsage::from(resp.usage.unwrap());
+        assert_eq!(usage.total_tokens, 15);
+        assert_eq!(usage.cached_prompt_tokens, Some(4));
+        assert_eq!(usage.reasoning_tokens, Some(2));
+        assert_eq!(usage.reported_cost, Some(0.0007));
     }
 
     #[test]
@@ -1222,6 +1586,7 @@ mod tests {
             config,
             client: reqwest::Client::new(),
             models: vec![],
+            discovered_models: ModelListCache::default(),
         };
 
         let caps = runner.capabilities();
@@ -1240,7 +1605,7 @@ mod tests {
     #[test]
     fn map_message_user_without_images() {
         let msg = ChatMessage::user("Hello");
-        let api_msg = map_message(&msg);
+        let api_msg = map_message(&msg).unwrap();
         assert_eq!(
             api_msg.content,
             serde_json::Value::String("Hello".to_owned())
@@ -1253,7 +1618,7 @@ mod tests {
 
         let img = ImagePart::new("aGVsbG8=", "image/png").unwrap();
         let msg = ChatMessage::user_with_images("Describe this", vec![img]);
-        let api_msg = map_message(&msg);
+        let api_msg = map_message(&msg).unwrap();
 
         let content = api_msg.content.as_array().expect("should be array");
         assert_eq!(content.len(), 2);
@@ -1266,10 +1631,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn map_message_user_with_attachments() {
+        let pdf = AttachmentPart::from_base64("JVBERi0=", "application/pdf")
+            .unwrap()
+            .with_filename("report.pdf");
+        let csv = AttachmentPart::from_base64("YSxiCjEsMg==", "text/csv")
+            .unwrap()
+            .with_filename("data.csv")
+            .with_inline(true);
+        let msg = ChatMessage::user_with_attachments("Summarize", vec![pdf, csv]);
+        let api_msg = map_message(&msg).unwrap();
+
+        let content = api_msg.content.as_array().expect("should be array");
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[1]["type"], "file");
+        assert_eq!(content[1]["file"]["filename"], "report.pdf");
+        assert_eq!(
+            content[1]["file"]["file_data"],
+            "data:application/pdf;base64,JVBERi0="
+        );
+        assert_eq!(content[2]["type"], "text");
+        assert_eq!(content[2]["text"], "[Attached file: data.csv]\na,b\n1,2");
+    }
+
     #[test]
     fn map_message_user_with_empty_images_stays_string() {
         let msg = ChatMessage::user_with_images("Hello", vec![]);
-        let api_msg = map_message(&msg);
+        let api_msg = map_message(&msg).unwrap();
         assert_eq!(
             api_msg.content,
             serde_json::Value::String("Hello".to_owned())
@@ -1282,7 +1671,7 @@ mod tests {
 
         let img = ImagePart::new("AAAA", "image/jpeg").unwrap();
         let msg = ChatMessage::user_with_images("What is this?", vec![img]);
-        let api_msg = map_message(&msg);
+        let api_msg = map_message(&msg).unwrap();
         let json = serde_json::to_value(&api_msg).unwrap();
 
         assert!(json["content"].is_array());
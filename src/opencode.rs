@@ -6,18 +6,19 @@
 
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError, TokenUsage,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError, TokenUsage,
 };
 use async_trait::async_trait;
 use serde::Deserialize;
 use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
-use crate::config::RunnerConfig;
-use crate::process::run_cli_command;
-use crate::prompt::prepare_prompt;
+use crate::config::{CliRunnerType, PromptDelivery, RunnerConfig};
+use crate::model_discovery::ModelDiscovery;
 use crate::sandbox::{apply_sandbox, build_policy};
 
 /// Token counts from `OpenCode` NDJSON `step_finish` events.
@@ -25,7 +26,16 @@ use crate::sandbox::{apply_sandbox, build_policy};
 struct OpenCodeTokens {
     input: Option<u64>,
     output: Option<u64>,
+    reasoning: Option<u64>,
     total: Option<u64>,
+    cache: Option<OpenCodeCacheTokens>,
+}
+
+/// Prompt-cache token counts nested under `tokens.cache`
+#[derive(Debug, Deserialize)]
+struct OpenCodeCacheTokens {
+    read: Option<u64>,
+    write: Option<u64>,
 }
 
 /// Default model for `OpenCode`
@@ -52,11 +62,12 @@ impl OpenCodeRunner {
     #[must_use]
     pub fn new(config: RunnerConfig) -> Self {
         Self {
-            base: CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS),
+            base: CliRunnerBase::new(config, DEFAULT_MODEL, FALLBACK_MODELS)
+                .with_model_discovery(ModelDiscovery::subcommand(&["models"])),
         }
     }
 
-    /// Store a session ID for later resumption
+    /// Store a session ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
@@ -64,7 +75,12 @@ impl OpenCodeRunner {
     /// Build the base command with common arguments
     fn build_command(&self, prompt: &str) -> Command {
         let mut cmd = Command::new(&self.base.config.binary_path);
-        cmd.args(["run", prompt, "--format", "json"]);
+        cmd.arg("run");
+        // `opencode run` takes piped stdin as the message
+        if self.base.prompt_delivery(CliRunnerType::OpenCode) == PromptDelivery::Argv {
+            cmd.arg(prompt);
+        }
+        cmd.args(["--format", "json"]);
 
         let model = self
             .base
@@ -92,7 +108,7 @@ impl OpenCodeRunner {
     ///
     /// `OpenCode` emits NDJSON lines with `type` discriminator:
     /// - `text`  — content in `part.text`
-    /// - `step_finish` — finish reason in `part.reason`, token counts in `part.tokens`
+    /// - `step_finish` — finish reason in `part.reason`, token counts in `part.tokens`, cost in `part.cost`
     /// - `step_start` / other — ignored
     ///
     /// The `sessionID` from any line is captured for session resumption.
@@ -141,10 +157,21 @@ impl OpenCodeRunner {
                                 let input = t.input.unwrap_or(0);
                                 let output = t.output.unwrap_or(0);
                                 let total = t.total.unwrap_or(input + output);
+                                let cache = t.cache.as_ref();
                                 usage = Some(TokenUsage {
                                     prompt_tokens: input as u32,
                                     completion_tokens: output as u32,
                                     total_tokens: total as u32,
+                                    reasoning_tokens: t.reasoning.map(|n| n as u32),
+                                    cached_prompt_tokens: cache
+                                        .and_then(|c| c.read)
+                                        .map(|n| n as u32),
+                                    cache_creation_tokens: cache
+                                        .and_then(|c| c.write)
+                                        .map(|n| n as u32),
+                                    reported_cost: value
+                                        .pointer("/part/cost")
+                                        .and_then(serde_json::Value::as_f64),
                                 });
                             }
                         }
@@ -163,6 +190,9 @@ impl OpenCodeRunner {
             finish_reason: finish_reason.or_else(|| Some("stop".to_owned())),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
 
         Ok((response, session_id))
@@ -173,26 +203,43 @@ impl OpenCodeRunner {
 impl LlmProvider for OpenCodeRunner {
     crate::delegate_provider_base!("opencode", "OpenCode CLI", LlmCapabilities::empty());
 
-    #[instrument(skip_all, fields(runner = "opencode"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "opencode"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt);
 
-        if let Some(model) = &request.model {
-            if let Some(sid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(sid) = self.base.get_session(key).await {
                 cmd.args(["--session", &sid]);
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(
+                request,
+                cmd,
+                self.base.prompt_stdin(CliRunnerType::OpenCode, prompt),
+                cancel,
+            )
+            .await?;
         self.base.check_exit_code(&output, "opencode")?;
 
         let (response, session_id) = Self::parse_ndjson_response(&output.stdout)?;
 
         if let Some(sid) = session_id {
-            if let Some(model) = &request.model {
-                self.base.set_session(model, &sid).await;
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &sid).await;
             }
         }
 
@@ -216,7 +263,7 @@ mod tests {
     fn test_parse_ndjson_response_basic() {
         let ndjson = br#"{"type":"step_start","timestamp":1772896674815,"sessionID":"ses_abc123","part":{"type":"step-start"}}
 {"type":"text","timestamp":1772896674817,"sessionID":"ses_abc123","part":{"type":"text","text":"PONG"}}
-{"type":"step_finish","timestamp":1772896674834,"sessionID":"ses_abc123","part":{"type":"step-finish","reason":"stop","tokens":{"total":14976,"input":14963,"output":13,"reasoning":0}}}"#;
+{"type":"step_finish","timestamp":1772896674834,"sessionID":"ses_abc123","part":{"type":"step-finish","reason":"stop","cost":0.0061,"tokens":{"total":14976,"input":14963,"output":13,"reasoning":0,"cache":{"read":12000,"write":2963}}}}"#;
 
         let (resp, sid) = OpenCodeRunner::parse_ndjson_response(ndjson).unwrap();
         assert_eq!(resp.content, "PONG");
@@ -226,6 +273,10 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 14963);
         assert_eq!(usage.completion_tokens, 13);
         assert_eq!(usage.total_tokens, 14976);
+        assert_eq!(usage.reasoning_tokens, Some(0));
+        assert_eq!(usage.cached_prompt_tokens, Some(12000));
+        assert_eq!(usage.cache_creation_tokens, Some(2963));
+        assert_eq!(usage.reported_cost, Some(0.0061));
     }
 
     #[test]
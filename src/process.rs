@@ -1,5 +1,5 @@
 // ABOUTME: Subprocess spawning with timeout and output-size safety limits
-// ABOUTME: Wraps tokio::process::Command with structured output and error handling
+// ABOUTME: Runs CLIs in their own process group so timeouts and drops tear down the whole tree
 //
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
@@ -8,14 +8,130 @@ use std::process::Stdio;
 use std::time::{Duration, Instant};
 
 use crate::types::RunnerError;
-use tokio::io::AsyncReadExt;
-use tokio::process::{ChildStderr, ChildStdout, Command};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
 use tokio::time::timeout as tokio_timeout;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
 
 /// Default maximum output size (10 MiB)
 const DEFAULT_MAX_OUTPUT_BYTES: usize = 10 * 1024 * 1024;
 
+/// Time a process group gets to exit after SIGTERM before it is sent SIGKILL
+pub const TERMINATE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Spawn `cmd` as the leader of a new process group.
+///
+/// Agentic CLIs start shells and MCP servers of their own; running them in
+/// a dedicated group lets [`terminate_process_group`] reach those
+/// grandchildren instead of orphaning them.
+///
+/// When `stdin` is `Some`, the bytes are written to the child's stdin on a
+/// background task, which then closes it so the CLI sees end-of-input.
+/// Writing in the background keeps a large prompt from deadlocking against
+/// a child that fills its stdout pipe before reading all of stdin.
+pub(crate) fn spawn_in_process_group(
+    cmd: &mut Command,
+    stdin: Option<&[u8]>,
+) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    cmd.kill_on_drop(true);
+    if stdin.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+    let mut child = cmd.spawn()?;
+
+    if let Some(input) = stdin {
+        let input = input.to_vec();
+        let mut pipe = child
+            .stdin
+            .take()
+            .ok_or_else(|| std::io::Error::other("failed to capture CLI stdin"))?;
+        tokio::spawn(async move {
+            if let Err(e) = pipe.write_all(&input).await {
+                debug!(error = %e, "CLI closed stdin before reading the whole prompt");
+            }
+            let _ = pipe.shutdown().await;
+        });
+    }
+    Ok(child)
+}
+
+/// Send `signal` to every process in the group led by `pgid`
+#[cfg(unix)]
+fn signal_group(pgid: u32, signal: nix::sys::signal::Signal) -> bool {
+    let Ok(pgid) = i32::try_from(pgid) else {
+        return false;
+    };
+    nix::sys::signal::killpg(nix::unistd::Pid::from_raw(pgid), signal).is_ok()
+}
+
+/// Tear down a child's process group: SIGTERM, wait up to `grace`, then SIGKILL.
+///
+/// The child must have been started with [`spawn_in_process_group`]. On
+/// non-Unix platforms only the direct child is killed.
+pub(crate) async fn terminate_process_group(child: &mut Child, grace: Duration) {
+    #[cfg(unix)]
+    if let Some(pgid) = child.id() {
+        use nix::sys::signal::Signal;
+
+        if signal_group(pgid, Signal::SIGTERM) {
+            let _ = tokio_timeout(grace, child.wait()).await;
+        }
+        // The leader may be gone while grandchildren linger in the group
+        signal_group(pgid, Signal::SIGKILL);
+    }
+    let _ = child.kill().await;
+}
+
+/// Non-blocking variant of [`terminate_process_group`] for `Drop` impls.
+///
+/// Sends SIGTERM immediately and finishes the grace period and SIGKILL on a
+/// background task. Without a Tokio runtime the group is killed at once.
+pub(crate) fn terminate_process_group_in_background(mut child: Child, grace: Duration) {
+    #[cfg(unix)]
+    if let Some(pgid) = child.id() {
+        use nix::sys::signal::Signal;
+
+        signal_group(pgid, Signal::SIGTERM);
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = tokio_timeout(grace, child.wait()).await;
+                signal_group(pgid, Signal::SIGKILL);
+                let _ = child.kill().await;
+            });
+            return;
+        }
+        signal_group(pgid, Signal::SIGKILL);
+    }
+    let _ = child.start_kill();
+}
+
+/// Owns a running child and tears down its process group if dropped early
+struct ProcessGroupGuard(Option<Child>);
+
+impl ProcessGroupGuard {
+    fn child(&mut self) -> &mut Child {
+        self.0
+            .as_mut()
+            .expect("child taken from process group guard")
+    }
+
+    /// Stop guarding a child that has already exited
+    fn disarm(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(child) = self.0.take() {
+            terminate_process_group_in_background(child, TERMINATE_GRACE_PERIOD);
+        }
+    }
+}
+
 /// Structured output from a CLI command execution
 #[derive(Debug, Clone)]
 pub struct CliOutput {
@@ -74,9 +190,11 @@ pub(crate) async fn read_stderr_capped(stream: Option<ChildStderr>, limit: usize
 
 /// Run a CLI command with timeout and output-size limits
 ///
-/// The command is spawned as a child process. If it does not exit within
-/// `timeout`, it is killed and an error is returned. Output is capped at
-/// `max_output_bytes` to prevent unbounded memory consumption.
+/// The command is spawned as a child process in its own process group. If it
+/// does not exit within `timeout`, the whole group is terminated (SIGTERM,
+/// then SIGKILL after [`TERMINATE_GRACE_PERIOD`]) and an error is returned.
+/// Output is capped at `max_output_bytes` to prevent unbounded memory
+/// consumption.
 ///
 /// # Errors
 ///
@@ -90,6 +208,51 @@ pub async fn run_cli_command(
     cmd: &mut Command,
     timeout: Duration,
     max_output_bytes: usize,
+) -> Result<CliOutput, RunnerError> {
+    run_cli_command_with_cancel(cmd, timeout, max_output_bytes, &CancellationToken::new()).await
+}
+
+/// Run a CLI command that can be cancelled before it exits
+///
+/// Behaves like [`run_cli_command`], but also terminates the process group as
+/// soon as `cancel` fires and returns an `ErrorKind::Cancelled` error.
+/// Dropping the returned future (for example when an HTTP handler is aborted)
+/// terminates the process group as well.
+///
+/// # Errors
+///
+/// Returns `RunnerError` if:
+/// - The process cannot be spawned
+/// - The process exceeds the timeout (killed and reported)
+/// - The token is cancelled before the process exits (killed and reported)
+pub async fn run_cli_command_with_cancel(
+    cmd: &mut Command,
+    timeout: Duration,
+    max_output_bytes: usize,
+    cancel: &CancellationToken,
+) -> Result<CliOutput, RunnerError> {
+    run_cli_command_with_stdin(cmd, None, timeout, max_output_bytes, cancel).await
+}
+
+/// Run a cancellable CLI command, optionally writing `stdin` to it
+///
+/// Behaves like [`run_cli_command_with_cancel`]. When `stdin` is `Some`, the
+/// bytes are written to the child's stdin, which is then closed so the CLI
+/// sees end-of-input; runners use this to keep prompts off the command line.
+/// When `None`, the child's stdin is left as configured on `cmd`.
+///
+/// # Errors
+///
+/// Returns `RunnerError` if:
+/// - The process cannot be spawned
+/// - The process exceeds the timeout (killed and reported)
+/// - The token is cancelled before the process exits (killed and reported)
+pub async fn run_cli_command_with_stdin(
+    cmd: &mut Command,
+    stdin: Option<&[u8]>,
+    timeout: Duration,
+    max_output_bytes: usize,
+    cancel: &CancellationToken,
 ) -> Result<CliOutput, RunnerError> {
     let effective_max = if max_output_bytes == 0 {
         DEFAULT_MAX_OUTPUT_BYTES
@@ -102,9 +265,11 @@ pub async fn run_cli_command(
 
     let start = Instant::now();
 
-    let mut child = cmd
-        .spawn()
-        .map_err(|e| RunnerError::internal(format!("Failed to spawn CLI process: {e}")))?;
+    let mut guard =
+        ProcessGroupGuard(Some(spawn_in_process_group(cmd, stdin).map_err(|e| {
+            RunnerError::internal(format!("Failed to spawn CLI process: {e}"))
+        })?));
+    let child = guard.child();
 
     let stdout_handle = child.stdout.take();
     let stderr_handle = child.stderr.take();
@@ -112,12 +277,17 @@ pub async fn run_cli_command(
     let stdout_task = tokio::spawn(read_stdout_capped(stdout_handle, effective_max));
     let stderr_task = tokio::spawn(read_stderr_capped(stderr_handle, effective_max));
 
-    let wait_result = tokio_timeout(timeout, child.wait()).await;
+    let wait_result = tokio::select! {
+        biased;
+        () = cancel.cancelled() => None,
+        result = tokio_timeout(timeout, child.wait()) => Some(result),
+    };
 
     let duration = start.elapsed();
 
     match wait_result {
-        Ok(Ok(status)) => {
+        Some(Ok(Ok(status))) => {
+            guard.disarm();
             let exit_code = status.code().unwrap_or(-1);
             let stdout = stdout_task.await.unwrap_or_default();
             let stderr = stderr_task.await.unwrap_or_default();
@@ -131,15 +301,183 @@ pub async fn run_cli_command(
                 duration,
             })
         }
-        Ok(Err(e)) => Err(RunnerError::internal(format!(
+        Some(Ok(Err(e))) => Err(RunnerError::internal(format!(
             "Failed to wait for CLI process: {e}"
         ))),
-        Err(_) => {
-            warn!(?timeout, "CLI command timed out, killing process");
-            let _ = child.kill().await;
+        Some(Err(_)) => {
+            warn!(?timeout, "CLI command timed out, terminating process group");
+            terminate_process_group(guard.child(), TERMINATE_GRACE_PERIOD).await;
+            guard.disarm();
             Err(RunnerError::timeout(format!(
                 "CLI command timed out after {timeout:?}"
             )))
         }
+        None => {
+            warn!(
+                ?duration,
+                "CLI command cancelled, terminating process group"
+            );
+            terminate_process_group(guard.child(), TERMINATE_GRACE_PERIOD).await;
+            guard.disarm();
+            stdout_task.abort();
+            stderr_task.abort();
+            Err(RunnerError::cancelled(format!(
+                "CLI command cancelled after {duration:?}"
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::types::ErrorKind;
+
+    /// Fake CLI that forks grandchildren and records their PIDs in a file
+    #[cfg(target_os = "linux")]
+    pub(crate) const FORKING_CLI: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/fake_forking_cli.sh"
+    );
+
+    /// Read the PIDs recorded by [`FORKING_CLI`] once all three are running
+    #[cfg(target_os = "linux")]
+    pub(crate) async fn forked_pids(pidfile: &std::path::Path) -> Vec<i32> {
+        for _ in 0..100 {
+            let pids: Vec<i32> = std::fs::read_to_string(pidfile)
+                .unwrap_or_default()
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect();
+            if pids.len() == 3 {
+                return pids;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("fake CLI did not start its grandchildren");
+    }
+
+    /// Whether `pid` is still running (unreaped zombies count as exited)
+    #[cfg(target_os = "linux")]
+    fn pid_running(pid: i32) -> bool {
+        std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+            stat.rsplit(") ")
+                .next()
+                .is_some_and(|state| !state.starts_with('Z'))
+        })
+    }
+
+    /// Wait up to a few seconds for every PID to exit
+    #[cfg(target_os = "linux")]
+    pub(crate) async fn all_exited(pids: &[i32]) -> bool {
+        for _ in 0..100 {
+            if !pids.iter().copied().any(pid_running) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        false
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn timeout_terminates_grandchildren() {
+        let pidfile = tempfile::NamedTempFile::new().unwrap();
+        let mut cmd = Command::new(FORKING_CLI);
+        cmd.arg(pidfile.path()).stdout(Stdio::null());
+        let err = run_cli_command(&mut cmd, Duration::from_secs(1), 0)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Timeout);
+        let pids = forked_pids(pidfile.path()).await;
+        assert!(
+            all_exited(&pids).await,
+            "grandchildren outlived the timeout"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropped_command_terminates_grandchildren() {
+        let pidfile = tempfile::NamedTempFile::new().unwrap();
+        let path = pidfile.path().to_owned();
+        let task = tokio::spawn(async move {
+            let mut cmd = Command::new(FORKING_CLI);
+            cmd.arg(&path);
+            run_cli_command(&mut cmd, Duration::from_secs(30), 0).await
+        });
+
+        let pids = forked_pids(pidfile.path()).await;
+        task.abort();
+        assert!(
+            all_exited(&pids).await,
+            "grandchildren outlived the dropped request"
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_kills_running_command() {
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            trigger.cancel();
+        });
+
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let start = Instant::now();
+        let err = run_cli_command_with_cancel(&mut cmd, Duration::from_secs(30), 0, &cancel)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn already_cancelled_token_short_circuits() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let err = run_cli_command_with_cancel(&mut cmd, Duration::from_secs(30), 0, &cancel)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn stdin_input_reaches_command() {
+        let prompt = "x".repeat(256 * 1024);
+        let mut cmd = Command::new("wc");
+        cmd.arg("-c");
+        let output = run_cli_command_with_stdin(
+            &mut cmd,
+            Some(prompt.as_bytes()),
+            Duration::from_secs(10),
+            0,
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("wc should run");
+
+        assert_eq!(output.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "262144");
+    }
+
+    #[tokio::test]
+    async fn uncancelled_command_completes_normally() {
+        let mut cmd = Command::new("echo");
+        cmd.arg("hello");
+        let output = run_cli_command(&mut cmd, Duration::from_secs(10), 0)
+            .await
+            .expect("echo should run");
+
+        assert_eq!(output.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
     }
 }
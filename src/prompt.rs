@@ -1,28 +1,30 @@
 // ABOUTME: Prompt construction from ChatMessage sequences for CLI invocations
-// ABOUTME: Extracts system messages, builds role-prefixed prompt strings, materializes images to temp files
+// ABOUTME: Extracts system messages, builds role-prefixed prompt strings, materializes images and attachments to temp files
 //
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
 
 use std::fmt::Write as FmtWrite;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use base64::Engine;
 use tracing::{debug, warn};
 
-use crate::types::{ChatMessage, ImagePart, MessageRole, RunnerError};
+use crate::types::{
+    AttachmentPart, AttachmentSource, ChatMessage, ImagePart, MessageRole, RunnerError,
+};
 
-/// Prompt with materialized image files for CLI runners
+/// Prompt with materialized image and attachment files for CLI runners
 ///
 /// Holds the built prompt string and an optional temp directory containing
-/// image files decoded from base64. The temp directory is automatically
-/// cleaned up when this struct is dropped, so it must be kept alive until
-/// the CLI subprocess finishes reading the files.
+/// images and inline attachments decoded from base64. The temp directory is
+/// automatically cleaned up when this struct is dropped, so it must be kept
+/// alive until the CLI subprocess finishes reading the files.
 pub struct PreparedPrompt {
-    /// The prompt text, with image file path references injected for any attached images
+    /// The prompt text, with file path references injected for any attached images or files
     pub prompt: String,
-    /// Temp directory holding decoded image files (cleaned up on drop)
+    /// Temp directory holding decoded image and attachment files (cleaned up on drop)
     pub image_dir: Option<tempfile::TempDir>,
 }
 
@@ -33,16 +35,29 @@ fn mime_to_extension(mime_type: &str) -> &str {
         "image/jpeg" => "jpg",
         "image/webp" => "webp",
         "image/gif" => "gif",
+        "application/pdf" => "pdf",
+        "application/json" => "json",
+        "text/csv" => "csv",
+        "text/markdown" => "md",
+        "text/html" => "html",
+        "text/plain" => "txt",
         _ => "bin",
     }
 }
 
+/// Write decoded bytes to a file in the given directory
+fn write_temp_file(path: &Path, bytes: &[u8], mime_type: &str) -> Result<(), RunnerError> {
+    let mut file = std::fs::File::create(path)
+        .map_err(|e| RunnerError::internal(format!("failed to create temp file: {e}")))?;
+    file.write_all(bytes)
+        .map_err(|e| RunnerError::internal(format!("failed to write temp file: {e}")))?;
+
+    debug!(path = %path.display(), size = bytes.len(), mime = %mime_type, "Materialized file to temp dir");
+    Ok(())
+}
+
 /// Decode a base64-encoded image and write it to a file in the given directory
-fn write_image_file(
-    dir: &std::path::Path,
-    image: &ImagePart,
-    index: usize,
-) -> Result<PathBuf, RunnerError> {
+fn write_image_file(dir: &Path, image: &ImagePart, index: usize) -> Result<PathBuf, RunnerError> {
     let ext = mime_to_extension(&image.mime_type);
     let path = dir.join(format!("{index}.{ext}"));
 
@@ -50,70 +65,136 @@ fn write_image_file(
         .decode(&image.data)
         .map_err(|e| RunnerError::internal(format!("failed to decode base64 image data: {e}")))?;
 
-    let mut file = std::fs::File::create(&path)
-        .map_err(|e| RunnerError::internal(format!("failed to create temp image file: {e}")))?;
-    file.write_all(&decoded)
-        .map_err(|e| RunnerError::internal(format!("failed to write temp image file: {e}")))?;
+    write_temp_file(&path, &decoded, &image.mime_type)?;
+    Ok(path)
+}
 
-    debug!(path = %path.display(), size = decoded.len(), mime = %image.mime_type, "Materialized image to temp file");
+/// Resolve an attachment to a readable file, writing inline data into `dir`
+///
+/// Path attachments are referenced in place unless `copy_paths` is set;
+/// everything else is written as `{index}-{filename}` (or `{index}.{ext}`
+/// when unnamed).
+fn attachment_file(
+    dir: &Path,
+    attachment: &AttachmentPart,
+    index: usize,
+    copy_paths: bool,
+) -> Result<PathBuf, RunnerError> {
+    if let AttachmentSource::Path(ref path) = attachment.source {
+        if !copy_paths {
+            return Ok(path.clone());
+        }
+    }
+    let name = attachment
+        .display_name()
+        .and_then(|n| Path::new(n).file_name())
+        .map_or_else(
+            || format!("{index}.{}", mime_to_extension(&attachment.mime_type)),
+            |n| format!("{index}-{}", n.to_string_lossy()),
+        );
+    let path = dir.join(name);
+    write_temp_file(&path, &attachment.read_bytes()?, &attachment.mime_type)?;
     Ok(path)
 }
 
-/// Materialize images from messages into temp files
+/// Append an inlined text attachment to `content` as a fenced block
+fn inline_text_attachment(
+    content: &mut String,
+    attachment: &AttachmentPart,
+) -> Result<(), RunnerError> {
+    let bytes = attachment.read_bytes()?;
+    let text = String::from_utf8_lossy(&bytes);
+    let name = attachment.display_name().unwrap_or("attachment");
+    let _ = write!(
+        content,
+        "\n\n[Attached file: {name} ({})]\n```\n{}\n```",
+        attachment.mime_type,
+        text.trim_end()
+    );
+    Ok(())
+}
+
+/// Whether a message carries images or attachments that need materializing
+fn has_files(msg: &ChatMessage) -> bool {
+    msg.images.as_ref().is_some_and(|imgs| !imgs.is_empty())
+        || msg.attachments.as_ref().is_some_and(|a| !a.is_empty())
+}
+
+/// Materialize images and attachments from messages into temp files
 ///
 /// Returns rewritten messages (with file path references appended to content)
 /// and a `TempDir` handle that must be kept alive until the CLI subprocess finishes.
-fn materialize_images(
+/// Text attachments marked `inline` are embedded in the content instead.
+///
+/// With a `root`, the temp dir is created under it and path attachments are
+/// copied in, so a process that only sees `root` can still read every file.
+fn materialize_attachments(
     messages: &[ChatMessage],
+    root: Option<&Path>,
 ) -> Result<(Vec<ChatMessage>, Option<tempfile::TempDir>), RunnerError> {
-    let has_any_images = messages
-        .iter()
-        .any(|m| m.images.as_ref().is_some_and(|imgs| !imgs.is_empty()));
-
-    if !has_any_images {
+    if !messages.iter().any(has_files) {
         return Ok((messages.to_vec(), None));
     }
 
-    let temp_dir = tempfile::Builder::new()
-        .prefix("embacle-images-")
-        .tempdir()
-        .map_err(|e| RunnerError::internal(format!("failed to create temp dir for images: {e}")))?;
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("embacle-attachments-");
+    let temp_dir = root
+        .map_or_else(|| builder.tempdir(), |root| builder.tempdir_in(root))
+        .map_err(|e| {
+            RunnerError::internal(format!("failed to create temp dir for attachments: {e}"))
+        })?;
 
     let mut rewritten = Vec::with_capacity(messages.len());
     let mut file_index: usize = 0;
 
     for msg in messages {
-        let images = msg.images.as_ref().filter(|imgs| !imgs.is_empty());
-
-        if msg.role != MessageRole::User || images.is_none() {
+        if msg.role != MessageRole::User || !has_files(msg) {
             rewritten.push(msg.clone());
             continue;
         }
 
-        let images = images.expect("checked above");
-        let mut file_refs = Vec::with_capacity(images.len());
+        let mut content = msg.content.clone();
 
-        for image in images {
-            let path = write_image_file(temp_dir.path(), image, file_index)?;
-            file_refs.push(path);
-            file_index += 1;
+        let images = msg.images.as_deref().unwrap_or_default();
+        if !images.is_empty() {
+            content.push_str("\n\n[Attached images — read these files to view them]");
+            for image in images {
+                let path = write_image_file(temp_dir.path(), image, file_index)?;
+                let _ = write!(content, "\n- {}", path.display());
+                file_index += 1;
+            }
         }
 
-        let mut content = msg.content.clone();
-        content.push_str("\n\n[Attached images — read these files to view them]");
-        for path in &file_refs {
-            let _ = write!(content, "\n- {}", path.display());
+        let attachments = msg.attachments.as_deref().unwrap_or_default();
+        let mut file_refs = Vec::new();
+        for attachment in attachments {
+            if attachment.inline && attachment.is_text() {
+                inline_text_attachment(&mut content, attachment)?;
+            } else {
+                let path =
+                    attachment_file(temp_dir.path(), attachment, file_index, root.is_some())?;
+                file_refs.push((path, &attachment.mime_type));
+                file_index += 1;
+            }
+        }
+        if !file_refs.is_empty() {
+            content.push_str("\n\n[Attached files — read these files to view them]");
+            for (path, mime_type) in &file_refs {
+                let _ = write!(content, "\n- {} ({mime_type})", path.display());
+            }
         }
 
         let mut rewritten_msg = ChatMessage::user(content);
-        // Preserve the original images field (downstream code may still want it)
+        // Preserve the original parts (downstream code may still want them)
         rewritten_msg.images.clone_from(&msg.images);
+        rewritten_msg.attachments.clone_from(&msg.attachments);
         rewritten.push(rewritten_msg);
 
         debug!(
-            image_count = file_refs.len(),
+            image_count = images.len(),
+            attachment_count = attachments.len(),
             dir = %temp_dir.path().display(),
-            "Materialized images for user message"
+            "Materialized attachments for user message"
         );
     }
 
@@ -147,38 +228,69 @@ pub fn build_prompt(messages: &[ChatMessage]) -> String {
     prompt
 }
 
-/// Build a prompt with images materialized to temp files
+/// Build a prompt with images and attachments materialized to temp files
 ///
-/// If any user messages contain attached images, they are decoded from base64,
-/// written to a temp directory, and file path references are appended to the
-/// message content. The returned `PreparedPrompt` holds the temp directory
-/// handle — keep it alive until the CLI subprocess finishes.
+/// If any user messages contain attached images or files, inline data is
+/// decoded from base64, written to a temp directory, and file path references
+/// are appended to the message content. Path attachments are referenced in
+/// place and text attachments marked `inline` are embedded in the prompt.
+/// The returned `PreparedPrompt` holds the temp directory handle — keep it
+/// alive until the CLI subprocess finishes.
 ///
 /// # Errors
 ///
-/// Returns an error if image decoding or temp file creation fails.
+/// Returns an error if decoding, reading an inlined file or temp file creation fails.
 pub fn prepare_prompt(messages: &[ChatMessage]) -> Result<PreparedPrompt, RunnerError> {
-    let (rewritten, image_dir) = materialize_images(messages)?;
+    prepare_prompt_in(messages, None)
+}
+
+/// [`prepare_prompt`], materializing files under `root` when given
+///
+/// Use when the CLI runs where only `root` is visible (a container mount
+/// or sandbox-allowed directory); path attachments are copied there too.
+///
+/// # Errors
+///
+/// Returns an error if decoding, reading an attachment or temp file creation fails.
+pub fn prepare_prompt_in(
+    messages: &[ChatMessage],
+    root: Option<&Path>,
+) -> Result<PreparedPrompt, RunnerError> {
+    let (rewritten, image_dir) = materialize_attachments(messages, root)?;
     let prompt = build_prompt(&rewritten);
     if image_dir.is_some() {
-        debug!("Built prompt with materialized images");
+        debug!("Built prompt with materialized attachments");
     }
     Ok(PreparedPrompt { prompt, image_dir })
 }
 
-/// Build a prompt with images materialized, excluding system messages
+/// Build a prompt with images and attachments materialized, excluding system messages
 ///
-/// Combines [`materialize_images`] with the system-message filtering of
+/// Combines [`materialize_attachments`] with the system-message filtering of
 /// [`build_user_prompt`]. Use when the CLI accepts a separate `--system-prompt` flag.
 ///
 /// # Errors
 ///
-/// Returns an error if image decoding or temp file creation fails.
+/// Returns an error if decoding, reading an inlined file or temp file creation fails.
 pub fn prepare_user_prompt(messages: &[ChatMessage]) -> Result<PreparedPrompt, RunnerError> {
-    let (rewritten, image_dir) = materialize_images(messages)?;
+    prepare_user_prompt_in(messages, None)
+}
+
+/// [`prepare_user_prompt`], materializing files under `root` when given
+///
+/// See [`prepare_prompt_in`].
+///
+/// # Errors
+///
+/// Returns an error if decoding, reading an attachment or temp file creation fails.
+pub fn prepare_user_prompt_in(
+    messages: &[ChatMessage],
+    root: Option<&Path>,
+) -> Result<PreparedPrompt, RunnerError> {
+    let (rewritten, image_dir) = materialize_attachments(messages, root)?;
     let prompt = build_user_prompt(&rewritten);
     if image_dir.is_some() {
-        debug!("Built user prompt with materialized images");
+        debug!("Built user prompt with materialized attachments");
     }
     Ok(PreparedPrompt { prompt, image_dir })
 }
@@ -386,6 +498,55 @@ mod tests {
         // but no files are written for non-user messages
     }
 
+    #[test]
+    fn test_prepare_prompt_with_attachments() {
+        let pdf = AttachmentPart::from_base64("JVBERi0=", "application/pdf")
+            .unwrap()
+            .with_filename("report.pdf");
+        let notes = AttachmentPart::from_base64("bm90ZXM=", "text/plain")
+            .unwrap()
+            .with_filename("notes.txt")
+            .with_inline(true);
+        let on_disk = AttachmentPart::from_path("/var/log/app.log", "text/plain").unwrap();
+        let messages = vec![ChatMessage::user_with_attachments(
+            "Review these",
+            vec![pdf, notes, on_disk],
+        )];
+
+        let prepared = prepare_prompt(&messages).unwrap();
+        let dir = prepared.image_dir.as_ref().unwrap();
+        assert_eq!(
+            std::fs::read(dir.path().join("0-report.pdf")).unwrap(),
+            b"%PDF-"
+        );
+        assert!(prepared.prompt.contains("[Attached files"));
+        assert!(prepared.prompt.contains("0-report.pdf (application/pdf)"));
+        assert!(prepared.prompt.contains("- /var/log/app.log (text/plain)"));
+        assert!(prepared
+            .prompt
+            .contains("[Attached file: notes.txt (text/plain)]\n```\nnotes\n```"));
+    }
+
+    #[test]
+    fn test_prepare_prompt_in_root_copies_path_attachments() {
+        let host = tempfile::tempdir().unwrap();
+        let source = host.path().join("source.txt");
+        std::fs::write(&source, "on host").unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let messages = vec![ChatMessage::user_with_attachments(
+            "Read this",
+            vec![AttachmentPart::from_path(&source, "text/plain").unwrap()],
+        )];
+
+        let prepared = prepare_prompt_in(&messages, Some(root.path())).unwrap();
+        let dir = prepared.image_dir.as_ref().unwrap();
+        assert!(dir.path().starts_with(root.path()));
+        let copy = dir.path().join("0-source.txt");
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "on host");
+        assert!(prepared.prompt.contains(&copy.display().to_string()));
+        assert!(!prepared.prompt.contains(&source.display().to_string()));
+    }
+
     #[test]
     fn test_mime_to_extension() {
         assert_eq!(mime_to_extension("image/png"), "png");
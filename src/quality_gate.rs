@@ -18,8 +18,10 @@
 //!   checking, since the full content is not available upfront.
 
 use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+use crate::model_catalog::ModelInfo;
 use crate::types::{
     ChatMessage, ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
 };
@@ -162,9 +164,26 @@ impl LlmProvider for QualityGateProvider {
         self.inner.available_models()
     }
 
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        self.inner.model_catalog()
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        self.inner.refresh_models().await
+    }
+
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
         let mut messages = request.messages.clone();
-        let mut last_response = self.inner.complete(request).await?;
+        let mut last_response = self.inner.complete_with_cancel(request, cancel).await?;
 
         for retry in 0..self.policy.max_retries {
             match validate_response(&last_response.content, &self.policy) {
@@ -186,18 +205,14 @@ impl LlmProvider for QualityGateProvider {
 
                     let retry_request = ChatRequest {
                         messages: messages.clone(),
-                        model: request.model.clone(),
-                        temperature: request.temperature,
-                        max_tokens: request.max_tokens,
                         stream: false,
-                        tools: request.tools.clone(),
-                        tool_choice: request.tool_choice.clone(),
-                        top_p: request.top_p,
-                        stop: request.stop.clone(),
-                        response_format: request.response_format.clone(),
+                        ..request.clone()
                     };
 
-                    last_response = self.inner.complete(&retry_request).await?;
+                    last_response = self
+                        .inner
+                        .complete_with_cancel(&retry_request, cancel)
+                        .await?;
                 }
             }
         }
@@ -230,7 +245,7 @@ mod tests {
     use super::*;
     use crate::types::{
         ChatMessage, ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider,
-        RunnerError,
+        RequestMetadata, RunnerError,
     };
     use async_trait::async_trait;
     use std::sync::atomic::{AtomicU32, Ordering};
@@ -278,6 +293,9 @@ mod tests {
                     finish_reason: Some("stop".to_owned()),
                     warnings: None,
                     tool_calls: None,
+                    reasoning: None,
+                    choices: None,
+                    metadata: RequestMetadata::default(),
                 })
             } else {
                 responses.remove(0)
@@ -299,6 +317,9 @@ mod tests {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         }
     }
 
@@ -0,0 +1,660 @@
+// ABOUTME: Per-provider concurrency and rate limiting decorator with bounded queueing
+// ABOUTME: Enforces max concurrent calls plus requests-per-minute and tokens-per-minute token buckets
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Rate Limiting
+//!
+//! [`RateLimitProvider`] wraps an inner provider and bounds how hard it is
+//! driven:
+//!
+//! - **`max_concurrent`** — calls (including open streams) running at once
+//! - **`requests_per_minute`** — a token bucket refilled continuously
+//! - **`tokens_per_minute`** — a budget charged with each response's reported
+//!   `total_tokens`; once spent, new calls wait for it to refill
+//!
+//! A call that cannot start immediately waits in a queue. At most
+//! `max_queued` callers wait at once; beyond that the call fails fast with an
+//! [`ErrorKind::RateLimited`](crate::types::ErrorKind::RateLimited) error
+//! carrying a `retry_after` hint. A limit of zero (or unset) disables it.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, TryAcquireError};
+use tokio::time::Instant;
+use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::model_catalog::ModelInfo;
+use crate::types::{
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
+};
+
+/// Callers allowed to wait for capacity when `max_queued` is not configured
+pub const DEFAULT_MAX_QUEUED: usize = 32;
+
+/// Retry hint returned when the queue is full and only concurrency is saturated
+const CONCURRENCY_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Limits applied by [`RateLimitProvider`]
+///
+/// Deserializes from a provider's `rate_limit` table in `embacle.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Maximum calls in flight at once
+    pub max_concurrent: Option<usize>,
+    /// Maximum calls started per minute
+    pub requests_per_minute: Option<u32>,
+    /// Maximum response tokens consumed per minute
+    pub tokens_per_minute: Option<u64>,
+    /// Maximum callers waiting for capacity before new calls are rejected
+    pub max_queued: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_queued: DEFAULT_MAX_QUEUED,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Limit the number of concurrent calls
+    #[must_use]
+    pub const fn with_max_concurrent(mut self, max: usize) -> Self {
+        self.max_concurrent = Some(max);
+        self
+    }
+
+    /// Limit the number of calls started per minute
+    #[must_use]
+    pub const fn with_requests_per_minute(mut self, rpm: u32) -> Self {
+        self.requests_per_minute = Some(rpm);
+        self
+    }
+
+    /// Limit the number of tokens consumed per minute
+    #[must_use]
+    pub const fn with_tokens_per_minute(mut self, tpm: u64) -> Self {
+        self.tokens_per_minute = Some(tpm);
+        self
+    }
+
+    /// Bound the number of callers waiting for capacity
+    #[must_use]
+    pub const fn with_max_queued(mut self, max: usize) -> Self {
+        self.max_queued = max;
+        self
+    }
+}
+
+/// Point-in-time view of a [`RateLimitProvider`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitStats {
+    /// Calls currently running (streams count until dropped)
+    pub in_flight: usize,
+    /// Callers currently waiting for capacity
+    pub queued: usize,
+    /// Calls rejected because the queue was full
+    pub rejected: u64,
+}
+
+/// Continuously refilled bucket holding up to one minute of allowance
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    #[allow(clippy::cast_precision_loss)]
+    fn per_minute(limit: u64, now: Instant) -> Self {
+        let capacity = limit as f64;
+        Self {
+            capacity,
+            available: capacity,
+            per_sec: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = elapsed
+            .mul_add(self.per_sec, self.available)
+            .min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Time until `amount` is available
+    fn wait_for(&self, amount: f64) -> Duration {
+        if self.available >= amount {
+            return Duration::ZERO;
+        }
+        Duration::try_from_secs_f64((amount - self.available) / self.per_sec)
+            .unwrap_or(Duration::MAX)
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+}
+
+/// Shared limiter state, also held by open streams
+struct Limiter {
+    max_queued: usize,
+    permits: Option<Arc<Semaphore>>,
+    buckets: Mutex<Buckets>,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+impl Limiter {
+    fn new(config: &RateLimitConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            max_queued: config.max_queued,
+            permits: config
+                .max_concurrent
+                .filter(|&max| max > 0)
+                .map(|max| Arc::new(Semaphore::new(max))),
+            buckets: Mutex::new(Buckets {
+                requests: config
+                    .requests_per_minute
+                    .filter(|&rpm| rpm > 0)
+                    .map(|rpm| TokenBucket::per_minute(u64::from(rpm), now)),
+                tokens: config
+                    .tokens_per_minute
+                    .filter(|&tpm| tpm > 0)
+                    .map(|tpm| TokenBucket::per_minute(tpm, now)),
+            }),
+            in_flight: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Take one request token, or return how long until one can be taken
+    fn try_take(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate-limit lock poisoned");
+        let mut wait = Duration::ZERO;
+        if let Some(bucket) = buckets.requests.as_mut() {
+            bucket.refill(now);
+            wait = wait.max(bucket.wait_for(1.0));
+        }
+        if let Some(bucket) = buckets.tokens.as_mut() {
+            bucket.refill(now);
+            wait = wait.max(bucket.wait_for(1.0));
+        }
+        if !wait.is_zero() {
+            return Some(wait);
+        }
+        if let Some(bucket) = buckets.requests.as_mut() {
+            bucket.available -= 1.0;
+        }
+        None
+    }
+
+    /// Charge consumed tokens against the per-minute budget, allowing debt
+    fn record_tokens(&self, tokens: u32) {
+        let mut buckets = self.buckets.lock().expect("rate-limit lock poisoned");
+        if let Some(bucket) = buckets.tokens.as_mut() {
+            bucket.refill(Instant::now());
+            bucket.available -= f64::from(tokens);
+        }
+    }
+}
+
+/// Marks a caller as waiting for capacity until dropped
+struct QueueSlot(Arc<Limiter>);
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Capacity held by one running call; released on drop
+struct Admission {
+    limiter: Arc<Limiter>,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        self.limiter.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Decorator that bounds concurrency and throughput of an inner provider
+///
+/// # Usage
+///
+/// ```rust,no_run
+/// # use embacle::rate_limit::{RateLimitConfig, RateLimitProvider};
+/// # use embacle::types::LlmProvider;
+/// # fn example(provider: Box<dyn LlmProvider>) {
+/// let config = RateLimitConfig::default()
+///     .with_max_concurrent(4)
+///     .with_requests_per_minute(30);
+/// let limited = RateLimitProvider::new(provider, &config);
+/// # }
+/// ```
+pub struct RateLimitProvider {
+    inner: Arc<dyn LlmProvider>,
+    limiter: Arc<Limiter>,
+}
+
+impl RateLimitProvider {
+    /// Wrap a provider with the given limits
+    pub fn new(inner: Box<dyn LlmProvider>, config: &RateLimitConfig) -> Self {
+        Self::from_shared(Arc::from(inner), config)
+    }
+
+    /// Wrap a provider that is already shared behind an `Arc`
+    pub fn from_shared(inner: Arc<dyn LlmProvider>, config: &RateLimitConfig) -> Self {
+        Self {
+            inner,
+            limiter: Arc::new(Limiter::new(config)),
+        }
+    }
+
+    /// Current in-flight, queued and rejected counts
+    pub fn stats(&self) -> RateLimitStats {
+        RateLimitStats {
+            in_flight: self.limiter.in_flight.load(Ordering::Acquire),
+            queued: self.limiter.queued.load(Ordering::Acquire),
+            rejected: self.limiter.rejected.load(Ordering::Acquire),
+        }
+    }
+
+    /// Reserve a queue slot, or reject the call if the queue is full
+    fn enqueue(&self, retry_after: Duration) -> Result<QueueSlot, RunnerError> {
+        let limiter = &self.limiter;
+        let reserved = limiter
+            .queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < limiter.max_queued).then_some(queued + 1)
+            });
+        if reserved.is_err() {
+            limiter.rejected.fetch_add(1, Ordering::AcqRel);
+            return Err(RunnerError::rate_limited(format!(
+                "{}: rate limit exceeded ({} calls already queued)",
+                self.inner.name(),
+                limiter.max_queued
+            ))
+            .with_retry_after(Some(retry_after)));
+        }
+        Ok(QueueSlot(Arc::clone(limiter)))
+    }
+
+    /// Wait (in the bounded queue if needed) until the call may start
+    ///
+    /// Gives up when `cancel` fires or the request's deadline passes.
+    async fn admit(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<Admission, RunnerError> {
+        let mut slot = None;
+
+        while let Some(wait) = self.limiter.try_take() {
+            if request.deadline.is_some() && request.timeout_or(Duration::MAX) < wait {
+                return Err(RunnerError::rate_limited(format!(
+                    "{}: rate limit would delay the call past its deadline",
+                    self.inner.name()
+                ))
+                .with_retry_after(Some(wait)));
+            }
+            if slot.is_none() {
+                slot = Some(self.enqueue(wait)?);
+                debug!(provider = self.inner.name(), ?wait, "rate limit: waiting");
+            }
+            tokio::select! {
+                () = tokio::time::sleep(wait) => {}
+                () = cancel.cancelled() => {
+                    return Err(RunnerError::cancelled("rate limit: caller cancelled"));
+                }
+            }
+        }
+
+        let permit = match &self.limiter.permits {
+            None => None,
+            Some(permits) => match Arc::clone(permits).try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(TryAcquireError::NoPermits) => {
+                    if slot.is_none() {
+                        slot = Some(self.enqueue(CONCURRENCY_RETRY_AFTER)?);
+                        debug!(
+                            provider = self.inner.name(),
+                            "rate limit: at max concurrency"
+                        );
+                    }
+                    tokio::select! {
+                        permit = Arc::clone(permits).acquire_owned() => Some(permit.map_err(|_| {
+                            RunnerError::internal("rate limit: semaphore closed")
+                        })?),
+                        () = cancel.cancelled() => {
+                            return Err(RunnerError::cancelled("rate limit: caller cancelled"));
+                        }
+                        () = tokio::time::sleep(request.timeout_or(Duration::MAX)),
+                            if request.deadline.is_some() =>
+                        {
+                            return Err(RunnerError::timeout(format!(
+                                "{}: deadline passed while waiting for a concurrency slot",
+                                self.inner.name()
+                            )));
+                        }
+                    }
+                }
+                Err(TryAcquireError::Closed) => {
+                    return Err(RunnerError::internal("rate limit: semaphore closed"));
+                }
+            },
+        };
+        drop(slot);
+
+        self.limiter.in_flight.fetch_add(1, Ordering::AcqRel);
+        Ok(Admission {
+            limiter: Arc::clone(&self.limiter),
+            _permit: permit,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RateLimitProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn display_name(&self) -> &str {
+        self.inner.display_name()
+    }
+
+    fn capabilities(&self) -> LlmCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn default_model(&self) -> &str {
+        self.inner.default_model()
+    }
+
+    fn available_models(&self) -> &[String] {
+        self.inner.available_models()
+    }
+
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        self.inner.model_catalog()
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        self.inner.refresh_models().await
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let admission = self.admit(request, cancel).await?;
+        let result = self.inner.complete_with_cancel(request, cancel).await;
+        if let Some(usage) = result.as_ref().ok().and_then(|r| r.usage.as_ref()) {
+            self.limiter.record_tokens(usage.total_tokens);
+        }
+        drop(admission);
+        result
+    }
+
+    /// The concurrency slot is held until the returned stream is dropped
+    ///
+    /// Streams carry no cancellation token: the wait for capacity ends at
+    /// the request's deadline, or when the caller drops the future.
+    async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+        let admission = self.admit(request, &CancellationToken::new()).await?;
+        let stream = self.inner.complete_stream(request).await?;
+        let limiter = Arc::clone(&self.limiter);
+        Ok(Box::pin(stream.map(move |chunk| {
+            let _held = &admission;
+            if let Some(usage) = chunk.as_ref().ok().and_then(|c| c.usage.as_ref()) {
+                limiter.record_tokens(usage.total_tokens);
+            }
+            chunk
+        })))
+    }
+
+    async fn health_check(&self) -> Result<bool, RunnerError> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    use crate::types::{ChatMessage, ErrorKind, TokenUsage};
+
+    /// Sleeps, then answers while tracking peak concurrency
+    struct SlowProvider {
+        delay: Duration,
+        tokens: u32,
+        active: AtomicU32,
+        peak: AtomicU32,
+    }
+
+    impl SlowProvider {
+        fn new(delay: Duration, tokens: u32) -> Arc<Self> {
+            Arc::new(Self {
+                delay,
+                tokens,
+                active: AtomicU32::new(0),
+                peak: AtomicU32::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for SlowProvider {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+        fn display_name(&self) -> &str {
+            "Slow"
+        }
+        fn capabilities(&self) -> LlmCapabilities {
+            LlmCapabilities::text_only()
+        }
+        fn default_model(&self) -> &'static str {
+            "slow-model"
+        }
+        fn available_models(&self) -> &[String] {
+            &[]
+        }
+        async fn complete(&self, _request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(ChatResponse {
+                content: "ok".to_owned(),
+                model: "slow-model".to_owned(),
+                usage: Some(TokenUsage {
+                    prompt_tokens: 0,
+                    completion_tokens: self.tokens,
+                    total_tokens: self.tokens,
+                    ..TokenUsage::default()
+                }),
+                finish_reason: Some("stop".to_owned()),
+                warnings: None,
+                tool_calls: None,
+                reasoning: None,
+                metadata: crate::types::RequestMetadata::default(),
+                choices: None,
+            })
+        }
+        async fn complete_stream(&self, _request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+            Err(RunnerError::internal("not supported"))
+        }
+        async fn health_check(&self) -> Result<bool, RunnerError> {
+            Ok(true)
+        }
+    }
+
+    fn request() -> ChatRequest {
+        ChatRequest::new(vec![ChatMessage::user("hi")])
+    }
+
+    #[tokio::test]
+    async fn concurrency_is_capped_and_waiters_are_served() {
+        let inner = SlowProvider::new(Duration::from_millis(50), 1);
+        let limited = Arc::new(RateLimitProvider::from_shared(
+            inner.clone(),
+            &RateLimitConfig::default().with_max_concurrent(2),
+        ));
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..6 {
+            let limited = Arc::clone(&limited);
+            tasks.spawn(async move { limited.complete(&request()).await });
+        }
+        while let Some(result) = tasks.join_next().await {
+            assert!(result.unwrap().is_ok());
+        }
+
+        assert_eq!(inner.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(limited.stats(), RateLimitStats::default());
+    }
+
+    #[tokio::test]
+    async fn full_queue_rejects_with_rate_limit_error() {
+        let inner = SlowProvider::new(Duration::from_millis(100), 1);
+        let limited = Arc::new(RateLimitProvider::from_shared(
+            inner,
+            &RateLimitConfig::default()
+                .with_max_concurrent(1)
+                .with_max_queued(1),
+        ));
+
+        let running = tokio::spawn({
+            let limited = Arc::clone(&limited);
+            async move { limited.complete(&request()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let waiting = tokio::spawn({
+            let limited = Arc::clone(&limited);
+            async move { limited.complete(&request()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(limited.stats().queued, 1);
+
+        let err = limited.complete(&request()).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::RateLimited);
+        assert_eq!(err.retry_after, Some(CONCURRENCY_RETRY_AFTER));
+        assert_eq!(limited.stats().rejected, 1);
+
+        assert!(running.await.unwrap().is_ok());
+        assert!(waiting.await.unwrap().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_per_minute_paces_calls() {
+        let inner = SlowProvider::new(Duration::ZERO, 1);
+        let limited = RateLimitProvider::from_shared(
+            inner,
+            &RateLimitConfig::default().with_requests_per_minute(2),
+        );
+
+        let start = tokio::time::Instant::now();
+        for _ in 0..3 {
+            limited.complete(&request()).await.unwrap();
+        }
+        // Two calls fit the burst; the third waits for half a minute of refill
+        assert!(start.elapsed() >= Duration::from_secs(29));
+
+        let err = RateLimitProvider::from_shared(
+            SlowProvider::new(Duration::ZERO, 1),
+            &RateLimitConfig::default()
+                .with_requests_per_minute(1)
+                .with_max_queued(0),
+        );
+        err.complete(&request()).await.unwrap();
+        let rejected = err.complete(&request()).await.unwrap_err();
+        assert_eq!(rejected.kind, ErrorKind::RateLimited);
+        assert!(rejected.retry_after.unwrap() > Duration::from_secs(50));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_budget_blocks_until_refilled() {
+        let inner = SlowProvider::new(Duration::ZERO, 1200);
+        let limited = RateLimitProvider::from_shared(
+            inner,
+            &RateLimitConfig::default()
+                .with_tokens_per_minute(600)
+                .with_max_queued(0),
+        );
+
+        // The first call is admitted and overdraws the budget by 600 tokens
+        limited.complete(&request()).await.unwrap();
+        let err = limited.complete(&request()).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::RateLimited);
+        assert!(err.retry_after.unwrap() >= Duration::from_mins(1));
+    }
+
+    #[tokio::test]
+    async fn wait_past_deadline_is_rejected() {
+        let limited = RateLimitProvider::from_shared(
+            SlowProvider::new(Duration::ZERO, 1),
+            &RateLimitConfig::default().with_requests_per_minute(1),
+        );
+        limited.complete(&request()).await.unwrap();
+
+        let req = request().with_timeout(Duration::from_secs(5));
+        let err = limited.complete(&req).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::RateLimited);
+        assert_eq!(limited.stats().queued, 0);
+    }
+
+    #[tokio::test]
+    async fn stream_wait_for_a_slot_ends_at_the_deadline() {
+        let limited = Arc::new(RateLimitProvider::from_shared(
+            SlowProvider::new(Duration::from_millis(200), 1),
+            &RateLimitConfig::default().with_max_concurrent(1),
+        ));
+        let running = tokio::spawn({
+            let limited = Arc::clone(&limited);
+            async move { limited.complete(&request()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let req = request().with_timeout(Duration::from_millis(20));
+        match limited.complete_stream(&req).await {
+            Err(err) => assert_eq!(err.kind, ErrorKind::Timeout),
+            Ok(_) => panic!("expected the wait to time out"),
+        }
+        assert_eq!(limited.stats().queued, 0);
+        assert!(running.await.unwrap().is_ok());
+    }
+}
@@ -1,5 +1,5 @@
 // ABOUTME: Environment sandboxing and tool policy for CLI subprocesses
-// ABOUTME: Clears environment, whitelists keys, and sets working directory
+// ABOUTME: Clears environment, whitelists keys, sets working directory, and describes OS-level restrictions
 //
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
@@ -13,13 +13,56 @@ use tracing::debug;
 
 use crate::config::default_allowed_env_keys;
 
+/// Resource limits applied to a sandboxed subprocess with `setrlimit`
+///
+/// `None` leaves the inherited limit in place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// CPU time in seconds (`RLIMIT_CPU`)
+    pub cpu_seconds: Option<u64>,
+    /// Address space in bytes (`RLIMIT_AS`)
+    ///
+    /// This caps *virtual* memory, not resident memory. Node/V8-based CLIs
+    /// such as Copilot and Gemini reserve several GiB of address space at
+    /// startup and abort under a tight limit; leave this unset for them or
+    /// size it well above their reservation.
+    pub memory_bytes: Option<u64>,
+    /// Largest file the process may write, in bytes (`RLIMIT_FSIZE`)
+    pub file_size_bytes: Option<u64>,
+    /// Processes owned by the user, counted by the kernel per user (`RLIMIT_NPROC`)
+    pub max_processes: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Whether no limit is set
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.cpu_seconds.is_none()
+            && self.memory_bytes.is_none()
+            && self.file_size_bytes.is_none()
+            && self.max_processes.is_none()
+    }
+}
+
 /// Policy controlling the subprocess execution environment
+///
+/// [`apply_sandbox`] enforces the environment whitelist and working
+/// directory. The filesystem, network and resource restrictions are
+/// enforced by the `LinuxSandbox` execution backend (Linux only).
 #[derive(Debug, Clone)]
 pub struct SandboxPolicy {
     /// Environment variable keys to pass through from the host
     pub allowed_env_keys: Vec<String>,
     /// Working directory for the subprocess
     pub working_directory: PathBuf,
+    /// Paths the subprocess may read and execute but not modify
+    pub read_only_paths: Vec<PathBuf>,
+    /// Paths the subprocess may read and modify
+    pub read_write_paths: Vec<PathBuf>,
+    /// Run the subprocess in its own network namespace with no interfaces but loopback
+    pub isolate_network: bool,
+    /// CPU, memory, file-size and process-count limits
+    pub limits: ResourceLimits,
 }
 
 impl SandboxPolicy {
@@ -29,6 +72,10 @@ impl SandboxPolicy {
         Self {
             allowed_env_keys: default_allowed_env_keys(),
             working_directory,
+            read_only_paths: Vec::new(),
+            read_write_paths: Vec::new(),
+            isolate_network: false,
+            limits: ResourceLimits::default(),
         }
     }
 
@@ -38,6 +85,58 @@ impl SandboxPolicy {
         self.allowed_env_keys = keys;
         self
     }
+
+    /// Restrict filesystem access: only these paths (plus the working
+    /// directory and `read_write`) remain readable
+    #[must_use]
+    pub fn with_read_only_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.read_only_paths = paths;
+        self
+    }
+
+    /// Restrict filesystem access: these paths (plus the working directory)
+    /// remain writable
+    #[must_use]
+    pub fn with_read_write_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.read_write_paths = paths;
+        self
+    }
+
+    /// Cut the subprocess off from the network
+    #[must_use]
+    pub const fn with_network_isolation(mut self, isolate: bool) -> Self {
+        self.isolate_network = isolate;
+        self
+    }
+
+    /// Apply resource limits to the subprocess
+    #[must_use]
+    pub const fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Whether the policy restricts the filesystem (any path list is set)
+    #[must_use]
+    pub fn restricts_filesystem(&self) -> bool {
+        !self.read_only_paths.is_empty() || !self.read_write_paths.is_empty()
+    }
+}
+
+/// System directories a CLI typically needs to start: binaries, shared
+/// libraries, configuration and `/proc`
+///
+/// Only paths that exist on this host are returned. Use it as the base of
+/// [`SandboxPolicy::with_read_only_paths`].
+#[must_use]
+pub fn default_read_only_paths() -> Vec<PathBuf> {
+    [
+        "/bin", "/etc", "/lib", "/lib64", "/opt", "/proc", "/sbin", "/usr",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .filter(|p| p.exists())
+    .collect()
 }
 
 /// Apply sandbox policy to a command before execution
@@ -0,0 +1,363 @@
+// ABOUTME: Conversation-scoped session storage mapping conversation keys to CLI session IDs
+// ABOUTME: Provides the SessionStore trait with in-memory and file-backed implementations and TTL eviction
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Session Store
+//!
+//! CLI runners that support resume (`--resume`, `--session`, `--taskId`, ...)
+//! record the session ID reported by the tool after each turn and replay it on
+//! the next turn of the same conversation. [`SessionStore`] maps a conversation
+//! key — [`ChatRequest::session_key`](crate::types::ChatRequest::session_key) —
+//! to that session ID. Requests without a session key never resume.
+//!
+//! Two implementations are provided:
+//!
+//! - [`InMemorySessionStore`] — process-local, the default for every CLI runner
+//! - [`FileSessionStore`] — JSON file on disk, survives process restarts
+//!
+//! Both honour [`SessionConfig`]: entries idle for longer than `ttl` are
+//! dropped, and the least recently used entry is evicted once `max_entries`
+//! is reached.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::types::RunnerError;
+
+/// Default idle lifetime of a session entry (24 hours)
+const DEFAULT_SESSION_TTL: Duration = Duration::from_hours(24);
+
+/// Default maximum number of tracked conversations
+const DEFAULT_MAX_SESSIONS: usize = 1024;
+
+/// Configuration for session retention
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    /// Entries not used for this long are discarded (`None` keeps them forever)
+    pub ttl: Option<Duration>,
+    /// Maximum number of conversations tracked before LRU eviction
+    pub max_entries: usize,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Some(DEFAULT_SESSION_TTL),
+            max_entries: DEFAULT_MAX_SESSIONS,
+        }
+    }
+}
+
+/// Storage backend mapping conversation keys to CLI session IDs
+#[async_trait]
+pub trait SessionStore: Send + Sync + fmt::Debug {
+    /// Look up the session ID for a conversation key, refreshing its idle timer
+    async fn get(&self, key: &str) -> Option<String>;
+
+    /// Record the session ID for a conversation key
+    async fn set(&self, key: &str, session_id: &str) -> Result<(), RunnerError>;
+
+    /// Forget the session for a conversation key
+    async fn remove(&self, key: &str) -> Result<(), RunnerError>;
+}
+
+/// A single tracked conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionEntry {
+    session_id: String,
+    /// Milliseconds since the unix epoch when the entry was last read or written
+    last_used_ms: u64,
+}
+
+/// Shared table logic for both store implementations
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionTable {
+    entries: HashMap<String, SessionEntry>,
+}
+
+impl SessionTable {
+    fn is_expired(entry: &SessionEntry, config: &SessionConfig, now_ms: u64) -> bool {
+        config.ttl.is_some_and(|ttl| {
+            #[allow(clippy::cast_possible_truncation)]
+            let ttl_ms = ttl.as_millis() as u64;
+            now_ms.saturating_sub(entry.last_used_ms) >= ttl_ms
+        })
+    }
+
+    fn get(&mut self, key: &str, config: &SessionConfig, now_ms: u64) -> Option<String> {
+        let expired = Self::is_expired(self.entries.get(key)?, config, now_ms);
+        if expired {
+            debug!(key, "session expired");
+            self.entries.remove(key);
+            return None;
+        }
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used_ms = now_ms;
+        Some(entry.session_id.clone())
+    }
+
+    fn insert(&mut self, key: &str, session_id: &str, config: &SessionConfig, now_ms: u64) {
+        self.entries
+            .retain(|_, entry| !Self::is_expired(entry, config, now_ms));
+
+        if !self.entries.contains_key(key) {
+            while !self.entries.is_empty() && self.entries.len() >= config.max_entries {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used_ms)
+                    .map(|(k, _)| k.clone());
+                if let Some(oldest) = oldest {
+                    debug!(key = %oldest, "session evicted (capacity)");
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+
+        self.entries.insert(
+            key.to_owned(),
+            SessionEntry {
+                session_id: session_id.to_owned(),
+                last_used_ms: now_ms,
+            },
+        );
+    }
+}
+
+/// Current wall-clock time in milliseconds since the unix epoch
+fn now_ms() -> u64 {
+    #[allow(clippy::cast_possible_truncation)]
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Process-local session store (the default for CLI runners)
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    config: SessionConfig,
+    table: Mutex<SessionTable>,
+}
+
+impl InMemorySessionStore {
+    /// Create an in-memory store with the given retention settings
+    pub fn new(config: SessionConfig) -> Self {
+        Self {
+            config,
+            table: Mutex::new(SessionTable::default()),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn get(&self, key: &str) -> Option<String> {
+        let mut table = self.table.lock().expect("session lock poisoned");
+        table.get(key, &self.config, now_ms())
+    }
+
+    async fn set(&self, key: &str, session_id: &str) -> Result<(), RunnerError> {
+        let mut table = self.table.lock().expect("session lock poisoned");
+        table.insert(key, session_id, &self.config, now_ms());
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), RunnerError> {
+        let mut table = self.table.lock().expect("session lock poisoned");
+        table.entries.remove(key);
+        Ok(())
+    }
+}
+
+/// Session store persisted as a JSON file
+///
+/// The whole table is rewritten (via a temp file and rename) on every
+/// `set`/`remove`. Idle-timer refreshes from `get` are kept in memory and
+/// flushed with the next write.
+#[derive(Debug)]
+pub struct FileSessionStore {
+    path: PathBuf,
+    config: SessionConfig,
+    table: tokio::sync::Mutex<SessionTable>,
+}
+
+impl FileSessionStore {
+    /// Open (or lazily create) a file-backed store at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] with `ErrorKind::Config` if the file exists
+    /// but cannot be read or does not contain a valid session table.
+    pub fn open(path: impl Into<PathBuf>, config: SessionConfig) -> Result<Self, RunnerError> {
+        let path = path.into();
+        let table = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                RunnerError::config(format!(
+                    "Invalid session store file {}: {e}",
+                    path.display()
+                ))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SessionTable::default(),
+            Err(e) => {
+                return Err(RunnerError::config(format!(
+                    "Failed to read session store file {}: {e}",
+                    path.display()
+                )))
+            }
+        };
+        Ok(Self {
+            path,
+            config,
+            table: tokio::sync::Mutex::new(table),
+        })
+    }
+
+    /// Path of the backing JSON file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Atomically write the table to disk
+    async fn persist(&self, table: &SessionTable) -> Result<(), RunnerError> {
+        let bytes = serde_json::to_vec_pretty(table)
+            .map_err(|e| RunnerError::internal(format!("Failed to serialize sessions: {e}")))?;
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                RunnerError::internal(format!(
+                    "Failed to create session directory {}: {e}",
+                    parent.display()
+                ))
+            })?;
+        }
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, bytes).await.map_err(|e| {
+            RunnerError::internal(format!(
+                "Failed to write session file {}: {e}",
+                tmp_path.display()
+            ))
+        })?;
+        tokio::fs::rename(&tmp_path, &self.path).await.map_err(|e| {
+            RunnerError::internal(format!(
+                "Failed to replace session file {}: {e}",
+                self.path.display()
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileSessionStore {
+    async fn get(&self, key: &str) -> Option<String> {
+        let mut table = self.table.lock().await;
+        table.get(key, &self.config, now_ms())
+    }
+
+    async fn set(&self, key: &str, session_id: &str) -> Result<(), RunnerError> {
+        let mut table = self.table.lock().await;
+        table.insert(key, session_id, &self.config, now_ms());
+        self.persist(&table).await
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), RunnerError> {
+        let mut table = self.table.lock().await;
+        if table.entries.remove(key).is_some() {
+            self.persist(&table).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_memory_round_trip() {
+        let store = InMemorySessionStore::default();
+        assert!(store.get("conv-1").await.is_none());
+
+        store.set("conv-1", "sid-a").await.unwrap();
+        store.set("conv-2", "sid-b").await.unwrap();
+
+        assert_eq!(store.get("conv-1").await.as_deref(), Some("sid-a"));
+        assert_eq!(store.get("conv-2").await.as_deref(), Some("sid-b"));
+
+        store.remove("conv-1").await.unwrap();
+        assert!(store.get("conv-1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn in_memory_overwrites_session_for_same_key() {
+        let store = InMemorySessionStore::default();
+        store.set("conv", "old").await.unwrap();
+        store.set("conv", "new").await.unwrap();
+        assert_eq!(store.get("conv").await.as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn entries_expire_after_ttl() {
+        let store = InMemorySessionStore::new(SessionConfig {
+            ttl: Some(Duration::from_millis(30)),
+            max_entries: 10,
+        });
+        store.set("conv", "sid").await.unwrap();
+        assert!(store.get("conv").await.is_some());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(store.get("conv").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_entry_is_evicted() {
+        let mut table = SessionTable::default();
+        let config = SessionConfig {
+            ttl: None,
+            max_entries: 2,
+        };
+        table.insert("a", "sid-a", &config, 1);
+        table.insert("b", "sid-b", &config, 2);
+        // Touch "a" so "b" becomes the least recently used
+        assert!(table.get("a", &config, 3).is_some());
+        table.insert("c", "sid-c", &config, 4);
+
+        assert_eq!(table.entries.len(), 2);
+        assert!(table.get("a", &config, 5).is_some());
+        assert!(table.get("b", &config, 5).is_none());
+        assert!(table.get("c", &config, 5).is_some());
+    }
+
+    #[tokio::test]
+    async fn file_store_persists_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("sessions.json");
+
+        let store = FileSessionStore::open(&path, SessionConfig::default()).unwrap();
+        store.set("conv-1", "sid-a").await.unwrap();
+        store.set("conv-2", "sid-b").await.unwrap();
+        store.remove("conv-2").await.unwrap();
+        drop(store);
+
+        let reopened = FileSessionStore::open(&path, SessionConfig::default()).unwrap();
+        assert_eq!(reopened.get("conv-1").await.as_deref(), Some("sid-a"));
+        assert!(reopened.get("conv-2").await.is_none());
+    }
+
+    #[test]
+    fn file_store_rejects_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+        std::fs::write(&path, b"not json").unwrap();
+
+        let err = FileSessionStore::open(&path, SessionConfig::default()).unwrap_err();
+        assert_eq!(err.kind, crate::types::ErrorKind::Config);
+    }
+}
@@ -0,0 +1,497 @@
+// ABOUTME: Request-coalescing decorator that lets concurrent identical requests share one completion
+// ABOUTME: Runs the shared call in a task and cancels it only after every waiting caller has gone away
+//
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2026 dravr.ai
+
+//! # Single-Flight Deduplication
+//!
+//! [`SingleFlightProvider`] wraps an inner provider so that concurrent
+//! identical `complete()` calls share one in-flight request: the first caller
+//! starts it, later callers with the same key join it, and every caller
+//! receives the same response or error. Keys are the same stable request
+//! hash used by [`CacheProvider`](crate::cache::CacheProvider), so placing
+//! this decorator in front of a cache closes the window in which concurrent
+//! misses would each spawn their own CLI subprocess.
+//!
+//! The shared call runs in its own task. A caller that is cancelled (or
+//! dropped) leaves without affecting the others; the inner call is cancelled
+//! only once every waiter has gone away.
+//!
+//! Requests with `temperature > 0` and the candidates of a fanned-out
+//! multi-choice request are not coalesced, since callers expect independent
+//! samples. Requests with a deadline only join flights with the same
+//! deadline, so no caller inherits a shorter deadline's timeout. Streaming
+//! requests always pass through.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, Instrument};
+
+use crate::cache::cache_key;
+use crate::choices::is_fan_out_candidate;
+use crate::model_catalog::ModelInfo;
+use crate::types::{
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
+};
+
+/// One shared in-flight request
+struct Flight {
+    /// Cancels the inner call once every waiter has left
+    cancel: CancellationToken,
+    /// Fires when `result` is set
+    done: CancellationToken,
+    result: Mutex<Option<Result<ChatResponse, RunnerError>>>,
+    waiters: Mutex<usize>,
+}
+
+type Flights = Arc<Mutex<HashMap<String, Arc<Flight>>>>;
+
+/// Decorator that coalesces concurrent identical requests into one inner call
+///
+/// # Usage
+///
+/// ```rust,no_run
+/// # use embacle::single_flight::SingleFlightProvider;
+/// # use embacle::types::LlmProvider;
+/// # fn example(provider: Box<dyn LlmProvider>) {
+/// let coalesced = SingleFlightProvider::new(provider);
+/// // Identical requests sent at the same time share one completion
+/// # }
+/// ```
+pub struct SingleFlightProvider {
+    inner: Arc<dyn LlmProvider>,
+    flights: Flights,
+}
+
+impl SingleFlightProvider {
+    /// Wrap a provider with request coalescing
+    pub fn new(inner: Box<dyn LlmProvider>) -> Self {
+        Self::from_shared(Arc::from(inner))
+    }
+
+    /// Wrap a provider that is already shared behind an `Arc`
+    pub fn from_shared(inner: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            inner,
+            flights: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Number of distinct requests currently in flight
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn in_flight(&self) -> usize {
+        self.flights
+            .lock()
+            .expect("single-flight lock poisoned")
+            .len()
+    }
+
+    /// Join the flight for `key`, starting it if none is running
+    ///
+    /// Returns the flight and whether this caller joined an existing one.
+    fn join(&self, key: &str, request: &ChatRequest) -> (Arc<Flight>, bool) {
+        let mut flights = self.flights.lock().expect("single-flight lock poisoned");
+        if let Some(flight) = flights.get(key) {
+            *flight.waiters.lock().expect("single-flight lock poisoned") += 1;
+            return (Arc::clone(flight), true);
+        }
+
+        let flight = Arc::new(Flight {
+            cancel: CancellationToken::new(),
+            done: CancellationToken::new(),
+            result: Mutex::new(None),
+            waiters: Mutex::new(1),
+        });
+        flights.insert(key.to_owned(), Arc::clone(&flight));
+        drop(flights);
+
+        let inner = Arc::clone(&self.inner);
+        let shared = Arc::clone(&flight);
+        let registry = Arc::clone(&self.flights);
+        let key = key.to_owned();
+        let request = request.clone();
+        let span = request.metadata.span("single_flight");
+        tokio::spawn(
+            async move {
+                let result = inner
+                    .complete_with_cancel(&request, &shared.cancel)
+                    .await
+                    .map(|response| without_request_metadata(response, &request));
+                *shared.result.lock().expect("single-flight lock poisoned") = Some(result);
+                remove_flight(&registry, &key, &shared);
+                shared.done.cancel();
+            }
+            .instrument(span),
+        );
+        (flight, false)
+    }
+}
+
+/// Cache key of `request`, qualified by its deadline when it has one
+///
+/// The shared call runs under the starting caller's deadline, so callers
+/// only share it when their deadlines are identical.
+fn flight_key(provider: &str, request: &ChatRequest) -> String {
+    let key = cache_key(provider, request);
+    match request.deadline {
+        Some(deadline) => format!("{key}@{deadline:?}"),
+        None => key,
+    }
+}
+
+/// Remove `flight` from the registry if it is still the entry for `key`
+fn remove_flight(flights: &Flights, key: &str, flight: &Arc<Flight>) {
+    let mut flights = flights.lock().expect("single-flight lock poisoned");
+    if flights.get(key).is_some_and(|f| Arc::ptr_eq(f, flight)) {
+        flights.remove(key);
+    }
+}
+
+/// Strip the starting caller's metadata so each waiter gets its own
+fn without_request_metadata(mut response: ChatResponse, request: &ChatRequest) -> ChatResponse {
+    let own = &request.metadata;
+    if response.metadata.request_id == own.request_id {
+        response.metadata.request_id = None;
+    }
+    if response.metadata.user == own.user {
+        response.metadata.user = None;
+    }
+    response
+        .metadata
+        .tags
+        .retain(|key, value| own.tags.get(key) != Some(value));
+    response
+}
+
+/// Leaves a flight when its caller returns or is dropped
+struct WaiterGuard {
+    flights: Flights,
+    key: String,
+    flight: Arc<Flight>,
+}
+
+impl Drop for WaiterGuard {
+    fn drop(&mut self) {
+        // Lock order matches `join`: registry, then waiter count
+        let mut flights = self.flights.lock().expect("single-flight lock poisoned");
+        let mut waiters = self
+            .flight
+            .waiters
+            .lock()
+            .expect("single-flight lock poisoned");
+        *waiters -= 1;
+        if *waiters == 0 && !self.flight.done.is_cancelled() {
+            debug!(key = %self.key, "single-flight: last waiter left, cancelling");
+            self.flight.cancel.cancel();
+            if flights
+                .get(&self.key)
+                .is_some_and(|f| Arc::ptr_eq(f, &self.flight))
+            {
+                flights.remove(&self.key);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for SingleFlightProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn display_name(&self) -> &str {
+        self.inner.display_name()
+    }
+
+    fn capabilities(&self) -> LlmCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn default_model(&self) -> &str {
+        self.inner.default_model()
+    }
+
+    fn available_models(&self) -> &[String] {
+        self.inner.available_models()
+    }
+
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        self.inner.model_catalog()
+    }
+
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        self.inner.refresh_models().await
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        if matches!(request.temperature, Some(t) if t > 0.0) || is_fan_out_candidate(request) {
+            return self.inner.complete_with_cancel(request, cancel).await;
+        }
+
+        let key = flight_key(self.inner.name(), request);
+        let (flight, joined) = self.join(&key, request);
+        if joined {
+            debug!(key, "single-flight: joined in-flight request");
+        }
+        let _guard = WaiterGuard {
+            flights: Arc::clone(&self.flights),
+            key,
+            flight: Arc::clone(&flight),
+        };
+
+        tokio::select! {
+            biased;
+            () = flight.done.cancelled() => {}
+            () = cancel.cancelled() => {
+                return Err(RunnerError::cancelled("single-flight: caller cancelled"));
+            }
+        }
+
+        let result = flight
+            .result
+            .lock()
+            .expect("single-flight lock poisoned")
+            .clone()
+            .unwrap_or_else(|| Err(RunnerError::internal("single-flight: missing result")));
+        result.map(|mut response| {
+            if joined {
+                response
+                    .metadata
+                    .tags
+                    .insert("single_flight".to_owned(), "shared".to_owned());
+            }
+            response.with_request_metadata(request)
+        })
+    }
+
+    /// Streaming requests are not coalesced
+    async fn complete_stream(&self, request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+        self.inner.complete_stream(request).await
+    }
+
+    async fn health_check(&self) -> Result<bool, RunnerError> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use crate::choices::CHOICE_TAG;
+    use crate::types::{ChatMessage, ErrorKind, RequestMetadata};
+
+    /// Sleeps, then answers "answer-<call number>" or fails; observes cancellation
+    struct SlowProvider {
+        calls: AtomicU32,
+        cancelled: AtomicU32,
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl SlowProvider {
+        fn new(delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicU32::new(0),
+                cancelled: AtomicU32::new(0),
+                delay,
+                fail: false,
+            })
+        }
+
+        fn failing(delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicU32::new(0),
+                cancelled: AtomicU32::new(0),
+                delay,
+                fail: true,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for SlowProvider {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+        fn display_name(&self) -> &str {
+            "Slow"
+        }
+        fn capabilities(&self) -> LlmCapabilities {
+            LlmCapabilities::text_only()
+        }
+        fn default_model(&self) -> &'static str {
+            "slow-model"
+        }
+        fn available_models(&self) -> &[String] {
+            &[]
+        }
+        async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
+            self.complete_with_cancel(request, &CancellationToken::new())
+                .await
+        }
+        async fn complete_with_cancel(
+            &self,
+            request: &ChatRequest,
+            cancel: &CancellationToken,
+        ) -> Result<ChatResponse, RunnerError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::select! {
+                () = cancel.cancelled() => {
+                    self.cancelled.fetch_add(1, Ordering::SeqCst);
+                    return Err(RunnerError::cancelled("slow: cancelled"));
+                }
+                () = tokio::time::sleep(self.delay) => {}
+            }
+            if self.fail {
+                return Err(RunnerError::external_service("slow", "boom"));
+            }
+            Ok(ChatResponse {
+                content: format!("answer-{n}"),
+                model: "slow-model".to_owned(),
+                usage: None,
+                finish_reason: Some("stop".to_owned()),
+                warnings: None,
+                tool_calls: None,
+                reasoning: None,
+                choices: None,
+                metadata: RequestMetadata::default(),
+            }
+            .with_request_metadata(request))
+        }
+        async fn complete_stream(&self, _request: &ChatRequest) -> Result<ChatStream, RunnerError> {
+            Err(RunnerError::internal("not used"))
+        }
+        async fn health_check(&self) -> Result<bool, RunnerError> {
+            Ok(true)
+        }
+    }
+
+    fn request(id: &str) -> ChatRequest {
+        ChatRequest::new(vec![ChatMessage::user("same prompt")])
+            .with_metadata(RequestMetadata::new().with_request_id(id))
+    }
+
+    #[tokio::test]
+    async fn different_deadlines_and_fan_out_candidates_are_not_shared() {
+        let inner = SlowProvider::new(Duration::from_millis(50));
+        let provider = SingleFlightProvider::from_shared(inner.clone());
+
+        let short = request("short").with_timeout(Duration::from_secs(5));
+        let long = request("long").with_timeout(Duration::from_secs(60));
+        let (a, b) = tokio::join!(provider.complete(&short), provider.complete(&long));
+        assert_ne!(a.unwrap().content, b.unwrap().content);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        let candidate = |index: &str| {
+            request(index).with_metadata(RequestMetadata::new().with_tag(CHOICE_TAG, index))
+        };
+        let (first, second) = (candidate("0"), candidate("1"));
+        let (a, b) = tokio::join!(provider.complete(&first), provider.complete(&second));
+        assert_ne!(a.unwrap().content, b.unwrap().content);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn concurrent_identical_requests_share_one_call() {
+        let inner = SlowProvider::new(Duration::from_millis(50));
+        let provider = SingleFlightProvider::from_shared(inner.clone());
+
+        let (req_a, req_b, req_c) = (request("a"), request("b"), request("c"));
+        let (a, b, c) = tokio::join!(
+            provider.complete(&req_a),
+            provider.complete(&req_b),
+            provider.complete(&req_c),
+        );
+
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+        let (a, b, c) = (a.unwrap(), b.unwrap(), c.unwrap());
+        assert_eq!(a.content, "answer-1");
+        assert_eq!(b.content, "answer-1");
+        assert_eq!(c.content, "answer-1");
+        assert_eq!(a.metadata.request_id.as_deref(), Some("a"));
+        assert_eq!(b.metadata.request_id.as_deref(), Some("b"));
+        assert!(!a.metadata.tags.contains_key("single_flight"));
+        assert_eq!(
+            b.metadata.tags.get("single_flight").map(String::as_str),
+            Some("shared")
+        );
+        assert_eq!(provider.in_flight(), 0);
+
+        // A later request starts a new flight
+        provider.complete(&request("d")).await.unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn errors_are_shared_with_every_waiter() {
+        let inner = SlowProvider::failing(Duration::from_millis(20));
+        let provider = SingleFlightProvider::from_shared(inner.clone());
+
+        let (req_a, req_b) = (request("a"), request("b"));
+        let (a, b) = tokio::join!(provider.complete(&req_a), provider.complete(&req_b));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(a.unwrap_err().kind, ErrorKind::ExternalService);
+        assert_eq!(b.unwrap_err().kind, ErrorKind::ExternalService);
+    }
+
+    #[tokio::test]
+    async fn cancellation_waits_for_the_last_waiter() {
+        let inner = SlowProvider::new(Duration::from_millis(100));
+        let provider = SingleFlightProvider::from_shared(inner.clone());
+        let first_cancel = CancellationToken::new();
+        let canceller = first_cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let (req_a, req_b) = (request("a"), request("b"));
+        let (first, second) = tokio::join!(
+            provider.complete_with_cancel(&req_a, &first_cancel),
+            provider.complete(&req_b),
+        );
+        assert_eq!(first.unwrap_err().kind, ErrorKind::Cancelled);
+        assert_eq!(second.unwrap().content, "answer-1");
+        assert_eq!(inner.cancelled.load(Ordering::SeqCst), 0);
+
+        let only_cancel = CancellationToken::new();
+        only_cancel.cancel();
+        let lone = provider
+            .complete_with_cancel(&request("c"), &only_cancel)
+            .await;
+        assert_eq!(lone.unwrap_err().kind, ErrorKind::Cancelled);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(inner.cancelled.load(Ordering::SeqCst), 1);
+        assert_eq!(provider.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn nonzero_temperature_is_not_coalesced() {
+        let inner = SlowProvider::new(Duration::from_millis(20));
+        let provider = SingleFlightProvider::from_shared(inner.clone());
+        let sampled = request("a").with_temperature(0.8);
+
+        let (a, b) = tokio::join!(provider.complete(&sampled), provider.complete(&sampled));
+        assert_ne!(a.unwrap().content, b.unwrap().content);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+}
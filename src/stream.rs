@@ -1,27 +1,130 @@
 // ABOUTME: Stream wrapper that owns a child process for proper lifecycle management
-// ABOUTME: Prevents zombie processes, drains stderr, and kills child on drop
+// ABOUTME: Prevents zombie processes, drains stderr, enforces stream timeouts, and kills child on drop
 //
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
 
+use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use tokio::process::Child;
 use tokio::task::JoinHandle;
+use tokio::time::{Instant, Sleep};
 use tokio_stream::Stream;
-use tracing::debug;
+use tracing::{debug, warn};
 
-use crate::types::{RunnerError, StreamChunk};
+use crate::config::RunnerConfig;
+use crate::execution::CleanupGuard;
+use crate::process::{terminate_process_group_in_background, TERMINATE_GRACE_PERIOD};
+use crate::types::{ChatRequest, RunnerError, StreamChunk};
 
 /// Maximum stderr to buffer during streaming (1 MiB)
 pub(crate) const MAX_STREAMING_STDERR_BYTES: usize = 1024 * 1024;
 
+/// Deadlines applied to a streaming response.
+///
+/// `total` bounds the whole stream; `idle` (when set) bounds the gap
+/// between consecutive chunks, so a hung producer is detected long
+/// before the total deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamTimeouts {
+    /// Maximum duration of the whole stream
+    pub total: Duration,
+    /// Maximum silence between chunks
+    pub idle: Option<Duration>,
+}
+
+impl StreamTimeouts {
+    /// Create timeouts with a total deadline and no idle deadline
+    #[must_use]
+    pub const fn new(total: Duration) -> Self {
+        Self { total, idle: None }
+    }
+
+    /// Set the maximum silence between chunks
+    #[must_use]
+    pub const fn with_idle(mut self, idle: Duration) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+
+    /// Timeouts from a runner's `timeout` and `idle_timeout`
+    #[must_use]
+    pub const fn from_config(config: &RunnerConfig) -> Self {
+        Self {
+            total: config.timeout,
+            idle: config.idle_timeout,
+        }
+    }
+
+    /// Timeouts for `request`: its remaining deadline, if set, replaces the runner's `timeout`
+    #[must_use]
+    pub fn for_request(config: &RunnerConfig, request: &ChatRequest) -> Self {
+        Self {
+            total: request.timeout_or(config.timeout),
+            idle: config.idle_timeout,
+        }
+    }
+
+    fn total_error(&self) -> RunnerError {
+        RunnerError::timeout(format!("stream timed out after {:?}", self.total))
+    }
+
+    fn idle_error(idle: Duration) -> RunnerError {
+        RunnerError::timeout(format!("stream produced no output for {idle:?}"))
+    }
+}
+
+/// Tracks [`StreamTimeouts`] across the reads of an async streaming loop.
+///
+/// The task-based counterpart of [`GuardedStream::with_timeouts`], used by
+/// readers that pump chunks into a channel rather than exposing a `Stream`.
+#[cfg(any(feature = "acp", feature = "openai-api"))]
+pub(crate) struct StreamDeadline {
+    timeouts: StreamTimeouts,
+    deadline: Instant,
+    label: &'static str,
+}
+
+#[cfg(any(feature = "acp", feature = "openai-api"))]
+impl StreamDeadline {
+    /// Start the total deadline now
+    pub(crate) fn start(timeouts: StreamTimeouts, label: &'static str) -> Self {
+        Self {
+            timeouts,
+            deadline: Instant::now() + timeouts.total,
+            label,
+        }
+    }
+
+    /// Await the next read, failing with [`RunnerError::timeout`] if either deadline passes first
+    pub(crate) async fn next<F: Future>(&self, read: F) -> Result<F::Output, RunnerError> {
+        let idle_deadline = self.timeouts.idle.map(|idle| Instant::now() + idle);
+        let deadline = idle_deadline.map_or(self.deadline, |idle| idle.min(self.deadline));
+        tokio::time::timeout_at(deadline, read).await.map_err(|_| {
+            let mut err = match (self.timeouts.idle, idle_deadline) {
+                (Some(idle), Some(at)) if at < self.deadline => StreamTimeouts::idle_error(idle),
+                _ => self.timeouts.total_error(),
+            };
+            warn!(label = self.label, error = %err.message, "Streaming deadline exceeded");
+            err.message = format!("{}: {}", self.label, err.message);
+            err
+        })
+    }
+}
+
 /// Guards a child process for the lifetime of a streaming response.
 ///
 /// When the stream is dropped (after natural completion or early
-/// cancellation), the child process is killed and the stderr drain
-/// task is aborted. This prevents zombie processes and resource leaks.
+/// cancellation), the child's process group is terminated (SIGTERM, then
+/// SIGKILL after a grace period) and the stderr drain task is aborted.
+/// This prevents zombie processes, orphaned grandchildren and resource leaks.
+///
+/// With [`with_timeouts`](Self::with_timeouts), the stream also ends with a
+/// [`RunnerError::timeout`] (and kills the child) when the total deadline
+/// passes or the child goes silent for longer than the idle deadline.
 ///
 /// All fields are `Unpin`, so `GuardedStream` is `Unpin` and the
 /// `Stream` impl can safely access inner fields through `Pin<&mut Self>`.
@@ -29,6 +132,11 @@ pub struct GuardedStream {
     inner: Pin<Box<dyn Stream<Item = Result<StreamChunk, RunnerError>> + Send>>,
     child: Option<Child>,
     stderr_task: Option<JoinHandle<Vec<u8>>>,
+    cleanup: Option<CleanupGuard>,
+    timeouts: Option<StreamTimeouts>,
+    total_sleep: Option<Pin<Box<Sleep>>>,
+    idle_sleep: Option<Pin<Box<Sleep>>>,
+    finished: bool,
 }
 
 impl GuardedStream {
@@ -47,7 +155,39 @@ impl GuardedStream {
             inner: Box::pin(inner),
             child: Some(child),
             stderr_task: Some(stderr_task),
+            cleanup: None,
+            timeouts: None,
+            total_sleep: None,
+            idle_sleep: None,
+            finished: false,
+        }
+    }
+
+    /// Run an execution backend's cleanup if the stream stops before the child finishes
+    #[must_use]
+    pub(crate) fn with_cleanup(mut self, cleanup: CleanupGuard) -> Self {
+        self.cleanup = Some(cleanup);
+        self
+    }
+
+    /// Enforce total and idle deadlines on the stream
+    #[must_use]
+    pub fn with_timeouts(mut self, timeouts: StreamTimeouts) -> Self {
+        self.total_sleep = Some(Box::pin(tokio::time::sleep(timeouts.total)));
+        self.idle_sleep = timeouts.idle.map(|idle| Box::pin(tokio::time::sleep(idle)));
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    /// Stop the stream with a timeout error, killing the child
+    fn expire(&mut self, err: RunnerError) -> Poll<Option<Result<StreamChunk, RunnerError>>> {
+        warn!(error = %err.message, "Streaming deadline exceeded, terminating child process group");
+        self.finished = true;
+        if let Some(child) = self.child.take() {
+            terminate_process_group_in_background(child, TERMINATE_GRACE_PERIOD);
         }
+        drop(self.cleanup.take());
+        Poll::Ready(Some(Err(err)))
     }
 }
 
@@ -55,18 +195,179 @@ impl Stream for GuardedStream {
     type Item = Result<StreamChunk, RunnerError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.inner.as_mut().poll_next(cx)
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        if let Poll::Ready(item) = self.inner.as_mut().poll_next(cx) {
+            match (item.is_some(), self.timeouts.and_then(|t| t.idle)) {
+                (true, Some(idle)) => {
+                    if let Some(sleep) = self.idle_sleep.as_mut() {
+                        sleep.as_mut().reset(Instant::now() + idle);
+                    }
+                }
+                (false, _) => {
+                    self.finished = true;
+                    if let Some(cleanup) = self.cleanup.as_mut() {
+                        cleanup.disarm();
+                    }
+                }
+                (true, None) => {}
+            }
+            return Poll::Ready(item);
+        }
+
+        let Some(timeouts) = self.timeouts else {
+            return Poll::Pending;
+        };
+        if let Some(sleep) = self.total_sleep.as_mut() {
+            if sleep.as_mut().poll(cx).is_ready() {
+                return self.expire(timeouts.total_error());
+            }
+        }
+        if let (Some(sleep), Some(idle)) = (self.idle_sleep.as_mut(), timeouts.idle) {
+            if sleep.as_mut().poll(cx).is_ready() {
+                return self.expire(StreamTimeouts::idle_error(idle));
+            }
+        }
+        Poll::Pending
     }
 }
 
 impl Drop for GuardedStream {
     fn drop(&mut self) {
-        debug!("GuardedStream dropped, cleaning up child process");
-        if let Some(mut child) = self.child.take() {
-            let _ = child.start_kill();
+        debug!("GuardedStream dropped, cleaning up child process group");
+        if let Some(child) = self.child.take() {
+            terminate_process_group_in_background(child, TERMINATE_GRACE_PERIOD);
         }
         if let Some(task) = self.stderr_task.take() {
             task.abort();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Stdio;
+    use tokio::process::Command;
+    use tokio_stream::StreamExt;
+
+    fn sleeping_child() -> (Child, JoinHandle<Vec<u8>>) {
+        let child = Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        (child, tokio::spawn(async { Vec::new() }))
+    }
+
+    fn chunk(delta: &str) -> StreamChunk {
+        StreamChunk {
+            delta: delta.to_owned(),
+            ..StreamChunk::default()
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn idle_timeout_fails_stream_and_kills_process_group() {
+        use crate::process::spawn_in_process_group;
+        use crate::process::tests::{all_exited, forked_pids, FORKING_CLI};
+
+        let pidfile = tempfile::NamedTempFile::new().unwrap();
+        let child = spawn_in_process_group(
+            Command::new(FORKING_CLI)
+                .arg(pidfile.path())
+                .stdout(Stdio::null()),
+            None,
+        )
+        .unwrap();
+        let pids = forked_pids(pidfile.path()).await;
+
+        let inner = tokio_stream::iter(vec![Ok(chunk("a"))]).chain(tokio_stream::pending());
+        let mut stream = GuardedStream::new(inner, child, tokio::spawn(async { Vec::new() }))
+            .with_timeouts(
+                StreamTimeouts::new(Duration::from_secs(30)).with_idle(Duration::from_millis(100)),
+            );
+
+        assert_eq!(stream.next().await.unwrap().unwrap().delta, "a");
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind, crate::types::ErrorKind::Timeout);
+        assert!(err.message.contains("no output"));
+        assert!(stream.next().await.is_none());
+        assert!(
+            all_exited(&pids).await,
+            "grandchildren outlived the idle timeout"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropped_stream_kills_process_group() {
+        use crate::process::spawn_in_process_group;
+        use crate::process::tests::{all_exited, forked_pids, FORKING_CLI};
+
+        let pidfile = tempfile::NamedTempFile::new().unwrap();
+        let child = spawn_in_process_group(
+            Command::new(FORKING_CLI)
+                .arg(pidfile.path())
+                .stdout(Stdio::null()),
+            None,
+        )
+        .unwrap();
+        let pids = forked_pids(pidfile.path()).await;
+
+        let stream = GuardedStream::new(
+            tokio_stream::pending(),
+            child,
+            tokio::spawn(async { Vec::new() }),
+        );
+        drop(stream);
+        assert!(all_exited(&pids).await, "grandchildren outlived the stream");
+    }
+
+    #[tokio::test]
+    async fn total_timeout_applies_while_chunks_keep_flowing() {
+        let (child, stderr_task) = sleeping_child();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while tx.send(Ok(chunk("."))).is_ok() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+        let inner = tokio_stream::wrappers::UnboundedReceiverStream::new(rx);
+        let mut stream = GuardedStream::new(inner, child, stderr_task).with_timeouts(
+            StreamTimeouts::new(Duration::from_millis(150)).with_idle(Duration::from_millis(100)),
+        );
+
+        let mut chunks = 0;
+        let err = loop {
+            match stream.next().await.unwrap() {
+                Ok(_) => chunks += 1,
+                Err(e) => break e,
+            }
+        };
+        assert!(chunks > 1);
+        assert!(err.message.contains("timed out after 150ms"));
+    }
+
+    #[cfg(any(feature = "acp", feature = "openai-api"))]
+    #[tokio::test]
+    async fn deadline_passes_through_reads_that_finish_in_time() {
+        let deadline = StreamDeadline::start(
+            StreamTimeouts::new(Duration::from_secs(5)).with_idle(Duration::from_millis(100)),
+            "test",
+        );
+        assert_eq!(deadline.next(async { 7 }).await.unwrap(), 7);
+
+        let err = deadline
+            .next(tokio::time::sleep(Duration::from_secs(1)))
+            .await
+            .unwrap_err();
+        assert!(err
+            .message
+            .starts_with("test: stream produced no output for 100ms"));
+    }
+}
@@ -84,15 +84,8 @@ pub async fn request_structured_output(
     for attempt in 0..total_attempts {
         let request = ChatRequest {
             messages: messages.clone(),
-            model: structured_request.request.model.clone(),
-            temperature: structured_request.request.temperature,
-            max_tokens: structured_request.request.max_tokens,
             stream: false,
-            tools: structured_request.request.tools.clone(),
-            tool_choice: structured_request.request.tool_choice.clone(),
-            top_p: structured_request.request.top_p,
-            stop: structured_request.request.stop.clone(),
-            response_format: structured_request.request.response_format.clone(),
+            ..structured_request.request.clone()
         };
 
         let response = provider.complete(&request).await?;
@@ -355,7 +348,7 @@ mod tests {
     use super::*;
     use crate::types::{
         ChatMessage, ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider,
-        RunnerError,
+        RequestMetadata, RunnerError,
     };
     use async_trait::async_trait;
     use serde_json::json;
@@ -418,6 +411,9 @@ mod tests {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         }
     }
 
@@ -10,25 +10,38 @@
 //! These types mirror the LLM provider contract without requiring
 //! any external platform dependency.
 
+use std::collections::BTreeMap;
 use std::fmt;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use tokio_stream::Stream;
+use tokio_util::sync::CancellationToken;
+
+use crate::model_catalog::ModelInfo;
 
 // ============================================================================
 // Error Type
 // ============================================================================
 
 /// Error type for CLI LLM runner operations
+///
+/// Construct values through [`RunnerError::new`] or the per-kind
+/// constructors; the struct is non-exhaustive so new fields are not breaking.
 #[derive(Debug, Clone)]
 #[must_use]
+#[non_exhaustive]
 pub struct RunnerError {
     /// Error category
     pub kind: ErrorKind,
     /// Human-readable error message
     pub message: String,
+    /// How long the provider asked callers to wait before retrying, if known
+    pub retry_after: Option<Duration>,
 }
 
 /// Categories of errors produced by CLI runners
@@ -48,26 +61,49 @@ pub enum ErrorKind {
     Config,
     /// Guardrail policy violation (request or response rejected)
     Guardrail,
+    /// Request was cancelled by the caller before it completed
+    Cancelled,
+    /// Provider rejected the request because of rate limiting
+    RateLimited,
+    /// Prompt exceeded the model's context window
+    ContextLengthExceeded,
+    /// Account quota, credits or usage allowance exhausted
+    QuotaExhausted,
+    /// Provider refused the request or response under its content policy
+    ContentFiltered,
 }
 
 impl ErrorKind {
     /// Whether this error category represents a transient failure worth retrying.
     ///
-    /// Transient errors (timeouts, external service issues) may succeed on a
-    /// subsequent attempt. Permanent errors (config, auth, missing binary) will
-    /// not benefit from retries.
+    /// Transient errors (timeouts, external service issues, rate limits) may
+    /// succeed on a subsequent attempt. Permanent errors (config, auth, missing
+    /// binary, exhausted quota, oversized prompts) will not benefit from retries.
     #[must_use]
     pub const fn is_transient(self) -> bool {
-        matches!(self, Self::Timeout | Self::ExternalService)
+        matches!(
+            self,
+            Self::Timeout | Self::ExternalService | Self::RateLimited
+        )
     }
 }
 
 impl RunnerError {
+    /// Create an error of any kind
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+
     /// Create an internal error
     pub fn internal(message: impl Into<String>) -> Self {
         Self {
             kind: ErrorKind::Internal,
             message: message.into(),
+            retry_after: None,
         }
     }
 
@@ -76,6 +112,7 @@ impl RunnerError {
         Self {
             kind: ErrorKind::ExternalService,
             message: format!("{}: {}", service.into(), message.into()),
+            retry_after: None,
         }
     }
 
@@ -84,6 +121,7 @@ impl RunnerError {
         Self {
             kind: ErrorKind::BinaryNotFound,
             message: format!("Binary not found: {}", binary.into()),
+            retry_after: None,
         }
     }
 
@@ -92,6 +130,7 @@ impl RunnerError {
         Self {
             kind: ErrorKind::AuthFailure,
             message: message.into(),
+            retry_after: None,
         }
     }
 
@@ -100,6 +139,7 @@ impl RunnerError {
         Self {
             kind: ErrorKind::Config,
             message: message.into(),
+            retry_after: None,
         }
     }
 
@@ -108,6 +148,7 @@ impl RunnerError {
         Self {
             kind: ErrorKind::Timeout,
             message: message.into(),
+            retry_after: None,
         }
     }
 
@@ -116,8 +157,60 @@ impl RunnerError {
         Self {
             kind: ErrorKind::Guardrail,
             message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Create a cancellation error
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::Cancelled,
+            message: message.into(),
+            retry_after: None,
         }
     }
+
+    /// Create a rate-limit error
+    pub fn rate_limited(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::RateLimited,
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Create a context-length error
+    pub fn context_length_exceeded(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::ContextLengthExceeded,
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Create a quota-exhausted error
+    pub fn quota_exhausted(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::QuotaExhausted,
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Create a content-filter error
+    pub fn content_filtered(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::ContentFiltered,
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Attach the provider's retry-after hint
+    pub const fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
 }
 
 impl fmt::Display for RunnerError {
@@ -161,6 +254,12 @@ bitflags::bitflags! {
         const STOP_SEQUENCES    = 0b0010_0000_0000;
         /// Provider supports response format control (JSON mode, JSON Schema)
         const RESPONSE_FORMAT   = 0b0100_0000_0000;
+        /// Provider can read attached documents and files
+        const FILE_INPUT        = 0b1000_0000_0000;
+        /// Provider returns several candidate choices natively (the `n` parameter)
+        const MULTIPLE_CHOICES  = 0b1_0000_0000_0000;
+        /// Provider honors reasoning effort / thinking budget controls
+        const REASONING         = 0b10_0000_0000_0000;
     }
 }
 
@@ -246,6 +345,24 @@ impl LlmCapabilities {
     pub const fn supports_response_format(&self) -> bool {
         self.contains(Self::RESPONSE_FORMAT)
     }
+
+    /// Check if document and file attachments are supported
+    #[must_use]
+    pub const fn supports_file_input(&self) -> bool {
+        self.contains(Self::FILE_INPUT)
+    }
+
+    /// Check if multiple choices per request are supported natively
+    #[must_use]
+    pub const fn supports_multiple_choices(&self) -> bool {
+        self.contains(Self::MULTIPLE_CHOICES)
+    }
+
+    /// Check if reasoning effort / thinking budget controls are supported
+    #[must_use]
+    pub const fn supports_reasoning(&self) -> bool {
+        self.contains(Self::REASONING)
+    }
 }
 
 // ============================================================================
@@ -314,6 +431,135 @@ impl ImagePart {
     }
 }
 
+/// MIME types outside `text/*` whose content is plain text
+const TEXT_LIKE_MIME_TYPES: &[&str] = &[
+    "application/json",
+    "application/xml",
+    "application/yaml",
+    "application/x-yaml",
+    "application/toml",
+    "application/javascript",
+    "application/x-sh",
+    "application/sql",
+];
+
+/// Where an attachment's bytes come from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentSource {
+    /// Base64-encoded bytes
+    Data(String),
+    /// A file already on disk, referenced in place
+    Path(PathBuf),
+}
+
+/// A document or file attached to a chat message (PDF, CSV, log, source file, ...)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AttachmentPart {
+    /// MIME type (e.g., "application/pdf", "text/csv")
+    pub mime_type: String,
+    /// Original file name, used for the materialized file and prompt references
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// Inline bytes or a path to the file
+    pub source: AttachmentSource,
+    /// Embed text content directly in the prompt instead of referencing a file
+    #[serde(default)]
+    pub inline: bool,
+}
+
+impl AttachmentPart {
+    /// Create an attachment from base64-encoded bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if the MIME type is not of the form `type/subtype`.
+    pub fn from_base64(
+        data: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> Result<Self, RunnerError> {
+        Self::with_source(AttachmentSource::Data(data.into()), mime_type.into())
+    }
+
+    /// Create an attachment referencing a file on disk
+    ///
+    /// The file is read when the request is dispatched, not here.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if the MIME type is not of the form `type/subtype`.
+    pub fn from_path(
+        path: impl Into<PathBuf>,
+        mime_type: impl Into<String>,
+    ) -> Result<Self, RunnerError> {
+        Self::with_source(AttachmentSource::Path(path.into()), mime_type.into())
+    }
+
+    fn with_source(source: AttachmentSource, mime_type: String) -> Result<Self, RunnerError> {
+        let valid = mime_type
+            .split_once('/')
+            .is_some_and(|(kind, sub)| !kind.is_empty() && !sub.is_empty());
+        if !valid {
+            return Err(RunnerError::config(format!(
+                "Invalid attachment MIME type '{mime_type}'; expected 'type/subtype'"
+            )));
+        }
+        Ok(Self {
+            mime_type,
+            filename: None,
+            source,
+            inline: false,
+        })
+    }
+
+    /// Set the file name shown to the model
+    #[must_use]
+    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    /// Embed the content in the prompt when the attachment is text
+    #[must_use]
+    pub const fn with_inline(mut self, inline: bool) -> Self {
+        self.inline = inline;
+        self
+    }
+
+    /// Whether the MIME type denotes plain text content
+    #[must_use]
+    pub fn is_text(&self) -> bool {
+        self.mime_type.starts_with("text/")
+            || TEXT_LIKE_MIME_TYPES.contains(&self.mime_type.as_str())
+    }
+
+    /// The explicit file name, or the file name of a path source
+    #[must_use]
+    pub fn display_name(&self) -> Option<&str> {
+        self.filename.as_deref().or_else(|| match &self.source {
+            AttachmentSource::Path(path) => path.file_name().and_then(|n| n.to_str()),
+            AttachmentSource::Data(_) => None,
+        })
+    }
+
+    /// Decode inline data or read the referenced file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunnerError`] if the base64 data is invalid or the file
+    /// cannot be read.
+    pub fn read_bytes(&self) -> Result<Vec<u8>, RunnerError> {
+        match &self.source {
+            AttachmentSource::Data(data) => base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| RunnerError::config(format!("invalid base64 attachment data: {e}"))),
+            AttachmentSource::Path(path) => std::fs::read(path).map_err(|e| {
+                RunnerError::config(format!("failed to read attachment {}: {e}", path.display()))
+            }),
+        }
+    }
+}
+
 /// A single message in a chat conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -324,6 +570,9 @@ pub struct ChatMessage {
     /// Images attached to the message (only meaningful for `User` role)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub images: Option<Vec<ImagePart>>,
+    /// Documents and files attached to the message (only meaningful for `User` role)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<AttachmentPart>>,
     /// Tool calls requested by the assistant (only for `Assistant` role)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCallRequest>>,
@@ -343,6 +592,7 @@ impl ChatMessage {
             role,
             content: content.into(),
             images: None,
+            attachments: None,
             tool_calls: None,
             tool_call_id: None,
             name: None,
@@ -368,6 +618,24 @@ impl ChatMessage {
             role: MessageRole::User,
             content: content.into(),
             images: Some(images),
+            attachments: None,
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    /// Create a user message with attached documents or files
+    #[must_use]
+    pub fn user_with_attachments(
+        content: impl Into<String>,
+        attachments: Vec<AttachmentPart>,
+    ) -> Self {
+        Self {
+            role: MessageRole::User,
+            content: content.into(),
+            images: None,
+            attachments: Some(attachments),
             tool_calls: None,
             tool_call_id: None,
             name: None,
@@ -391,6 +659,7 @@ impl ChatMessage {
             role: MessageRole::Tool,
             content: content.into(),
             images: None,
+            attachments: None,
             tool_calls: None,
             tool_call_id: Some(tool_call_id.into()),
             name: Some(name.into()),
@@ -441,6 +710,61 @@ pub enum ToolChoice {
     },
 }
 
+/// How much a model should reason before answering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    /// As little reasoning as the model allows
+    Minimal,
+    /// Light reasoning
+    Low,
+    /// Balanced reasoning (the usual provider default)
+    Medium,
+    /// Extended reasoning
+    High,
+}
+
+impl ReasoningEffort {
+    /// Lowercase name used by `OpenAI`-compatible APIs and the Codex CLI
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    /// Thinking-token budget for providers configured by budget rather than level
+    #[must_use]
+    pub const fn thinking_budget(self) -> u32 {
+        match self {
+            Self::Minimal => 1_024,
+            Self::Low => 4_000,
+            Self::Medium => 10_000,
+            Self::High => 31_999,
+        }
+    }
+
+    /// Closest effort level for a thinking-token budget
+    #[must_use]
+    pub const fn from_thinking_budget(tokens: u32) -> Self {
+        match tokens {
+            0..=2_047 => Self::Minimal,
+            2_048..=6_999 => Self::Low,
+            7_000..=19_999 => Self::Medium,
+            _ => Self::High,
+        }
+    }
+}
+
+impl fmt::Display for ReasoningEffort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Controls the response format from the model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ResponseFormat {
@@ -461,6 +785,90 @@ pub enum ResponseFormat {
 // Request/Response Types
 // ============================================================================
 
+/// Caller context attached to a request and echoed on its response
+///
+/// Decorators preserve it, record it as `tracing` span fields and may add
+/// tags of their own, e.g. the provider a [`FallbackProvider`](crate::fallback::FallbackProvider)
+/// used or a [`CacheProvider`](crate::cache::CacheProvider) hit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestMetadata {
+    /// Caller-assigned request identifier (e.g. from `X-Request-Id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// End user or tenant the request is made on behalf of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Free-form key/value tags for log correlation and metrics breakdowns
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl RequestMetadata {
+    /// Create empty metadata
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            request_id: None,
+            user: None,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    /// Set the request identifier
+    #[must_use]
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Set the end user or tenant
+    #[must_use]
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Add a key/value tag
+    #[must_use]
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Whether no field or tag is set
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.request_id.is_none() && self.user.is_none() && self.tags.is_empty()
+    }
+
+    /// Fill unset fields and missing tags from `other`
+    pub fn inherit(&mut self, other: &Self) {
+        if self.request_id.is_none() {
+            self.request_id.clone_from(&other.request_id);
+        }
+        if self.user.is_none() {
+            self.user.clone_from(&other.user);
+        }
+        for (key, value) in &other.tags {
+            self.tags
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    /// `tracing` span carrying these fields for the named decorator layer
+    #[must_use]
+    pub fn span(&self, layer: &'static str) -> tracing::Span {
+        tracing::info_span!(
+            "embacle_request",
+            layer,
+            request_id = self.request_id.as_deref(),
+            user = self.user.as_deref(),
+            tags = ?self.tags,
+        )
+    }
+}
+
 /// Configuration for a chat completion request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatRequest {
@@ -497,6 +905,39 @@ pub struct ChatRequest {
     /// Control over the response format (text, JSON, or schema-validated JSON)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub response_format: Option<ResponseFormat>,
+    /// Conversation identifier used to resume CLI sessions across turns.
+    ///
+    /// Runners that support resume look this key up in their
+    /// [`SessionStore`](crate::session::SessionStore); requests without a
+    /// key always start a fresh CLI session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_key: Option<String>,
+    /// Number of candidate choices to generate.
+    ///
+    /// Providers with [`LlmCapabilities::MULTIPLE_CHOICES`] honor it natively;
+    /// wrap others in [`ChoicesProvider`](crate::choices::ChoicesProvider) to
+    /// fan the request out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    /// How much the model should reason before answering.
+    ///
+    /// Support depends on each provider's [`LlmCapabilities::REASONING`] flag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Thinking-token budget, for providers that take one (overrides the
+    /// budget implied by `reasoning_effort`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
+    /// Caller context preserved by every decorator and echoed on the response
+    #[serde(default, skip_serializing_if = "RequestMetadata::is_empty")]
+    pub metadata: RequestMetadata,
+    /// Point in time by which the request must finish, overriding the
+    /// runner's configured timeout.
+    ///
+    /// Not serialized; set it with [`with_timeout`](Self::with_timeout) or
+    /// [`with_deadline`](Self::with_deadline).
+    #[serde(skip)]
+    pub deadline: Option<Instant>,
 }
 
 impl ChatRequest {
@@ -514,6 +955,12 @@ impl ChatRequest {
             top_p: None,
             stop: None,
             response_format: None,
+            session_key: None,
+            n: None,
+            reasoning_effort: None,
+            thinking_budget: None,
+            metadata: RequestMetadata::new(),
+            deadline: None,
         }
     }
 
@@ -580,6 +1027,94 @@ impl ChatRequest {
         self
     }
 
+    /// Set the conversation key used for CLI session resume
+    #[must_use]
+    pub fn with_session_key(mut self, session_key: impl Into<String>) -> Self {
+        self.session_key = Some(session_key.into());
+        self
+    }
+
+    /// Set the number of candidate choices to generate
+    #[must_use]
+    pub const fn with_n(mut self, n: u32) -> Self {
+        self.n = Some(n);
+        self
+    }
+
+    /// Set the reasoning effort level
+    #[must_use]
+    pub const fn with_reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning_effort = Some(effort);
+        self
+    }
+
+    /// Set the thinking-token budget
+    #[must_use]
+    pub const fn with_thinking_budget(mut self, tokens: u32) -> Self {
+        self.thinking_budget = Some(tokens);
+        self
+    }
+
+    /// Requested effort level, derived from `thinking_budget` when only that is set
+    #[must_use]
+    pub fn effective_reasoning_effort(&self) -> Option<ReasoningEffort> {
+        self.reasoning_effort.or_else(|| {
+            self.thinking_budget
+                .map(ReasoningEffort::from_thinking_budget)
+        })
+    }
+
+    /// Requested thinking budget, derived from `reasoning_effort` when only that is set
+    #[must_use]
+    pub fn effective_thinking_budget(&self) -> Option<u32> {
+        self.thinking_budget
+            .or_else(|| self.reasoning_effort.map(ReasoningEffort::thinking_budget))
+    }
+
+    /// Attach caller context (request id, user, tags)
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: RequestMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Require the request to finish within `timeout` from now
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Instant::now().checked_add(timeout);
+        self
+    }
+
+    /// Require the request to finish by `deadline`
+    #[must_use]
+    pub const fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Time left before the deadline, or `default` when none is set
+    ///
+    /// Returns [`Duration::ZERO`] once the deadline has passed.
+    #[must_use]
+    pub fn timeout_or(&self, default: Duration) -> Duration {
+        self.deadline.map_or(default, |deadline| {
+            deadline.saturating_duration_since(Instant::now())
+        })
+    }
+
+    /// Whether the request's deadline has already passed
+    #[must_use]
+    pub fn deadline_passed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    /// Number of requested choices, at least one
+    #[must_use]
+    pub fn choice_count(&self) -> u32 {
+        self.n.unwrap_or(1).max(1)
+    }
+
     /// Check whether any message in this request contains images
     #[must_use]
     pub fn has_images(&self) -> bool {
@@ -587,6 +1122,14 @@ impl ChatRequest {
             .iter()
             .any(|m| m.images.as_ref().is_some_and(|imgs| !imgs.is_empty()))
     }
+
+    /// Check whether any message in this request carries document or file attachments
+    #[must_use]
+    pub fn has_attachments(&self) -> bool {
+        self.messages
+            .iter()
+            .any(|m| m.attachments.as_ref().is_some_and(|a| !a.is_empty()))
+    }
 }
 
 /// Response from a chat completion
@@ -606,10 +1149,45 @@ pub struct ChatResponse {
     /// Tool calls requested by the model (populated by providers with native function calling)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCallRequest>>,
+    /// Reasoning text the model produced before its answer, kept out of `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    /// All candidates when several were requested via [`ChatRequest::n`]
+    ///
+    /// The top-level `content`, `finish_reason` and `tool_calls` mirror the
+    /// first choice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub choices: Option<Vec<ChatChoice>>,
+    /// Metadata of the request that produced this response, plus decorator tags
+    #[serde(default, skip_serializing_if = "RequestMetadata::is_empty")]
+    pub metadata: RequestMetadata,
 }
 
-/// Token usage statistics
+impl ChatResponse {
+    /// Echo `request`'s metadata onto this response, keeping tags already set
+    #[must_use]
+    pub fn with_request_metadata(mut self, request: &ChatRequest) -> Self {
+        self.metadata.inherit(&request.metadata);
+        self
+    }
+}
+
+/// One candidate completion in a multi-choice response
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatChoice {
+    /// Position of this choice in the response
+    pub index: u32,
+    /// Generated message content
+    pub content: String,
+    /// Finish reason (stop, length, etc.)
+    pub finish_reason: Option<String>,
+    /// Tool calls requested in this choice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallRequest>>,
+}
+
+/// Token usage statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenUsage {
     /// Number of tokens in the prompt
     pub prompt_tokens: u32,
@@ -617,10 +1195,53 @@ pub struct TokenUsage {
     pub completion_tokens: u32,
     /// Total tokens used
     pub total_tokens: u32,
+    /// Tokens spent on reasoning ("thinking"), if reported separately
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u32>,
+    /// Prompt tokens served from the provider's prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_prompt_tokens: Option<u32>,
+    /// Prompt tokens written to the provider's prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_tokens: Option<u32>,
+    /// Cost in USD as reported by the provider itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reported_cost: Option<f64>,
+}
+
+impl TokenUsage {
+    /// Add another response's usage to this running total
+    ///
+    /// Token counts saturate instead of overflowing. Optional token fields
+    /// stay `None` only while neither side reports them. `reported_cost` is
+    /// kept only when both sides report it: a partial sum would under-report
+    /// the total, so callers fall back to an estimate instead.
+    pub fn accumulate(&mut self, other: &Self) {
+        fn add(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.saturating_add(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        self.prompt_tokens = self.prompt_tokens.saturating_add(other.prompt_tokens);
+        self.completion_tokens = self
+            .completion_tokens
+            .saturating_add(other.completion_tokens);
+        self.total_tokens = self.total_tokens.saturating_add(other.total_tokens);
+        self.reasoning_tokens = add(self.reasoning_tokens, other.reasoning_tokens);
+        self.cached_prompt_tokens = add(self.cached_prompt_tokens, other.cached_prompt_tokens);
+        self.cache_creation_tokens = add(self.cache_creation_tokens, other.cache_creation_tokens);
+        self.reported_cost = self
+            .reported_cost
+            .zip(other.reported_cost)
+            .map(|(a, b)| a + b);
+    }
 }
 
 /// A chunk of a streaming response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Build with `..StreamChunk::default()` so new optional fields are not breaking.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StreamChunk {
     /// Content delta for this chunk
     pub delta: String,
@@ -628,6 +1249,35 @@ pub struct StreamChunk {
     pub is_final: bool,
     /// Finish reason if final
     pub finish_reason: Option<String>,
+    /// Token usage for the whole response, reported on the final chunk when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// Incremental tool-call fragments requested by the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+    /// Reasoning ("thinking") delta, kept separate from the content delta
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_delta: Option<String>,
+}
+
+/// Fragment of a tool call streamed across several chunks
+///
+/// Fragments sharing an `index` belong to the same call: `id` and
+/// `function_name` arrive on the first fragment, and concatenating every
+/// `arguments_delta` yields the JSON-encoded arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCallDelta {
+    /// Position of the tool call within the response
+    pub index: usize,
+    /// Tool call identifier (first fragment only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Function name (first fragment only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_name: Option<String>,
+    /// Next piece of the JSON-encoded arguments
+    #[serde(default)]
+    pub arguments_delta: String,
 }
 
 /// Stream type for chat completion responses
@@ -659,9 +1309,49 @@ pub trait LlmProvider: Send + Sync {
     /// Available models for this provider
     fn available_models(&self) -> &[String];
 
+    /// Structured metadata for each available model
+    ///
+    /// The default builds one entry per [`available_models()`](Self::available_models)
+    /// id with the provider's capabilities and any known limits and pricing.
+    fn model_catalog(&self) -> Vec<ModelInfo> {
+        crate::model_catalog::default_catalog(self.available_models(), self.capabilities())
+    }
+
+    /// Re-discover the models this provider can serve
+    ///
+    /// Runners that can list models at runtime ask their CLI or API, cache
+    /// the answer for a TTL and merge it with their fallback list; later
+    /// [`model_catalog()`](Self::model_catalog) calls include the result.
+    /// The default returns [`available_models()`](Self::available_models).
+    async fn refresh_models(&self) -> Result<Vec<String>, RunnerError> {
+        Ok(self.available_models().to_vec())
+    }
+
     /// Perform a chat completion (non-streaming)
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError>;
 
+    /// Perform a chat completion that can be cancelled cooperatively
+    ///
+    /// When `cancel` fires before the completion finishes, the call returns
+    /// an `ErrorKind::Cancelled` error. The default implementation races
+    /// [`complete()`](Self::complete) against the token and drops the
+    /// in-flight future; runners that own a subprocess override this to
+    /// kill the child explicitly.
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        tokio::select! {
+            biased;
+            () = cancel.cancelled() => Err(RunnerError::cancelled(format!(
+                "{}: request cancelled",
+                self.name()
+            ))),
+            result = self.complete(request) => result,
+        }
+    }
+
     /// Perform a streaming chat completion
     ///
     /// Returns a stream of chunks that can be consumed incrementally.
@@ -686,6 +1376,11 @@ mod tests {
         assert!(!ErrorKind::AuthFailure.is_transient());
         assert!(!ErrorKind::Config.is_transient());
         assert!(!ErrorKind::Guardrail.is_transient());
+        assert!(!ErrorKind::Cancelled.is_transient());
+        assert!(ErrorKind::RateLimited.is_transient());
+        assert!(!ErrorKind::ContextLengthExceeded.is_transient());
+        assert!(!ErrorKind::QuotaExhausted.is_transient());
+        assert!(!ErrorKind::ContentFiltered.is_transient());
     }
 
     #[test]
@@ -810,6 +1505,23 @@ mod tests {
         assert!(err.message.contains("image/bmp"));
     }
 
+    #[test]
+    fn attachment_part_validates_mime_and_reads_bytes() {
+        assert!(AttachmentPart::from_base64("data", "pdf").is_err());
+
+        let csv = AttachmentPart::from_base64("YSxi", "text/csv").unwrap();
+        assert!(csv.is_text());
+        assert_eq!(csv.read_bytes().unwrap(), b"a,b");
+        let pdf = AttachmentPart::from_path("/tmp/report.pdf", "application/pdf").unwrap();
+        assert!(!pdf.is_text());
+        assert_eq!(pdf.display_name(), Some("report.pdf"));
+
+        let req = ChatRequest::new(vec![ChatMessage::user_with_attachments("x", vec![pdf])]);
+        assert!(req.has_attachments());
+        let json = serde_json::to_value(&req.messages[0]).unwrap();
+        assert_eq!(json["attachments"][0]["source"]["path"], "/tmp/report.pdf");
+    }
+
     #[test]
     fn user_with_images_constructor() {
         let img = ImagePart::new("aGVsbG8=", "image/png").unwrap();
@@ -913,4 +1625,25 @@ mod tests {
         assert!(!empty.supports_stop_sequences());
         assert!(!empty.supports_response_format());
     }
+
+    #[test]
+    fn accumulate_saturates_and_drops_partial_cost() {
+        let mut total = TokenUsage {
+            prompt_tokens: u32::MAX - 1,
+            reported_cost: Some(0.5),
+            ..TokenUsage::default()
+        };
+        total.accumulate(&TokenUsage {
+            prompt_tokens: 10,
+            reasoning_tokens: Some(4),
+            reported_cost: Some(0.25),
+            ..TokenUsage::default()
+        });
+        assert_eq!(total.prompt_tokens, u32::MAX);
+        assert_eq!(total.reasoning_tokens, Some(4));
+        assert_eq!(total.reported_cost, Some(0.75));
+
+        total.accumulate(&TokenUsage::default());
+        assert_eq!(total.reported_cost, None);
+    }
 }
@@ -6,17 +6,17 @@
 
 use std::str;
 
-use crate::cli_common::{CliRunnerBase, MAX_OUTPUT_BYTES};
+use crate::cli_common::CliRunnerBase;
 use crate::types::{
-    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RunnerError,
+    ChatRequest, ChatResponse, ChatStream, LlmCapabilities, LlmProvider, RequestMetadata,
+    RunnerError,
 };
 use async_trait::async_trait;
 use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
 use crate::config::RunnerConfig;
-use crate::process::run_cli_command;
-use crate::prompt::prepare_prompt;
 use crate::sandbox::{apply_sandbox, build_policy};
 
 /// Default model for the Warp `oz` CLI
@@ -44,7 +44,7 @@ impl WarpCliRunner {
         }
     }
 
-    /// Store a session ID (conversation ID) for later resumption
+    /// Store a Warp conversation ID to resume the conversation identified by `key`
     pub async fn set_session(&self, key: &str, session_id: &str) {
         self.base.set_session(key, session_id).await;
     }
@@ -137,6 +137,9 @@ impl WarpCliRunner {
             finish_reason: Some("stop".to_owned()),
             warnings: None,
             tool_calls: None,
+            reasoning: None,
+            choices: None,
+            metadata: RequestMetadata::default(),
         };
 
         Ok((response, conversation_id))
@@ -147,26 +150,38 @@ impl WarpCliRunner {
 impl LlmProvider for WarpCliRunner {
     crate::delegate_provider_base!("warp_cli", "Warp oz CLI", LlmCapabilities::empty());
 
-    #[instrument(skip_all, fields(runner = "warp_cli"))]
     async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, RunnerError> {
-        let prepared = prepare_prompt(&request.messages)?;
+        self.complete_with_cancel(request, &CancellationToken::new())
+            .await
+    }
+
+    #[instrument(skip_all, fields(runner = "warp_cli"))]
+    async fn complete_with_cancel(
+        &self,
+        request: &ChatRequest,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse, RunnerError> {
+        let prepared = self.base.prepare_prompt(&request.messages)?;
         let prompt = &prepared.prompt;
         let mut cmd = self.build_command(prompt);
 
-        if let Some(model) = &request.model {
-            if let Some(cid) = self.base.get_session(model).await {
+        if let Some(key) = &request.session_key {
+            if let Some(cid) = self.base.get_session(key).await {
                 cmd.args(["--conversation", &cid]);
             }
         }
 
-        let output = run_cli_command(&mut cmd, self.base.config.timeout, MAX_OUTPUT_BYTES).await?;
+        let output = self
+            .base
+            .run_request_command(request, cmd, None, cancel)
+            .await?;
         self.base.check_exit_code(&output, "warp_cli")?;
 
         let (response, conversation_id) = Self::parse_ndjson_response(&output.stdout)?;
 
         if let Some(cid) = conversation_id {
-            if let Some(model) = &request.model {
-                self.base.set_session(model, &cid).await;
+            if let Some(key) = &request.session_key {
+                self.base.set_session(key, &cid).await;
             }
         }
 
@@ -0,0 +1,64 @@
+#!/bin/sh
+# ABOUTME: Scripted fake ACP agent used by tests in place of a real agent such as `copilot --acp`
+# ABOUTME: Speaks just enough NDJSON JSON-RPC for initialize, session/new, session/prompt and session/cancel
+#
+# SPDX-License-Identifier: Apache-2.0
+# Copyright (c) 2026 dravr.ai
+#
+# Prompt text drives the behaviour:
+#   CRASH - exit without replying (simulates a crashed agent)
+#   HANG  - leave the prompt unanswered until session/cancel arrives
+#   ENV   - reply with the agent's arguments and selected environment variables
+# Any other prompt gets one agent_message_chunk naming the agent PID, the
+# session ID and the per-session turn number, then an end_turn response.
+
+sessions=0
+pending_id=""
+args="$*"
+
+reply() {
+    printf '{"jsonrpc":"2.0","method":"session/update","params":{"sessionId":"%s","update":{"sessionUpdate":"agent_message_chunk","content":{"type":"text","text":"%s"}}}}\n' "$1" "$3"
+    printf '{"jsonrpc":"2.0","id":%s,"result":{"stopReason":"end_turn","usage":{"inputTokens":3,"outputTokens":2,"totalTokens":5}}}\n' "$2"
+}
+
+while IFS= read -r line; do
+    id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9][0-9]*\).*/\1/p')
+    method=$(printf '%s' "$line" | sed -n 's/.*"method":"\([^"]*\)".*/\1/p')
+    session=$(printf '%s' "$line" | sed -n 's/.*"sessionId":"\([^"]*\)".*/\1/p')
+
+    case "$method" in
+        initialize)
+            printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":1}}\n' "$id"
+            ;;
+        session/new)
+            sessions=$((sessions + 1))
+            printf '{"jsonrpc":"2.0","id":%s,"result":{"sessionId":"s%s-%s"}}\n' "$id" "$$" "$sessions"
+            ;;
+        session/prompt)
+            case "$line" in
+                *CRASH*)
+                    exit 1
+                    ;;
+                *HANG*)
+                    pending_id=$id
+                    ;;
+                *ENV*)
+                    reply "$session" "$id" "args=$args marker=${FAKE_ACP_MARKER:-unset} manifest=${CARGO_MANIFEST_DIR:-unset}"
+                    ;;
+                *)
+                    key=$(printf '%s' "$session" | tr -c 'A-Za-z0-9' '_')
+                    eval "turn=\${turns_$key:-0}"
+                    turn=$((turn + 1))
+                    eval "turns_$key=$turn"
+                    reply "$session" "$id" "pid=$$ session=$session turn=$turn"
+                    ;;
+            esac
+            ;;
+        session/cancel)
+            if [ -n "$pending_id" ]; then
+                printf '{"jsonrpc":"2.0","id":%s,"result":{"stopReason":"cancelled"}}\n' "$pending_id"
+                pending_id=""
+            fi
+            ;;
+    esac
+done
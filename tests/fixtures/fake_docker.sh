@@ -0,0 +1,37 @@
+#!/bin/sh
+# ABOUTME: Fake docker CLI that logs every invocation and emulates the output of `docker run`
+# ABOUTME: Used by tests to exercise the container execution backend without a Docker daemon
+#
+# SPDX-License-Identifier: Apache-2.0
+# Copyright (c) 2026 dravr.ai
+#
+# Each invocation is appended to docker.log next to this script, so tests
+# copy it into a temp directory first. `run` prints every forwarded `-e KEY`
+# with the value docker would copy into the container, echoes stdin when
+# `-i` is given, and hangs when its last argument is "hang".
+
+log="$(dirname "$0")/docker.log"
+echo "$*" >> "$log"
+[ "$1" = run ] || exit 0
+
+interactive=no
+prev=
+last=
+for arg; do
+  if [ "$prev" = "-e" ]; then
+    case "$arg" in
+      *=*) ;;
+      *) echo "env $arg=$(printenv "$arg")" ;;
+    esac
+  fi
+  [ "$arg" = "-i" ] && interactive=yes
+  prev=$arg
+  last=$arg
+done
+
+[ "$interactive" = yes ] && cat
+if [ "$last" = hang ]; then
+  sleep 300 &
+  wait
+fi
+exit 0
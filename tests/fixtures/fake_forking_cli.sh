@@ -0,0 +1,26 @@
+#!/bin/sh
+# ABOUTME: Fake agentic CLI that forks long-running grandchildren, like shells and MCP servers
+# ABOUTME: Used by tests to verify that timeouts and drops tear down the whole process group
+#
+# SPDX-License-Identifier: Apache-2.0
+# Copyright (c) 2026 dravr.ai
+#
+# Usage: fake_forking_cli.sh PIDFILE
+# Appends the PID of every descendant it starts to PIDFILE, prints "ready"
+# once they are all running, then waits forever.
+
+pidfile=$1
+
+sleep 300 &
+echo $! >> "$pidfile"
+
+sh -c 'sleep 300 & echo $! >> "$1"; wait' forked "$pidfile" &
+echo $! >> "$pidfile"
+
+# Wait for the nested shell to record its own child
+while [ "$(wc -l < "$pidfile")" -lt 3 ]; do
+    sleep 0.05
+done
+
+echo ready
+wait
@@ -1,5 +1,5 @@
 // ABOUTME: Cross-decorator scenario tests exercising FallbackProvider, MetricsProvider,
-// ABOUTME: QualityGateProvider, and GuardrailProvider together with ScriptedProvider
+// ABOUTME: QualityGateProvider, GuardrailProvider and circuit breaking with ScriptedProvider
 //
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2026 dravr.ai
@@ -10,13 +10,14 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 
+use embacle::circuit_breaker::{CircuitBreakerConfig, CircuitState};
 use embacle::fallback::{FallbackProvider, RetryConfig};
 use embacle::guardrail::{GuardrailProvider, TopicFilterGuardrail};
 use embacle::metrics::MetricsProvider;
 use embacle::quality_gate::{QualityGateProvider, QualityPolicy};
 use embacle::types::{
     ChatMessage, ChatRequest, ChatResponse, ChatStream, ErrorKind, LlmCapabilities, LlmProvider,
-    RunnerError,
+    RequestMetadata, RunnerError,
 };
 
 // ============================================================================
@@ -88,6 +89,9 @@ fn make_response(content: &str, model: &str) -> ChatResponse {
         finish_reason: Some("stop".to_owned()),
         warnings: None,
         tool_calls: None,
+        reasoning: None,
+        choices: None,
+        metadata: RequestMetadata::default(),
     }
 }
 
@@ -346,3 +350,135 @@ async fn guardrail_blocks_before_fallback() {
     assert_eq!(err.kind, ErrorKind::Guardrail);
     assert!(err.message.contains("prohibited"));
 }
+
+// ============================================================================
+// Scenario 8: Circuit breaker — A opens after failing, is skipped, then
+// closes again after a successful half-open trial
+// ============================================================================
+
+#[tokio::test(start_paused = true)]
+async fn circuit_breaker_skips_failing_provider_until_trial_succeeds() {
+    let a = ScriptedProvider::builder("flaky_a")
+        .with_response(Err(RunnerError::external_service("a", "down")))
+        .with_response(Ok(make_response("a recovered", "a-model")))
+        .build();
+    let mut b = ScriptedProvider::builder("steady_b");
+    for _ in 0..3 {
+        b = b.with_response(Ok(make_response("from b", "b-model")));
+    }
+
+    let fallback = FallbackProvider::new(vec![Box::new(a), Box::new(b.build())])
+        .expect("non-empty")
+        .with_circuit_breaker(
+            CircuitBreakerConfig::default()
+                .with_failure_threshold(1)
+                .with_cooldown(Duration::from_millis(50)),
+        );
+    let request = ChatRequest::new(vec![ChatMessage::user("hello")]);
+
+    for _ in 0..2 {
+        let response = fallback.complete(&request).await.expect("B answers");
+        assert_eq!(response.content, "from b");
+    }
+    assert_eq!(
+        fallback.circuit_states(),
+        vec![
+            ("flaky_a", CircuitState::Open),
+            ("steady_b", CircuitState::Closed)
+        ]
+    );
+
+    // A is not called while open: its scripted recovery is still pending
+    let response = fallback.complete(&request).await.expect("B answers");
+    assert_eq!(response.content, "from b");
+
+    tokio::time::advance(Duration::from_millis(60)).await;
+    assert_eq!(fallback.circuit_states()[0].1, CircuitState::HalfOpen);
+    let response = fallback.complete(&request).await.expect("A trial");
+    assert_eq!(response.content, "a recovered");
+    assert_eq!(
+        response
+            .metadata
+            .tags
+            .get("fallback_provider")
+            .map(String::as_str),
+        Some("flaky_a")
+    );
+    assert_eq!(fallback.circuit_states()[0].1, CircuitState::Closed);
+}
+
+// ============================================================================
+// Scenario 9: Circuit breaker — every circuit open fails fast; health check
+// ignores open providers
+// ============================================================================
+
+#[tokio::test]
+async fn all_circuits_open_fails_fast_and_reports_unhealthy() {
+    let a = ScriptedProvider::builder("down_a")
+        .with_response(Err(RunnerError::timeout("a timed out")))
+        .with_response(Ok(make_response("should not reach", "a-model")))
+        .build();
+    let b = ScriptedProvider::builder("down_b")
+        .with_response(Err(RunnerError::timeout("b timed out")))
+        .with_response(Ok(make_response("should not reach", "b-model")))
+        .build();
+
+    let fallback = FallbackProvider::new(vec![Box::new(a), Box::new(b)])
+        .expect("non-empty")
+        .with_circuit_breaker(
+            CircuitBreakerConfig::default()
+                .with_failure_threshold(1)
+                .with_cooldown(Duration::from_mins(1)),
+        );
+    assert!(fallback.health_check().await.expect("health check"));
+
+    let request = ChatRequest::new(vec![ChatMessage::user("hello")]);
+    let err = fallback.complete(&request).await.unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Timeout);
+
+    let err = fallback.complete(&request).await.unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ExternalService);
+    assert!(err.message.contains("circuit is open"));
+    assert!(err.retry_after.is_some_and(|d| d <= Duration::from_mins(1)));
+
+    // Both scripted providers still report healthy, but their circuits are open
+    assert!(!fallback.health_check().await.expect("health check"));
+}
+
+// ============================================================================
+// Scenario 10: Health-aware ordering — a provider that just failed is tried
+// after the healthy ones until its cooldown has passed
+// ============================================================================
+
+#[tokio::test(start_paused = true)]
+async fn recently_failed_provider_is_tried_last_until_cooldown() {
+    let a = ScriptedProvider::builder("flaky_a")
+        .with_response(Err(RunnerError::external_service("a", "blip")))
+        .with_response(Ok(make_response("from a", "a-model")))
+        .build();
+    let b = ScriptedProvider::builder("steady_b")
+        .with_response(Ok(make_response("from b", "b-model")))
+        .with_response(Ok(make_response("from b", "b-model")))
+        .build();
+
+    let fallback = FallbackProvider::new(vec![Box::new(a), Box::new(b)])
+        .expect("non-empty")
+        .with_circuit_breaker(
+            CircuitBreakerConfig::default()
+                .with_failure_threshold(5)
+                .with_cooldown(Duration::from_millis(50)),
+        );
+    let request = ChatRequest::new(vec![ChatMessage::user("hello")]);
+
+    let response = fallback.complete(&request).await.expect("B answers");
+    assert_eq!(response.content, "from b");
+
+    // A's circuit is still closed, but B now goes first
+    let response = fallback.complete(&request).await.expect("B answers");
+    assert_eq!(response.content, "from b");
+    assert_eq!(fallback.circuit_states()[0].1, CircuitState::Closed);
+
+    tokio::time::advance(Duration::from_millis(60)).await;
+    let response = fallback.complete(&request).await.expect("A answers");
+    assert_eq!(response.content, "from a");
+}